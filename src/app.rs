@@ -2,16 +2,22 @@ use std::sync::Arc;
 
 use crate::{
     common::*,
+    context_menu::{ContextMenu, ContextMenuMessage},
     dialog::{Dialog, DialogMessage},
     editor::{Editor, EditorMessage},
     file_panel::{FilePanel, FilePanelMessage},
     menu_bar::{MenuBar, MenuBarMessage},
-    preview::{Preview, PreviewMessage},
+    preview::{Preview, PreviewMessage, PreviewPage},
+    setting::{
+        self, RECENT_LIST_FILE, SESSION_FILE, SessionState, USER_SETTING_FILE, UserSetting,
+        WindowState,
+    },
 };
 use iced::{
-    Color, Element, Length, Subscription, Task,
+    Color, Element, Length, Point, Size, Subscription, Task,
     application::timed::UpdateFn,
     widget::{center, column, container, opaque, row, stack},
+    window,
 };
 use tracing::{error, info, warn};
 use tracing_appender::rolling;
@@ -24,7 +30,26 @@ pub struct App {
     file_panel: FilePanel,
     menu_bar: MenuBar,
     dialog: Dialog,
+    context_menu: ContextMenu,
     setting: AppSetting,
+    // 等待用户处理未保存文档后再关闭的窗口
+    pending_close: Option<(window::Id, Vec<UnsavedDoc>)>,
+    // 窗口当前的大小和位置，保存会话时使用
+    window_state: Option<WindowState>,
+    // 读取上次的会话前不保存，避免覆盖
+    is_session_loaded: bool,
+    // 等待用户确认的操作，为空时确认结果交给编辑器
+    pending_confirm: Option<PendingConfirm>,
+}
+
+#[derive(Debug, Clone)]
+enum PendingConfirm {
+    // 新建[[链接]]指向的笔记
+    WikiNote(String),
+    // 用系统程序打开预览中的外部链接
+    ExternalLink(String),
+    // 重命名或移动后更新其他文件中的链接
+    LinkRewrites,
 }
 
 #[derive(Debug, Clone)]
@@ -35,7 +60,15 @@ pub enum AppMessage {
     FilePanel(FilePanelMessage),
     MenuBar(MenuBarMessage),
     Dialog(DialogMessage),
+    ContextMenu(ContextMenuMessage),
     // 顶层消息
+    WindowCloseRequested(window::Id),
+    WindowResized(Size),
+    WindowMoved(Point),
+    SessionLoaded(SessionState),
+    SaveSession,
+    UserSettingLoaded(UserSetting),
+    SaveUserSetting,
     None,
 }
 
@@ -47,18 +80,82 @@ impl App {
             file_panel: FilePanel::new(),
             menu_bar: MenuBar::new(),
             dialog: Dialog::new(),
-            setting: DEFAULT_USER_SETTING,
+            context_menu: ContextMenu::new(),
+            setting: DEFAULT_USER_SETTING.clone(),
+            pending_close: None,
+            window_state: None,
+            is_session_loaded: false,
+            pending_confirm: None,
         };
-        let task = Task::batch([Task::none()]);
+        let task = Task::batch([
+            Task::perform(
+                setting::load_config::<SessionState>(SESSION_FILE),
+                AppMessage::SessionLoaded,
+            ),
+            Task::perform(setting::load_config(RECENT_LIST_FILE), |recent_list| {
+                AppMessage::MenuBar(MenuBarMessage::RecentListLoaded(recent_list))
+            }),
+            Task::perform(
+                setting::load_config::<UserSetting>(USER_SETTING_FILE),
+                AppMessage::UserSettingLoaded,
+            ),
+        ]);
         (app, task)
     }
 
     pub fn update(&mut self, app_message: AppMessage) -> Task<AppMessage> {
         match app_message {
             AppMessage::Dialog(dialog_message) => match dialog_message {
-                DialogMessage::SendConfirmResult(is_user_agreed) => Task::done(AppMessage::Editor(
-                    EditorMessage::GetConfirmResult(is_user_agreed),
+                DialogMessage::SendConfirmResult(choice) => match self.pending_confirm.take() {
+                    Some(PendingConfirm::WikiNote(note)) => match choice {
+                        DialogChoice::Create => Task::done(AppMessage::FilePanel(
+                            FilePanelMessage::CreateWikiNote(note),
+                        )),
+                        _ => Task::none(),
+                    },
+                    Some(PendingConfirm::ExternalLink(url)) => match choice {
+                        DialogChoice::Confirm => Task::done(AppMessage::Preview(
+                            PreviewMessage::OpenExternalLink(url),
+                        )),
+                        _ => Task::none(),
+                    },
+                    Some(PendingConfirm::LinkRewrites) => Task::done(AppMessage::FilePanel(
+                        FilePanelMessage::ResolveLinkRewrites(choice == DialogChoice::Confirm),
+                    )),
+                    None => Task::done(AppMessage::Editor(EditorMessage::GetConfirmResult(choice))),
+                },
+                DialogMessage::SendFrontMatterFields(fields) => Task::done(AppMessage::Editor(
+                    EditorMessage::ApplyFrontMatterFields(fields),
                 )),
+                DialogMessage::SendUserSetting(user_setting) => {
+                    user_setting.apply_to(&mut self.setting);
+                    Task::batch([
+                        Task::done(AppMessage::FilePanel(FilePanelMessage::RescanWorkspace)),
+                        Task::done(AppMessage::SaveUserSetting),
+                    ])
+                }
+                DialogMessage::SendNewFileRequest(request) => Task::done(AppMessage::FilePanel(
+                    FilePanelMessage::CreateFileFromTemplate(request),
+                )),
+                DialogMessage::SendCloseGuardChoice(choice) => match choice {
+                    DialogChoice::SaveAll => match self.pending_close {
+                        Some((_, ref mut docs)) => {
+                            let docs = std::mem::take(docs);
+                            Task::done(AppMessage::FilePanel(FilePanelMessage::SaveAllBeforeExit(
+                                docs,
+                            )))
+                        }
+                        None => Task::none(),
+                    },
+                    DialogChoice::DiscardAll => match self.pending_close.take() {
+                        Some((id, _)) => self.close_window(id),
+                        None => Task::none(),
+                    },
+                    _ => {
+                        self.pending_close = None;
+                        Task::none()
+                    }
+                },
                 _ => self.dialog.update(dialog_message).map(AppMessage::Dialog),
             },
             AppMessage::FilePanel(file_panel_message) => match file_panel_message {
@@ -74,12 +171,108 @@ impl App {
                 FilePanelMessage::SendImgDataToPreview(image_data) => Task::done(
                     AppMessage::Preview(PreviewMessage::GetImgPathFromFilePanel(image_data)),
                 ),
+                FilePanelMessage::SendRecordRecent(path, kind) => Task::done(AppMessage::MenuBar(
+                    MenuBarMessage::RecordRecent(path, kind),
+                )),
+                FilePanelMessage::SendNoteIndexToPreview(note_index) => Task::done(
+                    AppMessage::Preview(PreviewMessage::UpdateNoteIndex(note_index)),
+                ),
+                FilePanelMessage::SendRestoredImgDataToPreview(image_data) => Task::done(
+                    AppMessage::Preview(PreviewMessage::RestoreGalleryImages(image_data)),
+                ),
+                FilePanelMessage::SendTagsToEditor(tags) => {
+                    Task::done(AppMessage::Editor(EditorMessage::UpdateTags(tags)))
+                }
+                FilePanelMessage::SendOpenNewFileDialog(dir_key, templates) => Task::done(
+                    AppMessage::Dialog(DialogMessage::OpenNewFileDialog(dir_key, templates)),
+                ),
+                FilePanelMessage::SendJournalDatesToPreview(note_dates) => Task::done(
+                    AppMessage::Preview(PreviewMessage::UpdateJournalDates(note_dates)),
+                ),
+                FilePanelMessage::SendLinkGraphToPreview(graph, current) => Task::done(
+                    AppMessage::Preview(PreviewMessage::UpdateLinkGraph(graph, current)),
+                ),
+                FilePanelMessage::SendLinkGraphCurrentToPreview(current) => Task::done(
+                    AppMessage::Preview(PreviewMessage::UpdateLinkGraphCurrent(current)),
+                ),
                 FilePanelMessage::SendImgCodeToEditor(code) => Task::done(AppMessage::Editor(
                     EditorMessage::GetImgCodeFromFilePanel(code),
                 )),
                 FilePanelMessage::SendImgBasePathToPreview(path) => Task::done(
                     AppMessage::Preview(PreviewMessage::GetImgBasePathFromFilePanel(path)),
                 ),
+                FilePanelMessage::UnloadFileFromEditor(id) => {
+                    Task::done(AppMessage::Editor(EditorMessage::UnloadFile(id)))
+                }
+                FilePanelMessage::SendSearchFiles(result) => {
+                    // 编辑器中未保存的内容优先于磁盘上的文件参与搜索
+                    let result = result.map(|mut targets| {
+                        let editor_file = self.editor.unsaved_file();
+                        targets.buffers = self
+                            .file_panel
+                            .collect_unsaved_docs(editor_file.as_ref())
+                            .into_iter()
+                            .filter_map(|doc| doc.path.map(|path| (path, doc.content)))
+                            .collect();
+                        targets
+                    });
+                    Task::done(AppMessage::Preview(PreviewMessage::StartWorkspaceSearch(result)))
+                }
+                FilePanelMessage::SendLinkReport(result) => {
+                    Task::done(AppMessage::Preview(PreviewMessage::LinkReportLoaded(result)))
+                }
+                // 列出需要修改的文件，文件太多时只显示前面一部分
+                FilePanelMessage::SendConfirmLinkRewrites(files) => {
+                    let mut text = format!("以下 {} 个文件中的链接需要更新，是否一并修改?", files.len());
+                    for file in files.iter().take(LINK_REWRITE_LIST_MAX) {
+                        text.push('\n');
+                        text.push_str(file);
+                    }
+                    if files.len() > LINK_REWRITE_LIST_MAX {
+                        text.push_str("\n...");
+                    }
+                    self.pending_confirm = Some(PendingConfirm::LinkRewrites);
+                    Task::done(AppMessage::Dialog(DialogMessage::OpenConfirmDialog(
+                        text,
+                        vec![DialogChoice::Confirm, DialogChoice::Cancel],
+                    )))
+                }
+                FilePanelMessage::SendReplacementToEditor(replacement) => Task::done(
+                    AppMessage::Editor(EditorMessage::ApplyReplacement(replacement)),
+                ),
+                FilePanelMessage::SendReplaceResult(succeeded_count, failed_count) => Task::done(
+                    AppMessage::Preview(PreviewMessage::ReplaceFinished(
+                        succeeded_count,
+                        failed_count,
+                    )),
+                ),
+                FilePanelMessage::SendCursorLineToEditor(line) => {
+                    Task::done(AppMessage::Editor(EditorMessage::MoveCursorToLine(line)))
+                }
+                FilePanelMessage::SendDocViewToEditor(view) => {
+                    Task::done(AppMessage::Editor(EditorMessage::RestoreDocView(view)))
+                }
+                FilePanelMessage::SendPreviewScrollToLine(line) => {
+                    Task::done(AppMessage::Preview(PreviewMessage::ScrollToLine(line)))
+                }
+                FilePanelMessage::SendMovedContentToEditor(original, moved, line) => Task::done(
+                    AppMessage::Editor(EditorMessage::ReplaceAllContent(original, moved, line)),
+                ),
+                FilePanelMessage::SendContextMenuItems(items) => Task::done(
+                    AppMessage::ContextMenu(ContextMenuMessage::Open(items)),
+                ),
+                FilePanelMessage::ReturnSaveAllResult(result) => match result {
+                    Ok(_) => match self.pending_close.take() {
+                        Some((id, _)) => self.close_window(id),
+                        None => Task::none(),
+                    },
+                    Err(error) => {
+                        // 保存失败或用户取消另存为时放弃关闭窗口
+                        warn!("{}", error);
+                        self.pending_close = None;
+                        Task::none()
+                    }
+                },
                 _ => self
                     .file_panel
                     .update(file_panel_message, &self.setting)
@@ -92,6 +285,12 @@ impl App {
                 MenuBarMessage::CommandOpenFile => {
                     Task::done(AppMessage::FilePanel(FilePanelMessage::OpenFile))
                 }
+                MenuBarMessage::CommandOpenWorkspaceFile => {
+                    Task::done(AppMessage::FilePanel(FilePanelMessage::OpenWorkspaceFile))
+                }
+                MenuBarMessage::CommandSaveWorkspaceFile => {
+                    Task::done(AppMessage::FilePanel(FilePanelMessage::SaveWorkspaceFile))
+                }
                 MenuBarMessage::CommandImportImg => {
                     Task::done(AppMessage::FilePanel(FilePanelMessage::ImportImg))
                 }
@@ -107,22 +306,83 @@ impl App {
                 MenuBarMessage::CommandSaveAs => {
                     Task::done(AppMessage::Editor(EditorMessage::SaveAsRequested))
                 }
+                MenuBarMessage::CommandTodayNote => Task::done(AppMessage::FilePanel(
+                    FilePanelMessage::OpenDailyNote(jiff::Zoned::now().date()),
+                )),
+                MenuBarMessage::CommandShowCalendar => Task::done(AppMessage::Preview(
+                    PreviewMessage::ChangePageTo(PreviewPage::Calendar),
+                )),
+                MenuBarMessage::CommandShowLinkGraph => Task::done(AppMessage::Preview(
+                    PreviewMessage::ChangePageTo(PreviewPage::LinkGraph),
+                )),
+                MenuBarMessage::CommandUndoPathChange => {
+                    Task::done(AppMessage::FilePanel(FilePanelMessage::UndoPathChange))
+                }
+                MenuBarMessage::CommandCheckLinks => {
+                    Task::done(AppMessage::Preview(PreviewMessage::StartLinkCheck))
+                }
                 MenuBarMessage::SettingAutoSave(auto_save) => {
                     self.setting.auto_save = auto_save;
                     Task::none()
                 }
+                // 工作区的定时扫描会按新设置增删隐藏文件
+                MenuBarMessage::SettingScanHidden(scan_include_hidden) => {
+                    self.setting.scan_include_hidden = scan_include_hidden;
+                    Task::batch([
+                        Task::done(AppMessage::FilePanel(FilePanelMessage::RescanWorkspace)),
+                        Task::done(AppMessage::SaveUserSetting),
+                    ])
+                }
+                MenuBarMessage::SendOpenRecent(path, kind) => Task::done(AppMessage::FilePanel(
+                    FilePanelMessage::OpenRecent(path, kind),
+                )),
+                MenuBarMessage::SettingRestoreSession(restore_session) => {
+                    self.setting.restore_session = restore_session;
+                    Task::done(AppMessage::SaveSession)
+                }
+                MenuBarMessage::SettingTitleAsLabel(title_as_label) => {
+                    self.setting.title_as_label = title_as_label;
+                    Task::batch([
+                        Task::done(AppMessage::FilePanel(FilePanelMessage::RefreshLinkGraph)),
+                        Task::done(AppMessage::SaveUserSetting),
+                    ])
+                }
+                MenuBarMessage::SettingOpenPreference => Task::done(AppMessage::Dialog(
+                    DialogMessage::OpenPreferenceDialog(UserSetting::from(&self.setting)),
+                )),
+                MenuBarMessage::SettingEditableExtension(extension, is_enabled) => {
+                    self.setting
+                        .editable_extensions
+                        .retain(|editable_extension| editable_extension != extension);
+                    if is_enabled {
+                        self.setting.editable_extensions.push(extension.to_string());
+                    }
+                    Task::batch([
+                        Task::done(AppMessage::FilePanel(FilePanelMessage::RescanWorkspace)),
+                        Task::done(AppMessage::SaveUserSetting),
+                    ])
+                }
                 _ => self
                     .menu_bar
                     .update(menu_bar_message)
                     .map(AppMessage::MenuBar),
             },
             AppMessage::Editor(editor_message) => match editor_message {
-                EditorMessage::SendNewContentToPreview(new_content) => Task::done(
-                    AppMessage::Preview(PreviewMessage::SyncContnetWithEditor(new_content)),
-                ),
+                // 预览和大纲都跟随编辑器内容更新
+                EditorMessage::SendNewContentToPreview(new_content) => Task::batch([
+                    Task::done(AppMessage::FilePanel(FilePanelMessage::SyncOutlineWithEditor(
+                        Arc::clone(&new_content),
+                    ))),
+                    Task::done(AppMessage::Preview(PreviewMessage::SyncContnetWithEditor(
+                        new_content,
+                    ))),
+                ]),
                 EditorMessage::LoadPermitted => {
                     Task::done(AppMessage::FilePanel(FilePanelMessage::LoadPermitted))
                 }
+                EditorMessage::LoadCanceled => {
+                    Task::done(AppMessage::FilePanel(FilePanelMessage::LoadCanceled))
+                }
                 EditorMessage::AutoSaveToFile(file_data) => {
                     Task::done(AppMessage::FilePanel(FilePanelMessage::AutoSave(file_data)))
                 }
@@ -135,9 +395,18 @@ impl App {
                 EditorMessage::OpenEditorTableDialog => {
                     Task::done(AppMessage::Dialog(DialogMessage::OpenEditorTableDialog))
                 }
-                EditorMessage::OpenConfirmDialog(text) => {
-                    Task::done(AppMessage::Dialog(DialogMessage::OpenConfirmDialog(text)))
-                }
+                EditorMessage::SendContextMenuItems(items) => Task::done(AppMessage::ContextMenu(
+                    ContextMenuMessage::Open(items),
+                )),
+                EditorMessage::SendOpenAdjacentDailyNote(offset) => Task::done(
+                    AppMessage::FilePanel(FilePanelMessage::OpenAdjacentDailyNote(offset)),
+                ),
+                EditorMessage::OpenFrontMatterDialog(fields) => Task::done(AppMessage::Dialog(
+                    DialogMessage::OpenFrontMatterDialog(fields),
+                )),
+                EditorMessage::OpenConfirmDialog(text, choices) => Task::done(AppMessage::Dialog(
+                    DialogMessage::OpenConfirmDialog(text, choices),
+                )),
                 _ => self
                     .editor
                     .update(editor_message, &self.setting)
@@ -147,11 +416,192 @@ impl App {
                 PreviewMessage::SendImgIdToFilePanel(id) => Task::done(AppMessage::FilePanel(
                     FilePanelMessage::GetImgIdFromPreview(id),
                 )),
+                PreviewMessage::SendContextMenuItems(items) => Task::done(
+                    AppMessage::ContextMenu(ContextMenuMessage::Open(items)),
+                ),
+                PreviewMessage::SendSearchRequest => {
+                    Task::done(AppMessage::FilePanel(FilePanelMessage::CollectSearchFiles))
+                }
+                PreviewMessage::SendReplaceRequest(replacements) => Task::done(
+                    AppMessage::FilePanel(FilePanelMessage::ApplyReplacements(replacements)),
+                ),
+                PreviewMessage::SendLinkCheckRequest => {
+                    Task::done(AppMessage::FilePanel(FilePanelMessage::CheckLinks))
+                }
+                PreviewMessage::SendOpenFileAtLine(path, line) => Task::done(
+                    AppMessage::FilePanel(FilePanelMessage::OpenFileAtLine(path, line)),
+                ),
+                PreviewMessage::SendOpenWikiLink(note, heading) => Task::done(
+                    AppMessage::FilePanel(FilePanelMessage::OpenWikiLink(note, heading)),
+                ),
+                PreviewMessage::SendCreateWikiNote(note) => {
+                    let text = format!("笔记“{}”不存在，是否新建?", note);
+                    self.pending_confirm = Some(PendingConfirm::WikiNote(note));
+                    Task::done(AppMessage::Dialog(DialogMessage::OpenConfirmDialog(
+                        text,
+                        vec![DialogChoice::Create, DialogChoice::Cancel],
+                    )))
+                }
+                PreviewMessage::SendOpenLinkedPath(path, anchor) => Task::done(
+                    AppMessage::FilePanel(FilePanelMessage::OpenLinkedPath(path, anchor)),
+                ),
+                PreviewMessage::SendOpenDailyNote(date) => {
+                    Task::done(AppMessage::FilePanel(FilePanelMessage::OpenDailyNote(date)))
+                }
+                PreviewMessage::SendEditFrontMatter => {
+                    Task::done(AppMessage::Editor(EditorMessage::EditFrontMatter))
+                }
+                PreviewMessage::SendFindAnchor(anchor) => {
+                    Task::done(AppMessage::FilePanel(FilePanelMessage::FindAnchor(anchor)))
+                }
+                PreviewMessage::SendOpenExternalLink(url) => {
+                    let opener = self.setting.link_opener.first().cloned().unwrap_or_default();
+                    let text = format!("是否使用 {} 打开链接“{}”?", opener, url);
+                    self.pending_confirm = Some(PendingConfirm::ExternalLink(url));
+                    Task::done(AppMessage::Dialog(DialogMessage::OpenConfirmDialog(
+                        text,
+                        vec![DialogChoice::Confirm, DialogChoice::Cancel],
+                    )))
+                }
                 _ => self
                     .preview
                     .update(preview_message, &self.setting)
                     .map(AppMessage::Preview),
             },
+            AppMessage::ContextMenu(context_menu_message) => match context_menu_message {
+                // 根据菜单项的类型分发给对应模块
+                ContextMenuMessage::SendAction(action) => match action {
+                    ContextAction::Cut
+                    | ContextAction::Copy
+                    | ContextAction::Paste
+                    | ContextAction::SelectAll
+                    | ContextAction::FormatBold
+                    | ContextAction::FormatItalic
+                    | ContextAction::FormatCode
+                    | ContextAction::FormatLink => Task::done(AppMessage::Editor(
+                        EditorMessage::HandleContextAction(action),
+                    )),
+                    ContextAction::RemoveImage(_) => Task::done(AppMessage::Preview(
+                        PreviewMessage::HandleContextAction(action),
+                    )),
+                    _ => Task::done(AppMessage::FilePanel(
+                        FilePanelMessage::HandleContextAction(action),
+                    )),
+                },
+                _ => self
+                    .context_menu
+                    .update(context_menu_message)
+                    .map(AppMessage::ContextMenu),
+            },
+            AppMessage::WindowCloseRequested(id) => {
+                // 关闭前汇总编辑器、临时工作区和文本素材中所有未保存的文档
+                let editor_file = self.editor.unsaved_file();
+                let mut docs = self.file_panel.collect_unsaved_docs(editor_file.as_ref());
+                docs.extend(self.preview.collect_unsaved_docs());
+                if docs.is_empty() {
+                    return self.close_window(id);
+                }
+                let doc_names = docs
+                    .iter()
+                    .map(|doc| match doc.path {
+                        Some(ref path) => path.to_string_lossy().into_owned(),
+                        None => format!("{}（未命名）", doc.name),
+                    })
+                    .collect();
+                self.pending_close = Some((id, docs));
+                Task::done(AppMessage::Dialog(DialogMessage::OpenCloseGuardDialog(
+                    doc_names,
+                )))
+            }
+            AppMessage::WindowResized(size) => {
+                let window_state = self.window_state.get_or_insert(WindowState {
+                    width: size.width,
+                    height: size.height,
+                    position: None,
+                });
+                window_state.width = size.width;
+                window_state.height = size.height;
+                Task::none()
+            }
+            AppMessage::WindowMoved(position) => {
+                if let Some(window_state) = &mut self.window_state {
+                    window_state.position = Some((position.x, position.y));
+                }
+                Task::none()
+            }
+            AppMessage::SessionLoaded(session) => {
+                self.is_session_loaded = true;
+                self.setting.restore_session = session.restore_enabled;
+                if !session.restore_enabled {
+                    return Task::none();
+                }
+                info!("[App-SessionLoaded]:开始恢复上次的会话!");
+                let window_task = match session.window {
+                    Some(window_state) => {
+                        self.window_state = Some(window_state);
+                        window::latest().then(move |id| match id {
+                            Some(id) => {
+                                let size = Size::new(window_state.width, window_state.height);
+                                let move_task = match window_state.position {
+                                    Some((x, y)) => window::move_to(id, Point::new(x, y)),
+                                    None => Task::none(),
+                                };
+                                Task::batch([window::resize(id, size), move_task])
+                            }
+                            None => Task::none(),
+                        })
+                    }
+                    None => Task::none(),
+                };
+                Task::batch([
+                    window_task,
+                    Task::done(AppMessage::FilePanel(FilePanelMessage::RestoreSession(
+                        session.workspace,
+                    ))),
+                    Task::done(AppMessage::Preview(PreviewMessage::RestoreSession(
+                        session.preview_page,
+                        session.preview_scroll,
+                    ))),
+                ])
+            }
+            AppMessage::SaveSession => {
+                if !self.is_session_loaded {
+                    return Task::none();
+                }
+                Task::future(setting::save_config(SESSION_FILE, self.session_state())).then(
+                    |result| {
+                        if let Err(error) = result {
+                            error!("[App-SaveSession]:会话保存失败:{}", error);
+                        }
+                        Task::none()
+                    },
+                )
+            }
+            // 标题显示方式会影响关系图中的节点名称
+            AppMessage::UserSettingLoaded(user_setting) => {
+                let is_title_changed = user_setting.title_as_label != self.setting.title_as_label;
+                user_setting.apply_to(&mut self.setting);
+                // 读取设置前已经打开的工作区按新的扫描设置重新读取
+                let rescan_task =
+                    Task::done(AppMessage::FilePanel(FilePanelMessage::RescanWorkspace));
+                match is_title_changed {
+                    true => Task::batch([
+                        rescan_task,
+                        Task::done(AppMessage::FilePanel(FilePanelMessage::RefreshLinkGraph)),
+                    ]),
+                    false => rescan_task,
+                }
+            }
+            AppMessage::SaveUserSetting => Task::future(setting::save_config(
+                USER_SETTING_FILE,
+                UserSetting::from(&self.setting),
+            ))
+            .then(|result| {
+                if let Err(error) = result {
+                    error!("[App-SaveUserSetting]:设置保存失败:{}", error);
+                }
+                Task::none()
+            }),
             _ => Task::none(),
         }
     }
@@ -165,7 +615,7 @@ impl App {
             .into();
         let file_panel: Element<'_, FilePanelMessage> = self
             .file_panel
-            .view()
+            .view(&self.setting)
             .width(Length::FillPortion(2))
             .height(Length::Fill)
             .into();
@@ -200,14 +650,75 @@ impl App {
         .height(Length::Fill)
         .into();
 
-        match self.dialog.is_show() {
+        let base = match self.context_menu.is_open() {
+            true => stack![base, self.context_menu.view().map(AppMessage::ContextMenu)].into(),
+            false => base,
+        };
+
+        let base = match self.file_panel.is_quick_open_shown() {
+            true => stack![
+                base,
+                opaque(self.file_panel.view_quick_open().map(AppMessage::FilePanel))
+            ]
+            .into(),
+            false => base,
+        };
+
+        let base = match self.dialog.is_show() {
             true => App::set_modal(base, self.dialog.view().map(AppMessage::Dialog)),
             false => base,
-        }
+        };
+        ContextMenu::track_right_press(base, AppMessage::ContextMenu)
     }
 
     pub fn subscription(&self) -> Subscription<AppMessage> {
-        Subscription::batch([self.preview.subscription().map(AppMessage::Preview)])
+        Subscription::batch([
+            self.preview.subscription().map(AppMessage::Preview),
+            self.file_panel.subscription().map(AppMessage::FilePanel),
+            window::close_requests().map(AppMessage::WindowCloseRequested),
+            window::events().filter_map(|(_, event)| match event {
+                window::Event::Resized(size) => Some(AppMessage::WindowResized(size)),
+                window::Event::Moved(position) => Some(AppMessage::WindowMoved(position)),
+                _ => None,
+            }),
+            iced::time::every(iced::time::Duration::from_secs(SESSION_SAVE_INTERVAL_SECS))
+                .map(|_| AppMessage::SaveSession),
+        ])
+    }
+
+    // 关闭恢复功能时只保存开关
+    pub fn session_state(&self) -> SessionState {
+        if !self.setting.restore_session {
+            return SessionState {
+                restore_enabled: false,
+                ..SessionState::default()
+            };
+        }
+        let workspace = self
+            .file_panel
+            .session_state(&self.preview.gallery_node_ids(), &self.editor.doc_views());
+        SessionState {
+            restore_enabled: true,
+            workspace,
+            preview_page: self.preview.current_page(),
+            preview_scroll: self.preview.scroll_y(),
+            window: self.window_state,
+        }
+    }
+
+    // 退出前保存会话，保存失败也照常关闭窗口
+    pub fn close_window(&self, id: window::Id) -> Task<AppMessage> {
+        if !self.is_session_loaded {
+            return window::close(id);
+        }
+        Task::future(setting::save_config(SESSION_FILE, self.session_state())).then(
+            move |result| {
+                if let Err(error) = result {
+                    error!("[App-CloseWindow]:会话保存失败:{}", error);
+                }
+                window::close(id)
+            },
+        )
     }
 
     // 用于展示模态窗口
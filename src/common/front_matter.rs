@@ -0,0 +1,405 @@
+use std::ops::Range;
+
+// 文档开头的front matter，---包裹的为YAML，+++包裹的为TOML
+// 只解析顶层的简单字段和列表，足够显示和编辑标题、标签、日期等常用字段
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrontMatterFormat {
+    Yaml,
+    Toml,
+}
+
+impl FrontMatterFormat {
+    fn delimiter(&self) -> &'static str {
+        match self {
+            FrontMatterFormat::Yaml => "---",
+            FrontMatterFormat::Toml => "+++",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Text(String),
+    List(Vec<String>),
+}
+
+impl FieldValue {
+    // 显示用的文字，列表用逗号连接
+    pub fn display(&self) -> String {
+        match self {
+            FieldValue::Text(text) => text.clone(),
+            FieldValue::List(items) => items.join(", "),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrontMatterField {
+    pub key: String,
+    pub value: FieldValue,
+    // 字段所在行的字节范围，包括换行符和多行列表的后续行
+    pub range: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrontMatter {
+    pub format: FrontMatterFormat,
+    // 包括两侧分隔线在内的范围，end即正文开始的位置
+    pub range: Range<usize>,
+    // 结束分隔线所在行的开始位置，新增字段插在这里
+    pub closing_start: usize,
+    pub fields: Vec<FrontMatterField>,
+}
+
+// 表单中编辑的常用字段，为空表示删除该字段
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrontMatterFields {
+    pub title: String,
+    pub tags: Vec<String>,
+    pub date: String,
+}
+
+impl FrontMatter {
+    pub fn parse(raw: &str) -> Option<Self> {
+        let format = match raw.get(..3)? {
+            "---" => FrontMatterFormat::Yaml,
+            "+++" => FrontMatterFormat::Toml,
+            _ => return None,
+        };
+        let mut lines = raw.split_inclusive('\n');
+        let first_line = lines.next()?;
+        if first_line.trim_end() != format.delimiter() {
+            return None;
+        }
+        let mut offset = first_line.len();
+        let mut body_lines = vec![];
+        for line in lines {
+            let start = offset;
+            offset += line.len();
+            let trimmed = line.trim_end();
+            if trimmed == format.delimiter()
+                || (format == FrontMatterFormat::Yaml && trimmed == "...")
+            {
+                let fields = match format {
+                    FrontMatterFormat::Yaml => parse_yaml_fields(&body_lines),
+                    FrontMatterFormat::Toml => parse_toml_fields(&body_lines),
+                };
+                return Some(Self {
+                    format,
+                    range: 0..offset,
+                    closing_start: start,
+                    fields,
+                });
+            }
+            body_lines.push((start, line));
+        }
+        // 没有结束分隔线时不是front matter
+        None
+    }
+
+    pub fn get(&self, key: &str) -> Option<&FrontMatterField> {
+        self.fields
+            .iter()
+            .find(|field| field.key.eq_ignore_ascii_case(key))
+    }
+
+    pub fn text(&self, key: &str) -> Option<&str> {
+        match self.get(key).map(|field| &field.value) {
+            Some(FieldValue::Text(text)) if !text.is_empty() => Some(text),
+            _ => None,
+        }
+    }
+
+    // 字段写成单个字符串时按逗号拆分
+    pub fn list(&self, key: &str) -> Vec<String> {
+        match self.get(key).map(|field| &field.value) {
+            Some(FieldValue::List(items)) => items.clone(),
+            Some(FieldValue::Text(text)) => text
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.text("title")
+    }
+
+    pub fn common_fields(&self) -> FrontMatterFields {
+        FrontMatterFields {
+            title: self.title().unwrap_or_default().to_string(),
+            tags: self.list("tags"),
+            date: self.text("date").unwrap_or_default().to_string(),
+        }
+    }
+}
+
+// 更新常用字段，其余字段和写法保持不变，没有front matter时在开头插入YAML格式的
+pub fn update_common_fields(raw: &str, fields: &FrontMatterFields) -> String {
+    let front_matter = FrontMatter::parse(raw);
+    let format = front_matter
+        .as_ref()
+        .map(|front_matter| front_matter.format)
+        .unwrap_or(FrontMatterFormat::Yaml);
+    let values = [
+        ("title", format_text(format, &fields.title)),
+        ("date", format_date(format, &fields.date)),
+        ("tags", format_list(format, &fields.tags)),
+    ];
+    let Some(front_matter) = front_matter else {
+        let lines = values
+            .iter()
+            .filter_map(|(key, value)| value.as_ref().map(|value| field_line(format, key, value)))
+            .collect::<String>();
+        if lines.is_empty() {
+            return raw.to_string();
+        }
+        return format!("---\n{}---\n{}", lines, raw);
+    };
+    let mut edits = vec![];
+    let mut appended = String::new();
+    for (key, value) in &values {
+        let line = value.as_ref().map(|value| field_line(format, key, value));
+        match front_matter.get(key) {
+            Some(field) => edits.push((field.range.clone(), line.unwrap_or_default())),
+            None => appended.extend(line),
+        }
+    }
+    edits.push((
+        front_matter.closing_start..front_matter.closing_start,
+        appended,
+    ));
+    // 从后往前替换，前面的字节范围不受影响
+    edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+    let mut updated = raw.to_string();
+    for (range, text) in edits {
+        updated.replace_range(range, &text);
+    }
+    updated
+}
+
+fn parse_yaml_fields(lines: &[(usize, &str)]) -> Vec<FrontMatterField> {
+    let mut fields: Vec<FrontMatterField> = vec![];
+    for (start, line) in lines {
+        let end = start + line.len();
+        let trimmed = line.trim_end();
+        if trimmed.trim().is_empty() || trimmed.trim_start().starts_with('#') {
+            continue;
+        }
+        // 缩进的行和列表项属于上一个字段
+        if trimmed.starts_with([' ', '\t', '-']) {
+            if let Some(field) = fields.last_mut() {
+                field.range.end = end;
+                let item = trimmed.trim_start();
+                if let Some(item) = item.strip_prefix("- ").or(item.strip_prefix('-')) {
+                    match &mut field.value {
+                        FieldValue::List(items) => items.push(unquote(item.trim())),
+                        FieldValue::Text(text) if text.is_empty() => {
+                            field.value = FieldValue::List(vec![unquote(item.trim())]);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            continue;
+        }
+        let Some((key, value)) = trimmed.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        let value = match value
+            .strip_prefix('[')
+            .and_then(|value| value.strip_suffix(']'))
+        {
+            Some(items) => FieldValue::List(split_list(items)),
+            None => FieldValue::Text(unquote(value)),
+        };
+        fields.push(FrontMatterField {
+            key: unquote(key.trim()),
+            value,
+            range: *start..end,
+        });
+    }
+    fields
+}
+
+fn parse_toml_fields(lines: &[(usize, &str)]) -> Vec<FrontMatterField> {
+    let mut fields: Vec<FrontMatterField> = vec![];
+    // 多行数组还没有结束时记录已读取的内容
+    let mut open_array: Option<String> = None;
+    for (start, line) in lines {
+        let end = start + line.len();
+        let trimmed = line.trim();
+        if let Some(ref mut array) = open_array {
+            array.push_str(trimmed);
+            if let Some(field) = fields.last_mut() {
+                field.range.end = end;
+                if let Some(items) = array.strip_suffix(']') {
+                    field.value = FieldValue::List(split_list(items));
+                    open_array = None;
+                }
+            }
+            continue;
+        }
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        // 表之后的字段不再属于顶层
+        if trimmed.starts_with('[') {
+            break;
+        }
+        let Some((key, value)) = trimmed.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        let value = match value.strip_prefix('[') {
+            Some(items) => match items.strip_suffix(']') {
+                Some(items) => FieldValue::List(split_list(items)),
+                None => {
+                    open_array = Some(items.to_string());
+                    FieldValue::List(vec![])
+                }
+            },
+            None => FieldValue::Text(unquote(value)),
+        };
+        fields.push(FrontMatterField {
+            key: unquote(key.trim()),
+            value,
+            range: *start..end,
+        });
+    }
+    fields
+}
+
+fn split_list(items: &str) -> Vec<String> {
+    items
+        .split(',')
+        .map(|item| unquote(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn unquote(value: &str) -> String {
+    if value.len() >= 2 {
+        if let Some(inner) = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+        {
+            return inner.replace("\\\"", "\"").replace("\\\\", "\\");
+        }
+        if let Some(inner) = value
+            .strip_prefix('\'')
+            .and_then(|value| value.strip_suffix('\''))
+        {
+            return inner.to_string();
+        }
+    }
+    value.to_string()
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn field_line(format: FrontMatterFormat, key: &str, value: &str) -> String {
+    match format {
+        FrontMatterFormat::Yaml => format!("{}: {}\n", key, value),
+        FrontMatterFormat::Toml => format!("{} = {}\n", key, value),
+    }
+}
+
+// YAML中含有特殊字符时才加引号，TOML的字符串总是需要引号
+fn format_text(format: FrontMatterFormat, text: &str) -> Option<String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    let needs_quote = text.contains(": ")
+        || text.contains(" #")
+        || text.starts_with([
+            '#', '&', '*', '!', '|', '>', '\'', '"', '%', '@', '`', '[', '{', '-',
+        ]);
+    Some(match (format, needs_quote) {
+        (FrontMatterFormat::Yaml, false) => text.to_string(),
+        _ => quote(text),
+    })
+}
+
+// YYYY-MM-DD开头的日期在TOML中可以不加引号
+fn format_date(format: FrontMatterFormat, date: &str) -> Option<String> {
+    let date = date.trim();
+    let is_date = date.len() >= 10
+        && date.is_char_boundary(10)
+        && jiff::civil::Date::strptime("%Y-%m-%d", &date[..10]).is_ok();
+    match (format, is_date) {
+        (FrontMatterFormat::Toml, true) => (!date.is_empty()).then(|| date.to_string()),
+        _ => format_text(format, date),
+    }
+}
+
+fn format_list(format: FrontMatterFormat, items: &[String]) -> Option<String> {
+    let items = items
+        .iter()
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .map(|item| match format {
+            FrontMatterFormat::Yaml if !item.contains([',', '[', ']', '"', '#']) => {
+                item.to_string()
+            }
+            _ => quote(item),
+        })
+        .collect::<Vec<_>>();
+    (!items.is_empty()).then(|| format!("[{}]", items.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_front_matter() {
+        let raw = "---\ntitle: \"周报: 第一周\"\ntags:\n  - 工作\n  - rust\nauthor:\n  name: 张三\n---\n正文";
+        let front_matter = FrontMatter::parse(raw).unwrap();
+        assert_eq!(front_matter.title(), Some("周报: 第一周"));
+        assert_eq!(front_matter.list("tags"), ["工作", "rust"]);
+        assert_eq!(&raw[front_matter.range.end..], "正文");
+        let fields = FrontMatterFields {
+            title: "周报".to_string(),
+            tags: vec!["工作".to_string()],
+            date: "2024-05-01".to_string(),
+        };
+        assert_eq!(
+            update_common_fields(raw, &fields),
+            "---\ntitle: 周报\ntags: [工作]\nauthor:\n  name: 张三\ndate: 2024-05-01\n---\n正文"
+        );
+
+        let raw = "+++\ntitle = 'Hello'\ntags = [\n  \"a\",\n  \"b\",\n]\n[extra]\ntitle = \"x\"\n+++\nbody";
+        let front_matter = FrontMatter::parse(raw).unwrap();
+        assert_eq!(front_matter.format, FrontMatterFormat::Toml);
+        assert_eq!(front_matter.list("tags"), ["a", "b"]);
+        let fields = FrontMatterFields {
+            date: "2024-05-01".to_string(),
+            ..front_matter.common_fields()
+        };
+        assert_eq!(
+            update_common_fields(raw, &fields),
+            "+++\ntitle = \"Hello\"\ntags = [\"a\", \"b\"]\n[extra]\ntitle = \"x\"\ndate = 2024-05-01\n+++\nbody"
+        );
+
+        assert_eq!(FrontMatter::parse("---\n没有结束"), None);
+        assert_eq!(
+            update_common_fields(
+                "正文",
+                &FrontMatterFields {
+                    title: "标题".to_string(),
+                    ..Default::default()
+                }
+            ),
+            "---\ntitle: 标题\n---\n正文"
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use std::path::Path;
+
+use jiff::civil::{Date, Weekday};
+
+use crate::common::template::{self, TemplateContext};
+
+// 每日笔记，文件名为YYYY-MM-DD.md，放在工作区中设置的日记文件夹下
+// 新建时按模板生成内容，模板中的{{date}}、{{weekday}}为日记的日期和星期
+
+pub fn file_name(date: Date) -> String {
+    format!("{}.md", date)
+}
+
+// 文件名符合日期格式的md文件视为日记
+pub fn journal_date(path: &Path) -> Option<Date> {
+    let is_md = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("md"));
+    let stem = path.file_stem()?.to_str()?;
+    if !is_md || stem.len() != 10 {
+        return None;
+    }
+    Date::strptime("%Y-%m-%d", stem).ok()
+}
+
+pub fn weekday_label(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Monday => "星期一",
+        Weekday::Tuesday => "星期二",
+        Weekday::Wednesday => "星期三",
+        Weekday::Thursday => "星期四",
+        Weekday::Friday => "星期五",
+        Weekday::Saturday => "星期六",
+        Weekday::Sunday => "星期天",
+    }
+}
+
+// 日记模板和新建文件的模板使用同样的变量，标题为日记的日期
+pub fn render_template(template: &str, date: Date, author: &str) -> String {
+    let context = TemplateContext {
+        date_time: date.to_datetime(jiff::civil::Time::midnight()),
+        title: date.to_string(),
+        author: author.to_string(),
+    };
+    template::render(template, &context)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal() {
+        let date = jiff::civil::date(2024, 5, 1);
+        assert_eq!(file_name(date), "2024-05-01.md");
+        assert_eq!(
+            journal_date(Path::new("/notes/journal/2024-05-01.md")),
+            Some(date)
+        );
+        assert_eq!(journal_date(Path::new("/notes/journal/2024-5-1.md")), None);
+        assert_eq!(
+            journal_date(Path::new("/notes/journal/2024-05-01.txt")),
+            None
+        );
+        assert_eq!(
+            render_template("# {{date}} {{weekday}}\n", date, ""),
+            "# 2024-05-01 星期三\n"
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    path::PathBuf,
+};
+
+// 工作区的链接关系图，每个笔记为一个节点，笔记之间的markdown链接和[[链接]]为边
+// 边不区分方向，同一对笔记之间的多个链接只保留一条，指向自身的链接忽略
+#[derive(Debug, Clone, Default)]
+pub struct LinkGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GraphNode {
+    pub path: PathBuf,
+    pub label: String,
+}
+
+impl LinkGraph {
+    // links为各笔记链接到的笔记路径，不在nodes中的路径忽略
+    pub fn new(nodes: Vec<GraphNode>, links: &[(PathBuf, PathBuf)]) -> Self {
+        let indices = nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (node.path.clone(), index))
+            .collect::<HashMap<_, _>>();
+        let mut edges = links
+            .iter()
+            .filter_map(|(from, to)| Some((*indices.get(from)?, *indices.get(to)?)))
+            .filter(|(from, to)| from != to)
+            .map(|(from, to)| (from.min(to), from.max(to)))
+            .collect::<Vec<_>>();
+        edges.sort_unstable();
+        edges.dedup();
+        Self { nodes, edges }
+    }
+
+    pub fn index_of(&self, path: &std::path::Path) -> Option<usize> {
+        self.nodes.iter().position(|node| node.path == path)
+    }
+
+    pub fn degree(&self, index: usize) -> usize {
+        self.edges
+            .iter()
+            .filter(|(from, to)| *from == index || *to == index)
+            .count()
+    }
+
+    // 与center相距不超过hops条边的节点，包括center自身
+    pub fn neighbors_within(&self, center: usize, hops: usize) -> HashSet<usize> {
+        let mut adjacency: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (from, to) in &self.edges {
+            adjacency.entry(*from).or_default().push(*to);
+            adjacency.entry(*to).or_default().push(*from);
+        }
+        let mut visited = HashSet::from([center]);
+        let mut queue = VecDeque::from([(center, 0)]);
+        while let Some((index, distance)) = queue.pop_front() {
+            if distance >= hops {
+                continue;
+            }
+            for neighbor in adjacency.get(&index).into_iter().flatten() {
+                if visited.insert(*neighbor) {
+                    queue.push_back((*neighbor, distance + 1));
+                }
+            }
+        }
+        visited
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_graph() {
+        let nodes = ["a", "b", "c", "d", "e"]
+            .map(|name| GraphNode {
+                path: PathBuf::from(format!("/notes/{}.md", name)),
+                label: name.to_string(),
+            })
+            .to_vec();
+        let link = |from: &str, to: &str| {
+            (
+                PathBuf::from(format!("/notes/{}.md", from)),
+                PathBuf::from(format!("/notes/{}.md", to)),
+            )
+        };
+        let graph = LinkGraph::new(
+            nodes,
+            &[
+                link("a", "b"),
+                link("b", "a"),
+                link("b", "c"),
+                link("c", "d"),
+                link("d", "d"),
+                link("a", "missing"),
+            ],
+        );
+        assert_eq!(graph.edges, vec![(0, 1), (1, 2), (2, 3)]);
+        assert_eq!(graph.degree(1), 2);
+        assert_eq!(graph.neighbors_within(0, 0), HashSet::from([0]));
+        assert_eq!(graph.neighbors_within(0, 2), HashSet::from([0, 1, 2]));
+        assert_eq!(graph.neighbors_within(4, 3), HashSet::from([4]));
+    }
+}
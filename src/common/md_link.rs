@@ -0,0 +1,294 @@
+use std::{
+    ops::Range,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::common::wiki_link;
+
+// 预览中点击后交给系统程序打开的链接协议
+pub const EXTERNAL_LINK_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+// markdown中的行内链接和图片，[文字](地址 "标题")和![说明](地址)
+#[derive(Debug, Clone, PartialEq)]
+pub struct MdLink {
+    // 整个链接和其中地址部分的字节范围，地址不含尖括号
+    pub range: Range<usize>,
+    pub dest_range: Range<usize>,
+    pub dest: String,
+    pub is_image: bool,
+}
+
+// 解析全文中的链接，跳过代码块
+pub fn parse_md_links(raw: &str) -> Vec<MdLink> {
+    let mut links = vec![];
+    let mut fence = None;
+    let mut offset = 0;
+    for line in raw.split_inclusive('\n') {
+        if !wiki_link::update_fence(&mut fence, line) {
+            links.extend(find_md_links_in_line(line).into_iter().map(|mut link| {
+                link.range = link.range.start + offset..link.range.end + offset;
+                link.dest_range = link.dest_range.start + offset..link.dest_range.end + offset;
+                link
+            }));
+        }
+        offset += line.len();
+    }
+    links
+}
+
+// 解析单行中的链接，跳过行内代码，链接文字中嵌套的图片也会被解析
+pub fn find_md_links_in_line(line: &str) -> Vec<MdLink> {
+    let bytes = line.as_bytes();
+    let mut links = vec![];
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'`' => {
+                let run = bytes[index..].iter().take_while(|b| **b == b'`').count();
+                let ticks = &line[index..index + run];
+                index += match line[index + run..].find(ticks) {
+                    Some(end) => run + end + run,
+                    None => run,
+                };
+            }
+            b'\\' => index += 2,
+            b'[' => {
+                let is_image = index > 0 && bytes[index - 1] == b'!';
+                if let Some(mut link) = parse_link_at(line, index) {
+                    if is_image {
+                        link.range.start -= 1;
+                        link.is_image = true;
+                    }
+                    links.push(link);
+                }
+                index += 1;
+            }
+            _ => index += 1,
+        }
+    }
+    links
+}
+
+// 从左方括号开始解析，方括号需要配对，地址中的圆括号也需要配对
+fn parse_link_at(line: &str, start: usize) -> Option<MdLink> {
+    let bytes = line.as_bytes();
+    let mut depth = 0;
+    let mut index = start;
+    let text_end = loop {
+        match bytes.get(index)? {
+            b'\\' => index += 1,
+            b'[' => depth += 1,
+            b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    break index;
+                }
+            }
+            _ => {}
+        }
+        index += 1;
+    };
+    // [[笔记]]交给wiki链接处理
+    if bytes.get(start + 1) == Some(&b'[') && bytes.get(text_end - 1) == Some(&b']') {
+        return None;
+    }
+    if bytes.get(text_end + 1) != Some(&b'(') {
+        return None;
+    }
+    let mut index = text_end + 2;
+    while bytes.get(index).is_some_and(|b| *b == b' ') {
+        index += 1;
+    }
+    let dest_range = match bytes.get(index)? {
+        b'<' => {
+            let dest_start = index + 1;
+            let end = dest_start + line[dest_start..].find('>')?;
+            index = end + 1;
+            dest_start..end
+        }
+        _ => {
+            let dest_start = index;
+            let mut paren_depth = 0;
+            while let Some(b) = bytes.get(index) {
+                match b {
+                    b' ' | b'\t' | b'\n' | b'\r' => break,
+                    b'(' => paren_depth += 1,
+                    b')' if paren_depth == 0 => break,
+                    b')' => paren_depth -= 1,
+                    b'\\' => index += 1,
+                    _ => {}
+                }
+                index += 1;
+            }
+            dest_start..index.min(bytes.len())
+        }
+    };
+    // 跳过可选的标题，直到右圆括号
+    let close = index + line[index..].find(')')?;
+    let title = line[index..close].trim();
+    if !title.is_empty() && !title.starts_with(['"', '\'', '(']) {
+        return None;
+    }
+    Some(MdLink {
+        range: start..close + 1,
+        dest: line[dest_range.clone()].to_string(),
+        dest_range,
+        is_image: false,
+    })
+}
+
+// 带有协议的地址（http:、mailto:等）不是工作区中的文件
+pub fn is_external(dest: &str) -> bool {
+    match dest.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.len() > 1
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+// 拆分地址中的路径和#锚点
+pub fn split_anchor(dest: &str) -> (&str, Option<&str>) {
+    match dest.split_once('#') {
+        Some((path, anchor)) => (path, Some(anchor)),
+        None => (dest, None),
+    }
+}
+
+// 把%20等转义还原，无法还原时保留原文
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%'
+            && let Some(value) = text
+                .get(index + 1..index + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(value);
+            index += 3;
+            continue;
+        }
+        decoded.push(bytes[index]);
+        index += 1;
+    }
+    String::from_utf8(decoded).unwrap_or_else(|_| text.to_string())
+}
+
+// 按照预览的规则，相对于所在文件的文件夹解析地址，外部链接和只有锚点的链接返回None
+pub fn resolve_relative(base_file: &Path, dest: &str) -> Option<PathBuf> {
+    let dest = dest.trim();
+    if is_external(dest) {
+        return None;
+    }
+    let (path, _) = split_anchor(dest);
+    if path.is_empty() {
+        return None;
+    }
+    let path = PathBuf::from(percent_decode(path));
+    let joined = match path.is_absolute() {
+        true => path,
+        false => base_file.parent()?.join(path),
+    };
+    Some(normalize_path(&joined))
+}
+
+// 从文件夹指向目标文件的相对路径，统一使用/分隔，没有地址中需要转义的处理
+pub fn relative_path(from_dir: &Path, to: &Path) -> String {
+    let from = from_dir.components().collect::<Vec<_>>();
+    let to = to.components().collect::<Vec<_>>();
+    let common_len = from.iter().zip(to.iter()).take_while(|(a, b)| a == b).count();
+    let mut parts = vec!["..".to_string(); from.len() - common_len];
+    parts.extend(
+        to[common_len..]
+            .iter()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned()),
+    );
+    parts.join("/")
+}
+
+// 写回地址时，没有尖括号包裹的地址中不能出现空格
+pub fn format_dest(path: &str, is_angle_bracketed: bool) -> String {
+    match is_angle_bracketed {
+        true => path.to_string(),
+        false => path.replace(' ', "%20"),
+    }
+}
+
+// 按GitHub的规则由标题生成锚点，转为小写，空格换成-，去掉其余标点
+pub fn heading_anchor(title: &str) -> String {
+    title
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+// 锚点可以写成标题原文或生成的锚点，都不区分大小写
+pub fn is_heading_match(title: &str, anchor: &str) -> bool {
+    let anchor = percent_decode(anchor.trim());
+    title.trim().to_lowercase() == anchor.to_lowercase() || heading_anchor(title) == anchor.to_lowercase()
+}
+
+// 去掉路径中的.和..，不访问磁盘
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push(component);
+                }
+            }
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_md_links() {
+        let line = "见[周报](../周报.md#本周)和[![图](img/a%20b.png)](<笔记 1.md> \"标题\")，`[代码](x.md)`";
+        let links = find_md_links_in_line(line);
+        assert_eq!(links.len(), 3);
+        assert_eq!(links[0].dest, "../周报.md#本周");
+        assert_eq!(&line[links[0].range.clone()], "[周报](../周报.md#本周)");
+        assert_eq!(links[1].dest, "笔记 1.md");
+        assert!(links[2].is_image);
+        assert_eq!(&line[links[2].dest_range.clone()], "img/a%20b.png");
+        assert!(find_md_links_in_line("[[笔记]]").is_empty());
+
+        let base = Path::new("/notes/日记/今天.md");
+        assert_eq!(
+            resolve_relative(base, &links[0].dest),
+            Some(PathBuf::from("/notes/周报.md"))
+        );
+        assert_eq!(
+            resolve_relative(base, &links[2].dest),
+            Some(PathBuf::from("/notes/日记/img/a b.png"))
+        );
+        assert_eq!(resolve_relative(base, "https://example.com"), None);
+        assert_eq!(resolve_relative(base, "#本周"), None);
+        assert_eq!(
+            relative_path(Path::new("/notes/日记"), Path::new("/notes/img/a b.png")),
+            "../img/a b.png"
+        );
+        assert_eq!(heading_anchor("1. Getting Started!"), "1-getting-started");
+        assert!(is_heading_match("本周 计划", "%E6%9C%AC%E5%91%A8-%E8%AE%A1%E5%88%92"));
+    }
+}
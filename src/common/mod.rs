@@ -1,12 +1,31 @@
-use std::{path::PathBuf, sync::{Arc, LazyLock}};
+use std::{collections::HashMap, ops::Range, path::PathBuf, sync::{Arc, LazyLock}};
+use serde::{Deserialize, Serialize};
 use iced::{Border, Color, Font, Settings, Shadow, Theme, Vector, border::Radius, font, theme::Palette, widget::image};
+pub mod front_matter; // 文档开头YAML/TOML格式的front matter解析和更新
+pub mod journal; // 每日笔记的文件名、日期和模板
+pub mod link_graph; // 笔记之间的链接关系图和邻居查询
+pub mod md_link; // markdown行内链接和图片的解析，以及相对路径的解析规则
+pub mod tag; // 正文和front matter中的标签解析
+pub mod template; // 新建文件时的模板和模板变量
+pub mod wiki_link; // 笔记之间的[[链接]]解析和笔记名称索引
 // 这里定义各种公共类型
 // FileData用于文件区和编辑区交互
 #[derive(Debug, Clone)]
 pub struct FileData {
     pub global_id: u32,
     pub version: u64,
+    // 与磁盘内容一致的版本号，编辑器据此判断载入的内容是否已保存
+    pub saved_version: u64,
     pub content: Arc<String>,
+    // 文档路径，临时文件为空
+    pub path: Option<PathBuf>,
+}
+
+// 文档在编辑器中的光标所在行和滚动到的行，切换文件和恢复会话时使用
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DocView {
+    pub cursor_line: usize,
+    pub scroll_line: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -16,10 +35,222 @@ pub struct ImgData {
     pub handle: image::Handle,
 }
 
+// UnsavedDoc用于关闭窗口前汇总所有未保存的文档
+#[derive(Debug, Clone)]
+pub struct UnsavedDoc {
+    pub name: String,
+    pub path: Option<PathBuf>,
+    pub content: Arc<String>,
+    pub source: DocSource,
+}
+
+// 全文替换时单个文件的替换结果，original为搜索时读取的内容
+#[derive(Debug, Clone)]
+pub struct FileReplacement {
+    pub path: PathBuf,
+    pub original: Arc<String>,
+    pub replaced: Arc<String>,
+}
+
+// 全文搜索的目标文件，buffers为编辑器中未保存的内容，搜索时优先使用
+#[derive(Debug, Clone)]
+pub struct SearchTargets {
+    pub root_paths: Vec<PathBuf>,
+    pub files: Vec<PathBuf>,
+    pub buffers: HashMap<PathBuf, Arc<String>>,
+}
+
+// 工作区链接检查的结果，只包含有问题的文件
+#[derive(Debug, Clone)]
+pub struct FileLinkIssues {
+    pub path: PathBuf,
+    pub relative_path: String,
+    // 检查时读取的内容，修复时用来确认文件没有被再次修改
+    pub content: Arc<String>,
+    pub issues: Vec<LinkIssue>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LinkIssue {
+    pub kind: LinkIssueKind,
+    // 从0开始的行号，以及地址在全文中的字节范围
+    pub line: usize,
+    pub dest_range: Range<usize>,
+    pub dest: String,
+    // 按最接近的路径或标题给出的修改建议，替换整个地址
+    pub suggestion: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinkIssueKind {
+    BrokenLink,
+    MissingImage,
+    UnknownAnchor,
+}
+
+impl LinkIssueKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LinkIssueKind::BrokenLink => "链接失效",
+            LinkIssueKind::MissingImage => "图片缺失",
+            LinkIssueKind::UnknownAnchor => "标题不存在",
+        }
+    }
+}
+
+// 最近打开列表中条目的类型，文件在编辑器中打开，文件夹作为工作区打开
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RecentKind {
+    File,
+    Folder,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DocSource {
+    FileNode(u32),
+    TextBoard(String),
+}
+
+// 模态窗口的选项，按钮文字由选项本身决定
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DialogChoice {
+    Confirm,
+    Create,
+    Save,
+    DontSave,
+    SaveAll,
+    DiscardAll,
+    Cancel,
+}
+
+impl DialogChoice {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DialogChoice::Confirm => "确定",
+            DialogChoice::Create => "新建",
+            DialogChoice::Save => "保存",
+            DialogChoice::DontSave => "不保存",
+            DialogChoice::SaveAll => "全部保存",
+            DialogChoice::DiscardAll => "全部丢弃",
+            DialogChoice::Cancel => "取消",
+        }
+    }
+}
+
+// 右键菜单项，由各模块根据右键位置的内容生成
+#[derive(Debug, Clone)]
+pub struct ContextMenuItem {
+    pub label: &'static str,
+    pub action: ContextAction,
+}
+
+impl ContextMenuItem {
+    pub fn new(label: &'static str, action: ContextAction) -> Self {
+        Self { label, action }
+    }
+}
+
+// 右键菜单的各种操作，由App分发给对应模块
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContextAction {
+    // 文件树节点
+    OpenNode(u32),
+    CreateNewFile(u32),
+    CreateNewFolder(u32),
+    RenameNode(u32),
+    DuplicateNode(u32),
+    DeleteNode(u32),
+    RevealNode(u32),
+    // 编辑区
+    Cut,
+    Copy,
+    Paste,
+    SelectAll,
+    FormatBold,
+    FormatItalic,
+    FormatCode,
+    FormatLink,
+    // 图片，InsertImage为节点id，RemoveImage为图片库编号
+    InsertImage(u32),
+    RemoveImage(u32),
+    // 工作区根节点
+    CloseWorkspaceRoot(u32),
+}
+
+// 文件树的排序方式，文件夹始终排在文件前面
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum SortMode {
+    #[default]
+    Name,
+    Modified,
+    Size,
+}
+
+impl SortMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortMode::Name => "名称排序",
+            SortMode::Modified => "时间排序",
+            SortMode::Size => "大小排序",
+        }
+    }
+
+    // 工具栏按钮依次切换排序方式
+    pub fn next(&self) -> Self {
+        match self {
+            SortMode::Name => SortMode::Modified,
+            SortMode::Modified => SortMode::Size,
+            SortMode::Size => SortMode::Name,
+        }
+    }
+}
+
+// 每个工作区单独保存的文件树设置
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct TreeViewSetting {
+    pub sort_mode: SortMode,
+    // 隐藏不包含md文件的文件夹
+    pub hide_dirs_without_markdown: bool,
+}
+
+// .fugu-workspace工作区文件，保存打开的所有工作区和各自的文件树设置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkspaceFile {
+    pub roots: Vec<WorkspaceRoot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceRoot {
+    // 位于工作区文件所在文件夹下的路径保存为相对路径
+    pub path: PathBuf,
+    #[serde(default)]
+    pub tree_view: TreeViewSetting,
+}
+
 // 包括各种App设定
 #[derive(Debug, Clone)]
 pub struct AppSetting {
     pub auto_save: bool,
+    // 读取工作区时的最大层级和最大节点数量
+    pub scan_max_depth: usize,
+    pub scan_max_nodes: usize,
+    // 是否读取以.开头的隐藏文件和文件夹
+    pub scan_include_hidden: bool,
+    // 在编辑器中打开的文档扩展名，不含.
+    pub editable_extensions: Vec<String>,
+    // 启动时是否恢复上次的工作区、文件和窗口
+    pub restore_session: bool,
+    // 打开预览中http(s)和mailto链接的系统程序及其参数，链接作为最后一个参数
+    pub link_opener: Vec<String>,
+    // 文件树中用front matter的title字段代替文件名显示
+    pub title_as_label: bool,
+    // 每日笔记所在的文件夹，相对于工作区根目录，以及新建日记时使用的模板
+    pub journal_folder: String,
+    pub journal_template: String,
+    // 工作区中的模板文件夹，相对于工作区根目录，全局模板放在配置目录的templates文件夹中
+    pub template_folder: String,
+    // 模板中{{author}}替换的作者名
+    pub author: String,
 }
 // 全局错误类型
 #[derive(Debug, Clone)]
@@ -55,6 +286,21 @@ impl From<tokio::task::JoinError> for AppError {
     }
 }
 
+// 用设置中的程序打开外部链接、附件和文件夹，默认是各平台的LINK_OPENER
+pub async fn open_with_system(
+    opener: Vec<String>,
+    target: impl AsRef<std::ffi::OsStr>,
+) -> std::io::Result<()> {
+    let Some((program, args)) = opener.split_first() else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "没有设置打开链接的程序!",
+        ));
+    };
+    tokio::process::Command::new(program).args(args).arg(target).spawn()?;
+    Ok(())
+}
+
 //这里主要定义各种常量
 // 
 // 
@@ -125,22 +371,77 @@ pub const SHADOW_BASE_0_OFFSET: Shadow = Shadow {
     offset: Vector::ZERO
 };
 // 默认设置
-pub const DEFAULT_USER_SETTING: AppSetting = AppSetting {
-    auto_save: false
-};
+pub static DEFAULT_USER_SETTING: LazyLock<AppSetting> = LazyLock::new(|| {
+    AppSetting {
+        auto_save: false,
+        scan_max_depth: 32,
+        scan_max_nodes: 20000,
+        scan_include_hidden: false,
+        editable_extensions: EDITABLE_EXTENSIONS.map(String::from).to_vec(),
+        restore_session: true,
+        link_opener: LINK_OPENER.map(String::from).to_vec(),
+        title_as_label: false,
+        journal_folder: "journal".to_string(),
+        journal_template: "# {{date}} {{weekday}}\n\n".to_string(),
+        template_folder: ".templates".to_string(),
+        author: std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .unwrap_or_default(),
+    }
+});
+// 内置的中文字体，画布中的文字需要单独指定
+pub const DEFAULT_FONT: Font = Font::with_name("Noto Sans Mono CJK SC");
 // 默认aaa设置
 pub const DEFAULT_APP_SETTING: LazyLock<Settings> = LazyLock::new(|| {
     Settings {
         fonts: vec![
             include_bytes!("../../assets/NotoSansMonoCJKsc-VF.ttf").into()
         ],
-        default_font: Font::with_name("Noto Sans Mono CJK SC"),
+        default_font: DEFAULT_FONT,
         ..Default::default()
     }
 });
 
 // 文件区相关常量
 pub const TEXT_INDENTATION: u16 = 15;
+// 文件树每行的固定高度，只渲染可见行时据此计算滚动位置
+pub const FILE_TREE_ROW_HEIGHT: f32 = 24.;
+// 可见范围上下额外渲染的行数，避免快速滚动时出现空白
+pub const FILE_TREE_OVERSCAN_ROWS: usize = 10;
+// 按住节点后鼠标移动超过该距离才开始拖动，避免点击时轻微抖动被当作拖动
+pub const FILE_TREE_DRAG_THRESHOLD: f32 = 4.;
+// 可以在编辑器中打开的文档扩展名，md始终启用，其余可在设置中开关
+pub const EDITABLE_EXTENSIONS: [&str; 4] = ["md", "markdown", "mdx", "txt"];
+// 各平台打开外部链接的默认程序，Windows的start把第一个带引号的参数当作窗口标题
+#[cfg(target_os = "windows")]
+pub const LINK_OPENER: [&str; 4] = ["cmd", "/c", "start", ""];
+#[cfg(target_os = "macos")]
+pub const LINK_OPENER: [&str; 1] = ["open"];
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub const LINK_OPENER: [&str; 1] = ["xdg-open"];
+// 工作区文件的扩展名
+pub const WORKSPACE_FILE_EXTENSION: &str = "fugu-workspace";
+// 工作区中显示的附件扩展名，图片可以在图片库中查看，其余用系统默认程序打开
+pub const IMAGE_EXTENSIONS: [&str; 6] = ["png", "jpg", "jpeg", "gif", "webp", "bmp"];
+pub const PDF_EXTENSIONS: [&str; 1] = ["pdf"];
+pub const AUDIO_EXTENSIONS: [&str; 5] = ["mp3", "wav", "ogg", "flac", "m4a"];
+pub const VIDEO_EXTENSIONS: [&str; 5] = ["mp4", "mov", "webm", "mkv", "avi"];
+// 工作区变动的轮询间隔，连续失败达到次数后停止轮询该工作区
+pub const WORKSPACE_POLL_INTERVAL_MS: u64 = 1000;
+pub const WORKSPACE_POLL_MAX_ERRORS: usize = 5;
+// 读取工作区时跳过的依赖和构建产物文件夹
+pub const SCAN_SKIPPED_DIR_NAMES: [&str; 6] = [
+    ".git",
+    ".svn",
+    "node_modules",
+    "target",
+    "vendor",
+    "__pycache__",
+];
+// 扫描进度刷新间隔
+pub const SCAN_PROGRESS_INTERVAL_MS: u64 = 100;
+// 会话状态的定时保存间隔
+pub const SESSION_SAVE_INTERVAL_SECS: u64 = 60;
 // 背景颜色
 //pub const FILE_PANEL_BG_COLOR: Color = Color::from_rgb8(47, 52, 62);
 // 默认文字大小
@@ -153,8 +454,35 @@ pub const TEXT_INDENTATION: u16 = 15;
 
 pub const MENU_WIDTH: f32 = 150.;
 pub const MENU_OFFSET: f32 = 5.;
+// 最近打开子菜单的宽度，以及不含固定条目的最大条目数
+pub const RECENT_MENU_WIDTH: f32 = 360.;
+pub const RECENT_LIST_LIMIT: usize = 15;
+// 子菜单条目的hover编号从这里开始，避免与上级菜单冲突
+pub const RECENT_MENU_ID_OFFSET: usize = 100;
+// 右键菜单单项高度估计值，用于防止菜单超出窗口
+pub const CONTEXT_MENU_ITEM_HEIGHT: f32 = 32.;
+
+// 快速打开相关常量
+pub const QUICK_OPEN_WIDTH: f32 = 500.;
+pub const QUICK_OPEN_MAX_RESULTS: usize = 20;
+// 快速打开面板距离窗口顶部的距离
+pub const QUICK_OPEN_OFFSET: f32 = 80.;
+// 记录的最近打开文件数量
+pub const RECENT_NODES_LIMIT: usize = 50;
+// 模糊匹配到的字符的高亮颜色
+pub const MATCH_HIGHLIGHT_COLOR: Color = Color::from_rgb8(229, 192, 123);
+// 全文搜索结果中每行预览的最大字符数和第一处匹配前保留的字符数
+pub const SEARCH_PREVIEW_MAX_CHARS: usize = 120;
+pub const SEARCH_PREVIEW_CONTEXT_CHARS: usize = 30;
+pub const SEARCH_LINE_NUMBER_WIDTH: f32 = 40.;
+// 配置目录下存放全局模板的文件夹
+pub const GLOBAL_TEMPLATE_FOLDER: &str = "templates";
+// 重命名或移动后确认更新链接时最多列出的文件数
+pub const LINK_REWRITE_LIST_MAX: usize = 10;
 
 // 编辑区相关常量
+// 标签自动补全最多显示的候选数
+pub const TAG_COMPLETION_MAX: usize = 8;
 pub const H1_COLOR: Color = Color::from_rgb8(229, 192, 123);
 pub const H2_COLOR: Color = Color::from_rgb8(198, 120, 221);
 pub const H3_COLOR: Color = Color::from_rgb8(97, 175, 239);
@@ -164,14 +492,40 @@ pub const H6_COLOR: Color = Color::from_rgb8(150, 150, 150);
 pub const ORDERED_LSIT_COLOR: Color = Color::from_rgb8(209, 154, 102);
 pub const CODE_BLOCK_TEXT_COLOR: Color = Color::from_rgb8(152, 195, 121);
 pub const QUOTE_MARK_COLOR: Color = Color::from_rgb8(224, 108, 117);
+// 编辑器中的[[链接]]，以及预览中找不到对应笔记的链接
+pub const WIKI_LINK_COLOR: Color = Color::from_rgb8(86, 182, 194);
+pub const WIKI_MISSING_LINK_COLOR: Color = Color::from_rgb8(190, 80, 70);
 // 预览区相关常量
 //pub const PREVIEW_BG_COLOR: Color = Color::from_rgb8(47, 52, 62);
+// 日历中每天格子的高度
+pub const CALENDAR_CELL_HEIGHT: f32 = 56.;
+// 关系图力导向布局的参数，坐标单位为缩放前的像素
+pub const GRAPH_EDGE_LENGTH: f32 = 80.;
+pub const GRAPH_NODE_RADIUS: f32 = 5.;
+pub const GRAPH_LAYOUT_STEPS: usize = 300;
+pub const GRAPH_STEPS_PER_TICK: usize = 3;
+// 每帧布局计算的时间上限，节点很多时减少迭代次数，避免界面卡顿
+pub const GRAPH_TICK_BUDGET_MS: u64 = 8;
+// 斥力只计算该距离内的节点，按该大小划分网格后只查找相邻格子
+pub const GRAPH_REPULSION_RANGE: f32 = GRAPH_EDGE_LENGTH * 3.;
+pub const GRAPH_GRAVITY: f32 = 0.02;
+pub const GRAPH_MIN_SCALE: f32 = 0.1;
+pub const GRAPH_MAX_SCALE: f32 = 5.;
+// 缩放比例小于该值时只显示当前和鼠标所在笔记的名称
+pub const GRAPH_LABEL_SCALE: f32 = 0.6;
+pub const GRAPH_MAX_HOPS: usize = 5;
 
 
 // 模态窗口常量
 pub const TABLE_DIALOG_HEIGHT: u32 = 150;
 pub const TABLE_DIALOG_WIDTH: u32 = 200;
 pub const CONFIRM_DIALOG_WIDTH: u32 = 450;
+pub const FRONT_MATTER_DIALOG_WIDTH: u32 = 360;
+pub const FRONT_MATTER_DIALOG_HEIGHT: u32 = 220;
+pub const PREFERENCE_LABEL_WIDTH: f32 = 80.;
+pub const PREFERENCE_TEMPLATE_HEIGHT: f32 = 120.;
+pub const CLOSE_GUARD_LIST_HEIGHT: u32 = 200;
+pub const NEW_FILE_TEMPLATE_LIST_HEIGHT: u32 = 240;
 
 
 
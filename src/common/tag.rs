@@ -0,0 +1,136 @@
+use std::ops::Range;
+
+use crate::common::{front_matter::FrontMatter, wiki_link};
+
+// 笔记的标签，支持正文中的#标签和front matter中的tags字段
+// 标签不区分大小写，可以用/分隔层级，如#项目/周报
+
+// 解析全文中的标签，按第一次出现的写法去重
+pub fn parse_tags(raw: &str) -> Vec<String> {
+    let (mut tags, body_offset) = front_matter_tags(raw);
+    let mut fence = None;
+    for line in raw[body_offset..].lines() {
+        if wiki_link::update_fence(&mut fence, line) {
+            continue;
+        }
+        tags.extend(find_tags_in_line(line).into_iter().map(|(_, tag)| tag));
+    }
+    let mut keys = vec![];
+    tags.retain(|tag| {
+        let key = tag.to_lowercase();
+        match keys.contains(&key) {
+            true => false,
+            false => {
+                keys.push(key);
+                true
+            }
+        }
+    });
+    tags
+}
+
+// 解析单行中的#标签，返回包含#在内的字节范围，跳过行内代码和标题标记
+pub fn find_tags_in_line(line: &str) -> Vec<(Range<usize>, String)> {
+    let bytes = line.as_bytes();
+    let mut tags = vec![];
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'`' => {
+                let run = bytes[index..].iter().take_while(|b| **b == b'`').count();
+                let ticks = &line[index..index + run];
+                index += match line[index + run..].find(ticks) {
+                    Some(end) => run + end + run,
+                    None => run,
+                };
+            }
+            b'#' if is_tag_start(line, index) => {
+                let name_len = tag_name_len(&line[index + 1..]);
+                let name = &line[index + 1..index + 1 + name_len];
+                if is_valid_tag(name) {
+                    tags.push((index..index + 1 + name_len, name.to_string()));
+                }
+                index += 1 + name_len;
+            }
+            _ => index += 1,
+        }
+    }
+    tags
+}
+
+// 光标前正在输入的标签，用于编辑器中的自动补全，只输入了#时返回空字符串
+pub fn tag_prefix_before(line: &str, column: usize) -> Option<&str> {
+    let before = line.get(..column)?;
+    let start = before.rfind('#')?;
+    let prefix = &before[start + 1..];
+    (is_tag_start(line, start) && tag_name_len(prefix) == prefix.len()).then_some(prefix)
+}
+
+// 选中某个标签时也包括它的下级标签
+pub fn is_tag_match(tag: &str, filter: &str) -> bool {
+    let tag = tag.to_lowercase();
+    let filter = filter.to_lowercase();
+    tag == filter || tag.starts_with(&format!("{}/", filter))
+}
+
+// #前面需要是行首或空白，避免把网址中的锚点和标题标记当作标签
+fn is_tag_start(line: &str, index: usize) -> bool {
+    line[..index]
+        .chars()
+        .next_back()
+        .is_none_or(char::is_whitespace)
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '/')
+}
+
+fn tag_name_len(text: &str) -> usize {
+    text.char_indices()
+        .find(|(_, c)| !is_tag_char(*c))
+        .map(|(index, _)| index)
+        .unwrap_or(text.len())
+}
+
+// 纯数字的#1之类通常是编号，不作为标签
+fn is_valid_tag(name: &str) -> bool {
+    let name = name.trim_matches('/');
+    !name.is_empty() && !name.chars().all(|c| c.is_ascii_digit())
+}
+
+// 读取front matter中的tags字段，返回标签和正文开始的位置
+fn front_matter_tags(raw: &str) -> (Vec<String>, usize) {
+    match FrontMatter::parse(raw) {
+        Some(front_matter) => (
+            front_matter
+                .list("tags")
+                .iter()
+                .flat_map(|value| parse_tag_value(value))
+                .collect(),
+            front_matter.range.end,
+        ),
+        None => (vec![], 0),
+    }
+}
+
+fn parse_tag_value(value: &str) -> Option<String> {
+    let value = value.trim().trim_start_matches('#').trim();
+    (is_valid_tag(value) && tag_name_len(value) == value.len()).then(|| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tags() {
+        let raw = "---\ntitle: 周报\ntags: [工作, \"#Rust\"]\naliases:\n  - 周报\n---\n# 标题 #标题标签\n今天 #工作 和 #rust/异步，不是#标签，也不是 #123\n`#代码` [链接](a.md#锚点)\n```\n#代码块\n```";
+        assert_eq!(parse_tags(raw), ["工作", "Rust", "标题标签", "rust/异步"]);
+        let raw = "---\ntags:\n  - 读书\n  - 笔记\n---\n正文";
+        assert_eq!(parse_tags(raw), ["读书", "笔记"]);
+        assert_eq!(tag_prefix_before("今天 #工", "今天 #工".len()), Some("工"));
+        assert_eq!(tag_prefix_before("a#b", 3), None);
+        assert!(is_tag_match("Rust/异步", "rust"));
+        assert!(!is_tag_match("rustacean", "rust"));
+    }
+}
@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+
+use jiff::civil::DateTime;
+
+use crate::common::journal;
+
+// 新建文件时使用的模板，模板文件夹中的每个md文件为一个模板
+// 模板中的{{date}}、{{time}}、{{weekday}}、{{title}}、{{author}}在新建时替换
+// 日期和时间可以指定格式，如{{date:%Y年%m月%d日}}，无法识别的变量保持原样
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    pub name: String,
+    pub path: PathBuf,
+    // 来自全局模板文件夹，工作区中的同名模板优先
+    pub is_global: bool,
+}
+
+// 在模板选择窗口中确认后新建文件，template为空时新建空白文档
+#[derive(Debug, Clone)]
+pub struct NewFileRequest {
+    pub dir_key: u32,
+    pub title: String,
+    pub template: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TemplateContext {
+    pub date_time: DateTime,
+    pub title: String,
+    pub author: String,
+}
+
+pub fn render(template: &str, context: &TemplateContext) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}").map(|end| start + 2 + end) else {
+            break;
+        };
+        rendered.push_str(&rest[..start]);
+        match expand_variable(rest[start + 2..end].trim(), context) {
+            Some(value) => rendered.push_str(&value),
+            None => rendered.push_str(&rest[start..end + 2]),
+        }
+        rest = &rest[end + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+fn expand_variable(variable: &str, context: &TemplateContext) -> Option<String> {
+    let (name, format) = match variable.split_once(':') {
+        Some((name, format)) => (name.trim(), Some(format)),
+        None => (variable, None),
+    };
+    match name {
+        "date" => format_date_time(format.unwrap_or("%Y-%m-%d"), context.date_time),
+        "time" => format_date_time(format.unwrap_or("%H:%M"), context.date_time),
+        "weekday" => Some(journal::weekday_label(context.date_time.weekday()).to_string()),
+        "title" => Some(context.title.clone()),
+        "author" => Some(context.author.clone()),
+        _ => None,
+    }
+}
+
+// 用户填写的格式可能不合法，格式化失败时保持原样
+fn format_date_time(format: &str, date_time: DateTime) -> Option<String> {
+    jiff::fmt::strtime::format(format, date_time).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let context = TemplateContext {
+            date_time: jiff::civil::date(2024, 5, 1).at(9, 30, 0, 0),
+            title: "周报".to_string(),
+            author: "张三".to_string(),
+        };
+        assert_eq!(
+            render(
+                "# {{ title }}\n{{date}} {{time}} {{weekday}}\n{{date:%Y年%m月%d日}} {{author}} {{unknown}} {{",
+                &context
+            ),
+            "# 周报\n2024-05-01 09:30 星期三\n2024年05月01日 张三 {{unknown}} {{"
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use std::{
+    collections::HashMap,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use crate::common::EDITABLE_EXTENSIONS;
+
+// 预览时[[链接]]被转换成这两种协议的markdown链接，点击时据此打开或新建笔记
+pub const WIKI_LINK_SCHEME: &str = "wiki:";
+pub const WIKI_NEW_LINK_SCHEME: &str = "wiki-new:";
+
+// 笔记之间的链接，支持[[笔记]]、[[笔记#标题]]和[[笔记|别名]]三种写法
+#[derive(Debug, Clone, PartialEq)]
+pub struct WikiLink {
+    // 包含两侧方括号在内的字节范围
+    pub range: Range<usize>,
+    pub note: String,
+    pub heading: Option<String>,
+    pub alias: Option<String>,
+}
+
+impl WikiLink {
+    // 显示的文字，有别名时只显示别名
+    pub fn label(&self) -> String {
+        match (&self.alias, &self.heading) {
+            (Some(alias), _) => alias.clone(),
+            (None, Some(heading)) => format!("{} > {}", self.note, heading),
+            (None, None) => self.note.clone(),
+        }
+    }
+
+    pub fn target(&self) -> String {
+        match &self.heading {
+            Some(heading) => format!("{}#{}", self.note, heading),
+            None => self.note.clone(),
+        }
+    }
+}
+
+// 工作区中所有笔记按名称建立的索引，名称不区分大小写且不含扩展名
+#[derive(Debug, Clone, Default)]
+pub struct NoteIndex {
+    notes: HashMap<String, Vec<PathBuf>>,
+}
+
+impl NoteIndex {
+    pub fn new(paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        let mut notes: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        for path in paths {
+            let Some(name) = path.file_name().map(|name| name.to_string_lossy().into_owned())
+            else {
+                continue;
+            };
+            notes.entry(note_key(&name)).or_default().push(path);
+        }
+        // 同名笔记优先解析到层级更浅的
+        for paths in notes.values_mut() {
+            paths.sort_by_key(|path| (path.components().count(), path.clone()));
+            paths.dedup();
+        }
+        Self { notes }
+    }
+
+    pub fn insert(&mut self, path: PathBuf) {
+        let Some(name) = path.file_name().map(|name| name.to_string_lossy().into_owned()) else {
+            return;
+        };
+        let paths = self.notes.entry(note_key(&name)).or_default();
+        if !paths.contains(&path) {
+            paths.push(path);
+            paths.sort_by_key(|path| (path.components().count(), path.clone()));
+        }
+    }
+
+    // 删除该路径本身和其下的所有笔记
+    pub fn remove_under(&mut self, path: &Path) {
+        self.notes.retain(|_, paths| {
+            paths.retain(|note_path| !note_path.starts_with(path));
+            !paths.is_empty()
+        });
+    }
+
+    // 链接中带有上级文件夹时(如[[日记/周报]])只匹配该文件夹下的同名笔记
+    pub fn resolve(&self, note: &str) -> Option<&Path> {
+        let note = note.trim().trim_start_matches('/');
+        let (dir, name) = match note.rsplit_once('/') {
+            Some((dir, name)) => (Some(dir), name),
+            None => (None, note),
+        };
+        let paths = self.notes.get(&note_key(name))?;
+        match dir {
+            Some(dir) => paths
+                .iter()
+                .find(|path| path.parent().is_some_and(|parent| parent.ends_with(dir))),
+            None => paths.first(),
+        }
+        .map(PathBuf::as_path)
+    }
+}
+
+// 去掉可编辑文件的扩展名并转为小写，[[笔记]]和[[笔记.md]]指向同一个文件
+pub fn note_key(name: &str) -> String {
+    let name = name.trim();
+    let stem = match Path::new(name).extension().and_then(|extension| extension.to_str()) {
+        Some(extension) if EDITABLE_EXTENSIONS.contains(&extension.to_lowercase().as_str()) => {
+            &name[..name.len() - extension.len() - 1]
+        }
+        _ => name,
+    };
+    stem.to_lowercase()
+}
+
+// 拆分链接目标中的笔记名和标题
+pub fn split_target(target: &str) -> (String, Option<String>) {
+    match target.split_once('#') {
+        Some((note, heading)) if !heading.trim().is_empty() => {
+            (note.trim().to_string(), Some(heading.trim().to_string()))
+        }
+        Some((note, _)) => (note.trim().to_string(), None),
+        None => (target.trim().to_string(), None),
+    }
+}
+
+// 记录代码块围栏的状态，返回该行是否属于代码块（包括围栏本身）
+pub fn update_fence(fence: &mut Option<(char, usize)>, line: &str) -> bool {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    let trimmed = line.trim();
+    let fence_char = trimmed.chars().next().filter(|c| *c == '`' || *c == '~');
+    if indent < 4
+        && let Some(fence_char) = fence_char
+    {
+        let fence_len = trimmed.chars().take_while(|c| *c == fence_char).count();
+        match *fence {
+            None if fence_len >= 3 => {
+                *fence = Some((fence_char, fence_len));
+                return true;
+            }
+            Some((open_char, open_len))
+                if open_char == fence_char
+                    && fence_len >= open_len
+                    && trimmed.chars().all(|c| c == fence_char) =>
+            {
+                *fence = None;
+                return true;
+            }
+            _ => {}
+        }
+    }
+    fence.is_some()
+}
+
+// 解析全文中的链接，跳过代码块
+pub fn parse_wiki_links(raw: &str) -> Vec<WikiLink> {
+    let mut links = vec![];
+    let mut fence = None;
+    let mut offset = 0;
+    for line in raw.split_inclusive('\n') {
+        if !update_fence(&mut fence, line) {
+            links.extend(find_wiki_links_in_line(line).into_iter().map(|mut link| {
+                link.range = link.range.start + offset..link.range.end + offset;
+                link
+            }));
+        }
+        offset += line.len();
+    }
+    links
+}
+
+// 解析单行中的链接，跳过行内代码
+pub fn find_wiki_links_in_line(line: &str) -> Vec<WikiLink> {
+    let bytes = line.as_bytes();
+    let mut links = vec![];
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'`' => {
+                let run = bytes[index..].iter().take_while(|b| **b == b'`').count();
+                let ticks = &line[index..index + run];
+                // 没有对应的结束反引号时按普通字符处理
+                index += match line[index + run..].find(ticks) {
+                    Some(end) => run + end + run,
+                    None => run,
+                };
+            }
+            b'[' if bytes.get(index + 1) == Some(&b'[') => {
+                let link = line[index + 2..].find("]]").and_then(|end| {
+                    parse_link_inner(&line[index + 2..index + 2 + end], index..index + end + 4)
+                });
+                match link {
+                    Some(link) => {
+                        index = link.range.end;
+                        links.push(link);
+                    }
+                    None => index += 1,
+                }
+            }
+            _ => index += 1,
+        }
+    }
+    links
+}
+
+fn parse_link_inner(inner: &str, range: Range<usize>) -> Option<WikiLink> {
+    if inner.contains(['[', ']']) {
+        return None;
+    }
+    let (target, alias) = match inner.split_once('|') {
+        Some((target, alias)) => (target, Some(alias.trim()).filter(|alias| !alias.is_empty())),
+        None => (inner, None),
+    };
+    let (note, heading) = split_target(target);
+    if note.is_empty() {
+        return None;
+    }
+    Some(WikiLink {
+        range,
+        note,
+        heading,
+        alias: alias.map(str::to_string),
+    })
+}
+
+// 预览前把链接转换成markdown链接，找不到对应笔记的链接使用新建笔记的协议
+pub fn wiki_links_to_markdown(raw: &str, index: &NoteIndex) -> String {
+    let mut converted = String::with_capacity(raw.len());
+    let mut last_end = 0;
+    for link in parse_wiki_links(raw) {
+        let target = link.target();
+        // 尖括号包裹的链接地址中不能再出现尖括号
+        if target.contains(['<', '>']) {
+            continue;
+        }
+        let scheme = match index.resolve(&link.note) {
+            Some(_) => WIKI_LINK_SCHEME,
+            None => WIKI_NEW_LINK_SCHEME,
+        };
+        converted.push_str(&raw[last_end..link.range.start]);
+        converted.push_str(&format!(
+            "[{}](<{}{}>)",
+            escape_markdown(&link.label()),
+            scheme,
+            target
+        ));
+        last_end = link.range.end;
+    }
+    converted.push_str(&raw[last_end..]);
+    converted
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wiki_links() {
+        let raw = "见[[周报]]和[[项目/计划#目标|目标]]\n`[[代码]]`\n```\n[[代码块]]\n```\n[[不存在]]";
+        let links = parse_wiki_links(raw);
+        assert_eq!(links.len(), 3);
+        assert_eq!(&raw[links[0].range.clone()], "[[周报]]");
+        assert_eq!(links[1].note, "项目/计划");
+        assert_eq!(links[1].heading.as_deref(), Some("目标"));
+        assert_eq!(links[1].alias.as_deref(), Some("目标"));
+
+        let index = NoteIndex::new([
+            PathBuf::from("/notes/周报.md"),
+            PathBuf::from("/notes/项目/计划.md"),
+            PathBuf::from("/notes/归档/计划.md"),
+        ]);
+        assert_eq!(index.resolve("周报.MD"), Some(Path::new("/notes/周报.md")));
+        assert_eq!(index.resolve("归档/计划"), Some(Path::new("/notes/归档/计划.md")));
+        assert_eq!(index.resolve("不存在"), None);
+
+        let converted = wiki_links_to_markdown(raw, &index);
+        assert!(converted.starts_with("见[周报](<wiki:周报>)和[目标](<wiki:项目/计划#目标>)"));
+        assert!(converted.contains("`[[代码]]`"));
+        assert!(converted.ends_with("[不存在](<wiki-new:不存在>)"));
+
+        let mut index = index;
+        index.remove_under(Path::new("/notes/项目"));
+        assert_eq!(index.resolve("计划"), Some(Path::new("/notes/归档/计划.md")));
+        index.insert(PathBuf::from("/notes/计划.md"));
+        assert_eq!(index.resolve("计划"), Some(Path::new("/notes/计划.md")));
+    }
+}
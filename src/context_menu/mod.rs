@@ -0,0 +1,165 @@
+use crate::{common::*, context_menu::right_press::RightPressArea};
+use iced::{
+    Background, Border, Element, Length, Padding, Point, Size, Task, Theme, mouse,
+    widget::{column, container, mouse_area, pin, space, text},
+};
+
+mod right_press; // 右键按下时记录鼠标位置的外层控件
+
+#[derive(Debug)]
+pub struct ContextMenu {
+    // 为None时菜单处于关闭状态
+    position: Option<Point>,
+    items: Vec<ContextMenuItem>,
+    hovered_id: Option<usize>,
+    // 最近一次右键按下时的鼠标位置和窗口大小，打开菜单时据此确定菜单位置
+    cursor_position: Point,
+    window_size: Size,
+}
+
+#[derive(Debug, Clone)]
+pub enum ContextMenuMessage {
+    Open(Vec<ContextMenuItem>),
+    Close,
+    HoverEnter(usize),
+    RightPressed(Point, Size),
+    ItemClicked(ContextAction),
+    SendAction(ContextAction),
+}
+
+impl ContextMenu {
+    pub fn new() -> Self {
+        Self {
+            position: None,
+            items: vec![],
+            hovered_id: None,
+            cursor_position: Point::ORIGIN,
+            window_size: Size::new(DEFAULT_WINDOW_SIZE[0], DEFAULT_WINDOW_SIZE[1]),
+        }
+    }
+
+    pub fn update(&mut self, message: ContextMenuMessage) -> Task<ContextMenuMessage> {
+        match message {
+            ContextMenuMessage::Open(items) => {
+                if items.is_empty() {
+                    return Task::done(ContextMenuMessage::Close);
+                }
+                // 靠近窗口右侧或底部时向内收，保证菜单完整显示
+                let menu_height = items.len() as f32 * CONTEXT_MENU_ITEM_HEIGHT;
+                self.position = Some(Point::new(
+                    self.cursor_position
+                        .x
+                        .min(self.window_size.width - MENU_WIDTH)
+                        .max(0.),
+                    self.cursor_position
+                        .y
+                        .min(self.window_size.height - menu_height)
+                        .max(0.),
+                ));
+                self.items = items;
+                self.hovered_id = None;
+                Task::none()
+            }
+            ContextMenuMessage::Close => {
+                self.position = None;
+                self.items.clear();
+                Task::none()
+            }
+            ContextMenuMessage::HoverEnter(id) => {
+                self.hovered_id = Some(id);
+                Task::none()
+            }
+            ContextMenuMessage::RightPressed(position, size) => {
+                self.cursor_position = position;
+                self.window_size = size;
+                Task::none()
+            }
+            ContextMenuMessage::ItemClicked(action) => {
+                self.position = None;
+                self.items.clear();
+                Task::done(ContextMenuMessage::SendAction(action))
+            }
+            _ => Task::none(),
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, ContextMenuMessage> {
+        let Some(position) = self.position else {
+            return space().into();
+        };
+        let menu = container(column(self.items.iter().enumerate().map(|(id, item)| {
+            self.generate_menu_item(id, item)
+        })))
+        .padding(PADDING_SMALLEST)
+        .width(MENU_WIDTH)
+        .style(|theme: &Theme| {
+            let ex_palette = theme.extended_palette();
+            container::Style {
+                background: Some(Background::Color(ex_palette.background.weakest.color)),
+                border: Border {
+                    color: ex_palette.background.weak.color,
+                    ..DEFAULT_BORDER
+                },
+                shadow: SHADOW_BASE,
+                ..container::Style::default()
+            }
+        });
+        // 菜单外任意位置点击都会关闭菜单
+        mouse_area(
+            pin(menu)
+                .position(position)
+                .width(Length::Fill)
+                .height(Length::Fill),
+        )
+        .on_press(ContextMenuMessage::Close)
+        .on_right_press(ContextMenuMessage::Close)
+        .into()
+    }
+
+    // 样式与菜单栏的下拉菜单项保持一致
+    pub fn generate_menu_item<'a>(
+        &'a self,
+        id: usize,
+        item: &'a ContextMenuItem,
+    ) -> Element<'a, ContextMenuMessage> {
+        mouse_area(
+            container(text(item.label).width(Length::Fill).size(FONT_SIZE_BASE))
+                .padding(Padding::from([PADDING_SMALLER, PADDING_BASE]))
+                .style(move |theme: &Theme| {
+                    let ex_palette = theme.extended_palette();
+                    let background = if self.hovered_id == Some(id) {
+                        ex_palette.background.weaker.color
+                    } else {
+                        ex_palette.background.weakest.color
+                    };
+                    container::Style {
+                        background: Some(Background::Color(background)),
+                        ..container::Style::default()
+                    }
+                    .border(Border {
+                        color: ex_palette.background.weakest.color,
+                        ..DEFAULT_BORDER
+                    })
+                }),
+        )
+        .on_enter(ContextMenuMessage::HoverEnter(id))
+        .interaction(mouse::Interaction::Pointer)
+        .on_press(ContextMenuMessage::ItemClicked(item.action))
+        .into()
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.position.is_some()
+    }
+
+    // 只在右键按下时发出消息，鼠标移动不再触发更新
+    pub fn track_right_press<'a, Message: 'a>(
+        content: impl Into<Element<'a, Message>>,
+        on_message: impl Fn(ContextMenuMessage) -> Message + 'a,
+    ) -> Element<'a, Message> {
+        RightPressArea::new(content, move |position, size| {
+            on_message(ContextMenuMessage::RightPressed(position, size))
+        })
+        .into()
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{
+    common::*,
+    dialog::confirm::choice_row,
+};
+use iced::widget::text;
+use iced::{
+    Background, Border, Color, Element, Length, Padding, Task, Theme,
+    border::Radius,
+    padding,
+    widget::{column, container, scrollable, text::Alignment},
+};
+
+// 关闭窗口前列出所有未保存文档并询问用户如何处理
+#[derive(Debug, Default, Clone)]
+pub struct CloseGuardDialog {
+    doc_names: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum CloseGuardDialogMessage {
+    LoadDocNames(Vec<String>),
+    SendChoice(DialogChoice),
+}
+
+impl CloseGuardDialog {
+    pub fn update(&mut self, message: CloseGuardDialogMessage) -> Task<CloseGuardDialogMessage> {
+        match message {
+            CloseGuardDialogMessage::LoadDocNames(doc_names) => {
+                self.doc_names = doc_names;
+                Task::none()
+            }
+            _ => Task::none(),
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, CloseGuardDialogMessage> {
+        container(
+            column![
+                container(text("以下文档尚未保存!"))
+                    .width(Length::Fill)
+                    .height(Length::Shrink)
+                    .padding(Padding::from([PADDING_SMALLER, PADDING_BASE]))
+                    .style(|theme: &Theme| {
+                        let ex_palette = theme.extended_palette();
+                        container::Style {
+                            background: Some(Background::Color(
+                                ex_palette.background.strong.color.scale_alpha(0.75),
+                            )),
+                            border: Border {
+                                color: Color::TRANSPARENT,
+                                radius: Radius::default().top(DEFAULT_BORDER.radius.top_left),
+                                ..DEFAULT_BORDER
+                            },
+                            ..Default::default()
+                        }
+                    }),
+                container(scrollable(
+                    column(self.doc_names.iter().map(|name| {
+                        text(name)
+                            .size(FONT_SIZE_SMALLER)
+                            .width(Length::Fill)
+                            .align_x(Alignment::Center)
+                            .into()
+                    }))
+                    .spacing(SPACING_SMALLER)
+                ))
+                .max_height(CLOSE_GUARD_LIST_HEIGHT)
+                .padding(Padding::from([0., PADDING_BIGGER])),
+                choice_row(
+                    &[
+                        DialogChoice::SaveAll,
+                        DialogChoice::DiscardAll,
+                        DialogChoice::Cancel
+                    ],
+                    CloseGuardDialogMessage::SendChoice
+                )
+                .padding(padding::bottom(PADDING_BASE))
+            ]
+            .spacing(SPACING_BIGGER)
+            .height(Length::Shrink)
+            .width(CONFIRM_DIALOG_WIDTH),
+        )
+        .style(|theme: &Theme| {
+            let ex_palette = theme.extended_palette();
+            container::Style {
+                background: Some(Background::Color(ex_palette.background.weaker.color)),
+                shadow: SHADOW_BASE_0_OFFSET,
+                border: Border {
+                    color: Color::TRANSPARENT,
+                    ..DEFAULT_BORDER
+                },
+                ..Default::default()
+            }
+        })
+        .into()
+    }
+}
@@ -1,24 +1,26 @@
 use crate::common::*;
 use iced::{
-    Background, Border, Color, Element, Length, Padding, Task, Theme, border::Radius, mouse, padding, widget::{column, container, mouse_area, row, space, text::Alignment}
+    Background, Border, Color, Element, Length, Padding, Task, Theme, border::Radius, mouse, padding, widget::{Row, column, container, mouse_area, row, space, text::Alignment}
 };
 use iced::widget::text;
 #[derive(Debug, Default, Clone)]
 pub struct ConfirmDialog {
     content: String,
+    choices: Vec<DialogChoice>,
 }
 
 #[derive(Debug, Clone)]
 pub enum ConfirmDialogMessage {
-    LoadConfirmText(String),
-    SendConfirmResult(bool)
+    LoadConfirmText(String, Vec<DialogChoice>),
+    SendConfirmResult(DialogChoice),
 }
 
 impl ConfirmDialog {
     pub fn update(&mut self, message: ConfirmDialogMessage) -> Task<ConfirmDialogMessage> {
         match message {
-            ConfirmDialogMessage::LoadConfirmText(text) => {
+            ConfirmDialogMessage::LoadConfirmText(text, choices) => {
                 self.content = text;
+                self.choices = choices;
                 Task::none()
             }
             _ => Task::none(),
@@ -46,18 +48,11 @@ impl ConfirmDialog {
                             ..Default::default()
                         }
                     }),
-                text(&self.content).width(Length::Fill).align_x(Alignment::Center),
-                row![
-                    space::horizontal(),
-                    mouse_area(text("确定"))
-                        .interaction(mouse::Interaction::Pointer)
-                        .on_press(ConfirmDialogMessage::SendConfirmResult(true)),
-                    space::horizontal(),
-                    mouse_area(text("取消"))
-                        .interaction(mouse::Interaction::Pointer)
-                        .on_press(ConfirmDialogMessage::SendConfirmResult(false)),
-                    space::horizontal(),
-                ].padding(padding::bottom(PADDING_BASE))
+                text(&self.content)
+                    .width(Length::Fill)
+                    .align_x(Alignment::Center),
+                choice_row(&self.choices, ConfirmDialogMessage::SendConfirmResult)
+                    .padding(padding::bottom(PADDING_BASE))
             ]
             .spacing(SPACING_BIGGER)
             .height(Length::Shrink)
@@ -78,3 +73,21 @@ impl ConfirmDialog {
         .into()
     }
 }
+
+// 生成等间距排列的选项按钮
+pub fn choice_row<'a, Message: Clone + 'a>(
+    choices: &[DialogChoice],
+    on_choice: impl Fn(DialogChoice) -> Message,
+) -> Row<'a, Message> {
+    let mut row = row![space::horizontal()];
+    for choice in choices {
+        row = row
+            .push(
+                mouse_area(text(choice.label()))
+                    .interaction(mouse::Interaction::Pointer)
+                    .on_press(on_choice(*choice)),
+            )
+            .push(space::horizontal());
+    }
+    row
+}
@@ -0,0 +1,149 @@
+use iced::{
+    Alignment, Background, Border, Color, Element, Length, Padding, Task, Theme, mouse,
+    widget::{column, container, mouse_area, row, space, text, text_input},
+};
+
+use crate::common::{front_matter::FrontMatterFields, *};
+
+// 编辑front matter中的标题、标签和日期，标签用逗号分隔
+#[derive(Debug, Default, Clone)]
+pub struct FrontMatterDialog {
+    title: String,
+    tags: String,
+    date: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum FrontMatterDialogMessage {
+    LoadFields(FrontMatterFields),
+    TitleChanged(String),
+    TagsChanged(String),
+    DateChanged(String),
+    FillToday,
+    ConfirmInput,
+    CancelInput,
+    CloseDialog,
+    // 发送给其他模块的消息
+    SendFields(FrontMatterFields),
+}
+
+impl FrontMatterDialog {
+    pub fn update(&mut self, message: FrontMatterDialogMessage) -> Task<FrontMatterDialogMessage> {
+        match message {
+            FrontMatterDialogMessage::LoadFields(fields) => {
+                self.title = fields.title;
+                self.tags = fields.tags.join(", ");
+                self.date = fields.date;
+                Task::none()
+            }
+            FrontMatterDialogMessage::TitleChanged(title) => {
+                self.title = title;
+                Task::none()
+            }
+            FrontMatterDialogMessage::TagsChanged(tags) => {
+                self.tags = tags;
+                Task::none()
+            }
+            FrontMatterDialogMessage::DateChanged(date) => {
+                self.date = date;
+                Task::none()
+            }
+            FrontMatterDialogMessage::FillToday => {
+                self.date = jiff::Zoned::now().date().to_string();
+                Task::none()
+            }
+            FrontMatterDialogMessage::ConfirmInput => {
+                let fields = FrontMatterFields {
+                    title: self.title.trim().to_string(),
+                    tags: self
+                        .tags
+                        .split([',', '，'])
+                        .map(|tag| tag.trim().trim_start_matches('#').to_string())
+                        .filter(|tag| !tag.is_empty())
+                        .collect(),
+                    date: self.date.trim().to_string(),
+                };
+                Task::done(FrontMatterDialogMessage::SendFields(fields))
+            }
+            FrontMatterDialogMessage::CancelInput => {
+                Task::done(FrontMatterDialogMessage::CloseDialog)
+            }
+            _ => Task::none(),
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, FrontMatterDialogMessage> {
+        let label = |label: &'static str| {
+            text(label)
+                .size(FONT_SIZE_SMALLER)
+                .width(Length::Fixed(40.))
+        };
+        container(column![
+            space::vertical(),
+            text("编辑文档属性")
+                .width(Length::Fill)
+                .align_x(Alignment::Center),
+            space::vertical(),
+            column![
+                row![
+                    label("标题"),
+                    text_input("标题", &self.title)
+                        .line_height(1.)
+                        .on_input(FrontMatterDialogMessage::TitleChanged)
+                        .on_submit(FrontMatterDialogMessage::ConfirmInput),
+                ]
+                .align_y(Alignment::Center),
+                row![
+                    label("标签"),
+                    text_input("用逗号分隔", &self.tags)
+                        .line_height(1.)
+                        .on_input(FrontMatterDialogMessage::TagsChanged)
+                        .on_submit(FrontMatterDialogMessage::ConfirmInput),
+                ]
+                .align_y(Alignment::Center),
+                row![
+                    label("日期"),
+                    text_input("YYYY-MM-DD", &self.date)
+                        .line_height(1.)
+                        .on_input(FrontMatterDialogMessage::DateChanged)
+                        .on_submit(FrontMatterDialogMessage::ConfirmInput),
+                    mouse_area(text("今天").size(FONT_SIZE_SMALLER))
+                        .interaction(mouse::Interaction::Pointer)
+                        .on_press(FrontMatterDialogMessage::FillToday),
+                ]
+                .spacing(SPACING_SMALLER)
+                .align_y(Alignment::Center),
+            ]
+            .spacing(SPACING_SMALLER),
+            space::vertical(),
+            row![
+                space::horizontal(),
+                mouse_area(text("确定"))
+                    .interaction(mouse::Interaction::Pointer)
+                    .on_press(FrontMatterDialogMessage::ConfirmInput),
+                space::horizontal(),
+                mouse_area(text("取消"))
+                    .interaction(mouse::Interaction::Pointer)
+                    .on_press(FrontMatterDialogMessage::CancelInput),
+                space::horizontal(),
+            ],
+            space::vertical()
+        ])
+        .width(FRONT_MATTER_DIALOG_WIDTH)
+        .height(FRONT_MATTER_DIALOG_HEIGHT)
+        .padding(Padding::from([0., PADDING_BIGGER]))
+        .style(|theme: &Theme| {
+            let ex_palette = theme.extended_palette();
+            container::Style {
+                background: Some(Background::Color(ex_palette.background.weaker.color)),
+                shadow: SHADOW_BASE_0_OFFSET,
+                border: Border {
+                    color: Color::TRANSPARENT,
+                    ..DEFAULT_BORDER
+                },
+                ..Default::default()
+            }
+        })
+        .into()
+    }
+}
@@ -4,19 +4,37 @@ use iced::{
     widget::{center, container, opaque, space, stack},
 };
 
+use crate::common::{
+    front_matter::FrontMatterFields,
+    template::{NewFileRequest, Template},
+    *,
+};
 use crate::dialog::{
+    close_guard::{CloseGuardDialog, CloseGuardDialogMessage},
     confirm::{ConfirmDialog, ConfirmDialogMessage},
     editor_table::EditorTableDialogMessage,
+    front_matter::{FrontMatterDialog, FrontMatterDialogMessage},
+    new_file::{NewFileDialog, NewFileDialogMessage},
+    preference::{PreferenceDialog, PreferenceDialogMessage},
 };
+use crate::setting::UserSetting;
 
+mod close_guard;
 mod confirm;
 mod editor_table;
+mod front_matter;
+mod new_file; // 新建文件时填写标题和选择模板
+mod preference; // 日记文件夹、模板等需要输入文字的设置
 
 #[derive(Debug, Default, Clone)]
 pub struct Dialog {
     current_dialog: DialogType,
     editor_table: EditorTableDialog,
     confirm: ConfirmDialog,
+    close_guard: CloseGuardDialog,
+    front_matter: FrontMatterDialog,
+    new_file: NewFileDialog,
+    preference: PreferenceDialog,
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
@@ -25,15 +43,31 @@ pub enum DialogType {
     NoDialog,
     EditorTable,
     Confirm,
+    CloseGuard,
+    FrontMatter,
+    NewFile,
+    Preference,
 }
 
 #[derive(Debug, Clone)]
 pub enum DialogMessage {
     OpenEditorTableDialog,
-    OpenConfirmDialog(String),
+    OpenConfirmDialog(String, Vec<DialogChoice>),
+    OpenCloseGuardDialog(Vec<String>),
+    OpenFrontMatterDialog(FrontMatterFields),
+    OpenNewFileDialog(u32, Vec<Template>),
+    OpenPreferenceDialog(UserSetting),
     EditorTableDialogMessage(EditorTableDialogMessage),
     ConfirmDialogMessage(ConfirmDialogMessage),
-    SendConfirmResult(bool)
+    CloseGuardDialogMessage(CloseGuardDialogMessage),
+    FrontMatterDialogMessage(FrontMatterDialogMessage),
+    NewFileDialogMessage(NewFileDialogMessage),
+    PreferenceDialogMessage(PreferenceDialogMessage),
+    SendConfirmResult(DialogChoice),
+    SendCloseGuardChoice(DialogChoice),
+    SendFrontMatterFields(FrontMatterFields),
+    SendNewFileRequest(NewFileRequest),
+    SendUserSetting(UserSetting),
 }
 
 impl Dialog {
@@ -42,6 +76,10 @@ impl Dialog {
             current_dialog: DialogType::default(),
             editor_table: EditorTableDialog::default(),
             confirm: ConfirmDialog::default(),
+            close_guard: CloseGuardDialog::default(),
+            front_matter: FrontMatterDialog::default(),
+            new_file: NewFileDialog::default(),
+            preference: PreferenceDialog::default(),
         }
     }
 
@@ -51,10 +89,34 @@ impl Dialog {
                 self.current_dialog = DialogType::EditorTable;
                 Task::none()
             }
-            DialogMessage::OpenConfirmDialog(text) => {
+            DialogMessage::OpenConfirmDialog(text, choices) => {
                 self.current_dialog = DialogType::Confirm;
                 Task::done(DialogMessage::ConfirmDialogMessage(
-                    ConfirmDialogMessage::LoadConfirmText(text),
+                    ConfirmDialogMessage::LoadConfirmText(text, choices),
+                ))
+            }
+            DialogMessage::OpenCloseGuardDialog(doc_names) => {
+                self.current_dialog = DialogType::CloseGuard;
+                Task::done(DialogMessage::CloseGuardDialogMessage(
+                    CloseGuardDialogMessage::LoadDocNames(doc_names),
+                ))
+            }
+            DialogMessage::OpenFrontMatterDialog(fields) => {
+                self.current_dialog = DialogType::FrontMatter;
+                Task::done(DialogMessage::FrontMatterDialogMessage(
+                    FrontMatterDialogMessage::LoadFields(fields),
+                ))
+            }
+            DialogMessage::OpenNewFileDialog(dir_key, templates) => {
+                self.current_dialog = DialogType::NewFile;
+                Task::done(DialogMessage::NewFileDialogMessage(
+                    NewFileDialogMessage::LoadTemplates(dir_key, templates),
+                ))
+            }
+            DialogMessage::OpenPreferenceDialog(setting) => {
+                self.current_dialog = DialogType::Preference;
+                Task::done(DialogMessage::PreferenceDialogMessage(
+                    PreferenceDialogMessage::LoadSetting(setting),
                 ))
             }
             DialogMessage::EditorTableDialogMessage(editor_table_message) => {
@@ -71,9 +133,9 @@ impl Dialog {
             }
             DialogMessage::ConfirmDialogMessage(confirm_dialog_message) => {
                 match confirm_dialog_message {
-                    ConfirmDialogMessage::SendConfirmResult(is_user_agreed) => {
+                    ConfirmDialogMessage::SendConfirmResult(choice) => {
                         self.current_dialog = DialogType::default();
-                    Task::done(DialogMessage::SendConfirmResult(is_user_agreed))
+                        Task::done(DialogMessage::SendConfirmResult(choice))
                     }
                     _ => self
                         .confirm
@@ -81,6 +143,64 @@ impl Dialog {
                         .map(DialogMessage::ConfirmDialogMessage),
                 }
             }
+            DialogMessage::CloseGuardDialogMessage(close_guard_message) => {
+                match close_guard_message {
+                    CloseGuardDialogMessage::SendChoice(choice) => {
+                        self.current_dialog = DialogType::default();
+                        Task::done(DialogMessage::SendCloseGuardChoice(choice))
+                    }
+                    _ => self
+                        .close_guard
+                        .update(close_guard_message)
+                        .map(DialogMessage::CloseGuardDialogMessage),
+                }
+            }
+            DialogMessage::FrontMatterDialogMessage(front_matter_message) => {
+                match front_matter_message {
+                    FrontMatterDialogMessage::CloseDialog => {
+                        self.current_dialog = DialogType::default();
+                        Task::none()
+                    }
+                    FrontMatterDialogMessage::SendFields(fields) => {
+                        self.current_dialog = DialogType::default();
+                        Task::done(DialogMessage::SendFrontMatterFields(fields))
+                    }
+                    _ => self
+                        .front_matter
+                        .update(front_matter_message)
+                        .map(DialogMessage::FrontMatterDialogMessage),
+                }
+            }
+            DialogMessage::NewFileDialogMessage(new_file_message) => match new_file_message {
+                NewFileDialogMessage::CloseDialog => {
+                    self.current_dialog = DialogType::default();
+                    Task::none()
+                }
+                NewFileDialogMessage::SendRequest(request) => {
+                    self.current_dialog = DialogType::default();
+                    Task::done(DialogMessage::SendNewFileRequest(request))
+                }
+                _ => self
+                    .new_file
+                    .update(new_file_message)
+                    .map(DialogMessage::NewFileDialogMessage),
+            },
+            DialogMessage::PreferenceDialogMessage(preference_message) => {
+                match preference_message {
+                    PreferenceDialogMessage::CloseDialog => {
+                        self.current_dialog = DialogType::default();
+                        Task::none()
+                    }
+                    PreferenceDialogMessage::SendSetting(setting) => {
+                        self.current_dialog = DialogType::default();
+                        Task::done(DialogMessage::SendUserSetting(setting))
+                    }
+                    _ => self
+                        .preference
+                        .update(preference_message)
+                        .map(DialogMessage::PreferenceDialogMessage),
+                }
+            }
             _ => Task::none(),
         }
     }
@@ -92,6 +212,22 @@ impl Dialog {
                 .view()
                 .map(DialogMessage::EditorTableDialogMessage),
             DialogType::Confirm => self.confirm.view().map(DialogMessage::ConfirmDialogMessage),
+            DialogType::CloseGuard => self
+                .close_guard
+                .view()
+                .map(DialogMessage::CloseGuardDialogMessage),
+            DialogType::FrontMatter => self
+                .front_matter
+                .view()
+                .map(DialogMessage::FrontMatterDialogMessage),
+            DialogType::NewFile => self
+                .new_file
+                .view()
+                .map(DialogMessage::NewFileDialogMessage),
+            DialogType::Preference => self
+                .preference
+                .view()
+                .map(DialogMessage::PreferenceDialogMessage),
             _ => space().into(),
         }
     }
@@ -0,0 +1,156 @@
+use iced::{
+    Alignment, Background, Border, Color, Element, Length, Padding, Task, Theme, mouse,
+    widget::{column, container, mouse_area, row, scrollable, space, text, text_input},
+};
+
+use crate::common::{
+    template::{NewFileRequest, Template},
+    *,
+};
+
+pub const NEW_FILE_TITLE_INPUT_ID: &str = "new-file-title-input";
+
+// 新建文件前填写标题并选择模板，标题同时作为文件名和模板中的{{title}}
+#[derive(Debug, Default, Clone)]
+pub struct NewFileDialog {
+    dir_key: u32,
+    title: String,
+    templates: Vec<Template>,
+    // 选中的模板序号，为空时新建空白文档
+    selected: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub enum NewFileDialogMessage {
+    LoadTemplates(u32, Vec<Template>),
+    TitleChanged(String),
+    SelectTemplate(Option<usize>),
+    ConfirmInput,
+    CancelInput,
+    CloseDialog,
+    // 发送给其他模块的消息
+    SendRequest(NewFileRequest),
+}
+
+impl NewFileDialog {
+    pub fn update(&mut self, message: NewFileDialogMessage) -> Task<NewFileDialogMessage> {
+        match message {
+            NewFileDialogMessage::LoadTemplates(dir_key, templates) => {
+                self.dir_key = dir_key;
+                self.title = String::new();
+                self.templates = templates;
+                self.selected = None;
+                iced::widget::operation::focus(NEW_FILE_TITLE_INPUT_ID)
+            }
+            NewFileDialogMessage::TitleChanged(title) => {
+                self.title = title;
+                Task::none()
+            }
+            NewFileDialogMessage::SelectTemplate(selected) => {
+                self.selected = selected;
+                Task::none()
+            }
+            NewFileDialogMessage::ConfirmInput => {
+                let request = NewFileRequest {
+                    dir_key: self.dir_key,
+                    title: self.title.trim().to_string(),
+                    template: self
+                        .selected
+                        .and_then(|index| self.templates.get(index))
+                        .map(|template| template.path.clone()),
+                };
+                Task::done(NewFileDialogMessage::SendRequest(request))
+            }
+            NewFileDialogMessage::CancelInput => Task::done(NewFileDialogMessage::CloseDialog),
+            _ => Task::none(),
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, NewFileDialogMessage> {
+        let mut template_list = column![self.view_template_item("空白文档", None)];
+        for (index, template) in self.templates.iter().enumerate() {
+            let label = match template.is_global {
+                true => format!("{}  (全局)", template.name),
+                false => template.name.clone(),
+            };
+            template_list = template_list.push(self.view_template_item(label, Some(index)));
+        }
+        if self.templates.is_empty() {
+            template_list = template_list.push(
+                text("工作区和配置目录的模板文件夹中还没有模板")
+                    .size(FONT_SIZE_SMALLEST)
+                    .style(|theme: &Theme| text::Style {
+                        color: Some(theme.extended_palette().background.strong.text),
+                    }),
+            );
+        }
+        container(
+            column![
+                text("新建文件")
+                    .width(Length::Fill)
+                    .align_x(Alignment::Center),
+                text_input("标题，留空时为新建文件", &self.title)
+                    .id(NEW_FILE_TITLE_INPUT_ID)
+                    .line_height(1.)
+                    .on_input(NewFileDialogMessage::TitleChanged)
+                    .on_submit(NewFileDialogMessage::ConfirmInput),
+                text("模板").size(FONT_SIZE_SMALLER),
+                container(scrollable(template_list.spacing(SPACING_SMALLER)))
+                    .max_height(NEW_FILE_TEMPLATE_LIST_HEIGHT),
+                row![
+                    space::horizontal(),
+                    mouse_area(text("确定"))
+                        .interaction(mouse::Interaction::Pointer)
+                        .on_press(NewFileDialogMessage::ConfirmInput),
+                    space::horizontal(),
+                    mouse_area(text("取消"))
+                        .interaction(mouse::Interaction::Pointer)
+                        .on_press(NewFileDialogMessage::CancelInput),
+                    space::horizontal(),
+                ],
+            ]
+            .spacing(SPACING)
+            .width(CONFIRM_DIALOG_WIDTH),
+        )
+        .padding(Padding::from([PADDING_BASE, PADDING_BIGGER]))
+        .style(|theme: &Theme| {
+            let ex_palette = theme.extended_palette();
+            container::Style {
+                background: Some(Background::Color(ex_palette.background.weaker.color)),
+                shadow: SHADOW_BASE_0_OFFSET,
+                border: Border {
+                    color: Color::TRANSPARENT,
+                    ..DEFAULT_BORDER
+                },
+                ..Default::default()
+            }
+        })
+        .into()
+    }
+
+    fn view_template_item(
+        &self,
+        label: impl text::IntoFragment<'static>,
+        index: Option<usize>,
+    ) -> Element<'_, NewFileDialogMessage> {
+        let is_selected = self.selected == index;
+        mouse_area(
+            container(text(label).size(FONT_SIZE_SMALLER))
+                .width(Length::Fill)
+                .padding(Padding::from([PADDING_SMALLEST, PADDING_SMALLER]))
+                .style(move |theme: &Theme| {
+                    let ex_palette = theme.extended_palette();
+                    container::Style {
+                        background: Some(Background::Color(match is_selected {
+                            true => ex_palette.background.strong.color,
+                            false => Color::TRANSPARENT,
+                        })),
+                        ..container::Style::default()
+                    }
+                }),
+        )
+        .interaction(mouse::Interaction::Pointer)
+        .on_press(NewFileDialogMessage::SelectTemplate(index))
+        .into()
+    }
+}
@@ -0,0 +1,204 @@
+use iced::{
+    Alignment, Background, Border, Color, Element, Length, Padding, Task, Theme, mouse,
+    widget::{column, container, mouse_area, row, space, text, text_editor, text_input},
+};
+
+use crate::{common::*, setting::UserSetting};
+
+// 修改需要输入文字的设置，开关类的设置直接在设置菜单中切换
+#[derive(Debug, Default, Clone)]
+pub struct PreferenceDialog {
+    // 打开窗口时的设置，确定时只替换窗口中修改的字段
+    setting: UserSetting,
+    // 数字设置按文字编辑，确定时无法解析则保留原来的值
+    scan_max_depth: String,
+    scan_max_nodes: String,
+    journal_folder: String,
+    journal_template: text_editor::Content,
+    template_folder: String,
+    author: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum PreferenceDialogMessage {
+    LoadSetting(UserSetting),
+    ScanMaxDepthChanged(String),
+    ScanMaxNodesChanged(String),
+    JournalFolderChanged(String),
+    JournalTemplateEdited(text_editor::Action),
+    TemplateFolderChanged(String),
+    AuthorChanged(String),
+    ConfirmInput,
+    CancelInput,
+    CloseDialog,
+    // 发送给其他模块的消息
+    SendSetting(UserSetting),
+}
+
+impl PreferenceDialog {
+    pub fn update(&mut self, message: PreferenceDialogMessage) -> Task<PreferenceDialogMessage> {
+        match message {
+            PreferenceDialogMessage::LoadSetting(setting) => {
+                self.scan_max_depth = setting.scan_max_depth.to_string();
+                self.scan_max_nodes = setting.scan_max_nodes.to_string();
+                self.journal_folder = setting.journal_folder.clone();
+                self.journal_template = text_editor::Content::with_text(&setting.journal_template);
+                self.template_folder = setting.template_folder.clone();
+                self.author = setting.author.clone();
+                self.setting = setting;
+                Task::none()
+            }
+            PreferenceDialogMessage::ScanMaxDepthChanged(scan_max_depth) => {
+                self.scan_max_depth = scan_max_depth;
+                Task::none()
+            }
+            PreferenceDialogMessage::ScanMaxNodesChanged(scan_max_nodes) => {
+                self.scan_max_nodes = scan_max_nodes;
+                Task::none()
+            }
+            PreferenceDialogMessage::JournalFolderChanged(journal_folder) => {
+                self.journal_folder = journal_folder;
+                Task::none()
+            }
+            PreferenceDialogMessage::JournalTemplateEdited(action) => {
+                self.journal_template.perform(action);
+                Task::none()
+            }
+            PreferenceDialogMessage::TemplateFolderChanged(template_folder) => {
+                self.template_folder = template_folder;
+                Task::none()
+            }
+            PreferenceDialogMessage::AuthorChanged(author) => {
+                self.author = author;
+                Task::none()
+            }
+            PreferenceDialogMessage::ConfirmInput => {
+                let parse_limit = |input: &str, previous: usize| {
+                    input
+                        .trim()
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|limit| *limit > 0)
+                        .unwrap_or(previous)
+                };
+                let setting = UserSetting {
+                    scan_max_depth: parse_limit(&self.scan_max_depth, self.setting.scan_max_depth),
+                    scan_max_nodes: parse_limit(&self.scan_max_nodes, self.setting.scan_max_nodes),
+                    // 文件夹留空时日记放在工作区根目录
+                    journal_folder: self
+                        .journal_folder
+                        .trim()
+                        .trim_matches(['/', '\\'])
+                        .to_string(),
+                    journal_template: self.journal_template.text(),
+                    template_folder: self
+                        .template_folder
+                        .trim()
+                        .trim_matches(['/', '\\'])
+                        .to_string(),
+                    author: self.author.trim().to_string(),
+                    ..self.setting.clone()
+                };
+                Task::done(PreferenceDialogMessage::SendSetting(setting))
+            }
+            PreferenceDialogMessage::CancelInput => {
+                Task::done(PreferenceDialogMessage::CloseDialog)
+            }
+            _ => Task::none(),
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, PreferenceDialogMessage> {
+        let label = |label: &'static str| {
+            text(label)
+                .size(FONT_SIZE_SMALLER)
+                .width(Length::Fixed(PREFERENCE_LABEL_WIDTH))
+        };
+        container(
+            column![
+                text("偏好设置")
+                    .width(Length::Fill)
+                    .align_x(Alignment::Center),
+                row![
+                    label("最大层级"),
+                    text_input("读取工作区时的最大层级", &self.scan_max_depth)
+                        .line_height(1.)
+                        .on_input(PreferenceDialogMessage::ScanMaxDepthChanged)
+                        .on_submit(PreferenceDialogMessage::ConfirmInput),
+                ]
+                .align_y(Alignment::Center),
+                row![
+                    label("最大节点数"),
+                    text_input("读取工作区时的最大节点数量", &self.scan_max_nodes)
+                        .line_height(1.)
+                        .on_input(PreferenceDialogMessage::ScanMaxNodesChanged)
+                        .on_submit(PreferenceDialogMessage::ConfirmInput),
+                ]
+                .align_y(Alignment::Center),
+                row![
+                    label("日记文件夹"),
+                    text_input("相对于工作区根目录", &self.journal_folder)
+                        .line_height(1.)
+                        .on_input(PreferenceDialogMessage::JournalFolderChanged)
+                        .on_submit(PreferenceDialogMessage::ConfirmInput),
+                ]
+                .align_y(Alignment::Center),
+                row![
+                    label("日记模板"),
+                    text_editor(&self.journal_template)
+                        .height(PREFERENCE_TEMPLATE_HEIGHT)
+                        .size(FONT_SIZE_SMALLER)
+                        .on_action(PreferenceDialogMessage::JournalTemplateEdited),
+                ],
+                text("模板中可以使用{{date}}、{{weekday}}、{{author}}等变量")
+                    .size(FONT_SIZE_SMALLEST)
+                    .style(|theme: &Theme| text::Style {
+                        color: Some(theme.extended_palette().background.strong.text),
+                    }),
+                row![
+                    label("模板文件夹"),
+                    text_input("相对于工作区根目录", &self.template_folder)
+                        .line_height(1.)
+                        .on_input(PreferenceDialogMessage::TemplateFolderChanged)
+                        .on_submit(PreferenceDialogMessage::ConfirmInput),
+                ]
+                .align_y(Alignment::Center),
+                row![
+                    label("作者"),
+                    text_input("模板中的{{author}}", &self.author)
+                        .line_height(1.)
+                        .on_input(PreferenceDialogMessage::AuthorChanged)
+                        .on_submit(PreferenceDialogMessage::ConfirmInput),
+                ]
+                .align_y(Alignment::Center),
+                row![
+                    space::horizontal(),
+                    mouse_area(text("确定"))
+                        .interaction(mouse::Interaction::Pointer)
+                        .on_press(PreferenceDialogMessage::ConfirmInput),
+                    space::horizontal(),
+                    mouse_area(text("取消"))
+                        .interaction(mouse::Interaction::Pointer)
+                        .on_press(PreferenceDialogMessage::CancelInput),
+                    space::horizontal(),
+                ],
+            ]
+            .spacing(SPACING)
+            .width(CONFIRM_DIALOG_WIDTH),
+        )
+        .padding(Padding::from([PADDING_BASE, PADDING_BIGGER]))
+        .style(|theme: &Theme| {
+            let ex_palette = theme.extended_palette();
+            container::Style {
+                background: Some(Background::Color(ex_palette.background.weaker.color)),
+                shadow: SHADOW_BASE_0_OFFSET,
+                border: Border {
+                    color: Color::TRANSPARENT,
+                    ..DEFAULT_BORDER
+                },
+                ..Default::default()
+            }
+        })
+        .into()
+    }
+}
@@ -0,0 +1,83 @@
+use std::ops::Range;
+
+use crate::common::{wiki_link, *};
+use iced::{
+    Font, Theme,
+    advanced::text::{self, highlighter::Format},
+    highlighter::{self, Highlight},
+};
+
+// 在markdown语法高亮的基础上标出[[链接]]
+#[derive(Debug)]
+pub struct MarkdownHighlighter {
+    syntax: highlighter::Highlighter,
+    // 每行开始时所在的代码块状态，代码块中的内容不标出链接
+    fences: Vec<Option<(char, usize)>>,
+}
+
+#[derive(Debug)]
+pub enum MarkdownHighlight {
+    Syntax(Highlight),
+    WikiLink,
+}
+
+impl MarkdownHighlight {
+    pub fn to_format(&self, _theme: &Theme) -> Format<Font> {
+        match self {
+            MarkdownHighlight::Syntax(highlight) => highlight.to_format(),
+            MarkdownHighlight::WikiLink => Format {
+                color: Some(WIKI_LINK_COLOR),
+                font: None,
+            },
+        }
+    }
+}
+
+impl text::Highlighter for MarkdownHighlighter {
+    type Settings = highlighter::Settings;
+    type Highlight = MarkdownHighlight;
+    type Iterator<'a> = std::vec::IntoIter<(Range<usize>, MarkdownHighlight)>;
+
+    fn new(settings: &Self::Settings) -> Self {
+        Self {
+            syntax: highlighter::Highlighter::new(settings),
+            fences: vec![None],
+        }
+    }
+
+    fn update(&mut self, new_settings: &Self::Settings) {
+        self.syntax.update(new_settings);
+        self.fences.truncate(self.syntax.current_line() + 1);
+    }
+
+    fn change_line(&mut self, line: usize) {
+        self.syntax.change_line(line);
+        self.fences.truncate(self.syntax.current_line() + 1);
+    }
+
+    // 链接的高亮排在后面，覆盖同一范围内的语法高亮
+    fn highlight_line(&mut self, line: &str) -> Self::Iterator<'_> {
+        let line_index = self.syntax.current_line();
+        let mut fence = self.fences.get(line_index).copied().flatten();
+        let is_code = wiki_link::update_fence(&mut fence, line);
+        self.fences.resize(line_index + 1, None);
+        self.fences.push(fence);
+        let mut highlights = self
+            .syntax
+            .highlight_line(line)
+            .map(|(range, highlight)| (range, MarkdownHighlight::Syntax(highlight)))
+            .collect::<Vec<_>>();
+        if !is_code {
+            highlights.extend(
+                wiki_link::find_wiki_links_in_line(line)
+                    .into_iter()
+                    .map(|link| (link.range, MarkdownHighlight::WikiLink)),
+            );
+        }
+        highlights.into_iter()
+    }
+
+    fn current_line(&self) -> usize {
+        self.syntax.current_line()
+    }
+}
@@ -1,16 +1,23 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
-use crate::common::*;
+use crate::{
+    common::{
+        front_matter::{self, FrontMatter, FrontMatterFields},
+        journal, tag, *,
+    },
+    editor::highlighter::{MarkdownHighlight, MarkdownHighlighter},
+};
 use iced::{
     Background, Border, Color, Element, Length, Padding, Subscription, Task, Theme,
     border::Radius,
-    mouse,
+    keyboard, mouse,
     widget::{
         Container, Row, center, column, container, mouse_area, opaque, row, rule, space, stack,
         text, text_editor,
     },
 };
 use tracing::{error, info};
+mod highlighter; // markdown语法高亮，额外标出[[链接]]
 mod operation;
 
 #[derive(Debug)]
@@ -19,6 +26,23 @@ pub struct Editor {
     original_version: Option<u64>,
     editor_content: text_editor::Content,
     snap_shot: Vec<FileData>,
+    // 用户选择先保存再切换文件时，等保存结果返回后再加载
+    load_after_save: bool,
+    // 工作区中的标签，按使用次数排列，以及正在显示的补全
+    tags: Arc<Vec<String>>,
+    tag_completion: Option<TagCompletion>,
+    // 滚轮累计滚动的行数，编辑器不提供滚动位置，以此作为近似值
+    scroll_line: usize,
+    // 打开过的文档的光标和滚动位置，按节点编号记录
+    doc_views: HashMap<u32, DocView>,
+}
+
+#[derive(Debug)]
+struct TagCompletion {
+    // 光标前已经输入的字符数，选中候选后替换掉
+    prefix_chars: usize,
+    candidates: Vec<String>,
+    selected: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -38,12 +62,36 @@ pub enum EditorMessage {
     HandleSaveResult(Result<(), AppError>),
     CheckSaveState,
     LoadPermitted,
+    LoadCanceled,
     LoadFileDataFromFilePanel(FileData),
+    UnloadFile(u32),
     GetImgCodeFromFilePanel(String),
+    // 全文搜索跳转和替换
+    MoveCursorToLine(usize),
+    RestoreDocView(DocView),
+    ApplyReplacement(FileReplacement),
+    // 用新内容替换全部内容，参数为原内容、新内容和替换后光标所在行
+    ReplaceAllContent(Arc<String>, Arc<String>, usize),
+    // 右键菜单消息
+    RequestContextMenu,
+    SendContextMenuItems(Vec<ContextMenuItem>),
+    HandleContextAction(ContextAction),
+    PasteFromClipboard(Option<String>),
     // 各种模态窗口消息
     OpenEditorTableDialog,
-    OpenConfirmDialog(String),
-    GetConfirmResult(bool),
+    OpenConfirmDialog(String, Vec<DialogChoice>),
+    OpenFrontMatterDialog(FrontMatterFields),
+    GetConfirmResult(DialogChoice),
+    // 切换到前一天或后一天的日记
+    SendOpenAdjacentDailyNote(i32),
+    // 编辑front matter中的常用字段
+    EditFrontMatter,
+    ApplyFrontMatterFields(FrontMatterFields),
+    // 标签自动补全
+    UpdateTags(Arc<Vec<String>>),
+    SelectTagCompletion(usize),
+    AcceptTagCompletion(usize),
+    CloseTagCompletion,
 }
 
 impl Editor {
@@ -53,6 +101,11 @@ impl Editor {
             editor_content: text_editor::Content::default(),
             snap_shot: vec![],
             original_version: None,
+            load_after_save: false,
+            tags: Arc::default(),
+            tag_completion: None,
+            scroll_line: 0,
+            doc_views: HashMap::new(),
             //history: VecDeque::with_capacity(100),
             //undo_stack: vec![],
             //redo_stack: vec![],
@@ -79,8 +132,15 @@ impl Editor {
                                 Task::done(EditorMessage::AutoSaveToFile(file_data.clone()))
                                     .chain(Task::done(EditorMessage::LoadPermitted))
                             } else {
-                                let text = "是否丢弃未保存的更改？".to_string();
-                                Task::done(EditorMessage::OpenConfirmDialog(text))
+                                let text = "是否保存对当前文件的更改？".to_string();
+                                Task::done(EditorMessage::OpenConfirmDialog(
+                                    text,
+                                    vec![
+                                        DialogChoice::Save,
+                                        DialogChoice::DontSave,
+                                        DialogChoice::Cancel,
+                                    ],
+                                ))
                             }
                         } else {
                             Task::done(EditorMessage::LoadPermitted)
@@ -88,16 +148,29 @@ impl Editor {
                     })
                     .unwrap_or(Task::done(EditorMessage::LoadPermitted))
             }
-            EditorMessage::GetConfirmResult(is_user_agreed) => {
-                if is_user_agreed {
-                    return Task::done(EditorMessage::LoadPermitted);
+            EditorMessage::GetConfirmResult(choice) => match choice {
+                DialogChoice::Save => {
+                    self.load_after_save = true;
+                    Task::done(EditorMessage::SaveRequested)
                 }
-                Task::none()
-            }
+                DialogChoice::DontSave => Task::done(EditorMessage::LoadPermitted),
+                _ => Task::done(EditorMessage::LoadCanceled),
+            },
             EditorMessage::EditorAction(action) => {
                 //self.history.push_back(action.clone());
                 let is_edit = action.is_edit();
+                if let text_editor::Action::Scroll { lines } = action {
+                    self.scroll_line = self
+                        .scroll_line
+                        .saturating_add_signed(lines as isize)
+                        .min(self.editor_content.line_count());
+                }
                 self.editor_content.perform(action);
+                // 输入时根据光标前的#标签给出补全，移动光标等其他操作关闭补全
+                self.tag_completion = match is_edit {
+                    true => self.find_tag_completion(),
+                    false => None,
+                };
                 if is_edit {
                     let new_input = Arc::new(self.editor_content.text());
                     if let Some(file_data) = &mut self.selected_file {
@@ -116,7 +189,12 @@ impl Editor {
                 // 只是新加载内容，不希望触发版本号改变和自动保存计时器
                 // 所以不发送EditorMessage::EditorAction(file_data.content.clone())
                 // 而是直接操作编辑器，并直接发送内容给预览模块
+                if let Some(view) = self.current_doc_view() {
+                    let id = self.selected_file.as_ref().map(|file| file.global_id);
+                    self.doc_views.extend(id.map(|id| (id, view)));
+                }
                 self.selected_file = Some(file_data.clone());
+                self.scroll_line = 0;
                 self.editor_content = text_editor::Content::new();
                 self.editor_content
                     .perform(text_editor::Action::Edit(text_editor::Edit::Paste(
@@ -125,12 +203,34 @@ impl Editor {
                 self.editor_content.perform(text_editor::Action::Move(
                     text_editor::Motion::DocumentStart,
                 ));
-                self.original_version = Some(file_data.version);
+                self.original_version = Some(file_data.saved_version);
                 info!("文件内容载入成功!");
+                // 再次打开时直接回到上次的位置，之后的跳转指令可以覆盖
+                if let Some(view) = self.doc_views.get(&file_data.global_id).copied() {
+                    self.apply_doc_view(view);
+                }
                 Task::done(EditorMessage::SendNewContentToPreview(Arc::clone(
                     &file_data.content,
                 )))
             }
+            // 文件被删除后清空编辑器和预览
+            EditorMessage::UnloadFile(id) => {
+                if self
+                    .selected_file
+                    .as_ref()
+                    .is_some_and(|file_data| file_data.global_id == id)
+                {
+                    self.selected_file = None;
+                    self.original_version = None;
+                    self.editor_content = text_editor::Content::new();
+                    self.doc_views.remove(&id);
+                    info!("文件已卸载!");
+                    return Task::done(EditorMessage::SendNewContentToPreview(Arc::new(
+                        String::default(),
+                    )));
+                }
+                Task::none()
+            }
             EditorMessage::AutoSaveCheck(version) => {
                 if let Some(ref selected_file) = self.selected_file {
                     if setting.auto_save && version == selected_file.version {
@@ -139,18 +239,28 @@ impl Editor {
                 }
                 Task::none()
             }
-            EditorMessage::HandleSaveResult(operation_result) => match operation_result {
-                Ok(_) => {
-                    if let Some(ref selected_file) = self.selected_file {
-                        self.original_version = Some(selected_file.version);
+            EditorMessage::HandleSaveResult(operation_result) => {
+                let load_after_save = std::mem::take(&mut self.load_after_save);
+                match operation_result {
+                    Ok(_) => {
+                        if let Some(ref selected_file) = self.selected_file {
+                            self.original_version = Some(selected_file.version);
+                        }
+                        if load_after_save {
+                            return Task::done(EditorMessage::LoadPermitted);
+                        }
+                        Task::none()
+                    }
+                    Err(ref error) => {
+                        error!("{}", error);
+                        // 保存失败时不切换文件，保留当前未保存的内容
+                        if load_after_save {
+                            return Task::done(EditorMessage::LoadCanceled);
+                        }
+                        Task::none()
                     }
-                    Task::none()
-                }
-                Err(ref error) => {
-                    error!("{}", error);
-                    Task::none()
                 }
-            },
+            }
             EditorMessage::SaveRequested => {
                 if let Some(file_data) = &self.selected_file {
                     return Task::done(EditorMessage::SaveToFile(file_data.clone()));
@@ -163,14 +273,337 @@ impl Editor {
                 }
                 Task::none()
             }
+            EditorMessage::RequestContextMenu => {
+                if self.selected_file.is_none() {
+                    return Task::none();
+                }
+                let items = if self.editor_content.selection().is_some() {
+                    vec![
+                        ContextMenuItem::new("剪切", ContextAction::Cut),
+                        ContextMenuItem::new("复制", ContextAction::Copy),
+                        ContextMenuItem::new("粘贴", ContextAction::Paste),
+                        ContextMenuItem::new("加粗", ContextAction::FormatBold),
+                        ContextMenuItem::new("斜体", ContextAction::FormatItalic),
+                        ContextMenuItem::new("行内代码", ContextAction::FormatCode),
+                        ContextMenuItem::new("链接", ContextAction::FormatLink),
+                    ]
+                } else {
+                    vec![
+                        ContextMenuItem::new("粘贴", ContextAction::Paste),
+                        ContextMenuItem::new("全选", ContextAction::SelectAll),
+                    ]
+                };
+                Task::done(EditorMessage::SendContextMenuItems(items))
+            }
+            EditorMessage::HandleContextAction(action) => {
+                let selection = self.editor_content.selection();
+                let edit = |text: String| {
+                    Task::done(EditorMessage::EditorAction(text_editor::Action::Edit(
+                        text_editor::Edit::Paste(Arc::new(text)),
+                    )))
+                };
+                match (action, selection) {
+                    (ContextAction::Cut, Some(selection)) => iced::clipboard::write(selection)
+                        .chain(Task::done(EditorMessage::EditorAction(
+                            text_editor::Action::Edit(text_editor::Edit::Delete),
+                        ))),
+                    (ContextAction::Copy, Some(selection)) => iced::clipboard::write(selection),
+                    (ContextAction::Paste, _) => {
+                        iced::clipboard::read().map(EditorMessage::PasteFromClipboard)
+                    }
+                    (ContextAction::SelectAll, _) => {
+                        self.editor_content.perform(text_editor::Action::SelectAll);
+                        Task::none()
+                    }
+                    (ContextAction::FormatBold, Some(selection)) => {
+                        edit(format!("**{}**", selection))
+                    }
+                    (ContextAction::FormatItalic, Some(selection)) => {
+                        edit(format!("*{}*", selection))
+                    }
+                    (ContextAction::FormatCode, Some(selection)) => {
+                        edit(format!("`{}`", selection))
+                    }
+                    (ContextAction::FormatLink, Some(selection)) => {
+                        edit(format!("[{}]()", selection))
+                    }
+                    _ => Task::none(),
+                }
+            }
+            EditorMessage::PasteFromClipboard(Some(text)) => Task::done(
+                EditorMessage::EditorAction(text_editor::Action::Edit(text_editor::Edit::Paste(
+                    Arc::new(text),
+                ))),
+            ),
             EditorMessage::GetImgCodeFromFilePanel(code) => {
                 let action = text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(code)));
                 Task::done(EditorMessage::EditorAction(action))
             }
+            EditorMessage::MoveCursorToLine(line) => {
+                let line = line.min(self.editor_content.line_count().saturating_sub(1));
+                self.editor_content.move_to(text_editor::Cursor {
+                    position: text_editor::Position { line, column: 0 },
+                    selection: None,
+                });
+                Task::none()
+            }
+            EditorMessage::RestoreDocView(view) => {
+                self.apply_doc_view(view);
+                Task::none()
+            }
+            EditorMessage::ApplyReplacement(replacement) => {
+                Task::done(EditorMessage::ReplaceAllContent(
+                    replacement.original,
+                    replacement.replaced,
+                    self.editor_content.cursor().position.line,
+                ))
+            }
+            // 通过编辑操作替换全部内容，结果作为未保存的更改保留在编辑器中，可以撤销
+            EditorMessage::ReplaceAllContent(original, replaced, line) => {
+                if self.selected_file.is_none() || self.editor_content.text() != *original {
+                    error!("编辑器内容已发生变化，已跳过替换!");
+                    return Task::none();
+                }
+                self.editor_content.perform(text_editor::Action::SelectAll);
+                Task::done(EditorMessage::EditorAction(text_editor::Action::Edit(
+                    text_editor::Edit::Paste(replaced),
+                )))
+                .chain(Task::done(EditorMessage::MoveCursorToLine(line)))
+            }
+            EditorMessage::EditFrontMatter => {
+                if self.selected_file.is_none() {
+                    return Task::none();
+                }
+                let fields = FrontMatter::parse(&self.editor_content.text())
+                    .map(|front_matter| front_matter.common_fields())
+                    .unwrap_or_default();
+                Task::done(EditorMessage::OpenFrontMatterDialog(fields))
+            }
+            EditorMessage::ApplyFrontMatterFields(fields) => {
+                let original = self.editor_content.text();
+                let replaced = front_matter::update_common_fields(&original, &fields);
+                if replaced == original {
+                    return Task::none();
+                }
+                // 属性的行数变化时光标保持在正文的同一行
+                let line_delta =
+                    replaced.lines().count() as isize - original.lines().count() as isize;
+                let line = self
+                    .editor_content
+                    .cursor()
+                    .position
+                    .line
+                    .saturating_add_signed(line_delta);
+                Task::done(EditorMessage::ReplaceAllContent(
+                    Arc::new(original),
+                    Arc::new(replaced),
+                    line,
+                ))
+            }
+            EditorMessage::UpdateTags(tags) => {
+                self.tags = tags;
+                Task::none()
+            }
+            EditorMessage::SelectTagCompletion(index) => {
+                if let Some(completion) = &mut self.tag_completion {
+                    completion.selected = index.min(completion.candidates.len().saturating_sub(1));
+                }
+                Task::none()
+            }
+            // 先选中已输入的部分再粘贴，标签后补一个空格结束输入
+            EditorMessage::AcceptTagCompletion(index) => {
+                let Some(completion) = self.tag_completion.take() else {
+                    return Task::none();
+                };
+                let Some(tag) = completion.candidates.get(index) else {
+                    return Task::none();
+                };
+                for _ in 0..completion.prefix_chars {
+                    self.editor_content
+                        .perform(text_editor::Action::Select(text_editor::Motion::Left));
+                }
+                Task::done(EditorMessage::EditorAction(text_editor::Action::Edit(
+                    text_editor::Edit::Paste(Arc::new(format!("{} ", tag))),
+                )))
+            }
+            EditorMessage::CloseTagCompletion => {
+                self.tag_completion = None;
+                Task::none()
+            }
             _ => Task::none(),
         }
     }
 
+    fn find_tag_completion(&self) -> Option<TagCompletion> {
+        let cursor = self.editor_content.cursor();
+        if cursor.selection.is_some() {
+            return None;
+        }
+        let line = self.editor_content.line(cursor.position.line)?;
+        let prefix = tag::tag_prefix_before(&line.text, cursor.position.column)?;
+        if prefix.is_empty() {
+            return None;
+        }
+        let prefix_lowercase = prefix.to_lowercase();
+        let candidates = self
+            .tags
+            .iter()
+            .filter(|tag| {
+                let tag = tag.to_lowercase();
+                tag.starts_with(&prefix_lowercase) && tag != prefix_lowercase
+            })
+            .take(TAG_COMPLETION_MAX)
+            .cloned()
+            .collect::<Vec<_>>();
+        (!candidates.is_empty()).then(|| TagCompletion {
+            prefix_chars: prefix.chars().count(),
+            candidates,
+            selected: 0,
+        })
+    }
+
+    // 标签补全打开时接管方向键、Tab和Enter，Alt+PageUp/PageDown切换前一天和后一天的日记
+    fn key_binding(
+        &self,
+        key_press: text_editor::KeyPress,
+    ) -> Option<text_editor::Binding<EditorMessage>> {
+        if let Some(completion) = &self.tag_completion
+            && matches!(key_press.status, text_editor::Status::Focused { .. })
+        {
+            let last = completion.candidates.len() - 1;
+            match key_press.key.as_ref() {
+                keyboard::Key::Named(keyboard::key::Named::Tab | keyboard::key::Named::Enter) => {
+                    return Some(text_editor::Binding::Custom(
+                        EditorMessage::AcceptTagCompletion(completion.selected),
+                    ));
+                }
+                keyboard::Key::Named(keyboard::key::Named::ArrowDown) => {
+                    let index = match completion.selected >= last {
+                        true => 0,
+                        false => completion.selected + 1,
+                    };
+                    return Some(text_editor::Binding::Custom(
+                        EditorMessage::SelectTagCompletion(index),
+                    ));
+                }
+                keyboard::Key::Named(keyboard::key::Named::ArrowUp) => {
+                    let index = match completion.selected {
+                        0 => last,
+                        selected => selected - 1,
+                    };
+                    return Some(text_editor::Binding::Custom(
+                        EditorMessage::SelectTagCompletion(index),
+                    ));
+                }
+                keyboard::Key::Named(keyboard::key::Named::Escape) => {
+                    return Some(text_editor::Binding::Custom(EditorMessage::CloseTagCompletion));
+                }
+                _ => {}
+            }
+        }
+        if key_press.modifiers.alt()
+            && matches!(key_press.status, text_editor::Status::Focused { .. })
+        {
+            match key_press.key.as_ref() {
+                keyboard::Key::Named(keyboard::key::Named::PageUp) => {
+                    return Some(text_editor::Binding::Custom(
+                        EditorMessage::SendOpenAdjacentDailyNote(-1),
+                    ));
+                }
+                keyboard::Key::Named(keyboard::key::Named::PageDown) => {
+                    return Some(text_editor::Binding::Custom(
+                        EditorMessage::SendOpenAdjacentDailyNote(1),
+                    ));
+                }
+                _ => {}
+            }
+        }
+        text_editor::Binding::from_key_press(key_press)
+    }
+
+    fn view_tag_completion(&self) -> Element<'_, EditorMessage> {
+        let Some(completion) = &self.tag_completion else {
+            return space().into();
+        };
+        let mut candidates = row![].spacing(SPACING_BIGGER);
+        for (index, tag) in completion.candidates.iter().enumerate() {
+            let is_selected = index == completion.selected;
+            candidates = candidates.push(
+                mouse_area(text!("#{}", tag).size(FONT_SIZE_BASE).style(
+                    move |theme: &Theme| {
+                        let palette = theme.palette();
+                        text::Style {
+                            color: is_selected.then_some(palette.primary),
+                        }
+                    },
+                ))
+                .interaction(mouse::Interaction::Pointer)
+                .on_enter(EditorMessage::SelectTagCompletion(index))
+                .on_press(EditorMessage::AcceptTagCompletion(index)),
+            );
+        }
+        container(
+            row![
+                candidates,
+                space::horizontal(),
+                text("Tab 选择  Esc 关闭").size(FONT_SIZE_SMALLER),
+            ]
+            .width(Length::Fill),
+        )
+        .padding(Padding::from([PADDING_SMALLER, PADDING_BIGGER]))
+        .style(|theme: &Theme| {
+            let ex_palette = theme.extended_palette();
+            container::Style {
+                background: Some(Background::Color(ex_palette.background.weakest.color)),
+                ..container::Style::default()
+            }
+        })
+        .into()
+    }
+
+    // 已打开文件时返回光标所在行
+    // 先滚动到上次的位置，再移动光标
+    fn apply_doc_view(&mut self, view: DocView) {
+        let line_count = self.editor_content.line_count();
+        self.scroll_line = view.scroll_line.min(line_count);
+        self.editor_content.perform(text_editor::Action::Scroll {
+            lines: self.scroll_line as i32,
+        });
+        self.editor_content.move_to(text_editor::Cursor {
+            position: text_editor::Position {
+                line: view.cursor_line.min(line_count.saturating_sub(1)),
+                column: 0,
+            },
+            selection: None,
+        });
+    }
+
+    pub fn current_doc_view(&self) -> Option<DocView> {
+        self.selected_file.as_ref().map(|_| DocView {
+            cursor_line: self.editor_content.cursor().position.line,
+            scroll_line: self.scroll_line,
+        })
+    }
+
+    // 打开过的所有文档的位置，包括当前文档
+    pub fn doc_views(&self) -> HashMap<u32, DocView> {
+        let mut doc_views = self.doc_views.clone();
+        if let Some(file_data) = &self.selected_file
+            && let Some(view) = self.current_doc_view()
+        {
+            doc_views.insert(file_data.global_id, view);
+        }
+        doc_views
+    }
+
+    // 当前文件有未保存的更改时返回其数据
+    pub fn unsaved_file(&self) -> Option<FileData> {
+        self.selected_file
+            .as_ref()
+            .filter(|file_data| self.original_version != Some(file_data.version))
+            .cloned()
+    }
+
     pub fn view(&self) -> Container<'_, EditorMessage> {
         let (line_count, word_count) = if self.selected_file.is_some() {
             (
@@ -183,6 +616,7 @@ impl Editor {
         let editor_view: Element<'_, EditorMessage> = self.generate_editor_component().into();
         container(column![
             editor_view,
+            self.view_tag_completion(),
             container(
                 row![
                     text!("行数  {}", line_count).size(FONT_SIZE_BASE),
@@ -224,15 +658,45 @@ impl Editor {
                 .interaction(mouse::Interaction::Pointer),
             mouse_area(text("代码").size(FONT_SIZE_BIGGER))
                 .interaction(mouse::Interaction::Pointer),
-            space::horizontal()
-        ]
-        .width(Length::Fill)
-        .padding(Padding::from([PADDING_SMALLER, PADDING_BIGGER]))
-        .height(Length::Shrink);
+            mouse_area(text("属性").size(FONT_SIZE_BIGGER))
+                .interaction(mouse::Interaction::Pointer)
+                .on_press(EditorMessage::EditFrontMatter),
+        ];
+        // 只有日记才能切换到前一天或后一天
+        let is_journal = self
+            .selected_file
+            .as_ref()
+            .and_then(|file_data| file_data.path.as_deref())
+            .and_then(journal::journal_date)
+            .is_some();
+        if is_journal {
+            file_name_bar = file_name_bar
+                .push(
+                    mouse_area(text("前一天").size(FONT_SIZE_BIGGER))
+                        .interaction(mouse::Interaction::Pointer)
+                        .on_press(EditorMessage::SendOpenAdjacentDailyNote(-1)),
+                )
+                .push(
+                    mouse_area(text("后一天").size(FONT_SIZE_BIGGER))
+                        .interaction(mouse::Interaction::Pointer)
+                        .on_press(EditorMessage::SendOpenAdjacentDailyNote(1)),
+                );
+        }
+        file_name_bar = file_name_bar
+            .push(space::horizontal())
+            .width(Length::Fill)
+            .padding(Padding::from([PADDING_SMALLER, PADDING_BIGGER]))
+            .height(Length::Shrink);
         let mut file_content_editor = text_editor(&self.editor_content)
             .height(Length::Fill)
             .padding(Padding::from([PADDING_SMALLER, PADDING_BIGGER]))
-            .highlight("markdown", iced::highlighter::Theme::Base16Ocean)
+            .highlight_with::<MarkdownHighlighter>(
+                iced::highlighter::Settings {
+                    theme: iced::highlighter::Theme::Base16Ocean,
+                    token: "markdown".to_string(),
+                },
+                MarkdownHighlight::to_format,
+            )
             .style(|theme: &Theme, _| {
                 let palette = theme.palette();
                 text_editor::Style {
@@ -244,7 +708,9 @@ impl Editor {
                 }
             });
         if let Some(file_data) = &self.selected_file {
-            file_content_editor = file_content_editor.on_action(EditorMessage::EditorAction);
+            file_content_editor = file_content_editor
+                .on_action(EditorMessage::EditorAction)
+                .key_binding(|key_press| self.key_binding(key_press));
             if self.original_version == Some(file_data.version) {
                 file_name_bar = file_name_bar.push(text!("已保存").size(FONT_SIZE_BIGGER).style(
                     |theme: &Theme| {
@@ -268,6 +734,9 @@ impl Editor {
             }
         }
 
+        let file_content_editor =
+            mouse_area(file_content_editor).on_right_press(EditorMessage::RequestContextMenu);
+
         column![
             file_name_bar.spacing(SPACING_BIGGER),
             rule::horizontal(1).style(|theme: &Theme| {
@@ -0,0 +1,367 @@
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    common::{
+        md_link,
+        wiki_link::{self, NoteIndex},
+        *,
+    },
+    file_panel::link_index::{IndexedFile, LinkIndex},
+};
+use fancy_regex::Regex;
+use iced::{
+    Background, Color, Element, Length, Padding, Task, Theme, mouse, padding,
+    widget::{Column, column, container, mouse_area, scrollable, text},
+};
+use tracing::{error, info};
+
+// 反向链接面板，列出链接到当前文档的文件，以及提到了文档标题但没有加链接的地方
+#[derive(Debug, Default)]
+pub struct Backlinks {
+    path: Option<PathBuf>,
+    linked: Vec<Backlink>,
+    mentions: Vec<Backlink>,
+    hovered: Option<(bool, usize)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BacklinkResult {
+    pub linked: Vec<Backlink>,
+    pub mentions: Vec<Backlink>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Backlink {
+    pub path: PathBuf,
+    pub line: usize,
+    // 链接所在的句子
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum BacklinksMessage {
+    Clear,
+    BacklinksLoaded(PathBuf, BacklinkResult),
+    HoverEntry(bool, usize),
+    // 发送给其他模块的消息
+    SendOpenFileAtLine(PathBuf, usize),
+}
+
+impl Backlinks {
+    pub fn update(&mut self, message: BacklinksMessage) -> Task<BacklinksMessage> {
+        match message {
+            BacklinksMessage::Clear => {
+                self.path = None;
+                self.linked.clear();
+                self.mentions.clear();
+                Task::none()
+            }
+            BacklinksMessage::BacklinksLoaded(path, result) => {
+                info!(
+                    "[Backlinks-BacklinksLoaded]:{} 条反向链接，{} 处未链接的提及!",
+                    result.linked.len(),
+                    result.mentions.len()
+                );
+                self.path = Some(path);
+                self.linked = result.linked;
+                self.mentions = result.mentions;
+                self.hovered = None;
+                Task::none()
+            }
+            BacklinksMessage::HoverEntry(is_mention, index) => {
+                self.hovered = Some((is_mention, index));
+                Task::none()
+            }
+            _ => Task::none(),
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, BacklinksMessage> {
+        if self.path.is_none() {
+            return text("当前文档没有保存到磁盘")
+                .size(FONT_SIZE_SMALLER)
+                .into();
+        }
+        let mut content = Column::new().spacing(SPACING_SMALLER);
+        content = content.push(section_title(format!(
+            "链接到此文档  {}",
+            self.linked.len()
+        )));
+        for (index, backlink) in self.linked.iter().enumerate() {
+            content = content.push(self.view_entry(backlink, false, index));
+        }
+        content = content.push(
+            container(section_title(format!(
+                "未链接的提及  {}",
+                self.mentions.len()
+            )))
+            .padding(padding::top(PADDING_BASE)),
+        );
+        for (index, backlink) in self.mentions.iter().enumerate() {
+            content = content.push(self.view_entry(backlink, true, index));
+        }
+        scrollable(content).height(Length::Fill).into()
+    }
+
+    fn view_entry<'a>(
+        &'a self,
+        backlink: &'a Backlink,
+        is_mention: bool,
+        index: usize,
+    ) -> Element<'a, BacklinksMessage> {
+        let name = backlink
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        mouse_area(
+            container(column![
+                text!("{}  第{}行", name, backlink.line + 1).size(FONT_SIZE_SMALLER),
+                text(&backlink.snippet)
+                    .size(FONT_SIZE_SMALLEST)
+                    .style(|theme: &Theme| text::Style {
+                        color: Some(theme.extended_palette().background.strong.text),
+                    }),
+            ])
+            .width(Length::Fill)
+            .padding(Padding::from([PADDING_SMALLEST, PADDING_SMALLER]))
+            .style(move |theme: &Theme| {
+                let ex_palette = theme.extended_palette();
+                let bg = match self.hovered == Some((is_mention, index)) {
+                    true => ex_palette.background.weaker.color,
+                    false => Color::TRANSPARENT,
+                };
+                container::Style {
+                    background: Some(Background::Color(bg)),
+                    ..container::Style::default()
+                }
+            }),
+        )
+        .interaction(mouse::Interaction::Pointer)
+        .on_enter(BacklinksMessage::HoverEntry(is_mention, index))
+        .on_press(BacklinksMessage::SendOpenFileAtLine(
+            backlink.path.clone(),
+            backlink.line,
+        ))
+        .into()
+    }
+}
+
+fn section_title<'a>(title: String) -> Element<'a, BacklinksMessage> {
+    text(title)
+        .size(FONT_SIZE_BASE)
+        .style(|theme: &Theme| text::Style {
+            color: Some(theme.palette().primary),
+        })
+        .into()
+}
+
+// 在后台线程中遍历索引并读取文档，不阻塞界面
+pub async fn collect_backlinks(
+    link_index: Arc<LinkIndex>,
+    note_index: Arc<NoteIndex>,
+    path: PathBuf,
+) -> BacklinkResult {
+    tokio::task::spawn_blocking(move || {
+        find_backlinks(&link_index, &note_index, &path, |file_path| {
+            std::fs::read_to_string(file_path).ok()
+        })
+    })
+    .await
+    .unwrap_or_else(|error| {
+        error!("[Backlinks-CollectBacklinks]:{}", error);
+        BacklinkResult::default()
+    })
+}
+
+// 索引中不保存内容，摘要和提及从read_content读取的内容中查找
+pub fn find_backlinks(
+    link_index: &LinkIndex,
+    note_index: &NoteIndex,
+    path: &Path,
+    read_content: impl Fn(&Path) -> Option<String>,
+) -> BacklinkResult {
+    let mut result = BacklinkResult::default();
+    // 文件名和front matter中的标题都算作提及，太短时基本都是误判
+    let mut names = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .into_iter()
+        .chain(link_index.title(path).map(str::to_string))
+        .filter(|name| name.chars().count() >= 2)
+        .map(|name| fancy_regex::escape(&name).into_owned())
+        .collect::<Vec<_>>();
+    names.dedup();
+    let mention_regex = match names.is_empty() {
+        true => None,
+        false => Regex::new(&format!("(?i){}", names.join("|"))).ok(),
+    };
+    for (file_path, file) in link_index.files() {
+        if file_path == path {
+            continue;
+        }
+        let has_link = file
+            .links
+            .iter()
+            .any(|link| link.target.resolve(note_index) == Some(path));
+        if !has_link && mention_regex.is_none() {
+            continue;
+        }
+        let Some(content) = read_content(file_path) else {
+            continue;
+        };
+        let lines = content.lines().collect::<Vec<_>>();
+        // 索引之后文档可能又被修改，按读取到的内容重新解析链接，保证行号和范围一致
+        let links = match has_link {
+            true => IndexedFile::new(file_path, &content, None).links,
+            false => vec![],
+        };
+        for link in &links {
+            if link.target.resolve(note_index) == Some(path)
+                && let Some(line) = lines.get(link.line)
+            {
+                result.linked.push(Backlink {
+                    path: file_path.clone(),
+                    line: link.line,
+                    snippet: sentence_around(line, &link.range),
+                });
+            }
+        }
+        let Some(ref mention_regex) = mention_regex else {
+            continue;
+        };
+        let mut fence = None;
+        for (line_index, line) in lines.iter().enumerate() {
+            if wiki_link::update_fence(&mut fence, line) {
+                continue;
+            }
+            let link_ranges = wiki_link::find_wiki_links_in_line(line)
+                .into_iter()
+                .map(|link| link.range)
+                .chain(
+                    md_link::find_md_links_in_line(line)
+                        .into_iter()
+                        .map(|link| link.range),
+                )
+                .collect::<Vec<_>>();
+            // 每行只记录第一处提及
+            let mention = mention_regex.find_iter(line).flatten().find(|found| {
+                is_word_boundary(line, found.start(), found.end())
+                    && !link_ranges
+                        .iter()
+                        .any(|range| range.start <= found.start() && found.end() <= range.end)
+            });
+            if let Some(mention) = mention {
+                result.mentions.push(Backlink {
+                    path: file_path.clone(),
+                    line: line_index,
+                    snippet: sentence_around(line, &(mention.start()..mention.end())),
+                });
+            }
+        }
+    }
+    for backlinks in [&mut result.linked, &mut result.mentions] {
+        backlinks.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+    }
+    result
+}
+
+// 英文单词的前后不能紧接着字母或数字，中文没有单词边界
+fn is_word_boundary(line: &str, start: usize, end: usize) -> bool {
+    let is_word_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let before = line[..start].chars().next_back();
+    let after = line[end..].chars().next();
+    let first = line[start..end].chars().next();
+    let last = line[start..end].chars().next_back();
+    let joins_before = first.is_some_and(is_word_char) && before.is_some_and(is_word_char);
+    let joins_after = last.is_some_and(is_word_char) && after.is_some_and(is_word_char);
+    !joins_before && !joins_after
+}
+
+// 取出包含指定范围的句子，过长时截断
+// 截取链接所在的句子，句子太长时和搜索结果一样以链接为中心截取
+fn sentence_around(line: &str, range: &Range<usize>) -> String {
+    let is_sentence_end = |index: usize, c: char| match c {
+        '。' | '！' | '？' | '；' | '!' | '?' | ';' => true,
+        // 英文句号后面需要有空格，避免把文件扩展名当作句子结尾
+        '.' => line[index + 1..].starts_with(' ') || index + 1 == line.len(),
+        _ => false,
+    };
+    let start = line[..range.start]
+        .char_indices()
+        .rfind(|(index, c)| is_sentence_end(*index, *c))
+        .map(|(index, c)| index + c.len_utf8())
+        .unwrap_or(0);
+    let end = line[range.end..]
+        .char_indices()
+        .find(|(index, c)| is_sentence_end(range.end + index, *c))
+        .map(|(index, c)| range.end + index + c.len_utf8())
+        .unwrap_or(line.len());
+    let sentence = &line[start..end];
+    let start = start + sentence.len() - sentence.trim_start().len();
+    let end = end - (sentence.len() - sentence.trim_end().len());
+    if line[start..end].chars().count() <= SEARCH_PREVIEW_MAX_CHARS {
+        return line[start..end].to_string();
+    }
+    let window_start = line[start..range.start.clamp(start, end)]
+        .char_indices()
+        .rev()
+        .nth(SEARCH_PREVIEW_CONTEXT_CHARS)
+        .map(|(index, _)| start + index)
+        .unwrap_or(start);
+    let window_end = line[window_start..end]
+        .char_indices()
+        .nth(SEARCH_PREVIEW_MAX_CHARS)
+        .map(|(index, _)| window_start + index)
+        .unwrap_or(end);
+    format!(
+        "{}{}{}",
+        if window_start > start { "…" } else { "" },
+        &line[window_start..window_end],
+        if window_end < end { "…" } else { "" },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_backlinks() {
+        let target = PathBuf::from("/notes/周报.md");
+        let source = PathBuf::from("/notes/日记/今天.md");
+        let content = "开头。今天写了[[周报]]的初稿. 然后去吃饭。\n参考[上周](../周报.md)\n周报还没发\n```\n周报\n```\n补上weekly report";
+        let contents = HashMap::from([
+            (source.clone(), content.to_string()),
+            (
+                target.clone(),
+                "---\ntitle: Weekly Report\n---\n[[周报]]".to_string(),
+            ),
+        ]);
+        let mut link_index = LinkIndex::default();
+        for (path, content) in contents.iter() {
+            link_index.insert(path.clone(), IndexedFile::new(path, content, None));
+        }
+        let note_index = NoteIndex::new([target.clone(), source.clone()]);
+        let result = find_backlinks(&link_index, &note_index, &target, |path| {
+            contents.get(path).cloned()
+        });
+        assert_eq!(result.linked.len(), 2);
+        assert_eq!(result.linked[0].snippet, "今天写了[[周报]]的初稿.");
+        assert_eq!(result.linked[1].line, 1);
+        assert_eq!(result.mentions.len(), 2);
+        assert_eq!(result.mentions[0].line, 2);
+        assert_eq!(result.mentions[1].line, 6);
+
+        let line = format!("{}[[周报]]{}", "前".repeat(200), "后".repeat(200));
+        let snippet = sentence_around(&line, &(600..610));
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert!(snippet.contains("[[周报]]"));
+        assert_eq!(snippet.chars().count(), SEARCH_PREVIEW_MAX_CHARS + 2);
+    }
+}
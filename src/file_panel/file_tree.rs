@@ -1,24 +1,94 @@
 use crate::{
-    common::*,
-    file_panel::operation::{self, FileNode, ImageFile, IsAutoSave, MdFile, NodeContent, TempDir},
+    common::{
+        journal,
+        link_graph::{GraphNode, LinkGraph},
+        template::{NewFileRequest, Template, TemplateContext},
+        wiki_link::{self, NoteIndex},
+        *,
+    },
+    file_panel::{
+        link_check,
+        link_index::LinkIndex,
+        note_indexer::{IndexRoots, NoteIndexer, NoteIndexerMessage},
+        operation::{
+            self, AssetFile, AssetKind, DirChildren, FileNode, ImageFile, IsAutoSave, LoadState,
+            MarkdownDirCache, MdFile, NodeContent, NodeViewState, ScanEntry, ScanOptions,
+            ScanProgress, TempDir,
+        },
+        path_history::{PathHistory, PathHistoryMessage},
+        quick_open::{QuickOpen, QuickOpenCandidate, QuickOpenMessage, QuickOpenTarget},
+        workspace_scan::{PollTarget, WorkspaceScan, WorkspaceScanMessage},
+    },
+    setting::{self, DocumentSession, WorkspaceSession},
 };
 use iced::{
-    Element, Task,
-    widget::{Column, scrollable},
+    Element, Length, Padding, Point, Subscription, Task,
+    alignment::Horizontal,
+    event, keyboard, mouse, padding,
+    widget::{Column, column, container, image, mouse_area, row, scrollable, space, text},
 };
-use std::{collections::HashMap, path::PathBuf, sync::Arc};
-use tracing::{error, info};
+use jiff::civil::Date;
+use std::{
+    cmp,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tracing::{error, info, warn};
 
 #[derive(Debug)]
 pub struct FileTree {
-    workplace_root_key: Option<u32>,
+    // 所有打开的工作区根节点，按打开顺序显示
+    workplace_root_keys: Vec<u32>,
     temp_workplace_root_key: Option<u32>,
     temp_img_library_root_key: Option<u32>,
     pub all_nodes: HashMap<u32, FileNode>,
+    // 节点路径到节点的索引，增删节点和路径改变时同步维护
+    path_index: HashMap<PathBuf, u32>,
+    // 隐藏没有文档的文件夹时使用，所有工作区共用
+    markdown_dir_cache: Arc<MarkdownDirCache>,
     hovered_file_node_id: Option<u32>,
     selected_node_id: Option<u32>,
     // 暂时缓存用户选择的节点
     _selected_node_id_cache: Option<u32>,
+    // 最近一次点击的节点，新建、重命名、删除等操作默认以它为目标
+    focused_node_id: Option<u32>,
+    // 按下鼠标的节点和按下后记录到的第一个鼠标位置
+    pressed_node: Option<(u32, Option<Point>)>,
+    // 按住节点移动超过阈值后开始拖动，松开时在其他节点上则移动节点
+    dragging_node_id: Option<u32>,
+    // 正在重命名的节点和输入框内容
+    renaming: Option<(u32, String)>,
+    // 首次读取工作区的排队和进度，以及已打开工作区的定时扫描
+    workspace_scan: WorkspaceScan,
+    // 所有工作区保存过的排序和过滤设置，按根路径区分
+    tree_view_settings: HashMap<PathBuf, TreeViewSetting>,
+    // 文件树的滚动位置和可见高度，用于只渲染可见范围内的行
+    tree_scroll_offset: f32,
+    tree_viewport_height: f32,
+    // 快速打开面板，以及最近打开的文件，越靠前越近
+    quick_open: QuickOpen,
+    recent_node_ids: Vec<u32>,
+    // 打开文件后需要跳转到的行
+    pending_cursor_line: Option<(u32, usize)>,
+    // 恢复会话时等待对应工作区读取完成后再展开的文件夹和打开的文件
+    pending_expanded_dirs: Vec<PathBuf>,
+    pending_restore_file: Option<(PathBuf, usize)>,
+    // 上次会话中文档的位置，本次第一次打开该文档时恢复
+    pending_doc_views: HashMap<PathBuf, DocView>,
+    // 所有笔记的名称索引和出链索引，用于解析[[链接]]和反向链接面板
+    note_indexer: NoteIndexer,
+    // 等待用户确认的链接更新，以及最近一次可以撤销的重命名或移动
+    path_history: PathHistory,
+    // 按标签过滤文件树，只显示带有该标签的笔记和它们的上级文件夹
+    tag_filter: Option<TagFilter>,
+}
+
+#[derive(Debug)]
+struct TagFilter {
+    tag: String,
+    paths: HashSet<PathBuf>,
+    dirs: HashSet<PathBuf>,
 }
 
 #[derive(Debug, Clone)]
@@ -39,26 +109,148 @@ pub enum FileTreeMessage {
     SendFileDataToEditor(FileData),
     AskIsLoadPermitted,
     LoadPermitted,
+    LoadCanceled,
     SendImgCodeToEditor(String),
     SendImgDataToPreview(Vec<ImgData>),
+    // 工作区附件相关消息
+    OpenAsset(u32),
+    LoadAssetImage(u32, image::Handle),
     SendImgBasePathToPreview(PathBuf),
     CreateMdCache(String, u32),
     UpdateNodePath(PathBuf, FileData),
     UpdateNodeInfo(IsAutoSave, FileData),
     HandleError(AppError),
     CopyImgFileData(u32),
+    SaveUnsavedDocs(Vec<UnsavedDoc>),
+    ApplySavedDocs(Vec<(u32, PathBuf, Arc<String>)>, Result<(), AppError>),
+    ReturnSaveAllResult(Result<(), AppError>),
+    // 文件操作相关消息
+    PressNode(u32),
+    ReleaseNode(u32),
+    DragCursorMoved(Point),
+    DragReleased,
+    CreateNewFileInDir(u32),
+    SendOpenNewFileDialog(u32, Vec<Template>),
+    CreateFileFromTemplate(NewFileRequest),
+    TemplateFileCreated(u32, FileNode),
+    CreateNewFolderInDir(u32),
+    InsertChildNodes(u32, u32, HashMap<u32, FileNode>, bool),
+    StartRenameNode(u32),
+    RenameInputChanged(String),
+    ConfirmRenameNode,
+    CancelRenameNode,
+    ApplyRenamedPath(u32, PathBuf),
+    DeleteNode(u32),
+    RemoveNode(u32),
+    DuplicateNode(u32),
+    MoveNode(u32, u32),
+    ApplyMovedPath(u32, u32, PathBuf),
+    // 重命名或移动后更新其他文件中指向它的链接，用户确认后才修改
+    PlanLinkRewrites(PathBuf, PathBuf),
+    PathHistory(PathHistoryMessage),
+    SendConfirmLinkRewrites(Vec<String>),
+    UnloadFileFromEditor(u32),
+    // 右键菜单相关消息
+    RequestContextMenu(u32),
+    SendContextMenuItems(Vec<ContextMenuItem>),
+    HandleContextAction(ContextAction),
+    RevealNode(u32),
+    // 工作区外部变动同步相关消息
+    ScanWorkspace,
+    RescanWorkspace,
+    WorkspaceScan(WorkspaceScanMessage),
+    FileTreeScanFailed(AppError),
+    DirChildrenLoaded(u32, Result<BTreeMap<PathBuf, ScanEntry>, AppError>),
+    TreeScrolled(scrollable::Viewport),
+    // 快速打开相关消息
+    OpenQuickOpen,
+    QuickOpen(QuickOpenMessage),
+    // 打开文件并跳转到指定行，文件所在文件夹未读取时先读取
+    OpenPathAtLine(PathBuf, usize),
+    PathDirsLoaded(PathBuf, usize, Result<Vec<DirChildren>, AppError>),
+    ApplyPendingCursorLine(u32),
+    SendCursorLineToEditor(usize),
+    SendDocViewToEditor(DocView),
+    // 全文搜索和替换相关消息
+    CollectSearchFiles,
+    SendSearchFiles(Result<SearchTargets, AppError>),
+    ApplyReplacements(Vec<FileReplacement>),
+    ReplacementsApplied(usize, Vec<(PathBuf, Result<(), AppError>)>),
+    // 保存成功后标记缓存与磁盘一致
+    MarkFileSaved(PathBuf, Arc<String>),
+    SendReplacementToEditor(FileReplacement),
+    SendReplaceResult(usize, usize),
+    // 检查工作区中的失效链接
+    CheckLinks,
+    SendLinkReport(Result<Vec<FileLinkIssues>, AppError>),
+    // 排序和过滤相关消息
+    LoadTreeViewSettings(PathBuf, HashMap<PathBuf, TreeViewSetting>),
+    ChangeSortMode(u32, SortMode),
+    SetHideDirsWithoutMarkdown(u32, bool),
+    // 多工作区和工作区文件相关消息
+    CloseWorkspaceRoot(u32),
+    LoadWorkspaceFile(PathBuf),
+    WorkspaceFileLoaded(Result<WorkspaceFile, AppError>),
+    SaveWorkspaceFile(PathBuf),
+    // 恢复会话相关消息
+    RestoreSession(WorkspaceSession),
+    ExpandedDirsLoaded(Vec<PathBuf>, Vec<DirChildren>),
+    RestoredFilesLoaded(Vec<FileNode>),
+    RestoredImgsLoaded(HashMap<u32, FileNode>),
+    SendRestoredImgDataToPreview(Vec<ImgData>),
+    // 打开文件或工作区后记录到最近打开列表
+    SendRecordRecent(PathBuf, RecentKind),
+    // 笔记之间的[[链接]]相关消息
+    RefreshNoteIndex,
+    // 文件树增删、重命名和移动后只重新索引变动的路径
+    UpdateNoteIndex(Vec<PathBuf>),
+    NoteIndexer(NoteIndexerMessage),
+    SendNoteIndexToPreview(Arc<NoteIndex>),
+    SendLinkIndexChanged,
+    OpenWikiLink(String, Option<String>),
+    CreateWikiNote(String),
+    // 预览中点击的相对路径链接，可以带有#锚点
+    OpenLinkedPath(PathBuf, Option<String>),
+    WikiNoteCreated(PathBuf, FileNode),
+    // 按标签过滤，为空时取消过滤
+    FilterByTag(Option<String>),
+    TagDirsLoaded(Result<Vec<DirChildren>, AppError>),
+    // 每日笔记，不存在时按模板新建
+    OpenDailyNote(Date),
+    OpenAdjacentDailyNote(i32),
+    DailyNoteReady(PathBuf, PathBuf, bool),
+    DailyNoteDirsLoaded(PathBuf, Result<Vec<DirChildren>, AppError>),
 }
 
 impl FileTree {
     pub fn new() -> Self {
         let file_panel = Self {
             all_nodes: HashMap::new(),
-            workplace_root_key: None,
+            path_index: HashMap::new(),
+            markdown_dir_cache: Arc::default(),
+            workplace_root_keys: vec![],
             temp_workplace_root_key: None,
             temp_img_library_root_key: None,
             selected_node_id: None,
             _selected_node_id_cache: None,
             hovered_file_node_id: None,
+            focused_node_id: None,
+            pressed_node: None,
+            dragging_node_id: None,
+            renaming: None,
+            workspace_scan: WorkspaceScan::default(),
+            tree_view_settings: HashMap::new(),
+            tree_scroll_offset: 0.,
+            tree_viewport_height: DEFAULT_WINDOW_SIZE[1],
+            quick_open: QuickOpen::default(),
+            recent_node_ids: vec![],
+            pending_cursor_line: None,
+            pending_expanded_dirs: vec![],
+            pending_restore_file: None,
+            pending_doc_views: HashMap::new(),
+            note_indexer: NoteIndexer::default(),
+            path_history: PathHistory::default(),
+            tag_filter: None,
         };
         file_panel
     }
@@ -77,6 +269,7 @@ impl FileTree {
                             NodeContent::Markdown(MdFile {
                                 path: Some(path),
                                 version: 0,
+                                saved_version: 0,
                                 cache: Some(Arc::new(text)),
                             }),
                         );
@@ -102,15 +295,127 @@ impl FileTree {
                     Err(error) => FileTreeMessage::HandleError(error),
                 })
             }
-            FileTreeMessage::FetchFileTree(file_node) => Task::perform(
-                operation::fetch_file_tree(file_node.clone()),
-                |tree| match tree {
-                    Ok((root_node_key, all_nodes)) => {
-                        FileTreeMessage::LoadFileTree(root_node_key, all_nodes)
-                    }
-                    Err(error) => FileTreeMessage::HandleError(error),
-                },
-            ),
+            FileTreeMessage::FetchFileTree(file_node) => {
+                // 同一路径或者互相包含的文件夹不能同时作为工作区
+                if let Some(root_path) = self.root_paths().into_iter().find(|(_, root_path)| {
+                    file_node.starts_with(root_path) || root_path.starts_with(&file_node)
+                }) {
+                    return Task::done(FileTreeMessage::HandleError(AppError::FilePanelError(
+                        format!(
+                            "[FileTree-FetchFileTree]:{} 与已打开的工作区 {} 重叠!",
+                            file_node.display(),
+                            root_path.1.display()
+                        ),
+                    )));
+                }
+                Task::done(FileTreeMessage::WorkspaceScan(WorkspaceScanMessage::Start(
+                    file_node,
+                )))
+            }
+            // 先读取该工作区保存的排序和过滤设置，再按设置读取文件树
+            FileTreeMessage::WorkspaceScan(WorkspaceScanMessage::SendLoadRoot(root_path)) => {
+                Task::perform(
+                    setting::load_config(setting::TREE_VIEW_SETTING_FILE),
+                    move |tree_view_settings| {
+                        FileTreeMessage::LoadTreeViewSettings(root_path.clone(), tree_view_settings)
+                    },
+                )
+            }
+            FileTreeMessage::WorkspaceScan(WorkspaceScanMessage::SendFetchRoot(root_path)) => {
+                Task::done(FileTreeMessage::FetchFileTree(root_path))
+            }
+            FileTreeMessage::WorkspaceScan(WorkspaceScanMessage::SendError(error)) => {
+                Task::done(FileTreeMessage::HandleError(error))
+            }
+            FileTreeMessage::LoadTreeViewSettings(root_path, tree_view_settings) => {
+                let Some(progress) = self.workspace_scan.progress() else {
+                    return Task::none();
+                };
+                // 内存中的设置是最新的，只补充没有的部分
+                for (path, tree_view_setting) in tree_view_settings {
+                    self.tree_view_settings.entry(path).or_insert(tree_view_setting);
+                }
+                let options = self.scan_options(setting, &root_path);
+                Task::perform(
+                    operation::fetch_file_tree(root_path, options, progress),
+                    |tree| match tree {
+                        Ok((root_node_key, all_nodes)) => {
+                            FileTreeMessage::LoadFileTree(root_node_key, all_nodes)
+                        }
+                        Err(error) => FileTreeMessage::FileTreeScanFailed(error),
+                    },
+                )
+            }
+            FileTreeMessage::ChangeSortMode(root_key, sort_mode) => {
+                let Some(mut tree_view_setting) = self.tree_view_setting_of_root(root_key) else {
+                    return Task::none();
+                };
+                tree_view_setting.sort_mode = sort_mode;
+                let task = self.save_tree_view_setting(root_key, tree_view_setting);
+                self.sort_subtree(root_key);
+                task
+            }
+            // 文件夹的显示和隐藏交给定时扫描按新设置增删
+            FileTreeMessage::SetHideDirsWithoutMarkdown(root_key, is_hidden) => {
+                let Some(mut tree_view_setting) = self.tree_view_setting_of_root(root_key) else {
+                    return Task::none();
+                };
+                tree_view_setting.hide_dirs_without_markdown = is_hidden;
+                self.save_tree_view_setting(root_key, tree_view_setting)
+            }
+            FileTreeMessage::FileTreeScanFailed(error) => Task::batch([
+                Task::done(FileTreeMessage::HandleError(error)),
+                Task::done(FileTreeMessage::WorkspaceScan(WorkspaceScanMessage::Finish)),
+            ]),
+            FileTreeMessage::CloseWorkspaceRoot(root_key) => {
+                let root_path = self.node_path(root_key);
+                self.close_workspace_root(root_key);
+                Task::done(FileTreeMessage::UpdateNoteIndex(root_path.into_iter().collect()))
+            }
+            FileTreeMessage::LoadWorkspaceFile(path) => {
+                Task::perform(operation::read_workspace_file(path), |result| {
+                    FileTreeMessage::WorkspaceFileLoaded(result)
+                })
+            }
+            // 打开工作区文件时替换当前所有工作区
+            FileTreeMessage::WorkspaceFileLoaded(result) => {
+                let workspace_file = match result {
+                    Ok(workspace_file) => workspace_file,
+                    Err(error) => return Task::done(FileTreeMessage::HandleError(error)),
+                };
+                for root_key in self.workplace_root_keys.clone() {
+                    self.close_workspace_root(root_key);
+                }
+                let mut tasks = vec![];
+                for root in workspace_file.roots {
+                    self.tree_view_settings
+                        .insert(root.path.clone(), root.tree_view);
+                    tasks.push(Task::done(FileTreeMessage::FetchFileTree(root.path)));
+                }
+                tasks.push(self.save_tree_view_settings());
+                Task::batch(tasks)
+            }
+            FileTreeMessage::SaveWorkspaceFile(path) => {
+                let workspace_file = WorkspaceFile {
+                    roots: self
+                        .root_paths()
+                        .into_iter()
+                        .map(|(root_key, root_path)| WorkspaceRoot {
+                            tree_view: self.tree_view_setting_of_root(root_key).unwrap_or_default(),
+                            path: root_path,
+                        })
+                        .collect(),
+                };
+                Task::future(operation::write_workspace_file(path, workspace_file)).then(
+                    |result| match result {
+                        Ok(_) => {
+                            info!("[FileTree-SaveWorkspaceFile]:工作区文件保存成功!");
+                            Task::none()
+                        }
+                        Err(error) => Task::done(FileTreeMessage::HandleError(error)),
+                    },
+                )
+            }
             FileTreeMessage::FetchImgHandles(file_node) => Task::perform(
                 operation::fetch_img_handle(file_node),
                 |result| match result {
@@ -119,30 +424,115 @@ impl FileTree {
                 },
             ),
             FileTreeMessage::LoadFileTree(root_node_key, all_nodes) => {
-                self.workplace_root_key = Some(root_node_key);
-                self.all_nodes.extend(all_nodes);
-                Task::none()
+                self.workplace_root_keys.push(root_node_key);
+                self.insert_nodes(all_nodes);
+                self.sort_subtree(root_node_key);
+                let record_task = match self.node_path(root_node_key) {
+                    Some(path) => Task::batch([
+                        Task::done(FileTreeMessage::SendRecordRecent(
+                            path.clone(),
+                            RecentKind::Folder,
+                        )),
+                        Task::done(FileTreeMessage::UpdateNoteIndex(vec![path])),
+                    ]),
+                    None => Task::none(),
+                };
+                Task::batch([
+                    record_task,
+                    self.restore_expanded_dirs(root_node_key, setting),
+                    self.open_restored_file(),
+                    Task::done(FileTreeMessage::WorkspaceScan(WorkspaceScanMessage::Finish)),
+                ])
             }
             FileTreeMessage::LoadImgHandles(img_nodes) => {
-                let ids = img_nodes.keys().copied().collect::<Vec<u32>>();
-                let img_datas = img_nodes
-                    .values()
+                let img_datas = self.insert_img_nodes(img_nodes);
+                Task::done(FileTreeMessage::SendImgDataToPreview(img_datas))
+            }
+            // 工作区、临时工作区和图片库分别恢复，编辑器中的文件等所在位置读取完成后再打开
+            FileTreeMessage::RestoreSession(session) => {
+                self.pending_expanded_dirs = session.expanded_dirs;
+                self.pending_doc_views = session
+                    .documents
                     .into_iter()
-                    .filter_map(|img_node| {
-                        img_node
-                            .try_get_img()
-                            .ok()
-                            .map(|img_file| (img_file, img_node.global_id))
+                    .map(|document| {
+                        let view = DocView {
+                            cursor_line: document.cursor_line,
+                            scroll_line: document.scroll_line,
+                        };
+                        (document.path, view)
                     })
-                    .map(|(img_file, id)| ImgData {
-                        global_id: id,
-                        indep_id: img_file.indep_id.clone(),
-                        handle: img_file.cache.clone(),
+                    .collect();
+                self.pending_restore_file = session.active_file.map(|path| {
+                    let line = self
+                        .pending_doc_views
+                        .get(&path)
+                        .map(|view| view.cursor_line)
+                        .unwrap_or_default();
+                    (path, line)
+                });
+                let mut tasks = session
+                    .roots
+                    .into_iter()
+                    .map(|root_path| Task::done(FileTreeMessage::FetchFileTree(root_path)))
+                    .collect::<Vec<_>>();
+                if !session.open_files.is_empty() {
+                    tasks.push(Task::perform(
+                        operation::read_restored_md_files(session.open_files),
+                        FileTreeMessage::RestoredFilesLoaded,
+                    ));
+                }
+                if !session.gallery_images.is_empty() {
+                    tasks.push(Task::perform(
+                        operation::read_restored_img_files(session.gallery_images),
+                        FileTreeMessage::RestoredImgsLoaded,
+                    ));
+                }
+                info!("[FileTree-RestoreSession]:开始恢复上次的会话!");
+                Task::batch(tasks)
+            }
+            FileTreeMessage::ExpandedDirsLoaded(expanded_dirs, dirs) => {
+                for (dir_path, entries) in dirs {
+                    let Some(key) = self.find_node_by_path(&dir_path) else {
+                        continue;
+                    };
+                    if self
+                        .all_nodes
+                        .get(&key)
+                        .is_some_and(|node| node.load_state() != LoadState::Loaded)
+                    {
+                        self.apply_dir_children(key, entries);
+                    }
+                    if expanded_dirs.contains(&dir_path)
+                        && let Some(node) = self.all_nodes.get_mut(&key)
+                    {
+                        node.set_expanded_if_directory(true);
+                    }
+                }
+                Task::none()
+            }
+            // 已经在文件树中的文件不重复添加
+            FileTreeMessage::RestoredFilesLoaded(file_nodes) => {
+                let file_nodes = file_nodes
+                    .into_iter()
+                    .filter(|file_node| {
+                        file_node
+                            .try_get_path()
+                            .is_ok_and(|path| self.find_node_by_path(path).is_none())
                     })
-                    .collect::<Vec<ImgData>>();
-                self.all_nodes.extend(img_nodes);
-                self.insert_node_to_temp_img_library(ids);
-                Task::done(FileTreeMessage::SendImgDataToPreview(img_datas))
+                    .collect::<Vec<_>>();
+                self.insert_node_to_temp_workplace(
+                    file_nodes.iter().map(|file_node| file_node.global_id).collect(),
+                );
+                self.insert_nodes(
+                    file_nodes
+                        .into_iter()
+                        .map(|file_node| (file_node.global_id, file_node)),
+                );
+                self.open_restored_file()
+            }
+            FileTreeMessage::RestoredImgsLoaded(img_nodes) => {
+                let img_datas = self.insert_img_nodes(img_nodes);
+                Task::done(FileTreeMessage::SendRestoredImgDataToPreview(img_datas))
             }
             FileTreeMessage::InsertToFileTree(file_node) => {
                 if file_node.is_md_file() {
@@ -152,21 +542,36 @@ impl FileTree {
                     self.insert_node_to_temp_img_library(vec![file_node.global_id]);
                 };
                 let file_node_id = file_node.global_id;
-                self.all_nodes.insert(file_node.global_id, file_node);
-                Task::done(FileTreeMessage::ChangeSelectedNode(file_node_id))
+                let md_path = file_node
+                    .try_get_path()
+                    .ok()
+                    .filter(|_| file_node.is_md_file() && !file_node.is_temp_file())
+                    .map(Path::to_path_buf);
+                self.insert_nodes([(file_node.global_id, file_node)]);
+                match md_path {
+                    Some(md_path) => Task::batch([
+                        Task::done(FileTreeMessage::ChangeSelectedNode(file_node_id)),
+                        Task::done(FileTreeMessage::UpdateNoteIndex(vec![md_path])),
+                    ]),
+                    None => Task::done(FileTreeMessage::ChangeSelectedNode(file_node_id)),
+                }
             }
             FileTreeMessage::ChangeHoveredNode(id) => {
                 self.hovered_file_node_id = Some(id);
                 Task::none()
             }
             FileTreeMessage::ChangeSelectedNode(key) => {
+                // 附件不在编辑器中打开，不影响当前文档
+                if self.all_nodes.get(&key).is_some_and(FileNode::is_asset_file) {
+                    return Task::done(FileTreeMessage::OpenAsset(key));
+                }
                 if let Some(selected_file_node) = self.all_nodes.get_mut(&key) {
                     if selected_file_node.is_directory() {
                         selected_file_node.reverse_expanded_if_directory();
-                        return Task::none();
+                        self.load_dir_if_needed(key, setting)
                     } else {
                         self._selected_node_id_cache = Some(key);
-                        return Task::done(FileTreeMessage::AskIsLoadPermitted);
+                        Task::done(FileTreeMessage::AskIsLoadPermitted)
                     }
                 } else {
                     Task::done(FileTreeMessage::HandleError(AppError::FilePanelError(
@@ -177,11 +582,35 @@ impl FileTree {
             FileTreeMessage::LoadPermitted => {
                 if let Some(id) = self._selected_node_id_cache {
                     self.selected_node_id = Some(id);
-                    return Task::done(FileTreeMessage::LoadSelectedNodeData);
+                    let record_task = match self
+                        .all_nodes
+                        .get(&id)
+                        .filter(|node| node.is_md_file())
+                        .and_then(|node| node.try_get_path().ok())
+                    {
+                        Some(path) => Task::done(FileTreeMessage::SendRecordRecent(
+                            path.to_path_buf(),
+                            RecentKind::File,
+                        )),
+                        None => Task::none(),
+                    };
+                    return Task::batch([
+                        Task::done(FileTreeMessage::LoadSelectedNodeData),
+                        record_task,
+                    ]);
                 }
                 Task::none()
             }
+            // 用户取消切换时恢复到当前打开的节点
+            FileTreeMessage::LoadCanceled => {
+                self._selected_node_id_cache = self.selected_node_id;
+                self.pending_cursor_line = None;
+                Task::none()
+            }
             FileTreeMessage::LoadSelectedNodeData => {
+                if let Some(key) = self.selected_node_id {
+                    self.record_recent_node(key);
+                }
                 if let Some(node) = self
                     .selected_node_id
                     .and_then(|key| self.all_nodes.get(&key))
@@ -201,6 +630,7 @@ impl FileTree {
                     } else {
                         if let Ok(MdFile {
                             version,
+                            saved_version,
                             cache: Some(cache),
                             path: Some(path),
                         }) = node.try_get_md()
@@ -208,12 +638,15 @@ impl FileTree {
                             let file_data = FileData {
                                 global_id: id,
                                 version: *version,
+                                saved_version: *saved_version,
                                 content: Arc::clone(cache),
+                                path: Some(path.clone()),
                             };
                             return Task::done(FileTreeMessage::SendFileDataToEditor(file_data))
                                 .chain(Task::done(FileTreeMessage::SendImgBasePathToPreview(
                                     path.clone(),
-                                )));
+                                )))
+                                .chain(Task::done(FileTreeMessage::ApplyPendingCursorLine(id)));
                         } else {
                             if let Ok(path) = node.try_get_path() {
                                 return Task::perform(
@@ -231,16 +664,20 @@ impl FileTree {
                                     version,
                                     path: None,
                                     cache,
+                                    ..
                                 }) = node.try_get_md()
                                 {
                                     let content = match cache {
                                         Some(content) => Arc::clone(content),
                                         None => Arc::new(String::default()),
                                     };
+                                    // 临时文件只保存在缓冲区中，载入时视为已保存
                                     let new_file_data = FileData {
                                         global_id: node.global_id,
                                         version: *version,
+                                        saved_version: *version,
                                         content,
+                                        path: None,
                                     };
                                     return Task::done(FileTreeMessage::SendFileDataToEditor(
                                         new_file_data,
@@ -265,9 +702,12 @@ impl FileTree {
                     let file_data = FileData {
                         global_id: id,
                         version: 0,
+                        saved_version: 0,
                         content: Arc::clone(&content),
+                        path: md_file.path.clone(),
                     };
                     Task::done(FileTreeMessage::SendFileDataToEditor(file_data))
+                        .chain(Task::done(FileTreeMessage::ApplyPendingCursorLine(id)))
                 })
                 .unwrap_or(Task::done(FileTreeMessage::HandleError(
                     AppError::FilePanelError(
@@ -296,29 +736,28 @@ impl FileTree {
                         "[FileTree-UpdateNodeInfo]:更新节点内容失败!".to_string(),
                     ),
                 ))),
-            FileTreeMessage::UpdateNodePath(path, mut file_data) => self
-                .all_nodes
-                .get_mut(&file_data.global_id)
-                .map(|node| {
-                    node.name = operation::get_file_name(&path);
-                    if let NodeContent::Markdown(MdFile {
-                        path: ref mut file_path,
-                        ..
-                    }) = node.node_content
-                    {
-                        *file_path = Some(path.clone())
-                    }
-                    Task::done(FileTreeMessage::SaveFile(
-                        path.clone(),
-                        file_data.content.clone(),
-                    ))
-                    .chain(Task::done(FileTreeMessage::SendImgBasePathToPreview(path)))
-                })
-                .unwrap_or(Task::done(FileTreeMessage::HandleError(
-                    AppError::FilePanelError(
+            FileTreeMessage::UpdateNodePath(path, mut file_data) => {
+                let key = file_data.global_id;
+                let Some(node) = self.all_nodes.get_mut(&key) else {
+                    return Task::done(FileTreeMessage::HandleError(AppError::FilePanelError(
                         "[FileTree-UpdateNodePath]:更新节点path字段失败!".to_string(),
-                    ),
-                ))),
+                    )));
+                };
+                node.name = operation::get_file_name(&path);
+                if let NodeContent::Markdown(MdFile {
+                    path: ref mut file_path,
+                    ..
+                }) = node.node_content
+                {
+                    let old_path = file_path.replace(path.clone());
+                    self.reindex_path(key, old_path);
+                }
+                Task::done(FileTreeMessage::SaveFile(
+                    path.clone(),
+                    file_data.content.clone(),
+                ))
+                .chain(Task::done(FileTreeMessage::SendImgBasePathToPreview(path)))
+            }
             FileTreeMessage::SaveAs(file_data) => self
                 .all_nodes
                 .get(&file_data.global_id)
@@ -334,8 +773,10 @@ impl FileTree {
                                     FileTreeMessage::SaveFile(path, file_data.content)
                                 }
                             }
-                            None => FileTreeMessage::HandleError(AppError::FilePanelError(
-                                "[FileTree-SaveAs]:获取路径失败!".to_string(),
+                            None => FileTreeMessage::ReturnSaveResult(Err(
+                                AppError::FilePanelError(
+                                    "[FileTree-SaveAs]:获取路径失败!".to_string(),
+                                ),
                             )),
                         },
                     )
@@ -343,150 +784,2689 @@ impl FileTree {
                 .unwrap_or(Task::done(FileTreeMessage::HandleError(
                     AppError::FilePanelError("[FileTree-SaveAs]:获取节点名称失败!".to_string()),
                 ))),
-            FileTreeMessage::SaveFile(path, content) => {
-                Task::perform(operation::save_file(path, content), |result| match result {
-                    Ok(_) => {
-                        info!("[FileTree-SaveFile]:文件保存成功!");
-                        FileTreeMessage::ReturnSaveResult(Ok(()))
-                    }
-                    Err(error) => FileTreeMessage::ReturnSaveResult(Err(error)),
-                })
+            FileTreeMessage::SaveFile(path, content) => Task::future(operation::save_file(
+                path.clone(),
+                Arc::clone(&content),
+            ))
+            .then(move |result| match result {
+                Ok(_) => {
+                    info!("[FileTree-SaveFile]:文件保存成功!");
+                    Task::batch([
+                        Task::done(FileTreeMessage::ReturnSaveResult(Ok(()))),
+                        Task::done(FileTreeMessage::MarkFileSaved(
+                            path.clone(),
+                            Arc::clone(&content),
+                        )),
+                        Task::done(FileTreeMessage::NoteIndexer(
+                            NoteIndexerMessage::IndexSavedFile(path.clone(), Arc::clone(&content)),
+                        )),
+                    ])
+                }
+                Err(error) => Task::done(FileTreeMessage::ReturnSaveResult(Err(error))),
+            }),
+            // 保存期间缓存可能又被修改，只有内容相同时才视为已保存
+            FileTreeMessage::MarkFileSaved(path, content) => {
+                if let Some(md_file) = self
+                    .find_node_by_path(&path)
+                    .and_then(|key| self.all_nodes.get_mut(&key))
+                    .and_then(|node| node.try_get_md_mut().ok())
+                    && md_file.cache.as_ref() == Some(&content)
+                {
+                    md_file.saved_version = md_file.version;
+                }
+                Task::none()
             }
             FileTreeMessage::HandleError(error) => {
                 info!("{}", error.to_string());
                 Task::none()
             }
-            FileTreeMessage::CopyImgFileData(id) => {
-                if let Some(md_node) = self
-                    .selected_node_id
-                    .and_then(|selected_id| self.all_nodes.get(&selected_id))
-                {
-                    if md_node.is_temp_file() {
-                        // 新文件没有路径必须保存一次确定路径才能插入图片
-                        // 弹窗询问用户如何操作
-                        // TODO
-                    } else {
-                        if let Some((md_path, img_path)) =
-                            md_node.try_get_path().ok().and_then(|md_path| {
-                                self.all_nodes
-                                    .get(&id)
-                                    .and_then(|img_node| img_node.try_get_path().ok())
-                                    .map(|img_path| (md_path, img_path))
-                            })
-                        {
-                            return Task::perform(
-                                operation::copy_img_to_folder(
-                                    md_path.to_owned(),
-                                    img_path.to_owned(),
-                                ),
-                                |result| match result {
-                                    Ok(code) => FileTreeMessage::SendImgCodeToEditor(code),
+            FileTreeMessage::SaveUnsavedDocs(docs) => Task::perform(
+                operation::save_unsaved_docs(docs),
+                |(saved_files, result)| FileTreeMessage::ApplySavedDocs(saved_files, result),
+            ),
+            // 保存后同步节点路径和缓存，临时文件因此转为普通文件
+            FileTreeMessage::ApplySavedDocs(saved_files, result) => {
+                let mut tasks = vec![];
+                for (id, path, content) in saved_files {
+                    tasks.push(Task::done(FileTreeMessage::NoteIndexer(
+                        NoteIndexerMessage::IndexSavedFile(path.clone(), Arc::clone(&content)),
+                    )));
+                    if let Some(node) = self.all_nodes.get_mut(&id) {
+                        node.name = operation::get_file_name(&path);
+                        if let Ok(md_file) = node.try_get_md_mut() {
+                            let old_path = md_file.path.replace(path);
+                            md_file.cache = Some(content);
+                            md_file.saved_version = md_file.version;
+                            self.reindex_path(id, old_path);
+                        }
+                    }
+                }
+                tasks.push(Task::done(FileTreeMessage::ReturnSaveAllResult(result)));
+                Task::batch(tasks)
+            }
+            // 按下和松开在同一节点上视为点击，移动超过阈值后松开在其他节点上视为拖动
+            FileTreeMessage::PressNode(key) => {
+                self.pressed_node = Some((key, None));
+                self.dragging_node_id = None;
+                Task::none()
+            }
+            FileTreeMessage::ReleaseNode(key) => {
+                let pressed_key = self.pressed_node.take().map(|(pressed_key, _)| pressed_key);
+                match (self.dragging_node_id.take(), pressed_key) {
+                    (Some(source_key), _) if source_key != key => {
+                        Task::done(FileTreeMessage::MoveNode(source_key, key))
+                    }
+                    (_, Some(pressed_key)) if pressed_key == key => {
+                        self.focused_node_id = Some(key);
+                        Task::done(FileTreeMessage::ChangeSelectedNode(key))
+                    }
+                    _ => Task::none(),
+                }
+            }
+            FileTreeMessage::DragCursorMoved(position) => {
+                let Some((key, origin)) = self.pressed_node.as_mut() else {
+                    return Task::none();
+                };
+                match origin {
+                    None => *origin = Some(position),
+                    Some(origin) if origin.distance(position) >= FILE_TREE_DRAG_THRESHOLD => {
+                        self.dragging_node_id = Some(*key);
+                    }
+                    _ => {}
+                }
+                Task::none()
+            }
+            // 节点上松开时已经由ReleaseNode处理，这里只负责在文件树外松开时取消拖动
+            FileTreeMessage::DragReleased => {
+                self.pressed_node = None;
+                self.dragging_node_id = None;
+                Task::none()
+            }
+            // 先列出可用的模板，在模板选择窗口中填写标题并选择模板后再新建
+            // 模板文件夹设置为空时只使用全局模板
+            FileTreeMessage::CreateNewFileInDir(key) => {
+                let workspace_template_dir = match self.all_nodes.get(&key) {
+                    Some(FileNode {
+                        node_content: NodeContent::DirectoryMd(dir),
+                        ..
+                    }) => self
+                        .find_root_of_path(&dir.path)
+                        .filter(|_| !setting.template_folder.is_empty())
+                        .map(|(_, root_path)| root_path.join(&setting.template_folder)),
+                    _ if Some(key) == self.temp_workplace_root_key => None,
+                    _ => {
+                        return Task::done(FileTreeMessage::HandleError(
+                            AppError::FilePanelError(
+                                "[FileTree-CreateNewFileInDir]:该节点不是工作区文件夹!".to_string(),
+                            ),
+                        ));
+                    }
+                };
+                let global_template_dir =
+                    setting::config_dir().map(|dir| dir.join(GLOBAL_TEMPLATE_FOLDER));
+                let template_dirs = workspace_template_dir
+                    .map(|dir| (dir, false))
+                    .into_iter()
+                    .chain(global_template_dir.map(|dir| (dir, true)))
+                    .collect();
+                Task::perform(operation::list_templates(template_dirs), move |templates| {
+                    FileTreeMessage::SendOpenNewFileDialog(key, templates)
+                })
+            }
+            FileTreeMessage::CreateFileFromTemplate(request) => {
+                let key = request.dir_key;
+                let context = TemplateContext {
+                    date_time: jiff::Zoned::now().datetime(),
+                    title: request.title.trim().to_string(),
+                    author: setting.author.clone(),
+                };
+                match self.all_nodes.get(&key) {
+                    Some(FileNode {
+                        node_content: NodeContent::DirectoryMd(dir),
+                        ..
+                    }) => Task::perform(
+                        operation::create_md_file_from_template(
+                            dir.path.clone(),
+                            request.template,
+                            context,
+                        ),
+                        move |result| match result {
+                            Ok(file_node) => FileTreeMessage::TemplateFileCreated(key, file_node),
+                            Err(error) => FileTreeMessage::HandleError(error),
+                        },
+                    ),
+                    // 临时工作区中新建的是临时文件，保存时再选择路径
+                    _ if Some(key) == self.temp_workplace_root_key => {
+                        let file_name =
+                            format!("{}.md", operation::file_stem_from_title(&context.title));
+                        Task::perform(
+                            operation::render_new_file(request.template, context),
+                            move |result| match result {
+                                Ok(content) => FileTreeMessage::InsertToFileTree(FileNode::new(
+                                    file_name.clone(),
+                                    NodeContent::Markdown(MdFile {
+                                        path: None,
+                                        version: 0,
+                                        saved_version: 0,
+                                        cache: Some(Arc::new(content)),
+                                    }),
+                                )),
+                                Err(error) => FileTreeMessage::HandleError(error),
+                            },
+                        )
+                    }
+                    _ => Task::done(FileTreeMessage::HandleError(AppError::FilePanelError(
+                        "[FileTree-CreateFileFromTemplate]:该节点不是工作区文件夹!".to_string(),
+                    ))),
+                }
+            }
+            FileTreeMessage::TemplateFileCreated(dir_key, file_node) => {
+                info!("[FileTree-TemplateFileCreated]:新建文件 {} 成功!", file_node.name);
+                let file_key = file_node.global_id;
+                Task::done(FileTreeMessage::InsertChildNodes(
+                    dir_key,
+                    file_key,
+                    HashMap::from([(file_key, file_node)]),
+                    false,
+                ))
+                .chain(Task::done(FileTreeMessage::ChangeSelectedNode(file_key)))
+            }
+            FileTreeMessage::CreateNewFolderInDir(key) => match self.all_nodes.get(&key) {
+                Some(FileNode {
+                    node_content: NodeContent::DirectoryMd(dir),
+                    ..
+                }) => Task::perform(operation::create_folder(dir.path.clone()), move |result| {
+                    match result {
+                        Ok(file_node) => FileTreeMessage::InsertChildNodes(
+                            key,
+                            file_node.global_id,
+                            HashMap::from([(file_node.global_id, file_node)]),
+                            true,
+                        ),
+                        Err(error) => FileTreeMessage::HandleError(error),
+                    }
+                }),
+                _ => Task::done(FileTreeMessage::HandleError(AppError::FilePanelError(
+                    "[FileTree-CreateNewFolderInDir]:该节点不是工作区文件夹!".to_string(),
+                ))),
+            },
+            FileTreeMessage::InsertChildNodes(parent_key, child_key, nodes, is_rename_needed) => {
+                self.insert_nodes(nodes);
+                if let Some(parent_node) = self.all_nodes.get_mut(&parent_key) {
+                    parent_node.set_expanded_if_directory(true);
+                    if let Ok(children) = parent_node.try_get_children_mut() {
+                        children.push(child_key);
+                    }
+                }
+                self.focused_node_id = Some(child_key);
+                self.sort_children(parent_key);
+                self.sort_subtree(child_key);
+                let load_task = Task::batch([
+                    self.load_dir_if_needed(parent_key, setting),
+                    Task::done(FileTreeMessage::UpdateNoteIndex(
+                        self.node_path(child_key).into_iter().collect(),
+                    )),
+                ]);
+                if is_rename_needed {
+                    return load_task.chain(Task::done(FileTreeMessage::StartRenameNode(child_key)));
+                }
+                load_task
+            }
+            FileTreeMessage::StartRenameNode(key) => {
+                if self.is_root_node(key) {
+                    return Task::done(FileTreeMessage::HandleError(AppError::FilePanelError(
+                        "[FileTree-StartRenameNode]:根节点无法重命名!".to_string(),
+                    )));
+                }
+                match self.all_nodes.get(&key) {
+                    Some(node) => {
+                        self.renaming = Some((key, node.name.clone()));
+                        Task::batch([
+                            iced::widget::operation::focus(operation::RENAME_INPUT_ID),
+                            iced::widget::operation::select_all(operation::RENAME_INPUT_ID),
+                        ])
+                    }
+                    None => Task::none(),
+                }
+            }
+            FileTreeMessage::RenameInputChanged(value) => {
+                if let Some((_, ref mut name)) = self.renaming {
+                    *name = value;
+                }
+                Task::none()
+            }
+            FileTreeMessage::CancelRenameNode => {
+                self.renaming = None;
+                Task::none()
+            }
+            FileTreeMessage::ConfirmRenameNode => {
+                let Some((key, mut new_name)) = self.renaming.take() else {
+                    return Task::none();
+                };
+                let Some(node) = self.all_nodes.get_mut(&key) else {
+                    return Task::none();
+                };
+                if node.name == new_name {
+                    return Task::none();
+                }
+                if let Err(error) = operation::check_file_name(&new_name) {
+                    return Task::done(FileTreeMessage::HandleError(error));
+                }
+                // md文件省略扩展名时自动补全，避免重命名后从文件树中消失
+                if node.is_md_file() && Path::new(&new_name).extension().is_none() {
+                    new_name.push_str(".md");
+                }
+                if node.is_temp_file() {
+                    node.name = new_name;
+                    return Task::none();
+                }
+                match node.try_get_path() {
+                    Ok(path) => {
+                        let old_path = path.to_path_buf();
+                        Task::future(operation::rename_path(old_path.clone(), new_name)).then(
+                            move |result| match result {
+                                Ok(new_path) => Task::batch([
+                                    Task::done(FileTreeMessage::PlanLinkRewrites(
+                                        old_path.clone(),
+                                        new_path.clone(),
+                                    )),
+                                    Task::done(FileTreeMessage::ApplyRenamedPath(key, new_path)),
+                                ]),
+                                Err(error) => Task::done(FileTreeMessage::HandleError(error)),
+                            },
+                        )
+                    }
+                    Err(error) => Task::done(FileTreeMessage::HandleError(error)),
+                }
+            }
+            FileTreeMessage::ApplyRenamedPath(key, new_path) => {
+                let Some(old_path) = self
+                    .all_nodes
+                    .get(&key)
+                    .and_then(|node| node.try_get_path().ok())
+                    .map(Path::to_path_buf)
+                else {
+                    return Task::none();
+                };
+                if let Some(node) = self.all_nodes.get_mut(&key) {
+                    node.name = operation::get_file_name(&new_path);
+                }
+                self.update_subtree_paths(key, &old_path, &new_path);
+                if let Some(parent_key) = self.find_parent_key(key) {
+                    self.sort_children(parent_key);
+                }
+                info!("[FileTree-ApplyRenamedPath]:重命名成功!");
+                Task::batch([
+                    self.sync_selected_path_with_preview(key),
+                    Task::done(FileTreeMessage::UpdateNoteIndex(vec![old_path, new_path])),
+                ])
+            }
+            FileTreeMessage::DeleteNode(key) => {
+                if self.is_root_node(key) {
+                    return Task::done(FileTreeMessage::HandleError(AppError::FilePanelError(
+                        "[FileTree-DeleteNode]:根节点无法删除!".to_string(),
+                    )));
+                }
+                match self.all_nodes.get(&key) {
+                    Some(node) if node.is_temp_file() => {
+                        Task::done(FileTreeMessage::RemoveNode(key))
+                    }
+                    Some(node) if node.is_md_file() || node.is_md_directory() || node.is_asset_file() => {
+                        match node.try_get_path() {
+                            Ok(path) => Task::perform(
+                                operation::move_to_trash(path.to_path_buf()),
+                                move |result| match result {
+                                    Ok(_) => FileTreeMessage::RemoveNode(key),
                                     Err(error) => FileTreeMessage::HandleError(error),
                                 },
-                            );
+                            ),
+                            Err(error) => Task::done(FileTreeMessage::HandleError(error)),
                         }
                     }
+                    _ => Task::done(FileTreeMessage::HandleError(AppError::FilePanelError(
+                        "[FileTree-DeleteNode]:该节点不支持删除!".to_string(),
+                    ))),
                 }
-                Task::done(FileTreeMessage::HandleError(AppError::FilePanelError(
-                    "[FileTree-CopyImgFileData]:图片数据复制失败!".to_string(),
-                )))
             }
-
-            _ => Task::none(),
-        }
-    }
-
-    // 递归渲染文件树
-    pub fn view(&self) -> Element<'_, FileTreeMessage> {
-        let hidden_scroller = scrollable::Scrollbar::new().scroller_width(0).width(0);
-        let mut workplace_view = Column::new().spacing(SPACING_SMALLER);
-        if let Some(key) = self.temp_workplace_root_key {
-            workplace_view = workplace_view.push(operation::view_node(
-                self.hovered_file_node_id.clone(),
-                self.selected_node_id,
-                &self.all_nodes,
-                key,
-                0,
-            ));
-        }
-        if let Some(key) = self.temp_img_library_root_key {
-            workplace_view = workplace_view.push(operation::view_node(
-                self.hovered_file_node_id.clone(),
-                self.selected_node_id,
-                &self.all_nodes,
-                key,
-                0,
-            ));
-        }
-        if let Some(key) = self.workplace_root_key {
-            workplace_view = workplace_view.push(operation::view_node(
-                self.hovered_file_node_id.clone(),
-                self.selected_node_id,
-                &self.all_nodes,
-                key,
-                0,
-            ));
-        }
-        scrollable(workplace_view)
-            .direction(scrollable::Direction::Vertical(hidden_scroller))
-            .into()
-    }
-
-    pub fn insert_node_to_temp_workplace(&mut self, ids: Vec<u32>) {
-        match self.temp_workplace_root_key {
-            Some(key) => {
-                if let Some(children) = self
-                    .all_nodes
-                    .get_mut(&key)
-                    .and_then(|root_node| root_node.try_get_children_mut().ok())
+            FileTreeMessage::RemoveNode(key) => {
+                let removed_path = self.node_path(key);
+                let removed_keys = self.remove_subtree(key);
+                info!("[FileTree-RemoveNode]:删除成功!");
+                // 正在编辑的文件被删除时通知编辑器卸载
+                let unload_task = match self.selected_node_id {
+                    Some(id) if removed_keys.contains(&id) => {
+                        self.selected_node_id = None;
+                        self._selected_node_id_cache = None;
+                        Task::done(FileTreeMessage::UnloadFileFromEditor(id))
+                    }
+                    _ => Task::none(),
+                };
+                Task::batch([
+                    unload_task,
+                    Task::done(FileTreeMessage::UpdateNoteIndex(
+                        removed_path.into_iter().collect(),
+                    )),
+                ])
+            }
+            FileTreeMessage::DuplicateNode(key) => match self.all_nodes.get(&key) {
+                Some(node) if node.is_temp_file() => {
+                    let cache = node.try_get_md().ok().and_then(|md_file| md_file.cache.clone());
+                    let name = format!(
+                        "{} 副本.md",
+                        node.name.strip_suffix(".md").unwrap_or(&node.name)
+                    );
+                    let new_file = FileNode::new(
+                        name,
+                        NodeContent::Markdown(MdFile {
+                            path: None,
+                            version: 0,
+                            saved_version: 0,
+                            cache,
+                        }),
+                    );
+                    let new_file_id = new_file.global_id;
+                    self.insert_nodes([(new_file_id, new_file)]);
+                    self.insert_node_to_temp_workplace(vec![new_file_id]);
+                    self.focused_node_id = Some(new_file_id);
+                    Task::none()
+                }
+                Some(node)
+                    if (node.is_md_file() || node.is_md_directory() || node.is_asset_file())
+                        && !self.is_root_node(key) =>
                 {
-                    for id in ids {
-                        children.push(id)
+                    match (node.try_get_path(), self.find_parent_key(key)) {
+                        (Ok(path), Some(parent_key)) => Task::perform(
+                            operation::duplicate_path(
+                                path.to_path_buf(),
+                                self.scan_options(setting, path),
+                            ),
+                            move |result| match result {
+                                Ok((root_key, nodes)) => FileTreeMessage::InsertChildNodes(
+                                    parent_key, root_key, nodes, false,
+                                ),
+                                Err(error) => FileTreeMessage::HandleError(error),
+                            },
+                        ),
+                        _ => Task::done(FileTreeMessage::HandleError(AppError::FilePanelError(
+                            "[FileTree-DuplicateNode]:获取节点路径失败!".to_string(),
+                        ))),
                     }
                 }
+                _ => Task::done(FileTreeMessage::HandleError(AppError::FilePanelError(
+                    "[FileTree-DuplicateNode]:该节点不支持复制!".to_string(),
+                ))),
+            },
+            FileTreeMessage::MoveNode(source_key, target_key) => {
+                // 拖到文件上时移动到该文件所在的文件夹
+                let target_dir_key = match self.all_nodes.get(&target_key) {
+                    Some(node) if node.is_md_directory() => Some(target_key),
+                    Some(node) if (node.is_md_file() && !node.is_temp_file()) || node.is_asset_file() => {
+                        self.find_parent_key(target_key)
+                    }
+                    _ => None,
+                };
+                let source_path = self
+                    .all_nodes
+                    .get(&source_key)
+                    .filter(|node| node.is_md_file() || node.is_md_directory() || node.is_asset_file())
+                    .and_then(|node| node.try_get_path().ok());
+                let target_dir_path = target_dir_key
+                    .and_then(|key| self.all_nodes.get(&key))
+                    .and_then(|node| node.try_get_path().ok());
+                match (source_path, target_dir_key, target_dir_path) {
+                    (Some(source_path), Some(target_dir_key), Some(target_dir_path))
+                        if !self.is_root_node(source_key)
+                            && self.find_parent_key(source_key) != Some(target_dir_key) =>
+                    {
+                        let old_path = source_path.to_path_buf();
+                        Task::future(operation::move_path(
+                            old_path.clone(),
+                            target_dir_path.to_path_buf(),
+                        ))
+                        .then(move |result| match result {
+                            Ok(new_path) => Task::batch([
+                                Task::done(FileTreeMessage::PlanLinkRewrites(
+                                    old_path.clone(),
+                                    new_path.clone(),
+                                )),
+                                Task::done(FileTreeMessage::ApplyMovedPath(
+                                    source_key,
+                                    target_dir_key,
+                                    new_path,
+                                )),
+                            ]),
+                            Err(error) => Task::done(FileTreeMessage::HandleError(error)),
+                        })
+                    }
+                    _ => Task::none(),
+                }
             }
-            None => {
-                let temp_workplace_root_node = FileNode::new(
-                    "临时工作区".to_string(),
-                    NodeContent::DirectoryTemp(TempDir {
-                        children: ids,
-                        expanded: true,
-                    }),
-                );
-                let temp_workplace_id = temp_workplace_root_node.global_id;
-                self.all_nodes
-                    .insert(temp_workplace_id, temp_workplace_root_node);
-                self.temp_workplace_root_key = Some(temp_workplace_id);
-            }
-        }
-    }
-
-    pub fn insert_node_to_temp_img_library(&mut self, ids: Vec<u32>) {
-        match self.temp_img_library_root_key {
-            Some(key) => {
-                if let Some(children) = self
+            FileTreeMessage::ApplyMovedPath(key, target_dir_key, new_path) => {
+                let Some(old_path) = self
                     .all_nodes
-                    .get_mut(&key)
-                    .and_then(|root_node| root_node.try_get_children_mut().ok())
+                    .get(&key)
+                    .and_then(|node| node.try_get_path().ok())
+                    .map(Path::to_path_buf)
+                else {
+                    return Task::none();
+                };
+                if let Some(parent_key) = self.find_parent_key(key)
+                    && let Some(children) = self
+                        .all_nodes
+                        .get_mut(&parent_key)
+                        .and_then(|parent_node| parent_node.try_get_children_mut().ok())
                 {
-                    for id in ids {
-                        children.push(id)
+                    children.retain(|child_key| *child_key != key);
+                }
+                if let Some(target_dir_node) = self.all_nodes.get_mut(&target_dir_key) {
+                    target_dir_node.set_expanded_if_directory(true);
+                    if let Ok(children) = target_dir_node.try_get_children_mut() {
+                        children.push(key);
                     }
                 }
+                self.update_subtree_paths(key, &old_path, &new_path);
+                self.sort_children(target_dir_key);
+                info!("[FileTree-ApplyMovedPath]:移动成功!");
+                Task::batch([
+                    self.load_dir_if_needed(target_dir_key, setting),
+                    self.sync_selected_path_with_preview(key),
+                    Task::done(FileTreeMessage::UpdateNoteIndex(vec![old_path, new_path])),
+                ])
             }
-            None => {
-                let temp_img_library_root_node = FileNode::new(
-                    "临时图片库".to_string(),
-                    NodeContent::DirectoryTemp(TempDir {
-                        children: ids,
-                        expanded: true,
-                    }),
-                );
-                let temp_img_library_id = temp_img_library_root_node.global_id;
-                self.all_nodes
-                    .insert(temp_img_library_id, temp_img_library_root_node);
-                self.temp_img_library_root_key = Some(temp_img_library_id);
+            // 使用重命名之前的索引快照查找指向旧路径的链接
+            FileTreeMessage::PlanLinkRewrites(old_path, new_path) => {
+                Task::done(FileTreeMessage::PathHistory(PathHistoryMessage::Plan(
+                    Arc::clone(self.note_indexer.link_index()),
+                    Arc::clone(self.note_indexer.note_index()),
+                    old_path,
+                    new_path,
+                )))
             }
-        }
-    }
+            FileTreeMessage::PathHistory(PathHistoryMessage::SendConfirmLinkRewrites(paths)) => {
+                let root_paths = self
+                    .root_paths()
+                    .into_iter()
+                    .map(|(_, root_path)| root_path)
+                    .collect::<Vec<_>>();
+                let files = paths
+                    .iter()
+                    .map(|path| link_check::relative_display_path(&root_paths, path))
+                    .collect();
+                Task::done(FileTreeMessage::SendConfirmLinkRewrites(files))
+            }
+            FileTreeMessage::PathHistory(PathHistoryMessage::SendReplacements(replacements)) => {
+                Task::done(FileTreeMessage::ApplyReplacements(replacements))
+            }
+            FileTreeMessage::PathHistory(PathHistoryMessage::SendError(error)) => {
+                Task::done(FileTreeMessage::HandleError(error))
+            }
+            // 先在文件树中还原节点，再把更新过链接的文件换回原来的内容
+            FileTreeMessage::PathHistory(PathHistoryMessage::SendPathRestored(path_change)) => {
+                let old_path = path_change.old_path.clone();
+                let restore_task = match self.find_node_by_path(&path_change.new_path) {
+                    Some(key) if path_change.new_path.parent() == old_path.parent() => {
+                        Task::done(FileTreeMessage::ApplyRenamedPath(key, old_path))
+                    }
+                    Some(key) => match old_path
+                        .parent()
+                        .and_then(|parent_path| self.find_node_by_path(parent_path))
+                    {
+                        Some(parent_key) => {
+                            Task::done(FileTreeMessage::ApplyMovedPath(key, parent_key, old_path))
+                        }
+                        None => Task::done(FileTreeMessage::RemoveNode(key)),
+                    },
+                    None => Task::done(FileTreeMessage::UpdateNoteIndex(vec![
+                        path_change.new_path.clone(),
+                        old_path,
+                    ])),
+                };
+                let replacements = path_change.reversed_replacements();
+                match replacements.is_empty() {
+                    true => restore_task,
+                    false => restore_task
+                        .chain(Task::done(FileTreeMessage::ApplyReplacements(replacements))),
+                }
+            }
+            FileTreeMessage::PathHistory(path_history_message) => self
+                .path_history
+                .update(path_history_message)
+                .map(FileTreeMessage::PathHistory),
+            FileTreeMessage::RequestContextMenu(key) => {
+                self.focused_node_id = Some(key);
+                Task::done(FileTreeMessage::SendContextMenuItems(
+                    self.generate_context_menu_items(key),
+                ))
+            }
+            FileTreeMessage::HandleContextAction(action) => match action {
+                ContextAction::OpenNode(key) => Task::done(FileTreeMessage::ChangeSelectedNode(key)),
+                ContextAction::CreateNewFile(key) => {
+                    Task::done(FileTreeMessage::CreateNewFileInDir(key))
+                }
+                ContextAction::CreateNewFolder(key) => {
+                    Task::done(FileTreeMessage::CreateNewFolderInDir(key))
+                }
+                ContextAction::RenameNode(key) => Task::done(FileTreeMessage::StartRenameNode(key)),
+                ContextAction::DuplicateNode(key) => Task::done(FileTreeMessage::DuplicateNode(key)),
+                ContextAction::DeleteNode(key) => Task::done(FileTreeMessage::DeleteNode(key)),
+                ContextAction::RevealNode(key) => Task::done(FileTreeMessage::RevealNode(key)),
+                ContextAction::InsertImage(key) => Task::done(FileTreeMessage::CopyImgFileData(key)),
+                ContextAction::CloseWorkspaceRoot(key) => {
+                    Task::done(FileTreeMessage::CloseWorkspaceRoot(key))
+                }
+                _ => Task::none(),
+            },
+            FileTreeMessage::OpenAsset(key) => {
+                self.record_recent_node(key);
+                let Some(AssetFile { path, kind, .. }) = self
+                    .all_nodes
+                    .get(&key)
+                    .and_then(|node| match &node.node_content {
+                        NodeContent::Asset(asset_file) => Some(asset_file),
+                        _ => None,
+                    })
+                else {
+                    return Task::none();
+                };
+                match kind {
+                    AssetKind::Image => Task::perform(
+                        operation::read_img_file(path.clone()),
+                        move |result| match result {
+                            Ok((_, handle)) => FileTreeMessage::LoadAssetImage(key, handle),
+                            Err(error) => FileTreeMessage::HandleError(error),
+                        },
+                    ),
+                    _ => Task::future(operation::open_with_default_app(
+                        path.clone(),
+                        setting.link_opener.clone(),
+                    ))
+                    .then(|result| match result {
+                        Ok(_) => {
+                            info!("[FileTree-OpenAsset]:已使用默认程序打开!");
+                            Task::none()
+                        }
+                        Err(error) => Task::done(FileTreeMessage::HandleError(error)),
+                    }),
+                }
+            }
+            // 同一张图片重复打开时沿用编号，图片库不会重复添加
+            FileTreeMessage::LoadAssetImage(key, handle) => {
+                match self
+                    .all_nodes
+                    .get_mut(&key)
+                    .and_then(|node| node.try_get_asset_mut().ok())
+                {
+                    Some(asset_file) => {
+                        let indep_id = *asset_file
+                            .indep_id
+                            .get_or_insert_with(operation::get_next_img_id);
+                        Task::done(FileTreeMessage::SendImgDataToPreview(vec![ImgData {
+                            global_id: key,
+                            indep_id,
+                            handle,
+                        }]))
+                    }
+                    None => Task::none(),
+                }
+            }
+            FileTreeMessage::OpenQuickOpen => Task::done(FileTreeMessage::QuickOpen(
+                QuickOpenMessage::Open(self.collect_quick_open_candidates()),
+            )),
+            // 先在文件树中展开并滚动到该文件，再走正常的打开流程
+            FileTreeMessage::QuickOpen(QuickOpenMessage::SendOpenTarget(target)) => match target {
+                QuickOpenTarget::Node(key) => {
+                    let scroll_task = self.reveal_node_in_tree(key);
+                    Task::batch([
+                        scroll_task,
+                        Task::done(FileTreeMessage::ChangeSelectedNode(key)),
+                    ])
+                }
+                // 未读取的文件按路径打开，会先读取沿途的文件夹
+                QuickOpenTarget::Path(path) => {
+                    Task::done(FileTreeMessage::OpenPathAtLine(path, 0))
+                }
+            },
+            FileTreeMessage::QuickOpen(quick_open_message) => self
+                .quick_open
+                .update(quick_open_message)
+                .map(FileTreeMessage::QuickOpen),
+            FileTreeMessage::OpenPathAtLine(path, line) => {
+                if let Some(key) = self.find_node_by_path(&path) {
+                    return self.open_node_at_line(key, line);
+                }
+                let Some((_, root_path)) = self.find_root_of_path(&path) else {
+                    return Task::done(FileTreeMessage::HandleError(AppError::FilePanelError(
+                        format!("[FileTree-OpenPathAtLine]:{} 不在当前工作区中!", path.display()),
+                    )));
+                };
+                // 从上到下找出还没有读取子节点的上级文件夹
+                let mut dir_paths = path
+                    .ancestors()
+                    .skip(1)
+                    .take_while(|ancestor| ancestor.starts_with(&root_path))
+                    .filter(|ancestor| {
+                        self.find_node_by_path(ancestor)
+                            .and_then(|key| self.all_nodes.get(&key))
+                            .is_none_or(|node| node.load_state() != LoadState::Loaded)
+                    })
+                    .map(Path::to_path_buf)
+                    .collect::<Vec<_>>();
+                dir_paths.reverse();
+                let options = self.scan_options(setting, &root_path);
+                Task::perform(
+                    operation::read_dirs_children(
+                        root_path.clone(),
+                        dir_paths,
+                        options,
+                        self.scan_progress_of(&root_path),
+                    ),
+                    move |result| FileTreeMessage::PathDirsLoaded(path.clone(), line, result),
+                )
+            }
+            FileTreeMessage::PathDirsLoaded(path, line, result) => {
+                match result {
+                    Ok(dirs) => self.apply_loaded_dirs(dirs),
+                    Err(error) => return Task::done(FileTreeMessage::HandleError(error)),
+                }
+                match self.find_node_by_path(&path) {
+                    Some(key) => self.open_node_at_line(key, line),
+                    None => Task::done(FileTreeMessage::HandleError(AppError::FilePanelError(
+                        format!("[FileTree-PathDirsLoaded]:文件树中找不到 {}!", path.display()),
+                    ))),
+                }
+            }
+            // 跳转到指定行优先，恢复会话时该行就是上次的光标位置，同时恢复滚动位置
+            FileTreeMessage::ApplyPendingCursorLine(key) => {
+                let view = self
+                    .all_nodes
+                    .get(&key)
+                    .and_then(|node| node.try_get_path().ok())
+                    .and_then(|path| self.pending_doc_views.remove(path));
+                match (self.pending_cursor_line, view) {
+                    (Some((pending_key, line)), view) if pending_key == key => {
+                        self.pending_cursor_line = None;
+                        match view.filter(|view| view.cursor_line == line) {
+                            Some(view) => Task::done(FileTreeMessage::SendDocViewToEditor(view)),
+                            None => Task::done(FileTreeMessage::SendCursorLineToEditor(line)),
+                        }
+                    }
+                    (_, Some(view)) => Task::done(FileTreeMessage::SendDocViewToEditor(view)),
+                    _ => Task::none(),
+                }
+            }
+            FileTreeMessage::CollectSearchFiles => {
+                let roots = self.workspace_scan_roots(setting);
+                if roots.is_empty() {
+                    return Task::done(FileTreeMessage::SendSearchFiles(Err(
+                        AppError::FilePanelError("请先打开工作区!".to_string()),
+                    )));
+                }
+                let root_paths = roots.iter().map(|(root_path, _)| root_path.clone()).collect();
+                Task::perform(
+                    async move {
+                        let mut files = vec![];
+                        for (root_path, options) in roots {
+                            files.extend(operation::collect_workspace_files(root_path, options).await?);
+                        }
+                        Ok(files)
+                    },
+                    move |result| {
+                        FileTreeMessage::SendSearchFiles(result.map(|files| SearchTargets {
+                            root_paths: Vec::clone(&root_paths),
+                            files,
+                            buffers: HashMap::new(),
+                        }))
+                    },
+                )
+            }
+            FileTreeMessage::CheckLinks => {
+                let roots = self.workspace_scan_roots(setting);
+                if roots.is_empty() {
+                    return Task::done(FileTreeMessage::SendLinkReport(Err(
+                        AppError::FilePanelError("请先打开工作区!".to_string()),
+                    )));
+                }
+                Task::perform(
+                    link_check::check_workspace_links(roots),
+                    FileTreeMessage::SendLinkReport,
+                )
+            }
+            // 编辑器中打开的文件交给编辑器替换，缓存中有未保存改动的文件替换缓存
+            // 其余文件直接写入磁盘
+            FileTreeMessage::ApplyReplacements(replacements) => {
+                let editor_path = self
+                    .selected_node_id
+                    .and_then(|key| self.all_nodes.get(&key))
+                    .and_then(|node| node.try_get_path().ok())
+                    .map(Path::to_path_buf);
+                let (editor_replacements, other_replacements): (Vec<_>, Vec<_>) = replacements
+                    .into_iter()
+                    .partition(|replacement| Some(&replacement.path) == editor_path.as_ref());
+                let editor_count = editor_replacements.len();
+                let mut buffer_results = vec![];
+                let mut file_replacements = vec![];
+                for replacement in other_replacements {
+                    match self
+                        .find_node_by_path(&replacement.path)
+                        .and_then(|key| self.all_nodes.get_mut(&key))
+                        .and_then(|node| node.try_get_md_mut().ok())
+                        .filter(|md_file| md_file.is_dirty())
+                    {
+                        Some(md_file) => {
+                            let path = replacement.path.clone();
+                            let result =
+                                operation::apply_replacement_to_cache(md_file, replacement);
+                            buffer_results.push((path, result));
+                        }
+                        None => file_replacements.push(replacement),
+                    }
+                }
+                let mut tasks = editor_replacements
+                    .into_iter()
+                    .map(|replacement| {
+                        Task::done(FileTreeMessage::SendReplacementToEditor(replacement))
+                    })
+                    .collect::<Vec<_>>();
+                tasks.push(Task::perform(
+                    async move {
+                        let mut results = buffer_results;
+                        for replacement in file_replacements {
+                            let path = replacement.path.clone();
+                            results.push((path, operation::apply_replacement(replacement).await));
+                        }
+                        results
+                    },
+                    move |results| FileTreeMessage::ReplacementsApplied(editor_count, results),
+                ));
+                Task::batch(tasks)
+            }
+            // 清空已替换文件的缓存，下次打开时重新读取，有未保存改动的缓存保留
+            FileTreeMessage::ReplacementsApplied(editor_count, results) => {
+                let mut succeeded_count = editor_count;
+                let mut failed_count = 0;
+                let mut replaced_paths = vec![];
+                for (path, result) in results {
+                    match result {
+                        Ok(_) => {
+                            succeeded_count += 1;
+                            replaced_paths.push(path.clone());
+                            if let Some(md_file) = self
+                                .find_node_by_path(&path)
+                                .and_then(|key| self.all_nodes.get_mut(&key))
+                                .and_then(|node| node.try_get_md_mut().ok())
+                                .filter(|md_file| !md_file.is_dirty())
+                            {
+                                md_file.cache = None;
+                            }
+                        }
+                        Err(error) => {
+                            failed_count += 1;
+                            error!("[FileTree-ReplacementsApplied]:{}", error);
+                        }
+                    }
+                }
+                info!(
+                    "[FileTree-ReplacementsApplied]:已替换 {} 个文件，失败 {} 个!",
+                    succeeded_count, failed_count
+                );
+                // 替换可能改动了链接，重新索引替换过的文件
+                Task::batch([
+                    Task::done(FileTreeMessage::SendReplaceResult(succeeded_count, failed_count)),
+                    Task::done(FileTreeMessage::UpdateNoteIndex(replaced_paths)),
+                ])
+            }
+            FileTreeMessage::RevealNode(key) => match self
+                .all_nodes
+                .get(&key)
+                .and_then(|node| node.try_get_path().ok())
+            {
+                Some(path) => Task::future(operation::reveal_in_file_manager(
+                    path.to_path_buf(),
+                    setting.link_opener.clone(),
+                ))
+                .then(|result| match result {
+                    Ok(_) => {
+                        info!("[FileTree-RevealNode]:已在文件管理器中打开!");
+                        Task::none()
+                    }
+                    Err(error) => Task::done(FileTreeMessage::HandleError(error)),
+                }),
+                None => Task::done(FileTreeMessage::HandleError(AppError::FilePanelError(
+                    "[FileTree-RevealNode]:该节点没有路径!".to_string(),
+                ))),
+            },
+            // 只重新读取展开过的文件夹，未读取的文件夹展开时自然是最新内容
+            FileTreeMessage::ScanWorkspace => {
+                let targets = self
+                    .root_paths()
+                    .into_iter()
+                    .map(|(root_key, root_path)| {
+                        let dir_paths = self
+                            .collect_subtree_keys(root_key)
+                            .into_iter()
+                            .filter_map(|key| self.all_nodes.get(&key))
+                            .filter(|node| {
+                                node.is_md_directory() && node.load_state() == LoadState::Loaded
+                            })
+                            .filter_map(|node| node.try_get_path().ok().map(Path::to_path_buf))
+                            .collect();
+                        PollTarget {
+                            root_key,
+                            options: self.scan_options(setting, &root_path),
+                            root_path,
+                            dir_paths,
+                        }
+                    })
+                    .collect();
+                Task::done(FileTreeMessage::WorkspaceScan(WorkspaceScanMessage::Poll(
+                    targets,
+                )))
+            }
+            FileTreeMessage::RescanWorkspace => Task::batch([
+                Task::done(FileTreeMessage::WorkspaceScan(
+                    WorkspaceScanMessage::ClearDirModified,
+                ))
+                .chain(Task::done(FileTreeMessage::ScanWorkspace)),
+                Task::done(FileTreeMessage::RefreshNoteIndex),
+            ]),
+            // 扫描期间关闭了工作区则直接结束
+            FileTreeMessage::WorkspaceScan(WorkspaceScanMessage::SendDirSnapshots(
+                root_key,
+                dir_snapshots,
+            )) => {
+                if !self.workplace_root_keys.contains(&root_key) {
+                    return Task::none();
+                }
+                // 只对比重新读取过的文件夹的直接子节点，其余节点保持不变
+                // 文件内容的修改不会改变文件夹的修改时间，排序用的时间随文件夹的下次变动更新
+                let current_paths = self
+                    .collect_workspace_paths(root_key)
+                    .into_iter()
+                    .filter(|(path, _)| {
+                        path.parent()
+                            .is_some_and(|parent| dir_snapshots.contains_key(parent))
+                    })
+                    .collect::<BTreeMap<_, _>>();
+                let snapshot = dir_snapshots
+                    .into_values()
+                    .flat_map(|dir_snapshot| dir_snapshot.entries)
+                    .collect::<BTreeMap<_, _>>();
+                self.refresh_node_metadata(root_key, &current_paths, &snapshot);
+                let is_unchanged = snapshot.len() == current_paths.len()
+                    && snapshot.iter().all(|(path, entry)| {
+                        current_paths
+                            .get(path)
+                            .is_some_and(|(_, is_dir)| *is_dir == entry.is_dir)
+                    });
+                if is_unchanged {
+                    return Task::none();
+                }
+                let changed_paths =
+                    self.apply_workspace_snapshot(root_key, current_paths, snapshot);
+                Task::done(FileTreeMessage::UpdateNoteIndex(changed_paths))
+            }
+            FileTreeMessage::WorkspaceScan(workspace_scan_message) => self
+                .workspace_scan
+                .update(workspace_scan_message)
+                .map(FileTreeMessage::WorkspaceScan),
+            FileTreeMessage::DirChildrenLoaded(key, result) => {
+                let entries = match result {
+                    Ok(entries) => entries,
+                    Err(error) => {
+                        if let Some(node) = self.all_nodes.get_mut(&key) {
+                            node.set_load_state_if_directory(LoadState::Unloaded);
+                            node.set_expanded_if_directory(false);
+                        }
+                        return Task::done(FileTreeMessage::HandleError(error));
+                    }
+                };
+                self.apply_dir_children(key, entries);
+                Task::none()
+            }
+            FileTreeMessage::TreeScrolled(viewport) => {
+                self.tree_scroll_offset = viewport.absolute_offset().y;
+                self.tree_viewport_height = viewport.bounds().height;
+                Task::none()
+            }
+            // 扫描设置改变后全部重建，进行中的任务结果作废
+            FileTreeMessage::RefreshNoteIndex => Task::done(FileTreeMessage::NoteIndexer(
+                NoteIndexerMessage::Refresh(self.index_roots(setting)),
+            )),
+            FileTreeMessage::UpdateNoteIndex(paths) => Task::done(FileTreeMessage::NoteIndexer(
+                NoteIndexerMessage::Update(self.index_roots(setting), paths),
+            )),
+            FileTreeMessage::NoteIndexer(NoteIndexerMessage::SendNoteIndexChanged(note_index)) => {
+                Task::batch([
+                    Task::done(FileTreeMessage::SendNoteIndexToPreview(note_index)),
+                    self.refresh_tag_filter(setting),
+                ])
+            }
+            FileTreeMessage::NoteIndexer(NoteIndexerMessage::SendLinkIndexChanged) => {
+                Task::batch([
+                    Task::done(FileTreeMessage::SendLinkIndexChanged),
+                    self.refresh_tag_filter(setting),
+                ])
+            }
+            FileTreeMessage::NoteIndexer(note_indexer_message) => self
+                .note_indexer
+                .update(note_indexer_message)
+                .map(FileTreeMessage::NoteIndexer),
+            FileTreeMessage::FilterByTag(None) => {
+                self.tag_filter = None;
+                Task::none()
+            }
+            FileTreeMessage::FilterByTag(Some(tag)) => {
+                info!("[FileTree-FilterByTag]:按标签 #{} 过滤文件树", tag);
+                self.apply_tag_filter(tag, setting)
+            }
+            FileTreeMessage::TagDirsLoaded(result) => {
+                match result {
+                    Ok(dirs) => self.apply_loaded_dirs(dirs),
+                    Err(error) => return Task::done(FileTreeMessage::HandleError(error)),
+                }
+                Task::none()
+            }
+            FileTreeMessage::OpenWikiLink(note, heading) => {
+                let Some(path) = self
+                    .note_indexer
+                    .note_index()
+                    .resolve(&note)
+                    .map(Path::to_path_buf)
+                else {
+                    return Task::done(FileTreeMessage::HandleError(AppError::FilePanelError(
+                        format!("[FileTree-OpenWikiLink]:找不到笔记 {}!", note),
+                    )));
+                };
+                match heading {
+                    Some(heading) => Task::perform(
+                        operation::find_heading_line(path.clone(), heading),
+                        move |line| FileTreeMessage::OpenPathAtLine(path.clone(), line),
+                    ),
+                    None => Task::done(FileTreeMessage::OpenPathAtLine(path, 0)),
+                }
+            }
+            // 预览中点击的本地链接，图片在图片页打开，其他附件交给默认程序
+            FileTreeMessage::OpenLinkedPath(path, anchor) => {
+                if let Some(kind) = AssetKind::from_path(&path) {
+                    return match (self.find_node_by_path(&path), kind) {
+                        (Some(key), _) => Task::done(FileTreeMessage::ChangeSelectedNode(key)),
+                        (None, AssetKind::Image) => {
+                            Task::done(FileTreeMessage::FetchImgFileData(path))
+                        }
+                        (None, _) => Task::future(operation::open_with_default_app(
+                            path,
+                            setting.link_opener.clone(),
+                        ))
+                        .then(|result| match result {
+                            Ok(_) => Task::none(),
+                            Err(error) => Task::done(FileTreeMessage::HandleError(error)),
+                        }),
+                    };
+                }
+                // 工作区外的文档和通过对话框打开时一样放到临时工作区
+                if self.find_node_by_path(&path).is_none() && self.find_root_of_path(&path).is_none()
+                {
+                    return Task::done(FileTreeMessage::FetchMdFileData(path));
+                }
+                match anchor {
+                    Some(anchor) => Task::perform(
+                        operation::find_heading_line(path.clone(), anchor),
+                        move |line| FileTreeMessage::OpenPathAtLine(path.clone(), line),
+                    ),
+                    None => Task::done(FileTreeMessage::OpenPathAtLine(path, 0)),
+                }
+            }
+            // 日记放在当前笔记所在的工作区，当前笔记不在工作区中时放在第一个工作区
+            FileTreeMessage::OpenDailyNote(date) => {
+                let Some(root_path) = self.current_root_path() else {
+                    return Task::done(FileTreeMessage::HandleError(AppError::FilePanelError(
+                        "[FileTree-OpenDailyNote]:请先打开工作区!".to_string(),
+                    )));
+                };
+                let path = root_path
+                    .join(&setting.journal_folder)
+                    .join(journal::file_name(date));
+                if self.find_node_by_path(&path).is_some() {
+                    return Task::done(FileTreeMessage::OpenPathAtLine(path, 0));
+                }
+                let content =
+                    journal::render_template(&setting.journal_template, date, &setting.author);
+                Task::perform(
+                    operation::create_daily_note(path.clone(), content),
+                    move |result| match result {
+                        Ok(is_created) => FileTreeMessage::DailyNoteReady(
+                            root_path.clone(),
+                            path.clone(),
+                            is_created,
+                        ),
+                        Err(error) => FileTreeMessage::HandleError(error),
+                    },
+                )
+            }
+            FileTreeMessage::OpenAdjacentDailyNote(offset) => {
+                let Some(date) = self
+                    .selected_md_path()
+                    .and_then(|path| journal::journal_date(&path))
+                else {
+                    return Task::done(FileTreeMessage::HandleError(AppError::FilePanelError(
+                        "[FileTree-OpenAdjacentDailyNote]:当前文档不是日记!".to_string(),
+                    )));
+                };
+                match date.checked_add(jiff::Span::new().days(offset)) {
+                    Ok(date) => Task::done(FileTreeMessage::OpenDailyNote(date)),
+                    Err(error) => Task::done(FileTreeMessage::HandleError(
+                        AppError::FilePanelError(format!(
+                            "[FileTree-OpenAdjacentDailyNote]:{}",
+                            error
+                        )),
+                    )),
+                }
+            }
+            // 重新读取已读取过的最深一级上级文件夹，把新建的日记和日记文件夹加入文件树
+            FileTreeMessage::DailyNoteReady(root_path, path, is_created) => {
+                if !is_created {
+                    return Task::done(FileTreeMessage::OpenPathAtLine(path, 0));
+                }
+                info!("[FileTree-DailyNoteReady]:新建日记 {} 成功!", path.display());
+                let mut dir_paths = vec![];
+                for ancestor in path.ancestors().skip(1) {
+                    if !ancestor.starts_with(&root_path) {
+                        break;
+                    }
+                    dir_paths.push(ancestor.to_path_buf());
+                    if self
+                        .find_node_by_path(ancestor)
+                        .and_then(|key| self.all_nodes.get(&key))
+                        .is_some_and(|node| node.load_state() == LoadState::Loaded)
+                    {
+                        break;
+                    }
+                }
+                dir_paths.reverse();
+                let options = self.scan_options(setting, &root_path);
+                Task::perform(
+                    operation::read_dirs_children(
+                        root_path.clone(),
+                        dir_paths,
+                        options,
+                        self.scan_progress_of(&root_path),
+                    ),
+                    move |result| FileTreeMessage::DailyNoteDirsLoaded(path.clone(), result),
+                )
+            }
+            FileTreeMessage::DailyNoteDirsLoaded(path, result) => {
+                match result {
+                    Ok(dirs) => {
+                        for (dir_path, entries) in dirs {
+                            if let Some(key) = self.find_node_by_path(&dir_path) {
+                                self.apply_dir_children(key, entries);
+                            }
+                        }
+                    }
+                    Err(error) => warn!("[FileTree-DailyNoteDirsLoaded]:{}", error),
+                }
+                Task::batch([
+                    Task::done(FileTreeMessage::UpdateNoteIndex(vec![path.clone()])),
+                    Task::done(FileTreeMessage::OpenPathAtLine(path, 0)),
+                ])
+            }
+            // 新笔记放在当前笔记所在的文件夹，当前笔记不在工作区中时放在第一个工作区
+            FileTreeMessage::CreateWikiNote(note) => {
+                if let Err(error) = operation::check_file_name(&note) {
+                    return Task::done(FileTreeMessage::HandleError(error));
+                }
+                let dir_path = self
+                    .selected_node_id
+                    .and_then(|key| self.all_nodes.get(&key))
+                    .and_then(|node| node.try_get_path().ok())
+                    .filter(|path| self.find_root_of_path(path).is_some())
+                    .and_then(Path::parent)
+                    .map(Path::to_path_buf)
+                    .or_else(|| self.root_paths().into_iter().next().map(|(_, path)| path));
+                let Some(dir_path) = dir_path else {
+                    return Task::done(FileTreeMessage::HandleError(AppError::FilePanelError(
+                        "[FileTree-CreateWikiNote]:请先打开工作区!".to_string(),
+                    )));
+                };
+                // 链接中没有写扩展名时补全为md文件
+                let file_name = match wiki_link::note_key(&note) == note.to_lowercase() {
+                    true => format!("{}.md", note),
+                    false => note,
+                };
+                Task::perform(
+                    operation::create_named_md_file(dir_path.join(file_name)),
+                    move |result| match result {
+                        Ok(file_node) => FileTreeMessage::WikiNoteCreated(dir_path.clone(), file_node),
+                        Err(error) => FileTreeMessage::HandleError(error),
+                    },
+                )
+            }
+            // 所在文件夹已经读取过时插入新节点，否则打开时会从磁盘读取
+            FileTreeMessage::WikiNoteCreated(dir_path, file_node) => {
+                info!("[FileTree-WikiNoteCreated]:新建笔记 {} 成功!", file_node.name);
+                let Ok(path) = file_node.try_get_path().map(Path::to_path_buf) else {
+                    return Task::none();
+                };
+                let insert_task = match self.find_node_by_path(&dir_path).filter(|key| {
+                    self.all_nodes
+                        .get(key)
+                        .is_some_and(|node| node.load_state() == LoadState::Loaded)
+                }) {
+                    Some(dir_key) => Task::done(FileTreeMessage::InsertChildNodes(
+                        dir_key,
+                        file_node.global_id,
+                        HashMap::from([(file_node.global_id, file_node)]),
+                        false,
+                    )),
+                    None => Task::done(FileTreeMessage::UpdateNoteIndex(vec![path.clone()])),
+                };
+                insert_task.chain(Task::done(FileTreeMessage::OpenPathAtLine(path, 0)))
+            }
+            FileTreeMessage::CopyImgFileData(id) => {
+                if let Some(md_node) = self
+                    .selected_node_id
+                    .and_then(|selected_id| self.all_nodes.get(&selected_id))
+                {
+                    if md_node.is_temp_file() {
+                        // 新文件没有路径必须保存一次确定路径才能插入图片
+                        // 弹窗询问用户如何操作
+                        // TODO
+                    } else {
+                        if let Some((md_path, img_path)) =
+                            md_node.try_get_path().ok().and_then(|md_path| {
+                                self.all_nodes
+                                    .get(&id)
+                                    .and_then(|img_node| img_node.try_get_path().ok())
+                                    .map(|img_path| (md_path, img_path))
+                            })
+                        {
+                            return Task::perform(
+                                operation::copy_img_to_folder(
+                                    md_path.to_owned(),
+                                    img_path.to_owned(),
+                                ),
+                                |result| match result {
+                                    Ok(code) => FileTreeMessage::SendImgCodeToEditor(code),
+                                    Err(error) => FileTreeMessage::HandleError(error),
+                                },
+                            );
+                        }
+                    }
+                }
+                Task::done(FileTreeMessage::HandleError(AppError::FilePanelError(
+                    "[FileTree-CopyImgFileData]:图片数据复制失败!".to_string(),
+                )))
+            }
+
+            _ => Task::none(),
+        }
+    }
+
+    pub fn is_quick_open_shown(&self) -> bool {
+        self.quick_open.is_open()
+    }
+
+    // 面板外任意位置点击都会关闭面板
+    pub fn view_quick_open(&self) -> Element<'_, FileTreeMessage> {
+        Element::from(
+            mouse_area(
+                container(self.quick_open.view())
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .align_x(Horizontal::Center)
+                    .padding(padding::top(QUICK_OPEN_OFFSET)),
+            )
+            .on_press(QuickOpenMessage::Close),
+        )
+        .map(FileTreeMessage::QuickOpen)
+    }
+
+    // title_as_label为true时，有front matter标题的文档显示标题而不是文件名
+    pub fn view(&self, title_as_label: bool) -> Element<'_, FileTreeMessage> {
+        let hidden_scroller = scrollable::Scrollbar::new().scroller_width(0).width(0);
+        let state = NodeViewState {
+            hovered_id: self.hovered_file_node_id,
+            selected_id: self.selected_node_id,
+            focused_id: self.focused_node_id,
+            dragging_id: self.dragging_node_id,
+            renaming: self
+                .renaming
+                .as_ref()
+                .map(|(key, name)| (*key, name.as_str())),
+        };
+        let rows = self.collect_visible_rows();
+        let first_row = ((self.tree_scroll_offset / FILE_TREE_ROW_HEIGHT) as usize)
+            .saturating_sub(FILE_TREE_OVERSCAN_ROWS)
+            .min(rows.len());
+        let last_row = (((self.tree_scroll_offset + self.tree_viewport_height)
+            / FILE_TREE_ROW_HEIGHT)
+            .ceil() as usize
+            + FILE_TREE_OVERSCAN_ROWS)
+            .min(rows.len());
+        let mut workplace_view = Column::new()
+            .push(space().height(first_row as f32 * FILE_TREE_ROW_HEIGHT));
+        for (key, depth) in rows[first_row..last_row].iter() {
+            if let Some(node) = self.all_nodes.get(key) {
+                let label = title_as_label
+                    .then(|| node.try_get_md().ok())
+                    .flatten()
+                    .and_then(|md_file| md_file.path.as_deref())
+                    .and_then(|path| self.note_indexer.link_index().title(path));
+                workplace_view =
+                    workplace_view.push(operation::view_row(state, node, label, *depth));
+            }
+        }
+        workplace_view = workplace_view
+            .push(space().height((rows.len() - last_row) as f32 * FILE_TREE_ROW_HEIGHT));
+        column![
+            self.generate_tool_bar(),
+            self.generate_scan_progress(),
+            scrollable(workplace_view)
+                .id(operation::FILE_TREE_SCROLLABLE_ID)
+                .direction(scrollable::Direction::Vertical(hidden_scroller))
+                .on_scroll(FileTreeMessage::TreeScrolled)
+                .height(Length::Fill)
+        ]
+        .spacing(SPACING_SMALLER)
+        .into()
+    }
+
+    // 按显示顺序展开所有可见行，折叠的文件夹不再向下遍历
+    // 按标签过滤时只显示工作区，并展开所有包含匹配笔记的文件夹
+    pub fn collect_visible_rows(&self) -> Vec<(u32, u16)> {
+        let mut rows = vec![];
+        let tag_filter = self.tag_filter.as_ref();
+        let temp_root_keys = match tag_filter {
+            Some(_) => [None, None],
+            None => [self.temp_img_library_root_key, self.temp_workplace_root_key],
+        };
+        // 栈顶先显示，所以按显示顺序的反序入栈
+        let mut key_stack = self
+            .workplace_root_keys
+            .iter()
+            .rev()
+            .copied()
+            .chain(temp_root_keys.into_iter().flatten())
+            .map(|key| (key, 0))
+            .collect::<Vec<_>>();
+        while let Some((key, depth)) = key_stack.pop() {
+            let Some(node) = self.all_nodes.get(&key) else {
+                continue;
+            };
+            let is_expanded = match tag_filter {
+                Some(tag_filter) => {
+                    let Ok(path) = node.try_get_path() else {
+                        continue;
+                    };
+                    let is_matched = match node.is_md_directory() {
+                        true => tag_filter.dirs.contains(path) || depth == 0,
+                        false => tag_filter.paths.contains(path),
+                    };
+                    if !is_matched {
+                        continue;
+                    }
+                    true
+                }
+                None => node.is_expanded(),
+            };
+            rows.push((key, depth));
+            if is_expanded
+                && let Ok(children) = node.try_get_children()
+            {
+                key_stack.extend(children.iter().rev().map(|child_key| (*child_key, depth + 1)));
+            }
+        }
+        rows
+    }
+
+    // 第一次展开文件夹时读取子节点，读取期间显示读取中标记
+    pub fn load_dir_if_needed(&mut self, key: u32, setting: &AppSetting) -> Task<FileTreeMessage> {
+        let Some(node) = self.all_nodes.get(&key) else {
+            return Task::none();
+        };
+        if !node.is_expanded() || node.load_state() != LoadState::Unloaded {
+            return Task::none();
+        }
+        let Ok(dir_path) = node.try_get_path().map(Path::to_path_buf) else {
+            return Task::none();
+        };
+        let root_path = self
+            .find_root_of_path(&dir_path)
+            .map(|(_, root_path)| root_path)
+            .unwrap_or(dir_path.clone());
+        let options = self.scan_options(setting, &root_path);
+        if let Some(node) = self.all_nodes.get_mut(&key) {
+            node.set_load_state_if_directory(LoadState::Loading);
+        }
+        let progress = self.scan_progress_of(&root_path);
+        Task::perform(
+            operation::read_dir_children(root_path, dir_path, options, progress),
+            move |result| FileTreeMessage::DirChildrenLoaded(key, result),
+        )
+    }
+
+    // 继续读取工作区时已读取的节点也计入节点上限
+    fn scan_progress_of(&self, root_path: &Path) -> Arc<ScanProgress> {
+        let loaded_count = self
+            .path_index
+            .keys()
+            .filter(|path| path.starts_with(root_path))
+            .count();
+        Arc::new(ScanProgress::starting_at(loaded_count))
+    }
+
+    // 文件操作工具栏，操作目标为最近点击的节点，没有时为第一个工作区根目录
+    pub fn generate_tool_bar(&self) -> Element<'_, FileTreeMessage> {
+        let Some(key) = self
+            .focused_node_id
+            .or(self.workplace_root_keys.first().copied())
+        else {
+            return space().into();
+        };
+        let dir_key = self.resolve_dir_key(key).unwrap_or(key);
+        let tool_bar = row![
+            generate_tool_button("新建", FileTreeMessage::CreateNewFileInDir(dir_key)),
+            generate_tool_button("新文件夹", FileTreeMessage::CreateNewFolderInDir(dir_key)),
+            generate_tool_button("重命名", FileTreeMessage::StartRenameNode(key)),
+            generate_tool_button("复制", FileTreeMessage::DuplicateNode(key)),
+            generate_tool_button("删除", FileTreeMessage::DeleteNode(key)),
+        ]
+        .spacing(SPACING)
+        .padding(Padding::from([0., PADDING_SMALLER]));
+        // 排序和过滤作用于最近点击的节点所在的工作区
+        let Some((root_key, setting)) = self
+            .all_nodes
+            .get(&key)
+            .and_then(|node| node.try_get_path().ok())
+            .and_then(|path| self.find_root_of_path(path))
+            .or_else(|| self.root_paths().into_iter().next())
+            .and_then(|(root_key, _)| {
+                self.tree_view_setting_of_root(root_key)
+                    .map(|setting| (root_key, setting))
+            })
+        else {
+            return tool_bar.into();
+        };
+        let filter_label = match setting.hide_dirs_without_markdown {
+            true => "显示空文件夹",
+            false => "隐藏空文件夹",
+        };
+        let tag_filter_bar = match self.tag_filter {
+            Some(ref tag_filter) => Element::from(
+                row![
+                    text!("标签 #{}  {} 篇", tag_filter.tag, tag_filter.paths.len())
+                        .size(FONT_SIZE_SMALLER),
+                    space::horizontal(),
+                    generate_tool_button("清除", FileTreeMessage::FilterByTag(None)),
+                ]
+                .spacing(SPACING)
+                .padding(Padding::from([0., PADDING_SMALLER])),
+            ),
+            None => space().into(),
+        };
+        column![
+            tool_bar,
+            row![
+                generate_tool_button(
+                    setting.sort_mode.label(),
+                    FileTreeMessage::ChangeSortMode(root_key, setting.sort_mode.next()),
+                ),
+                generate_tool_button(
+                    filter_label,
+                    FileTreeMessage::SetHideDirsWithoutMarkdown(
+                        root_key,
+                        !setting.hide_dirs_without_markdown
+                    ),
+                ),
+            ]
+            .spacing(SPACING)
+            .padding(Padding::from([0., PADDING_SMALLER])),
+            tag_filter_bar,
+        ]
+        .spacing(SPACING_SMALLER)
+        .into()
+    }
+
+    pub fn generate_scan_progress(&self) -> Element<'_, FileTreeMessage> {
+        self.workspace_scan
+            .view()
+            .map(FileTreeMessage::WorkspaceScan)
+    }
+
+    pub fn subscription(&self) -> Subscription<FileTreeMessage> {
+        // 输入框会捕获Esc，所以这里需要监听所有事件
+        let rename_subscription = if self.renaming.is_some() {
+            event::listen_with(|event, _status, _id| match event {
+                iced::Event::Keyboard(keyboard::Event::KeyPressed {
+                    key: keyboard::Key::Named(keyboard::key::Named::Escape),
+                    ..
+                }) => Some(FileTreeMessage::CancelRenameNode),
+                _ => None,
+            })
+        } else {
+            Subscription::none()
+        };
+        // 打开工作区后定时扫描，同步外部程序对文件的增删改名
+        let workspace_subscription = if self
+            .workplace_root_keys
+            .iter()
+            .any(|key| self.workspace_scan.is_polling(*key))
+        {
+            iced::time::every(iced::time::Duration::from_millis(WORKSPACE_POLL_INTERVAL_MS))
+                .map(|_| FileTreeMessage::ScanWorkspace)
+        } else {
+            Subscription::none()
+        };
+        let scan_progress_subscription = self
+            .workspace_scan
+            .subscription()
+            .map(FileTreeMessage::WorkspaceScan);
+        // Ctrl+P打开快速打开面板，面板打开时用方向键选择、Esc关闭
+        let quick_open_subscription = if self.quick_open.is_open() {
+            event::listen_with(|event, _status, _id| match event {
+                iced::Event::Keyboard(keyboard::Event::KeyPressed {
+                    key: keyboard::Key::Named(named),
+                    ..
+                }) => match named {
+                    keyboard::key::Named::Escape => Some(QuickOpenMessage::Close),
+                    keyboard::key::Named::ArrowDown => Some(QuickOpenMessage::SelectNext),
+                    keyboard::key::Named::ArrowUp => Some(QuickOpenMessage::SelectPrevious),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .map(FileTreeMessage::QuickOpen)
+        } else {
+            event::listen_with(|event, _status, _id| match event {
+                iced::Event::Keyboard(keyboard::Event::KeyPressed {
+                    key: keyboard::Key::Character(c),
+                    modifiers,
+                    ..
+                }) if modifiers.command() && c.as_str() == "p" => {
+                    Some(FileTreeMessage::OpenQuickOpen)
+                }
+                _ => None,
+            })
+        };
+        // 按住节点期间记录鼠标移动，判断是否开始拖动
+        let drag_subscription = if self.pressed_node.is_some() {
+            event::listen_with(|event, _status, _id| match event {
+                iced::Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                    Some(FileTreeMessage::DragCursorMoved(position))
+                }
+                iced::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                    Some(FileTreeMessage::DragReleased)
+                }
+                _ => None,
+            })
+        } else {
+            Subscription::none()
+        };
+        Subscription::batch([
+            rename_subscription,
+            workspace_subscription,
+            scan_progress_subscription,
+            quick_open_subscription,
+            drag_subscription,
+        ])
+    }
+
+    // 根据节点类型生成右键菜单项
+    pub fn generate_context_menu_items(&self, key: u32) -> Vec<ContextMenuItem> {
+        let Some(node) = self.all_nodes.get(&key) else {
+            return vec![];
+        };
+        let is_root_node = self.is_root_node(key);
+        match node.node_content {
+            NodeContent::DirectoryMd(_) => {
+                let mut items = vec![
+                    ContextMenuItem::new("新建文件", ContextAction::CreateNewFile(key)),
+                    ContextMenuItem::new("新建文件夹", ContextAction::CreateNewFolder(key)),
+                ];
+                if !is_root_node {
+                    items.extend([
+                        ContextMenuItem::new("重命名", ContextAction::RenameNode(key)),
+                        ContextMenuItem::new("复制", ContextAction::DuplicateNode(key)),
+                        ContextMenuItem::new("删除", ContextAction::DeleteNode(key)),
+                    ]);
+                }
+                items.push(ContextMenuItem::new(
+                    "在文件管理器中显示",
+                    ContextAction::RevealNode(key),
+                ));
+                if self.workplace_root_keys.contains(&key) {
+                    items.push(ContextMenuItem::new(
+                        "关闭工作区",
+                        ContextAction::CloseWorkspaceRoot(key),
+                    ));
+                }
+                items
+            }
+            NodeContent::DirectoryTemp(_) if Some(key) == self.temp_workplace_root_key => {
+                vec![ContextMenuItem::new(
+                    "新建临时文件",
+                    ContextAction::CreateNewFile(key),
+                )]
+            }
+            NodeContent::DirectoryTemp(_) => vec![],
+            NodeContent::Markdown(MdFile { path: None, .. }) => vec![
+                ContextMenuItem::new("打开", ContextAction::OpenNode(key)),
+                ContextMenuItem::new("重命名", ContextAction::RenameNode(key)),
+                ContextMenuItem::new("复制", ContextAction::DuplicateNode(key)),
+                ContextMenuItem::new("删除", ContextAction::DeleteNode(key)),
+            ],
+            NodeContent::Markdown(_) => vec![
+                ContextMenuItem::new("打开", ContextAction::OpenNode(key)),
+                ContextMenuItem::new("重命名", ContextAction::RenameNode(key)),
+                ContextMenuItem::new("复制", ContextAction::DuplicateNode(key)),
+                ContextMenuItem::new("删除", ContextAction::DeleteNode(key)),
+                ContextMenuItem::new("在文件管理器中显示", ContextAction::RevealNode(key)),
+            ],
+            NodeContent::Asset(AssetFile { kind, .. }) => vec![
+                ContextMenuItem::new(
+                    match kind {
+                        AssetKind::Image => "查看图片",
+                        _ => "打开",
+                    },
+                    ContextAction::OpenNode(key),
+                ),
+                ContextMenuItem::new("重命名", ContextAction::RenameNode(key)),
+                ContextMenuItem::new("复制", ContextAction::DuplicateNode(key)),
+                ContextMenuItem::new("删除", ContextAction::DeleteNode(key)),
+                ContextMenuItem::new("在文件管理器中显示", ContextAction::RevealNode(key)),
+            ],
+            NodeContent::Image(_) => vec![
+                ContextMenuItem::new("查看图片", ContextAction::OpenNode(key)),
+                ContextMenuItem::new("插入到文档", ContextAction::InsertImage(key)),
+                ContextMenuItem::new("在文件管理器中显示", ContextAction::RevealNode(key)),
+            ],
+        }
+    }
+
+    pub fn is_root_node(&self, key: u32) -> bool {
+        self.workplace_root_keys.contains(&key)
+            || [self.temp_workplace_root_key, self.temp_img_library_root_key].contains(&Some(key))
+    }
+
+    pub fn find_parent_key(&self, key: u32) -> Option<u32> {
+        self.all_nodes
+            .values()
+            .find(|node| {
+                node.try_get_children()
+                    .is_ok_and(|children| children.contains(&key))
+            })
+            .map(|node| node.global_id)
+    }
+
+    // 文件夹返回自身，文件返回所在文件夹
+    pub fn resolve_dir_key(&self, key: u32) -> Option<u32> {
+        match self.all_nodes.get(&key) {
+            Some(node) if node.is_directory() => Some(key),
+            Some(_) => self.find_parent_key(key),
+            None => None,
+        }
+    }
+
+    // 收集节点自身及所有子孙节点
+    pub fn collect_subtree_keys(&self, key: u32) -> Vec<u32> {
+        let mut keys = vec![];
+        let mut key_stack = vec![key];
+        while let Some(key) = key_stack.pop() {
+            if let Some(node) = self.all_nodes.get(&key) {
+                keys.push(key);
+                if let Ok(children) = node.try_get_children() {
+                    key_stack.extend(children.iter().copied());
+                }
+            }
+        }
+        keys
+    }
+
+    // 从父节点中摘除并删除整棵子树，返回被删除的节点
+    pub fn remove_subtree(&mut self, key: u32) -> Vec<u32> {
+        if let Some(parent_key) = self.find_parent_key(key)
+            && let Some(children) = self
+                .all_nodes
+                .get_mut(&parent_key)
+                .and_then(|parent_node| parent_node.try_get_children_mut().ok())
+        {
+            children.retain(|child_key| *child_key != key);
+        }
+        let removed_keys = self.collect_subtree_keys(key);
+        for removed_key in removed_keys.iter() {
+            self.remove_node(*removed_key);
+        }
+        if self
+            .focused_node_id
+            .is_some_and(|id| removed_keys.contains(&id))
+        {
+            self.focused_node_id = None;
+        }
+        if self
+            .renaming
+            .as_ref()
+            .is_some_and(|(id, _)| removed_keys.contains(id))
+        {
+            self.renaming = None;
+        }
+        removed_keys
+    }
+
+    // 工作区中除根节点外所有节点的路径，值为节点id和是否为文件夹
+    pub fn collect_workspace_paths(&self, root_key: u32) -> BTreeMap<PathBuf, (u32, bool)> {
+        self.collect_subtree_keys(root_key)
+            .into_iter()
+            .filter(|key| *key != root_key)
+            .filter_map(|key| self.all_nodes.get(&key))
+            .filter_map(|node| {
+                node.try_get_path()
+                    .ok()
+                    .map(|path| (path.to_path_buf(), (node.global_id, node.is_directory())))
+            })
+            .collect()
+    }
+
+    // 把扫描结果与文件树的差异增量应用到节点上，未变动的节点保持展开和选中状态
+    pub fn apply_workspace_snapshot(
+        &mut self,
+        root_key: u32,
+        current_paths: BTreeMap<PathBuf, (u32, bool)>,
+        snapshot: BTreeMap<PathBuf, ScanEntry>,
+    ) -> Vec<PathBuf> {
+        let mut changed_paths = vec![];
+        // 父路径排在子路径之前，父节点删除后子节点已不存在，直接跳过
+        for (path, (key, is_dir)) in current_paths.iter() {
+            if snapshot.get(path).is_some_and(|entry| entry.is_dir == *is_dir)
+                || !self.all_nodes.contains_key(key)
+            {
+                continue;
+            }
+            self.keep_selected_node_as_temp_file(*key);
+            self.remove_subtree(*key);
+            changed_paths.push(path.clone());
+            info!("[FileTree-ApplyWorkspaceSnapshot]:外部删除 {}", path.display());
+        }
+        let mut path_keys = self
+            .collect_workspace_paths(root_key)
+            .into_iter()
+            .map(|(path, (key, _))| (path, key))
+            .collect::<HashMap<PathBuf, u32>>();
+        if let Some(root_path) = self
+            .all_nodes
+            .get(&root_key)
+            .and_then(|node| node.try_get_path().ok())
+        {
+            path_keys.insert(root_path.to_path_buf(), root_key);
+        }
+        let mut changed_dir_keys = vec![];
+        for (path, entry) in snapshot {
+            if path_keys.contains_key(&path) {
+                continue;
+            }
+            let Some(parent_key) = path.parent().and_then(|parent| path_keys.get(parent)).copied()
+            else {
+                continue;
+            };
+            info!("[FileTree-ApplyWorkspaceSnapshot]:外部新增 {}", path.display());
+            let node = operation::new_workspace_node(path.clone(), entry);
+            let node_key = node.global_id;
+            self.insert_nodes([(node_key, node)]);
+            if let Some(children) = self
+                .all_nodes
+                .get_mut(&parent_key)
+                .and_then(|parent_node| parent_node.try_get_children_mut().ok())
+            {
+                children.push(node_key);
+            }
+            changed_paths.push(path.clone());
+            path_keys.insert(path, node_key);
+            changed_dir_keys.push(parent_key);
+        }
+        changed_dir_keys.dedup();
+        for key in changed_dir_keys {
+            self.sort_children(key);
+        }
+        changed_paths
+    }
+
+    // 更新排序用的修改时间和大小，按时间或大小排序时同步调整顺序
+    pub fn refresh_node_metadata(
+        &mut self,
+        root_key: u32,
+        current_paths: &BTreeMap<PathBuf, (u32, bool)>,
+        snapshot: &BTreeMap<PathBuf, ScanEntry>,
+    ) {
+        let mut changed_dir_keys = vec![];
+        for (path, entry) in snapshot {
+            if let Some((key, _)) = current_paths.get(path)
+                && let Some(node) = self.all_nodes.get_mut(key)
+                && node.metadata != entry.metadata
+            {
+                node.metadata = entry.metadata;
+                changed_dir_keys.push(*key);
+            }
+        }
+        if self
+            .tree_view_setting_of_root(root_key)
+            .is_none_or(|setting| setting.sort_mode == SortMode::Name)
+        {
+            return;
+        }
+        let mut parent_keys = changed_dir_keys
+            .into_iter()
+            .filter_map(|key| self.find_parent_key(key))
+            .collect::<Vec<_>>();
+        parent_keys.sort();
+        parent_keys.dedup();
+        for key in parent_keys {
+            self.sort_children(key);
+        }
+    }
+
+    // 按所在工作区的排序方式重新排列子节点
+    pub fn sort_children(&mut self, key: u32) {
+        let Some((node_path, mut children)) = self
+            .all_nodes
+            .get(&key)
+            .filter(|node| node.is_md_directory())
+            .and_then(|node| Some((node.try_get_path().ok()?, node.try_get_children().ok()?)))
+            .map(|(path, children)| (path.to_path_buf(), children.clone()))
+        else {
+            return;
+        };
+        let sort_mode = self
+            .find_root_of_path(&node_path)
+            .map(|(_, root_path)| self.tree_view_setting_of(&root_path).sort_mode)
+            .unwrap_or_default();
+        children.sort_by(|a, b| match (self.all_nodes.get(a), self.all_nodes.get(b)) {
+            (Some(a), Some(b)) => operation::compare_nodes(a, b, sort_mode),
+            _ => cmp::Ordering::Equal,
+        });
+        if let Some(node_children) = self
+            .all_nodes
+            .get_mut(&key)
+            .and_then(|node| node.try_get_children_mut().ok())
+        {
+            *node_children = children;
+        }
+    }
+
+    pub fn sort_subtree(&mut self, key: u32) {
+        for key in self.collect_subtree_keys(key) {
+            self.sort_children(key);
+        }
+    }
+
+    pub fn apply_dir_children(&mut self, key: u32, entries: BTreeMap<PathBuf, ScanEntry>) {
+        let Some(existing_paths) = self.all_nodes.get(&key).map(|node| {
+            node.try_get_children()
+                .into_iter()
+                .flatten()
+                .filter_map(|child_key| self.all_nodes.get(child_key))
+                .filter_map(|child_node| child_node.try_get_path().ok())
+                .map(Path::to_path_buf)
+                .collect::<Vec<_>>()
+        }) else {
+            return;
+        };
+        // 读取期间新建或移入的节点已经在文件树中，跳过避免重复
+        let new_nodes = entries
+            .into_iter()
+            .filter(|(path, _)| !existing_paths.contains(path))
+            .map(|(path, entry)| operation::new_workspace_node(path, entry))
+            .collect::<Vec<_>>();
+        if let Some(node) = self.all_nodes.get_mut(&key) {
+            node.set_load_state_if_directory(LoadState::Loaded);
+            if let Ok(children) = node.try_get_children_mut() {
+                children.extend(new_nodes.iter().map(|child_node| child_node.global_id));
+            }
+        }
+        self.insert_nodes(
+            new_nodes
+                .into_iter()
+                .map(|child_node| (child_node.global_id, child_node)),
+        );
+        self.sort_children(key);
+    }
+
+    pub fn find_node_by_path(&self, path: &Path) -> Option<u32> {
+        self.path_index.get(path).copied()
+    }
+
+    // 所有新节点都经过这里加入all_nodes，同时记录有路径的节点
+    fn insert_nodes(&mut self, nodes: impl IntoIterator<Item = (u32, FileNode)>) {
+        for (key, node) in nodes {
+            if let Ok(path) = node.try_get_path() {
+                self.path_index.insert(path.to_path_buf(), key);
+            }
+            self.all_nodes.insert(key, node);
+        }
+    }
+
+    fn remove_node(&mut self, key: u32) -> Option<FileNode> {
+        let node = self.all_nodes.remove(&key)?;
+        if let Ok(path) = node.try_get_path()
+            && self.path_index.get(path) == Some(&key)
+        {
+            self.path_index.remove(path);
+        }
+        Some(node)
+    }
+
+    // 节点路径改变后更新索引，old_path为改变前的路径
+    fn reindex_path(&mut self, key: u32, old_path: Option<PathBuf>) {
+        if let Some(old_path) = old_path
+            && self.path_index.get(&old_path) == Some(&key)
+        {
+            self.path_index.remove(&old_path);
+        }
+        if let Some(path) = self
+            .all_nodes
+            .get(&key)
+            .and_then(|node| node.try_get_path().ok())
+        {
+            self.path_index.insert(path.to_path_buf(), key);
+        }
+    }
+
+    fn node_path(&self, key: u32) -> Option<PathBuf> {
+        self.all_nodes
+            .get(&key)
+            .and_then(|node| node.try_get_path().ok())
+            .map(Path::to_path_buf)
+    }
+
+    // 所有工作区的根节点和根路径
+    pub fn root_paths(&self) -> Vec<(u32, PathBuf)> {
+        self.workplace_root_keys
+            .iter()
+            .filter_map(|key| {
+                self.all_nodes
+                    .get(key)
+                    .and_then(|node| node.try_get_path().ok())
+                    .map(|path| (*key, path.to_path_buf()))
+            })
+            .collect()
+    }
+
+    // 笔记索引需要读取的所有工作区和临时工作区中的文档
+    fn index_roots(&self, setting: &AppSetting) -> IndexRoots {
+        IndexRoots {
+            workspaces: self.workspace_scan_roots(setting),
+            temp_paths: self.temp_workplace_paths(),
+        }
+    }
+
+    // 临时工作区中有路径的文档，同样参与笔记索引
+    fn temp_workplace_paths(&self) -> Vec<PathBuf> {
+        self.temp_workplace_root_key
+            .and_then(|key| self.all_nodes.get(&key))
+            .and_then(|node| node.try_get_children().ok())
+            .into_iter()
+            .flatten()
+            .filter_map(|key| self.all_nodes.get(key))
+            .filter_map(|node| node.try_get_path().ok().map(Path::to_path_buf))
+            .collect()
+    }
+
+    pub fn workspace_scan_roots(&self, setting: &AppSetting) -> Vec<(PathBuf, ScanOptions)> {
+        self.root_paths()
+            .into_iter()
+            .map(|(_, root_path)| {
+                let options = self.scan_options(setting, &root_path);
+                (root_path, options)
+            })
+            .collect()
+    }
+
+    // 菜单中新建文件的目标文件夹，和工具栏一样为最近点击的节点所在的文件夹
+    // 没有打开工作区时新建在临时工作区中
+    pub fn new_file_dir_key(&self) -> Option<u32> {
+        self.focused_node_id
+            .or(self.workplace_root_keys.first().copied())
+            .and_then(|key| self.resolve_dir_key(key))
+            .or(self.temp_workplace_root_key)
+    }
+
+    // 当前笔记所在的工作区，当前笔记不在工作区中时为第一个工作区
+    fn current_root_path(&self) -> Option<PathBuf> {
+        self.selected_node_id
+            .and_then(|key| self.all_nodes.get(&key))
+            .and_then(|node| node.try_get_path().ok())
+            .and_then(|path| self.find_root_of_path(path))
+            .or_else(|| self.root_paths().into_iter().next())
+            .map(|(_, root_path)| root_path)
+    }
+
+    // 各工作区日记文件夹中的日记日期，用于在日历中标记
+    pub fn journal_dates(&self, setting: &AppSetting) -> HashSet<Date> {
+        let journal_dirs = self
+            .root_paths()
+            .into_iter()
+            .map(|(_, root_path)| root_path.join(&setting.journal_folder))
+            .collect::<Vec<_>>();
+        self.note_indexer
+            .link_index()
+            .files()
+            .filter(|(path, _)| {
+                path.parent()
+                    .is_some_and(|parent| journal_dirs.iter().any(|dir| dir == parent))
+            })
+            .filter_map(|(path, _)| journal::journal_date(path))
+            .collect()
+    }
+
+    // 链接索引中的笔记和它们之间的链接，用于在预览中绘制关系图
+    pub fn link_graph(&self, title_as_label: bool) -> LinkGraph {
+        let mut nodes = self
+            .note_indexer
+            .link_index()
+            .files()
+            .map(|(path, file)| GraphNode {
+                path: path.clone(),
+                label: file
+                    .title
+                    .clone()
+                    .filter(|_| title_as_label)
+                    .or_else(|| path.file_stem().map(|stem| stem.to_string_lossy().to_string()))
+                    .unwrap_or_default(),
+            })
+            .collect::<Vec<_>>();
+        nodes.sort_by(|a, b| a.path.cmp(&b.path));
+        let links = self
+            .note_indexer
+            .link_index()
+            .files()
+            .flat_map(|(path, file)| {
+                file.links.iter().filter_map(|link| {
+                    link.target
+                        .resolve(self.note_indexer.note_index())
+                        .map(|target| (path.clone(), target.to_path_buf()))
+                })
+            })
+            .collect::<Vec<_>>();
+        LinkGraph::new(nodes, &links)
+    }
+
+    // 包含指定路径的工作区，工作区之间不会互相包含
+    pub fn find_root_of_path(&self, path: &Path) -> Option<(u32, PathBuf)> {
+        self.root_paths()
+            .into_iter()
+            .find(|(_, root_path)| path.starts_with(root_path))
+    }
+
+    pub fn tree_view_setting_of(&self, root_path: &Path) -> TreeViewSetting {
+        self.tree_view_settings
+            .get(root_path)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn tree_view_setting_of_root(&self, root_key: u32) -> Option<TreeViewSetting> {
+        self.all_nodes
+            .get(&root_key)
+            .and_then(|node| node.try_get_path().ok())
+            .map(|root_path| self.tree_view_setting_of(root_path))
+    }
+
+    // 关闭工作区并删除它的所有节点，正在编辑的文件移到临时工作区继续编辑
+    pub fn close_workspace_root(&mut self, root_key: u32) {
+        if !self.workplace_root_keys.contains(&root_key) {
+            return;
+        }
+        // 正在编辑的文件和缓存中有未保存改动的文件移到临时工作区，避免丢失内容
+        let kept_ids = self
+            .collect_subtree_keys(root_key)
+            .into_iter()
+            .filter(|id| *id != root_key)
+            .filter(|id| {
+                self.all_nodes.get(id).is_some_and(|node| {
+                    node.try_get_md().is_ok_and(|md_file| {
+                        self.selected_node_id == Some(*id) || md_file.is_dirty()
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+        for id in &kept_ids {
+            if let Some(parent_key) = self.find_parent_key(*id)
+                && let Some(children) = self
+                    .all_nodes
+                    .get_mut(&parent_key)
+                    .and_then(|parent_node| parent_node.try_get_children_mut().ok())
+            {
+                children.retain(|child_key| child_key != id);
+            }
+        }
+        if !kept_ids.is_empty() {
+            self.insert_node_to_temp_workplace(kept_ids);
+        }
+        self.workplace_root_keys.retain(|key| *key != root_key);
+        self.workspace_scan.remove_root(root_key);
+        let removed_keys = self.remove_subtree(root_key);
+        self.recent_node_ids
+            .retain(|key| !removed_keys.contains(key));
+        if self
+            .pending_cursor_line
+            .is_some_and(|(key, _)| removed_keys.contains(&key))
+        {
+            self.pending_cursor_line = None;
+        }
+        if self
+            .hovered_file_node_id
+            .is_some_and(|key| removed_keys.contains(&key))
+        {
+            self.hovered_file_node_id = None;
+        }
+        self.pressed_node = None;
+        self.dragging_node_id = None;
+        info!("[FileTree-CloseWorkspaceRoot]:已关闭工作区，移除 {} 个节点!", removed_keys.len());
+    }
+
+    // 当前文件直接跳转，其他文件打开后再跳转
+    pub fn open_node_at_line(&mut self, key: u32, line: usize) -> Task<FileTreeMessage> {
+        let scroll_task = self.reveal_node_in_tree(key);
+        if self.selected_node_id == Some(key) {
+            return Task::batch([
+                scroll_task,
+                Task::done(FileTreeMessage::SendCursorLineToEditor(line)),
+            ]);
+        }
+        self.pending_cursor_line = Some((key, line));
+        Task::batch([scroll_task, Task::done(FileTreeMessage::ChangeSelectedNode(key))])
+    }
+
+    // 读取工作区下上次展开的文件夹，上级文件夹一并读取以便找到对应节点
+    pub fn restore_expanded_dirs(
+        &mut self,
+        root_key: u32,
+        setting: &AppSetting,
+    ) -> Task<FileTreeMessage> {
+        let Some(root_path) = self
+            .all_nodes
+            .get(&root_key)
+            .and_then(|node| node.try_get_path().ok())
+            .map(Path::to_path_buf)
+        else {
+            return Task::none();
+        };
+        let (expanded_dirs, pending_expanded_dirs): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut self.pending_expanded_dirs)
+                .into_iter()
+                .partition(|dir_path| dir_path.starts_with(&root_path));
+        self.pending_expanded_dirs = pending_expanded_dirs;
+        let dir_paths = expanded_dirs
+            .iter()
+            .flat_map(|dir_path| {
+                dir_path
+                    .ancestors()
+                    .take_while(|ancestor| *ancestor != root_path)
+                    .map(Path::to_path_buf)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<BTreeSet<_>>();
+        if dir_paths.is_empty() {
+            return Task::none();
+        }
+        let options = self.scan_options(setting, &root_path);
+        Task::perform(
+            operation::read_restored_dirs_children(
+                root_path,
+                dir_paths.into_iter().collect(),
+                options,
+            ),
+            move |dirs| FileTreeMessage::ExpandedDirsLoaded(expanded_dirs.clone(), dirs),
+        )
+    }
+
+    // 恢复会话时上次编辑的文件所在位置读取完成后打开它并跳转到上次的行
+    pub fn open_restored_file(&mut self) -> Task<FileTreeMessage> {
+        let Some((path, line)) = self.pending_restore_file.take() else {
+            return Task::none();
+        };
+        if let Some(key) = self.find_node_by_path(&path) {
+            return self.open_node_at_line(key, line);
+        }
+        if self.find_root_of_path(&path).is_some() {
+            return Task::done(FileTreeMessage::OpenPathAtLine(path, line));
+        }
+        self.pending_restore_file = Some((path, line));
+        Task::none()
+    }
+
+    // 收集需要保存的会话状态，图片库中的图片由预览区提供对应的节点
+    pub fn session_state(
+        &self,
+        gallery_node_ids: &[u32],
+        doc_views: &HashMap<u32, DocView>,
+    ) -> WorkspaceSession {
+        let root_paths = self.root_paths();
+        // 还没读取完成的工作区和还没恢复的状态原样保留
+        let roots = root_paths
+            .iter()
+            .map(|(_, root_path)| root_path.clone())
+            .chain(self.workspace_scan.queued_root_paths().cloned())
+            .collect();
+        let expanded_dirs = root_paths
+            .iter()
+            .flat_map(|(root_key, _)| self.collect_subtree_keys(*root_key))
+            .filter(|key| !self.workplace_root_keys.contains(key))
+            .filter_map(|key| self.all_nodes.get(&key))
+            .filter(|node| {
+                node.is_md_directory()
+                    && node.is_expanded()
+                    && node.load_state() == LoadState::Loaded
+            })
+            .filter_map(|node| node.try_get_path().ok().map(Path::to_path_buf))
+            .chain(self.pending_expanded_dirs.iter().cloned())
+            .collect();
+        let open_files = self
+            .temp_workplace_root_key
+            .and_then(|key| self.all_nodes.get(&key))
+            .and_then(|node| node.try_get_children().ok())
+            .into_iter()
+            .flatten()
+            .filter_map(|key| self.all_nodes.get(key))
+            .filter(|node| node.is_md_file())
+            .filter_map(|node| node.try_get_path().ok().map(Path::to_path_buf))
+            .collect();
+        let active_file = self
+            .selected_node_id
+            .and_then(|key| self.all_nodes.get(&key))
+            .and_then(|node| node.try_get_path().ok())
+            .map(Path::to_path_buf)
+            .or_else(|| self.pending_restore_file.as_ref().map(|(path, _)| path.clone()));
+        // 还没再次打开的文档沿用上次会话中的位置
+        let mut documents = doc_views
+            .iter()
+            .filter_map(|(key, view)| {
+                let path = self.all_nodes.get(key)?.try_get_path().ok()?;
+                Some((path.to_path_buf(), *view))
+            })
+            .collect::<HashMap<_, _>>();
+        for (path, view) in &self.pending_doc_views {
+            documents.entry(path.clone()).or_insert(*view);
+        }
+        let documents = documents
+            .into_iter()
+            .map(|(path, view)| DocumentSession {
+                path,
+                cursor_line: view.cursor_line,
+                scroll_line: view.scroll_line,
+            })
+            .collect();
+        let gallery_images = gallery_node_ids
+            .iter()
+            .filter_map(|key| self.all_nodes.get(key))
+            .filter_map(|node| node.try_get_path().ok().map(Path::to_path_buf))
+            .collect();
+        WorkspaceSession {
+            roots,
+            expanded_dirs,
+            open_files,
+            active_file,
+            documents,
+            gallery_images,
+        }
+    }
+
+    pub fn insert_img_nodes(&mut self, img_nodes: HashMap<u32, FileNode>) -> Vec<ImgData> {
+        let ids = img_nodes.keys().copied().collect::<Vec<u32>>();
+        let img_datas = img_nodes
+            .values()
+            .filter_map(|img_node| {
+                img_node
+                    .try_get_img()
+                    .ok()
+                    .map(|img_file| (img_file, img_node.global_id))
+            })
+            .map(|(img_file, id)| ImgData {
+                global_id: id,
+                indep_id: img_file.indep_id,
+                handle: img_file.cache.clone(),
+            })
+            .collect::<Vec<ImgData>>();
+        self.insert_nodes(img_nodes);
+        self.insert_node_to_temp_img_library(ids);
+        img_datas
+    }
+
+    pub fn record_recent_node(&mut self, key: u32) {
+        self.recent_node_ids.retain(|recent_key| *recent_key != key);
+        self.recent_node_ids.insert(0, key);
+        self.recent_node_ids.truncate(RECENT_NODES_LIMIT);
+    }
+
+    // 收集所有已读取的文件节点，相对路径由各级节点名称拼接而成
+    // 再补充链接索引中还没有读取到文件树的笔记，它们所在的文件夹可能还没有展开过
+    pub fn collect_quick_open_candidates(&self) -> Vec<QuickOpenCandidate> {
+        let mut candidates = vec![];
+        let workspace_roots = self
+            .workplace_root_keys
+            .iter()
+            .map(|key| (Some(*key), self.quick_open_prefix(*key)));
+        let mut key_stack = workspace_roots
+            .chain([
+                (self.temp_workplace_root_key, "临时工作区".to_string()),
+                (self.temp_img_library_root_key, "图片库".to_string()),
+            ])
+            .filter_map(|(key, prefix)| key.map(|key| (key, prefix)))
+        .flat_map(|(key, prefix)| {
+            self.all_nodes
+                .get(&key)
+                .and_then(|node| node.try_get_children().ok())
+                .into_iter()
+                .flatten()
+                .map(move |child_key| (*child_key, prefix.clone()))
+        })
+        .collect::<Vec<_>>();
+        while let Some((key, prefix)) = key_stack.pop() {
+            let Some(node) = self.all_nodes.get(&key) else {
+                continue;
+            };
+            let relative_path = match prefix.is_empty() {
+                true => node.name.clone(),
+                false => format!("{}/{}", prefix, node.name),
+            };
+            match node.try_get_children() {
+                Ok(children) => key_stack.extend(
+                    children
+                        .iter()
+                        .map(|child_key| (*child_key, relative_path.clone())),
+                ),
+                Err(_) => candidates.push(QuickOpenCandidate {
+                    target: QuickOpenTarget::Node(key),
+                    name: node.name.clone(),
+                    relative_path,
+                    recent_rank: self
+                        .recent_node_ids
+                        .iter()
+                        .position(|recent_key| *recent_key == key),
+                }),
+            }
+        }
+        for path in self.note_indexer.link_index().files().map(|(path, _)| path) {
+            if self.find_node_by_path(path).is_some() {
+                continue;
+            }
+            let Some((root_key, root_path)) = self.find_root_of_path(path) else {
+                continue;
+            };
+            let Ok(relative_path) = path.strip_prefix(&root_path) else {
+                continue;
+            };
+            let relative_path = relative_path
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let prefix = self.quick_open_prefix(root_key);
+            candidates.push(QuickOpenCandidate {
+                target: QuickOpenTarget::Path(path.clone()),
+                name: path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                relative_path: match prefix.is_empty() {
+                    true => relative_path,
+                    false => format!("{}/{}", prefix, relative_path),
+                },
+                recent_rank: None,
+            });
+        }
+        candidates
+    }
+
+    // 只有一个工作区时省略工作区名称
+    fn quick_open_prefix(&self, root_key: u32) -> String {
+        match self.workplace_root_keys.len() {
+            1 => String::new(),
+            _ => self
+                .all_nodes
+                .get(&root_key)
+                .map(|node| node.name.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    // 展开节点的所有上级文件夹，并把它滚动到文件树中间
+    pub fn reveal_node_in_tree(&mut self, key: u32) -> Task<FileTreeMessage> {
+        let mut parent_key = self.find_parent_key(key);
+        while let Some(key) = parent_key {
+            if let Some(node) = self.all_nodes.get_mut(&key) {
+                node.set_expanded_if_directory(true);
+            }
+            parent_key = self.find_parent_key(key);
+        }
+        self.focused_node_id = Some(key);
+        let Some(row_index) = self
+            .collect_visible_rows()
+            .iter()
+            .position(|(row_key, _)| *row_key == key)
+        else {
+            return Task::none();
+        };
+        let offset = (row_index as f32 * FILE_TREE_ROW_HEIGHT
+            - (self.tree_viewport_height - FILE_TREE_ROW_HEIGHT) / 2.)
+            .max(0.);
+        iced::widget::operation::scroll_to(
+            operation::FILE_TREE_SCROLLABLE_ID,
+            scrollable::AbsoluteOffset { x: 0., y: offset },
+        )
+    }
+
+    // 读取工作区时在全局设置之外加上路径所在工作区的过滤设置
+    pub fn scan_options(&self, setting: &AppSetting, path: &Path) -> ScanOptions {
+        let root_path = self
+            .find_root_of_path(path)
+            .map(|(_, root_path)| root_path)
+            .unwrap_or(path.to_path_buf());
+        ScanOptions {
+            hide_dirs_without_markdown: self
+                .tree_view_setting_of(&root_path)
+                .hide_dirs_without_markdown,
+            markdown_dir_cache: Arc::clone(&self.markdown_dir_cache),
+            ..ScanOptions::from(setting)
+        }
+    }
+
+    pub fn save_tree_view_setting(
+        &mut self,
+        root_key: u32,
+        tree_view_setting: TreeViewSetting,
+    ) -> Task<FileTreeMessage> {
+        let Some(root_path) = self
+            .all_nodes
+            .get(&root_key)
+            .and_then(|node| node.try_get_path().ok())
+        else {
+            return Task::none();
+        };
+        self.tree_view_settings
+            .insert(root_path.to_path_buf(), tree_view_setting);
+        self.save_tree_view_settings()
+    }
+
+    pub fn save_tree_view_settings(&self) -> Task<FileTreeMessage> {
+        Task::future(setting::save_config(
+            setting::TREE_VIEW_SETTING_FILE,
+            self.tree_view_settings.clone(),
+        ))
+        .then(|result| match result {
+            Ok(_) => Task::none(),
+            Err(error) => Task::done(FileTreeMessage::HandleError(error)),
+        })
+    }
+
+    // 正在编辑的文件在外部被删除时转为临时文件，避免编辑器中的内容丢失
+    pub fn keep_selected_node_as_temp_file(&mut self, removed_key: u32) {
+        let Some(selected_id) = self
+            .selected_node_id
+            .filter(|id| self.collect_subtree_keys(removed_key).contains(id))
+            .filter(|id| self.all_nodes.get(id).is_some_and(FileNode::is_md_file))
+        else {
+            return;
+        };
+        if let Some(parent_key) = self.find_parent_key(selected_id)
+            && let Some(children) = self
+                .all_nodes
+                .get_mut(&parent_key)
+                .and_then(|parent_node| parent_node.try_get_children_mut().ok())
+        {
+            children.retain(|child_key| *child_key != selected_id);
+        }
+        if let Some(md_file) = self
+            .all_nodes
+            .get_mut(&selected_id)
+            .and_then(|node| node.try_get_md_mut().ok())
+        {
+            let old_path = md_file.path.take();
+            self.reindex_path(selected_id, old_path);
+            warn!("[FileTree-ApplyWorkspaceSnapshot]:正在编辑的文件已被外部删除，已转入临时工作区!");
+        }
+        self.insert_node_to_temp_workplace(vec![selected_id]);
+    }
+
+    // 重命名或移动后把子树中所有节点的路径前缀替换为新路径
+    pub fn update_subtree_paths(&mut self, key: u32, old_path: &Path, new_path: &Path) {
+        for key in self.collect_subtree_keys(key) {
+            if let Some(node) = self.all_nodes.get_mut(&key)
+                && let Ok(node_path) = node.try_get_path().map(Path::to_path_buf)
+                && let Ok(rest) = node_path.strip_prefix(old_path)
+            {
+                let path = match rest.as_os_str().is_empty() {
+                    true => new_path.to_path_buf(),
+                    false => new_path.join(rest),
+                };
+                node.set_path(path);
+                self.reindex_path(key, Some(node_path));
+            }
+        }
+    }
+
+    pub fn note_index(&self) -> Arc<NoteIndex> {
+        Arc::clone(self.note_indexer.note_index())
+    }
+
+    pub fn link_index(&self) -> Arc<LinkIndex> {
+        Arc::clone(self.note_indexer.link_index())
+    }
+
+    pub fn tag_filter(&self) -> Option<&str> {
+        self.tag_filter
+            .as_ref()
+            .map(|tag_filter| tag_filter.tag.as_str())
+    }
+
+    // 按索引找出带有标签的笔记，读取它们所在的还没有读取的文件夹
+    fn apply_tag_filter(&mut self, tag: String, setting: &AppSetting) -> Task<FileTreeMessage> {
+        let paths = self.note_indexer.link_index().files_with_tag(&tag);
+        let mut tasks = vec![];
+        let mut dirs = HashSet::new();
+        for (_, root_path) in self.root_paths() {
+            let mut dir_paths = BTreeSet::new();
+            for path in paths.iter().filter(|path| path.starts_with(&root_path)) {
+                for ancestor in path
+                    .ancestors()
+                    .skip(1)
+                    .take_while(|ancestor| ancestor.starts_with(&root_path))
+                {
+                    if !dirs.insert(ancestor.to_path_buf()) {
+                        break;
+                    }
+                    let is_loaded = self
+                        .find_node_by_path(ancestor)
+                        .and_then(|key| self.all_nodes.get(&key))
+                        .is_some_and(|node| node.load_state() == LoadState::Loaded);
+                    if !is_loaded {
+                        dir_paths.insert(ancestor.to_path_buf());
+                    }
+                }
+            }
+            if dir_paths.is_empty() {
+                continue;
+            }
+            // 上级文件夹排在前面，读取后才能找到下级文件夹的节点
+            let options = self.scan_options(setting, &root_path);
+            tasks.push(Task::perform(
+                operation::read_dirs_children(
+                    root_path.clone(),
+                    dir_paths.into_iter().collect(),
+                    options,
+                    self.scan_progress_of(&root_path),
+                ),
+                FileTreeMessage::TagDirsLoaded,
+            ));
+        }
+        self.tag_filter = Some(TagFilter { tag, paths, dirs });
+        Task::batch(tasks)
+    }
+
+    // 索引更新后重新计算过滤结果
+    fn refresh_tag_filter(&mut self, setting: &AppSetting) -> Task<FileTreeMessage> {
+        match self.tag_filter.take() {
+            Some(tag_filter) => self.apply_tag_filter(tag_filter.tag, setting),
+            None => Task::none(),
+        }
+    }
+
+    // 依次把读取到的子节点加入文件树，上级文件夹需要排在前面
+    fn apply_loaded_dirs(&mut self, dirs: Vec<DirChildren>) {
+        for (dir_path, entries) in dirs {
+            if let Some(key) = self.find_node_by_path(&dir_path)
+                && self
+                    .all_nodes
+                    .get(&key)
+                    .is_some_and(|node| node.load_state() != LoadState::Loaded)
+            {
+                self.apply_dir_children(key, entries);
+            }
+        }
+    }
+
+    // 当前在编辑器中打开的文档路径，临时文件没有路径
+    pub fn selected_md_path(&self) -> Option<PathBuf> {
+        self.selected_node_id
+            .and_then(|id| self.all_nodes.get(&id))
+            .and_then(|node| node.try_get_md().ok())
+            .and_then(|md_file| md_file.path.clone())
+    }
+
+    // 正在编辑的文件路径改变后，同步预览区的图片根路径
+    pub fn sync_selected_path_with_preview(&self, key: u32) -> Task<FileTreeMessage> {
+        self.selected_node_id
+            .filter(|id| self.collect_subtree_keys(key).contains(id))
+            .and_then(|id| self.all_nodes.get(&id))
+            .and_then(|node| node.try_get_path().ok())
+            .map(|path| Task::done(FileTreeMessage::SendImgBasePathToPreview(path.to_path_buf())))
+            .unwrap_or(Task::none())
+    }
+
+    // 汇总编辑器中未保存的文件、缓存中有未保存改动的文件和临时工作区中有内容的临时文件
+    pub fn collect_unsaved_docs(&self, editor_file: Option<&FileData>) -> Vec<UnsavedDoc> {
+        let mut docs = vec![];
+        if let Some(file_data) = editor_file
+            && let Some(node) = self.all_nodes.get(&file_data.global_id)
+            && !node.is_temp_file()
+        {
+            docs.push(UnsavedDoc {
+                name: node.name.clone(),
+                path: node.try_get_path().ok().map(Path::to_path_buf),
+                content: Arc::clone(&file_data.content),
+                source: DocSource::FileNode(node.global_id),
+            });
+        }
+        for node in self.all_nodes.values() {
+            if editor_file.is_some_and(|file_data| file_data.global_id == node.global_id) {
+                continue;
+            }
+            if let Ok(md_file) = node.try_get_md()
+                && let (Some(path), Some(content)) = (&md_file.path, &md_file.cache)
+                && md_file.is_dirty()
+            {
+                docs.push(UnsavedDoc {
+                    name: node.name.clone(),
+                    path: Some(path.clone()),
+                    content: Arc::clone(content),
+                    source: DocSource::FileNode(node.global_id),
+                });
+            }
+        }
+        let temp_children = self
+            .temp_workplace_root_key
+            .and_then(|key| self.all_nodes.get(&key))
+            .and_then(|root_node| root_node.try_get_children().ok());
+        for id in temp_children.into_iter().flatten() {
+            if let Some(node) = self.all_nodes.get(id)
+                && node.is_temp_file()
+                && let Ok(md_file) = node.try_get_md()
+            {
+                // 正在编辑的临时文件以编辑器内容为准
+                let content = match editor_file {
+                    Some(file_data) if file_data.global_id == *id => {
+                        Some(Arc::clone(&file_data.content))
+                    }
+                    _ => md_file.cache.clone(),
+                };
+                if let Some(content) = content
+                    && !content.is_empty()
+                {
+                    docs.push(UnsavedDoc {
+                        name: node.name.clone(),
+                        path: None,
+                        content,
+                        source: DocSource::FileNode(node.global_id),
+                    });
+                }
+            }
+        }
+        docs
+    }
+
+    pub fn insert_node_to_temp_workplace(&mut self, ids: Vec<u32>) {
+        match self.temp_workplace_root_key {
+            Some(key) => {
+                if let Some(children) = self
+                    .all_nodes
+                    .get_mut(&key)
+                    .and_then(|root_node| root_node.try_get_children_mut().ok())
+                {
+                    for id in ids {
+                        children.push(id)
+                    }
+                }
+            }
+            None => {
+                let temp_workplace_root_node = FileNode::new(
+                    "临时工作区".to_string(),
+                    NodeContent::DirectoryTemp(TempDir {
+                        children: ids,
+                        expanded: true,
+                    }),
+                );
+                let temp_workplace_id = temp_workplace_root_node.global_id;
+                self.all_nodes
+                    .insert(temp_workplace_id, temp_workplace_root_node);
+                self.temp_workplace_root_key = Some(temp_workplace_id);
+            }
+        }
+    }
+
+    pub fn insert_node_to_temp_img_library(&mut self, ids: Vec<u32>) {
+        match self.temp_img_library_root_key {
+            Some(key) => {
+                if let Some(children) = self
+                    .all_nodes
+                    .get_mut(&key)
+                    .and_then(|root_node| root_node.try_get_children_mut().ok())
+                {
+                    for id in ids {
+                        children.push(id)
+                    }
+                }
+            }
+            None => {
+                let temp_img_library_root_node = FileNode::new(
+                    "临时图片库".to_string(),
+                    NodeContent::DirectoryTemp(TempDir {
+                        children: ids,
+                        expanded: true,
+                    }),
+                );
+                let temp_img_library_id = temp_img_library_root_node.global_id;
+                self.all_nodes
+                    .insert(temp_img_library_id, temp_img_library_root_node);
+                self.temp_img_library_root_key = Some(temp_img_library_id);
+            }
+        }
+    }
+}
+
+pub fn generate_tool_button(
+    label: &'static str,
+    message: FileTreeMessage,
+) -> Element<'static, FileTreeMessage> {
+    mouse_area(text(label).size(FONT_SIZE_SMALLER))
+        .interaction(mouse::Interaction::Pointer)
+        .on_press(message)
+        .into()
 }
@@ -0,0 +1,168 @@
+use std::path::{Path, PathBuf};
+
+// 读取工作区时需要遵守的忽略规则文件
+pub const IGNORE_FILE_NAMES: [&str; 2] = [".gitignore", ".fuguignore"];
+
+// 从各级文件夹的.gitignore和.fuguignore中收集的忽略规则
+// 子文件夹继承父文件夹的规则，后出现的规则优先级更高
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+#[derive(Debug, Clone)]
+struct IgnoreRule {
+    // 规则文件所在文件夹，匹配时使用相对它的路径
+    base: PathBuf,
+    pattern: String,
+    // 以!开头的规则，重新包含之前被忽略的路径
+    is_negated: bool,
+    // 以/结尾的规则，只匹配文件夹
+    is_dir_only: bool,
+    // 包含/的规则相对规则文件所在文件夹匹配，否则匹配任意层级的名称
+    is_anchored: bool,
+}
+
+impl IgnoreRules {
+    // 读取文件夹中的规则文件，没有规则文件时返回None以便继续共用父文件夹的规则
+    pub async fn load_dir(&self, dir: &Path) -> Option<IgnoreRules> {
+        let mut rules = None;
+        for file_name in IGNORE_FILE_NAMES {
+            if let Ok(content) = tokio::fs::read_to_string(dir.join(file_name)).await {
+                rules
+                    .get_or_insert_with(|| self.clone())
+                    .extend_from_str(dir, &content);
+            }
+        }
+        rules
+    }
+
+    pub fn extend_from_str(&mut self, base: &Path, content: &str) {
+        for line in content.lines() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (is_negated, line) = match line.strip_prefix('!') {
+                Some(line) => (true, line),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let (is_dir_only, line) = match line.strip_suffix('/') {
+                Some(line) => (true, line),
+                None => (false, line),
+            };
+            let is_anchored = line.contains('/');
+            let pattern = line.trim_start_matches('/');
+            if pattern.is_empty() {
+                continue;
+            }
+            self.rules.push(IgnoreRule {
+                base: base.to_path_buf(),
+                pattern: pattern.to_string(),
+                is_negated,
+                is_dir_only,
+                is_anchored,
+            });
+        }
+    }
+
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let mut is_ignored = false;
+        for rule in self.rules.iter() {
+            if rule.is_dir_only && !is_dir {
+                continue;
+            }
+            let Ok(relative_path) = path.strip_prefix(&rule.base) else {
+                continue;
+            };
+            let relative_path = relative_path
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let target = match rule.is_anchored {
+                true => relative_path.as_str(),
+                false => relative_path.rsplit('/').next().unwrap_or_default(),
+            };
+            if glob_match(rule.pattern.as_bytes(), target.as_bytes()) {
+                is_ignored = !rule.is_negated;
+            }
+        }
+        is_ignored
+    }
+}
+
+// gitignore风格的通配符匹配，*和?不跨越/，**可以匹配任意层级
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', b'/', rest @ ..] => {
+            glob_match(rest, text)
+                || text
+                    .iter()
+                    .enumerate()
+                    .any(|(i, c)| *c == b'/' && glob_match(rest, &text[i + 1..]))
+        }
+        [b'*', b'*', rest @ ..] => (0..=text.len()).any(|i| glob_match(rest, &text[i..])),
+        [b'*', rest @ ..] => {
+            let segment_len = text.iter().position(|c| *c == b'/').unwrap_or(text.len());
+            (0..=segment_len).any(|i| glob_match(rest, &text[i..]))
+        }
+        [b'?', rest @ ..] => match text {
+            [c, text_rest @ ..] if *c != b'/' => glob_match(rest, text_rest),
+            _ => false,
+        },
+        [b'[', rest @ ..] => match (text, rest.iter().position(|c| *c == b']')) {
+            ([c, text_rest @ ..], Some(end)) if end > 0 => {
+                let (class, is_negated) = match rest[..end].strip_prefix(b"!") {
+                    Some(class) => (class, true),
+                    None => (&rest[..end], false),
+                };
+                let mut is_matched = false;
+                let mut i = 0;
+                while i < class.len() {
+                    if i + 2 < class.len() && class[i + 1] == b'-' {
+                        is_matched |= (class[i]..=class[i + 2]).contains(c);
+                        i += 3;
+                    } else {
+                        is_matched |= class[i] == *c;
+                        i += 1;
+                    }
+                }
+                is_matched != is_negated && glob_match(&rest[end + 1..], text_rest)
+            }
+            // 没有闭合的[按普通字符处理
+            ([c, text_rest @ ..], _) if *c == b'[' => glob_match(rest, text_rest),
+            _ => false,
+        },
+        [b'\\', escaped, rest @ ..] => match text {
+            [c, text_rest @ ..] if c == escaped => glob_match(rest, text_rest),
+            _ => false,
+        },
+        [p, rest @ ..] => match text {
+            [c, text_rest @ ..] if c == p => glob_match(rest, text_rest),
+            _ => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ignore_rules() {
+        let root = Path::new("/notes");
+        let mut rules = IgnoreRules::default();
+        rules.extend_from_str(root, "# 注释\n*.tmp.md\nbuild/\n/drafts\ndocs/**/private\n!keep.tmp.md\n");
+        assert!(rules.is_ignored(&root.join("a/b.tmp.md"), false));
+        assert!(!rules.is_ignored(&root.join("a/keep.tmp.md"), false));
+        assert!(rules.is_ignored(&root.join("a/build"), true));
+        assert!(!rules.is_ignored(&root.join("a/build"), false));
+        assert!(rules.is_ignored(&root.join("drafts"), true));
+        assert!(!rules.is_ignored(&root.join("a/drafts"), true));
+        assert!(rules.is_ignored(&root.join("docs/private"), true));
+        assert!(rules.is_ignored(&root.join("docs/x/y/private"), true));
+        assert!(!rules.is_ignored(&root.join("other/private"), true));
+    }
+}
@@ -0,0 +1,289 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    common::{md_link, *},
+    file_panel::{
+        operation::{self, AssetKind, ScanOptions},
+        outline,
+    },
+};
+use tracing::{info, warn};
+
+// 检查工作区中所有文档的链接、图片和标题锚点，地址的解析规则和预览相同
+pub async fn check_workspace_links(
+    roots: Vec<(PathBuf, ScanOptions)>,
+) -> Result<Vec<FileLinkIssues>, AppError> {
+    let root_paths = roots
+        .iter()
+        .map(|(root_path, _)| root_path.clone())
+        .collect::<Vec<_>>();
+    let mut files = vec![];
+    let mut contents = HashMap::new();
+    for (root_path, mut options) in roots {
+        let editable_extensions = Arc::clone(&options.editable_extensions);
+        // 链接也可能指向图片和其他附件，一并收集用于判断和给出建议
+        options.editable_extensions = editable_extensions
+            .iter()
+            .cloned()
+            .chain(
+                [
+                    &IMAGE_EXTENSIONS[..],
+                    &PDF_EXTENSIONS[..],
+                    &AUDIO_EXTENSIONS[..],
+                    &VIDEO_EXTENSIONS[..],
+                ]
+                .concat()
+                .into_iter()
+                .map(str::to_string),
+            )
+            .collect();
+        for path in operation::collect_workspace_files(root_path, options).await? {
+            if operation::is_editable_path(&path, &editable_extensions) {
+                match tokio::fs::read_to_string(&path).await {
+                    Ok(content) => {
+                        contents.insert(path.clone(), Arc::new(content));
+                    }
+                    Err(error) => warn!(
+                        "[FileTree-CheckLinks]:{} 读取失败:{}",
+                        path.display(),
+                        error
+                    ),
+                }
+            }
+            files.push(path);
+        }
+    }
+    info!(
+        "[FileTree-CheckLinks]:开始检查 {} 个文档中的链接",
+        contents.len()
+    );
+    let report =
+        tokio::task::spawn_blocking(move || find_link_issues(&root_paths, &files, &contents))
+            .await?;
+    Ok(report)
+}
+
+pub fn find_link_issues(
+    root_paths: &[PathBuf],
+    files: &[PathBuf],
+    contents: &HashMap<PathBuf, Arc<String>>,
+) -> Vec<FileLinkIssues> {
+    let existing = files.iter().map(PathBuf::as_path).collect::<HashSet<_>>();
+    let images = files
+        .iter()
+        .filter(|path| AssetKind::from_path(path) == Some(AssetKind::Image))
+        .cloned()
+        .collect::<Vec<_>>();
+    // 各文档的标题，工作区外的文档需要时再读取
+    let mut heading_cache: HashMap<PathBuf, Vec<String>> = contents
+        .iter()
+        .map(|(path, content)| (path.clone(), heading_titles(content)))
+        .collect();
+    let mut paths = contents.keys().collect::<Vec<_>>();
+    paths.sort();
+    let mut report = vec![];
+    for path in paths {
+        let content = &contents[path];
+        let mut issues = vec![];
+        for link in md_link::parse_md_links(content) {
+            let dest = link.dest.trim();
+            if dest.is_empty() || md_link::is_external(dest) {
+                continue;
+            }
+            let line = content[..link.range.start].matches('\n').count();
+            let is_angle_bracketed =
+                link.dest_range.start > 0 && content.as_bytes()[link.dest_range.start - 1] == b'<';
+            let (dest_path, anchor) = md_link::split_anchor(dest);
+            let anchor = anchor.filter(|anchor| !anchor.is_empty());
+            let target = match dest_path.is_empty() {
+                true => path.clone(),
+                false => match md_link::resolve_relative(path, dest) {
+                    Some(target) => target,
+                    None => continue,
+                },
+            };
+            if !existing.contains(target.as_path()) && !target.exists() {
+                let candidates = match link.is_image {
+                    true => &images,
+                    false => files,
+                };
+                let suggestion = suggest_path(&target, candidates).map(|candidate| {
+                    let parent = path.parent().unwrap_or(Path::new(""));
+                    let relative = md_link::relative_path(parent, candidate);
+                    let mut suggestion = md_link::format_dest(&relative, is_angle_bracketed);
+                    if let Some(anchor) = anchor {
+                        suggestion.push('#');
+                        suggestion.push_str(anchor);
+                    }
+                    suggestion
+                });
+                issues.push(LinkIssue {
+                    kind: match link.is_image {
+                        true => LinkIssueKind::MissingImage,
+                        false => LinkIssueKind::BrokenLink,
+                    },
+                    line,
+                    dest_range: link.dest_range,
+                    dest: dest.to_string(),
+                    suggestion,
+                });
+                continue;
+            }
+            let Some(anchor) = anchor else {
+                continue;
+            };
+            if link.is_image || AssetKind::from_path(&target).is_some() {
+                continue;
+            }
+            let titles = heading_cache.entry(target.clone()).or_insert_with(|| {
+                std::fs::read_to_string(&target)
+                    .map(|content| heading_titles(&content))
+                    .unwrap_or_default()
+            });
+            if titles
+                .iter()
+                .any(|title| md_link::is_heading_match(title, anchor))
+            {
+                continue;
+            }
+            let suggestion = suggest_anchor(anchor, titles)
+                .map(|heading_anchor| format!("{}#{}", dest_path, heading_anchor));
+            issues.push(LinkIssue {
+                kind: LinkIssueKind::UnknownAnchor,
+                line,
+                dest_range: link.dest_range,
+                dest: dest.to_string(),
+                suggestion,
+            });
+        }
+        if !issues.is_empty() {
+            report.push(FileLinkIssues {
+                path: path.clone(),
+                relative_path: relative_display_path(root_paths, path),
+                content: Arc::clone(content),
+                issues,
+            });
+        }
+    }
+    report
+}
+
+fn heading_titles(content: &str) -> Vec<String> {
+    outline::parse_headings(content)
+        .into_iter()
+        .map(|heading| heading.title)
+        .collect()
+}
+
+// 文件名最接近的文件，文件名相同时（通常是被移动了）取路径最接近的
+fn suggest_path<'a>(target: &Path, candidates: &'a [PathBuf]) -> Option<&'a PathBuf> {
+    let target_name = file_name_lowercase(target);
+    let target_text = target.to_string_lossy();
+    candidates
+        .iter()
+        .map(|candidate| {
+            let name_distance = edit_distance(&target_name, &file_name_lowercase(candidate));
+            let path_distance = edit_distance(&target_text, &candidate.to_string_lossy());
+            ((name_distance, path_distance), candidate)
+        })
+        .filter(|((name_distance, _), _)| {
+            *name_distance <= (target_name.chars().count() / 3).max(1)
+        })
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn suggest_anchor(anchor: &str, titles: &[String]) -> Option<String> {
+    let anchor = md_link::percent_decode(anchor).to_lowercase();
+    titles
+        .iter()
+        .map(|title| md_link::heading_anchor(title))
+        .map(|heading_anchor| (edit_distance(&anchor, &heading_anchor), heading_anchor))
+        .filter(|(distance, _)| *distance <= (anchor.chars().count() / 2).max(2))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, heading_anchor)| heading_anchor)
+}
+
+fn file_name_lowercase(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+// 按字符计算的编辑距离
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = (previous + usize::from(a_char != *b_char))
+                .min(row[j] + 1)
+                .min(current + 1);
+            previous = current;
+        }
+    }
+    row[b.len()]
+}
+
+// 多个工作区时保留工作区名称以便区分
+pub fn relative_display_path(root_paths: &[PathBuf], path: &Path) -> String {
+    root_paths
+        .iter()
+        .find(|root_path| path.starts_with(root_path))
+        .and_then(|root_path| match root_paths.len() {
+            1 => path.strip_prefix(root_path).ok(),
+            _ => path
+                .strip_prefix(root_path.parent().unwrap_or(root_path))
+                .ok(),
+        })
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_issues() {
+        let root = PathBuf::from("/notes-link-check");
+        let note = root.join("日记/今天.md");
+        let report_path = root.join("项目/周报.md");
+        let content = "[周报](../周报.md#本周)\n[周报](../项目/周报.md#下周计化)\n![图](<img/a b.png>)\n[锚点](#不存在)";
+        let files = vec![note.clone(), report_path.clone(), root.join("图片/a b.png")];
+        let contents = HashMap::from([
+            (note.clone(), Arc::new(content.to_string())),
+            (
+                report_path.clone(),
+                Arc::new("# 本周\n## 下周计划".to_string()),
+            ),
+        ]);
+        let report = find_link_issues(&[root], &files, &contents);
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].relative_path, "日记/今天.md");
+        let issues = &report[0].issues;
+        assert_eq!(issues.len(), 4);
+        assert_eq!(issues[0].kind, LinkIssueKind::BrokenLink);
+        assert_eq!(
+            issues[0].suggestion.as_deref(),
+            Some("../项目/周报.md#本周")
+        );
+        assert_eq!(issues[1].kind, LinkIssueKind::UnknownAnchor);
+        assert_eq!(
+            issues[1].suggestion.as_deref(),
+            Some("../项目/周报.md#下周计划")
+        );
+        assert_eq!(issues[2].kind, LinkIssueKind::MissingImage);
+        assert_eq!(issues[2].suggestion.as_deref(), Some("../图片/a b.png"));
+        assert_eq!(&content[issues[2].dest_range.clone()], "img/a b.png");
+        assert_eq!(issues[3].suggestion, None);
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use crate::common::{
+    front_matter::FrontMatter,
+    md_link, tag,
+    wiki_link::{self, NoteIndex},
+};
+use tracing::warn;
+
+// 工作区中各文档的出链、标签和标题，保存文件或文件树变动时只重新读取修改时间变化的文档
+// 不保留文档内容，反向链接的摘要和更新链接时再从磁盘读取
+#[derive(Debug, Clone, Default)]
+pub struct LinkIndex {
+    files: HashMap<PathBuf, IndexedFile>,
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexedFile {
+    modified: Option<SystemTime>,
+    pub links: Vec<FileLink>,
+    pub tags: Vec<String>,
+    // front matter中的title字段
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileLink {
+    pub target: LinkTarget,
+    // 链接所在行和在该行中的字节范围，行号从0开始
+    pub line: usize,
+    pub range: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkTarget {
+    // [[笔记]]，使用时再按笔记索引解析
+    Note(String),
+    // 相对路径的markdown链接，已转为绝对路径
+    Path(PathBuf),
+}
+
+impl LinkTarget {
+    pub fn resolve<'a>(&'a self, note_index: &'a NoteIndex) -> Option<&'a Path> {
+        match self {
+            LinkTarget::Note(note) => note_index.resolve(note),
+            LinkTarget::Path(path) => Some(path),
+        }
+    }
+}
+
+impl IndexedFile {
+    pub fn new(path: &Path, content: &str, modified: Option<SystemTime>) -> Self {
+        let mut links = vec![];
+        let mut fence = None;
+        for (line_index, line) in content.lines().enumerate() {
+            if wiki_link::update_fence(&mut fence, line) {
+                continue;
+            }
+            links.extend(
+                wiki_link::find_wiki_links_in_line(line)
+                    .into_iter()
+                    .map(|link| FileLink {
+                        target: LinkTarget::Note(link.note),
+                        line: line_index,
+                        range: link.range,
+                    }),
+            );
+            links.extend(
+                md_link::find_md_links_in_line(line)
+                    .into_iter()
+                    .filter(|link| !link.is_image)
+                    .filter_map(|link| {
+                        md_link::resolve_relative(path, &link.dest).map(|target| FileLink {
+                            target: LinkTarget::Path(target),
+                            line: line_index,
+                            range: link.range,
+                        })
+                    }),
+            );
+        }
+        links.sort_by_key(|link| (link.line, link.range.start));
+        let tags = tag::parse_tags(content);
+        let title = FrontMatter::parse(content)
+            .and_then(|front_matter| front_matter.title().map(str::to_string));
+        Self {
+            modified,
+            links,
+            tags,
+            title,
+        }
+    }
+}
+
+impl LinkIndex {
+    pub fn insert(&mut self, path: PathBuf, file: IndexedFile) {
+        self.files.insert(path, file);
+    }
+
+    // 删除该路径本身和其下的所有文档，用于文件树中的增删、重命名和移动
+    pub fn remove_under(&mut self, path: &Path) {
+        self.files
+            .retain(|file_path, _| !file_path.starts_with(path));
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.files.contains_key(path)
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = (&PathBuf, &IndexedFile)> {
+        self.files.iter()
+    }
+
+    pub fn title(&self, path: &Path) -> Option<&str> {
+        self.files.get(path)?.title.as_deref()
+    }
+
+    // 各标签的笔记数，不区分大小写，按数量从多到少排列
+    pub fn tag_counts(&self) -> Vec<(String, usize)> {
+        let mut counts: HashMap<String, (String, usize)> = HashMap::new();
+        for file in self.files.values() {
+            for tag in &file.tags {
+                counts
+                    .entry(tag.to_lowercase())
+                    .or_insert_with(|| (tag.clone(), 0))
+                    .1 += 1;
+            }
+        }
+        let mut counts = counts.into_values().collect::<Vec<_>>();
+        counts.sort_by(|(a_tag, a_count), (b_tag, b_count)| {
+            b_count
+                .cmp(a_count)
+                .then_with(|| a_tag.to_lowercase().cmp(&b_tag.to_lowercase()))
+        });
+        counts
+    }
+
+    pub fn files_with_tag(&self, filter: &str) -> HashSet<PathBuf> {
+        self.files
+            .iter()
+            .filter(|(_, file)| file.tags.iter().any(|tag| tag::is_tag_match(tag, filter)))
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+}
+
+// 按新的文件列表更新索引，修改时间没变的文档沿用上一次的结果
+pub async fn update_link_index(files: Vec<PathBuf>, previous: Arc<LinkIndex>) -> LinkIndex {
+    let mut link_index = LinkIndex::default();
+    for path in files {
+        let modified = tokio::fs::metadata(&path)
+            .await
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if let Some(file) = previous.files.get(&path)
+            && file.modified.is_some()
+            && file.modified == modified
+        {
+            link_index.insert(path, file.clone());
+            continue;
+        }
+        match tokio::fs::read_to_string(&path).await {
+            Ok(content) => {
+                let file = IndexedFile::new(&path, &content, modified);
+                link_index.insert(path, file);
+            }
+            Err(error) => warn!(
+                "[FileTree-UpdateLinkIndex]:{} 读取失败:{}",
+                path.display(),
+                error
+            ),
+        }
+    }
+    link_index
+}
+
+// 读取并解析文件树变动涉及的文档，读取失败的跳过
+pub async fn index_files(files: Vec<PathBuf>) -> Vec<(PathBuf, IndexedFile)> {
+    let mut indexed_files = vec![];
+    for path in files {
+        let modified = tokio::fs::metadata(&path)
+            .await
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        match tokio::fs::read_to_string(&path).await {
+            Ok(content) => {
+                let file = IndexedFile::new(&path, &content, modified);
+                indexed_files.push((path, file));
+            }
+            Err(error) => warn!(
+                "[FileTree-IndexFiles]:{} 读取失败:{}",
+                path.display(),
+                error
+            ),
+        }
+    }
+    indexed_files
+}
+
+// 保存后直接用保存的内容更新该文档
+pub async fn index_saved_file(path: PathBuf, content: Arc<String>) -> (PathBuf, IndexedFile) {
+    let modified = tokio::fs::metadata(&path)
+        .await
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    let file = IndexedFile::new(&path, &content, modified);
+    (path, file)
+}
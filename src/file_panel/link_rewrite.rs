@@ -0,0 +1,253 @@
+use std::{
+    cmp,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    common::{
+        md_link,
+        wiki_link::{self, NoteIndex},
+        *,
+    },
+    file_panel::link_index::LinkIndex,
+};
+use tracing::error;
+
+// 一次重命名或移动，以及随之更新链接的文件，撤销时整体还原
+#[derive(Debug, Clone)]
+pub struct PathChange {
+    pub old_path: PathBuf,
+    pub new_path: PathBuf,
+    // 替换的路径为重命名或移动之后的路径
+    pub replacements: Vec<FileReplacement>,
+}
+
+impl PathChange {
+    // 撤销时先还原路径，再把各文件的内容换回去
+    pub fn reversed_replacements(&self) -> Vec<FileReplacement> {
+        self.replacements
+            .iter()
+            .map(|replacement| FileReplacement {
+                path: map_path(&replacement.path, &self.new_path, &self.old_path)
+                    .unwrap_or_else(|| replacement.path.clone()),
+                original: Arc::clone(&replacement.replaced),
+                replaced: Arc::clone(&replacement.original),
+            })
+            .collect()
+    }
+}
+
+// 在后台线程中遍历索引并读取文档，索引为重命名之前的快照
+pub async fn plan_link_rewrites(
+    link_index: Arc<LinkIndex>,
+    note_index: Arc<NoteIndex>,
+    old_path: PathBuf,
+    new_path: PathBuf,
+) -> PathChange {
+    let replacements = {
+        let old_path = old_path.clone();
+        let new_path = new_path.clone();
+        tokio::task::spawn_blocking(move || {
+            find_link_rewrites(&link_index, &note_index, &old_path, &new_path, |path| {
+                std::fs::read_to_string(path).ok()
+            })
+        })
+        .await
+        .unwrap_or_else(|error| {
+            error!("[FileTree-PlanLinkRewrites]:{}", error);
+            vec![]
+        })
+    };
+    PathChange {
+        old_path,
+        new_path,
+        replacements,
+    }
+}
+
+// 索引中不保存内容，调用时重命名已经完成，read_content按重命名之后的路径读取
+// 图片链接不在索引的出链中，所以每个文档都要读取
+pub fn find_link_rewrites(
+    link_index: &LinkIndex,
+    note_index: &NoteIndex,
+    old_path: &Path,
+    new_path: &Path,
+    read_content: impl Fn(&Path) -> Option<String>,
+) -> Vec<FileReplacement> {
+    let mut files = link_index.files().map(|(path, _)| path).collect::<Vec<_>>();
+    files.sort();
+    let mut replacements = vec![];
+    for file_path in files {
+        // 被移动的文件夹中的文件，自身的相对链接也要按新位置重新计算
+        let moved_file_path = map_path(file_path, old_path, new_path);
+        let current_path = moved_file_path.as_deref().unwrap_or(file_path);
+        let Some(content) = read_content(current_path) else {
+            continue;
+        };
+        let content = content.as_str();
+        let current_dir = current_path.parent().unwrap_or(Path::new(""));
+        let mut edits: Vec<(Range<usize>, String)> = vec![];
+        for link in md_link::parse_md_links(content) {
+            let dest = link.dest.trim();
+            if dest.is_empty() || md_link::is_external(dest) {
+                continue;
+            }
+            let (dest_path, anchor) = md_link::split_anchor(dest);
+            let Some(target) = md_link::resolve_relative(file_path, dest) else {
+                continue;
+            };
+            let new_target = map_path(&target, old_path, new_path).unwrap_or(target);
+            // 原地址从新位置仍然指向同一个文件时保留原来的写法
+            if md_link::resolve_relative(current_path, dest).as_ref() == Some(&new_target) {
+                continue;
+            }
+            let is_absolute = Path::new(&md_link::percent_decode(dest_path)).is_absolute();
+            let path_text = match is_absolute {
+                true => new_target.to_string_lossy().into_owned(),
+                false => md_link::relative_path(current_dir, &new_target),
+            };
+            let is_angle_bracketed =
+                link.dest_range.start > 0 && content.as_bytes()[link.dest_range.start - 1] == b'<';
+            let mut new_dest = md_link::format_dest(&path_text, is_angle_bracketed);
+            if let Some(anchor) = anchor {
+                new_dest.push('#');
+                new_dest.push_str(anchor);
+            }
+            edits.push((link.dest_range, new_dest));
+        }
+        for link in wiki_link::parse_wiki_links(content) {
+            let Some(new_target) = note_index
+                .resolve(&link.note)
+                .and_then(|target| map_path(target, old_path, new_path))
+            else {
+                continue;
+            };
+            let note = rewrite_note_name(&link.note, &new_target);
+            if note == link.note {
+                continue;
+            }
+            let mut new_link = format!("[[{}", note);
+            if let Some(ref heading) = link.heading {
+                new_link.push('#');
+                new_link.push_str(heading);
+            }
+            if let Some(ref alias) = link.alias {
+                new_link.push('|');
+                new_link.push_str(alias);
+            }
+            new_link.push_str("]]");
+            edits.push((link.range, new_link));
+        }
+        if edits.is_empty() {
+            continue;
+        }
+        // 从后往前替换，前面的字节范围不受影响
+        edits.sort_by_key(|(range, _)| cmp::Reverse(range.start));
+        let mut replaced = content.to_string();
+        for (range, text) in edits {
+            replaced.replace_range(range, &text);
+        }
+        replacements.push(FileReplacement {
+            path: current_path.to_path_buf(),
+            original: Arc::new(content.to_string()),
+            replaced: Arc::new(replaced),
+        });
+    }
+    replacements
+}
+
+// 路径在被重命名或移动的节点之下时，返回新的路径
+pub fn map_path(path: &Path, old_path: &Path, new_path: &Path) -> Option<PathBuf> {
+    let rest = path.strip_prefix(old_path).ok()?;
+    Some(match rest.as_os_str().is_empty() {
+        true => new_path.to_path_buf(),
+        false => new_path.join(rest),
+    })
+}
+
+// 按原来的写法生成新的笔记名，带扩展名或上级文件夹的写法保持不变
+fn rewrite_note_name(note: &str, new_target: &Path) -> String {
+    let (dir, name) = match note.rsplit_once('/') {
+        Some((dir, name)) => (Some(dir), name),
+        None => (None, note),
+    };
+    let has_extension = wiki_link::note_key(name) != name.trim().to_lowercase();
+    let new_name = match has_extension {
+        true => new_target.file_name(),
+        false => new_target.file_stem(),
+    }
+    .map(|name| name.to_string_lossy().into_owned())
+    .unwrap_or_default();
+    let Some(dir) = dir else {
+        return new_name;
+    };
+    let dir_depth = dir.split('/').filter(|part| !part.is_empty()).count();
+    let parents = new_target
+        .parent()
+        .map(|parent| {
+            parent
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let mut parts = parents[parents.len().saturating_sub(dir_depth)..].to_vec();
+    parts.push(new_name);
+    let prefix = match dir.starts_with('/') {
+        true => "/",
+        false => "",
+    };
+    format!("{}{}", prefix, parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_panel::link_index::IndexedFile;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_link_rewrites() {
+        let root = PathBuf::from("/notes-link-rewrite");
+        let old_path = root.join("项目/周报.md");
+        let new_path = root.join("归档/2024/周报.md");
+        let source = root.join("日记/今天.md");
+        let source_content = "[周报](../项目/周报.md#本周)\n[[周报|上周]]\n[[项目/周报]]\n```\n[周报](../项目/周报.md)\n```";
+        let report_content = "![图](<../图片/a b.png>)\n[今天](../日记/今天.md)";
+        let mut link_index = LinkIndex::default();
+        for (path, content) in [(&source, source_content), (&old_path, report_content)] {
+            link_index.insert(path.clone(), IndexedFile::new(path, content, None));
+        }
+        // 重命名已经完成，磁盘上的周报在新路径下
+        let contents = HashMap::from([
+            (source.clone(), source_content),
+            (new_path.clone(), report_content),
+        ]);
+        let note_index = NoteIndex::new([source.clone(), old_path.clone()]);
+        let replacements =
+            find_link_rewrites(&link_index, &note_index, &old_path, &new_path, |path| {
+                contents.get(path).map(|content| content.to_string())
+            });
+        assert_eq!(replacements.len(), 2);
+        assert_eq!(replacements[1].path, new_path);
+        assert_eq!(
+            *replacements[1].replaced,
+            "![图](<../../图片/a b.png>)\n[今天](../../日记/今天.md)"
+        );
+        assert_eq!(replacements[0].path, source);
+        assert_eq!(
+            *replacements[0].replaced,
+            "[周报](../归档/2024/周报.md#本周)\n[[周报|上周]]\n[[2024/周报]]\n```\n[周报](../项目/周报.md)\n```"
+        );
+        let change = PathChange {
+            old_path,
+            new_path,
+            replacements,
+        };
+        let reversed = change.reversed_replacements();
+        assert_eq!(reversed[1].path, change.old_path);
+        assert_eq!(*reversed[1].replaced, report_content);
+    }
+}
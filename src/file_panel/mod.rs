@@ -1,12 +1,27 @@
-use std::path::PathBuf;
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::Arc,
+};
 
 use crate::{
-    common::*,
+    common::{
+        link_graph::LinkGraph,
+        template::{NewFileRequest, Template},
+        wiki_link::NoteIndex,
+        *,
+    },
     file_panel::{
+        backlinks::{Backlinks, BacklinksMessage},
         file_tree::{FileTree, FileTreeMessage},
         operation::{FileNode, IsAutoSave, MdFile, NodeContent},
+        outline::{Outline, OutlineMessage},
+        path_history::PathHistoryMessage,
+        tags::{TagList, TagListMessage},
     },
+    setting::WorkspaceSession,
 };
+use jiff::civil::Date;
 use iced::{
     Alignment,
     border::Radius,
@@ -14,17 +29,30 @@ use iced::{
     widget::{mouse_area, row},
 };
 use iced::{
-    Background, Length, Padding, Task, Theme,
+    Background, Element, Length, Padding, Subscription, Task, Theme,
     widget::{Container, column, container, rule, text},
 };
 use tracing::info;
+mod backlinks; // 链接到当前文档的其他文档和未链接的提及
 mod file_tree;
+mod ignore; // 读取工作区时的.gitignore/.fuguignore忽略规则
+mod link_check; // 检查工作区中失效的链接、图片和标题锚点
+mod link_index; // 工作区中各文档的出链索引，增量更新
+mod link_rewrite; // 重命名或移动文件后更新指向它的链接
+mod note_indexer; // 笔记名称索引和出链索引的增量更新
 mod operation; // 各种文件操作，新建、删除、重命名、移动等
-mod outline;
+mod outline; // 根据编辑器内容生成的标题大纲
+mod path_history; // 确认重命名后的链接更新，撤销最近一次重命名或移动
+mod quick_open; // Ctrl+P快速打开文件
+mod tags; // 工作区中的标签列表
+mod workspace_scan; // 首次读取工作区的排队和进度，以及已打开工作区的定时扫描
 
 #[derive(Debug)]
 pub struct FilePanel {
     file_tree: FileTree,
+    outline: Outline,
+    backlinks: Backlinks,
+    tags: TagList,
     mode: Mode,
 }
 
@@ -32,12 +60,16 @@ pub struct FilePanel {
 pub enum Mode {
     FileTree,
     Content,
+    Backlinks,
+    Tags,
 }
 
 #[derive(Debug, Clone)]
 pub enum FilePanelMessage {
     OpenMdFolder,
     OpenFile,
+    OpenWorkspaceFile,
+    SaveWorkspaceFile,
     CreateNewFile,
     AutoSave(FileData),
     Save(FileData),
@@ -49,18 +81,80 @@ pub enum FilePanelMessage {
     SendFileDataToEditor(FileData),
     AskIsLoadPermitted,
     LoadPermitted,
+    LoadCanceled,
     SendImgDataToPreview(Vec<ImgData>),
+    SendRestoredImgDataToPreview(Vec<ImgData>),
+    RestoreSession(WorkspaceSession),
+    // 最近打开列表
+    OpenRecent(PathBuf, RecentKind),
+    SendRecordRecent(PathBuf, RecentKind),
     SendImgBasePathToPreview(PathBuf),
     SendImgCodeToEditor(String),
     FileTree(FileTreeMessage),
     GetImgIdFromPreview(u32),
     HandleError(AppError),
+    SaveAllBeforeExit(Vec<UnsavedDoc>),
+    ReturnSaveAllResult(Result<(), AppError>),
+    UnloadFileFromEditor(u32),
+    SendContextMenuItems(Vec<ContextMenuItem>),
+    HandleContextAction(ContextAction),
+    // 扫描设置改变后重新读取已展开的文件夹
+    RescanWorkspace,
+    // 全文搜索和替换
+    CollectSearchFiles,
+    SendSearchFiles(Result<SearchTargets, AppError>),
+    ApplyReplacements(Vec<FileReplacement>),
+    SendReplacementToEditor(FileReplacement),
+    SendReplaceResult(usize, usize),
+    CheckLinks,
+    SendLinkReport(Result<Vec<FileLinkIssues>, AppError>),
+    OpenFileAtLine(PathBuf, usize),
+    SendCursorLineToEditor(usize),
+    SendDocViewToEditor(DocView),
+    // 大纲相关消息
+    Outline(OutlineMessage),
+    SyncOutlineWithEditor(Arc<String>),
+    SendPreviewScrollToLine(usize),
+    SendMovedContentToEditor(Arc<String>, Arc<String>, usize),
+    // 笔记之间的[[链接]]
+    SendNoteIndexToPreview(Arc<NoteIndex>),
+    OpenWikiLink(String, Option<String>),
+    CreateWikiNote(String),
+    // 预览中点击的链接
+    OpenLinkedPath(PathBuf, Option<String>),
+    FindAnchor(String),
+    // 重命名或移动后更新链接，以及撤销
+    SendConfirmLinkRewrites(Vec<String>),
+    ResolveLinkRewrites(bool),
+    UndoPathChange,
+    // 反向链接面板
+    Backlinks(BacklinksMessage),
+    RefreshBacklinks,
+    // 标签列表和按标签过滤
+    Tags(TagListMessage),
+    RefreshTags,
+    SendTagsToEditor(Arc<Vec<String>>),
+    // 选择模板新建文件
+    SendOpenNewFileDialog(u32, Vec<Template>),
+    CreateFileFromTemplate(NewFileRequest),
+    // 每日笔记
+    OpenDailyNote(Date),
+    OpenAdjacentDailyNote(i32),
+    RefreshJournalDates,
+    SendJournalDatesToPreview(Arc<HashSet<Date>>),
+    // 预览中的链接关系图
+    RefreshLinkGraph,
+    SendLinkGraphToPreview(Arc<LinkGraph>, Option<PathBuf>),
+    SendLinkGraphCurrentToPreview(Option<PathBuf>),
 }
 
 impl FilePanel {
     pub fn new() -> Self {
         Self {
             file_tree: FileTree::new(),
+            outline: Outline::default(),
+            backlinks: Backlinks::default(),
+            tags: TagList::default(),
             mode: Mode::FileTree,
         }
     }
@@ -73,22 +167,91 @@ impl FilePanel {
         match file_panel_message {
             FilePanelMessage::ChangeMode(mode) => {
                 self.mode = mode;
-                Task::none()
+                Task::done(FilePanelMessage::RefreshBacklinks)
+            }
+            // 只在面板显示时重新统计，索引本身已经在文件树中增量更新
+            FilePanelMessage::RefreshBacklinks => {
+                if self.mode != Mode::Backlinks {
+                    return Task::none();
+                }
+                match self.file_tree.selected_md_path() {
+                    Some(path) => Task::perform(
+                        backlinks::collect_backlinks(
+                            self.file_tree.link_index(),
+                            self.file_tree.note_index(),
+                            path.clone(),
+                        ),
+                        move |result| {
+                            FilePanelMessage::Backlinks(BacklinksMessage::BacklinksLoaded(
+                                path.clone(),
+                                result,
+                            ))
+                        },
+                    ),
+                    None => Task::done(FilePanelMessage::Backlinks(BacklinksMessage::Clear)),
+                }
             }
+            FilePanelMessage::Backlinks(backlinks_message) => match backlinks_message {
+                BacklinksMessage::SendOpenFileAtLine(path, line) => {
+                    Task::done(FilePanelMessage::OpenFileAtLine(path, line))
+                }
+                _ => self
+                    .backlinks
+                    .update(backlinks_message)
+                    .map(FilePanelMessage::Backlinks),
+            },
+            // 标签从文件树的链接索引中统计，同时作为编辑器自动补全的候选
+            FilePanelMessage::RefreshTags => {
+                let task = self
+                    .tags
+                    .update(TagListMessage::TagsLoaded(
+                        self.file_tree.link_index().tag_counts(),
+                    ))
+                    .map(FilePanelMessage::Tags);
+                Task::batch([
+                    task,
+                    Task::done(FilePanelMessage::SendTagsToEditor(Arc::new(
+                        self.tags.tag_names(),
+                    ))),
+                ])
+            }
+            FilePanelMessage::Tags(tags_message) => match tags_message {
+                // 选中标签后切换到文件树查看过滤结果
+                TagListMessage::SendFilterByTag(tag) => {
+                    if tag.is_some() {
+                        self.mode = Mode::FileTree;
+                    }
+                    Task::done(FilePanelMessage::FileTree(FileTreeMessage::FilterByTag(tag)))
+                }
+                _ => self.tags.update(tags_message).map(FilePanelMessage::Tags),
+            },
             // 转发给文件树模块
             FilePanelMessage::LoadPermitted => Task::done(
                 FilePanelMessage::FileTree(FileTreeMessage::LoadPermitted),
             ),
+            FilePanelMessage::LoadCanceled => Task::done(
+                FilePanelMessage::FileTree(FileTreeMessage::LoadCanceled),
+            ),
             FilePanelMessage::FileTree(file_tree_message) => match file_tree_message {
                 FileTreeMessage::ReturnSaveResult(result) => {
                     Task::done(FilePanelMessage::ReturnSaveResult(result))
                 }
-                FileTreeMessage::SendFileDataToEditor(file_data) => {
-                    Task::done(FilePanelMessage::SendFileDataToEditor(file_data))
-                }
+                FileTreeMessage::SendFileDataToEditor(file_data) => Task::batch([
+                    Task::done(FilePanelMessage::SendFileDataToEditor(file_data)),
+                    Task::done(FilePanelMessage::RefreshBacklinks),
+                    Task::done(FilePanelMessage::SendLinkGraphCurrentToPreview(
+                        self.file_tree.selected_md_path(),
+                    )),
+                ]),
                 FileTreeMessage::SendImgDataToPreview(image_data) => {
                     Task::done(FilePanelMessage::SendImgDataToPreview(image_data))
                 }
+                FileTreeMessage::SendRestoredImgDataToPreview(image_data) => {
+                    Task::done(FilePanelMessage::SendRestoredImgDataToPreview(image_data))
+                }
+                FileTreeMessage::SendRecordRecent(path, kind) => {
+                    Task::done(FilePanelMessage::SendRecordRecent(path, kind))
+                }
                 FileTreeMessage::SendImgBasePathToPreview(path) => {
                     Task::done(FilePanelMessage::SendImgBasePathToPreview(path))
                 }
@@ -98,11 +261,77 @@ impl FilePanel {
                 FileTreeMessage::AskIsLoadPermitted => {
                     Task::done(FilePanelMessage::AskIsLoadPermitted)
                 }
+                FileTreeMessage::ReturnSaveAllResult(result) => {
+                    Task::done(FilePanelMessage::ReturnSaveAllResult(result))
+                }
+                FileTreeMessage::UnloadFileFromEditor(id) => {
+                    Task::done(FilePanelMessage::UnloadFileFromEditor(id))
+                }
+                FileTreeMessage::SendContextMenuItems(items) => {
+                    Task::done(FilePanelMessage::SendContextMenuItems(items))
+                }
+                FileTreeMessage::SendSearchFiles(result) => {
+                    Task::done(FilePanelMessage::SendSearchFiles(result))
+                }
+                FileTreeMessage::SendReplacementToEditor(replacement) => {
+                    Task::done(FilePanelMessage::SendReplacementToEditor(replacement))
+                }
+                FileTreeMessage::SendReplaceResult(succeeded_count, failed_count) => Task::done(
+                    FilePanelMessage::SendReplaceResult(succeeded_count, failed_count),
+                ),
+                FileTreeMessage::SendLinkReport(result) => {
+                    Task::done(FilePanelMessage::SendLinkReport(result))
+                }
+                FileTreeMessage::SendOpenNewFileDialog(key, templates) => {
+                    Task::done(FilePanelMessage::SendOpenNewFileDialog(key, templates))
+                }
+                FileTreeMessage::SendConfirmLinkRewrites(files) => {
+                    Task::done(FilePanelMessage::SendConfirmLinkRewrites(files))
+                }
+                FileTreeMessage::SendCursorLineToEditor(line) => {
+                    Task::done(FilePanelMessage::SendCursorLineToEditor(line))
+                }
+                FileTreeMessage::SendDocViewToEditor(view) => {
+                    Task::done(FilePanelMessage::SendDocViewToEditor(view))
+                }
+                FileTreeMessage::SendNoteIndexToPreview(note_index) => Task::batch([
+                    Task::done(FilePanelMessage::SendNoteIndexToPreview(note_index)),
+                    Task::done(FilePanelMessage::RefreshBacklinks),
+                    Task::done(FilePanelMessage::RefreshTags),
+                    Task::done(FilePanelMessage::RefreshJournalDates),
+                    Task::done(FilePanelMessage::RefreshLinkGraph),
+                ]),
+                FileTreeMessage::SendLinkIndexChanged => Task::batch([
+                    Task::done(FilePanelMessage::RefreshBacklinks),
+                    Task::done(FilePanelMessage::RefreshTags),
+                    Task::done(FilePanelMessage::RefreshJournalDates),
+                    Task::done(FilePanelMessage::RefreshLinkGraph),
+                ]),
                 _ => self
                     .file_tree
                     .update(file_tree_message, setting)
                     .map(FilePanelMessage::FileTree),
             },
+            FilePanelMessage::Outline(outline_message) => match outline_message {
+                // 点击标题同时移动编辑器光标和滚动预览
+                OutlineMessage::SendJumpToLine(line) => Task::batch([
+                    Task::done(FilePanelMessage::SendCursorLineToEditor(line)),
+                    Task::done(FilePanelMessage::SendPreviewScrollToLine(line)),
+                ]),
+                OutlineMessage::SendPreviewScrollToLine(line) => {
+                    Task::done(FilePanelMessage::SendPreviewScrollToLine(line))
+                }
+                OutlineMessage::SendMovedContent(original, moved, line) => Task::done(
+                    FilePanelMessage::SendMovedContentToEditor(original, moved, line),
+                ),
+                _ => self
+                    .outline
+                    .update(outline_message)
+                    .map(FilePanelMessage::Outline),
+            },
+            FilePanelMessage::SyncOutlineWithEditor(raw) => Task::done(FilePanelMessage::Outline(
+                OutlineMessage::SyncContent(raw),
+            )),
             FilePanelMessage::OpenMdFolder => {
                 Task::perform(operation::open_folder_dialog(), |result| match result {
                     Some(path) => FilePanelMessage::FileTree(FileTreeMessage::FetchFileTree(path)),
@@ -111,6 +340,26 @@ impl FilePanel {
                     )),
                 })
             }
+            FilePanelMessage::OpenWorkspaceFile => {
+                Task::perform(operation::open_workspace_file_dialog(), |result| match result {
+                    Some(path) => {
+                        FilePanelMessage::FileTree(FileTreeMessage::LoadWorkspaceFile(path))
+                    }
+                    None => FilePanelMessage::HandleError(AppError::FilePanelError(
+                        "文件路径为空!".to_string(),
+                    )),
+                })
+            }
+            FilePanelMessage::SaveWorkspaceFile => {
+                Task::perform(operation::save_workspace_file_dialog(), |result| match result {
+                    Some(path) => {
+                        FilePanelMessage::FileTree(FileTreeMessage::SaveWorkspaceFile(path))
+                    }
+                    None => FilePanelMessage::HandleError(AppError::FilePanelError(
+                        "文件路径为空!".to_string(),
+                    )),
+                })
+            }
             FilePanelMessage::OpenFile => {
                 Task::perform(operation::open_md_file_dialog(), |result| match result {
                     Some(path) => {
@@ -141,19 +390,29 @@ impl FilePanel {
                     )),
                 })
             }
-            FilePanelMessage::CreateNewFile => {
-                let new_file = FileNode::new(
-                    "新建文件.md".to_string(),
-                    NodeContent::Markdown(MdFile {
-                        path: None,
-                        version: 0,
-                        cache: None,
-                    }),
-                );
-                Task::done(FilePanelMessage::FileTree(
-                    FileTreeMessage::InsertToFileTree(new_file),
-                ))
-            }
+            // 和文件树工具栏的新建一样先选择模板，还没有临时工作区时直接新建临时文件
+            FilePanelMessage::CreateNewFile => match self.file_tree.new_file_dir_key() {
+                Some(key) => Task::done(FilePanelMessage::FileTree(
+                    FileTreeMessage::CreateNewFileInDir(key),
+                )),
+                None => {
+                    let new_file = FileNode::new(
+                        "新建文件.md".to_string(),
+                        NodeContent::Markdown(MdFile {
+                            path: None,
+                            version: 0,
+                            saved_version: 0,
+                            cache: None,
+                        }),
+                    );
+                    Task::done(FilePanelMessage::FileTree(
+                        FileTreeMessage::InsertToFileTree(new_file),
+                    ))
+                }
+            },
+            FilePanelMessage::CreateFileFromTemplate(request) => Task::done(
+                FilePanelMessage::FileTree(FileTreeMessage::CreateFileFromTemplate(request)),
+            ),
             FilePanelMessage::AutoSave(file_data) => Task::done(FilePanelMessage::FileTree(
                 FileTreeMessage::UpdateNodeInfo(IsAutoSave(true), file_data),
             )),
@@ -163,6 +422,79 @@ impl FilePanel {
             FilePanelMessage::SaveAs(file_data) => Task::done(FilePanelMessage::FileTree(
                 FileTreeMessage::SaveAs(file_data),
             )),
+            FilePanelMessage::HandleContextAction(action) => Task::done(
+                FilePanelMessage::FileTree(FileTreeMessage::HandleContextAction(action)),
+            ),
+            FilePanelMessage::SaveAllBeforeExit(docs) => Task::done(FilePanelMessage::FileTree(
+                FileTreeMessage::SaveUnsavedDocs(docs),
+            )),
+            FilePanelMessage::RescanWorkspace => Task::done(FilePanelMessage::FileTree(
+                FileTreeMessage::RescanWorkspace,
+            )),
+            FilePanelMessage::CollectSearchFiles => Task::done(FilePanelMessage::FileTree(
+                FileTreeMessage::CollectSearchFiles,
+            )),
+            FilePanelMessage::CheckLinks => {
+                Task::done(FilePanelMessage::FileTree(FileTreeMessage::CheckLinks))
+            }
+            FilePanelMessage::ApplyReplacements(replacements) => Task::done(
+                FilePanelMessage::FileTree(FileTreeMessage::ApplyReplacements(replacements)),
+            ),
+            FilePanelMessage::OpenFileAtLine(path, line) => Task::done(FilePanelMessage::FileTree(
+                FileTreeMessage::OpenPathAtLine(path, line),
+            )),
+            // 和通过对话框打开时走同样的流程
+            FilePanelMessage::OpenRecent(path, kind) => match kind {
+                RecentKind::File => Task::done(FilePanelMessage::FileTree(
+                    FileTreeMessage::FetchMdFileData(path),
+                )),
+                RecentKind::Folder => Task::done(FilePanelMessage::FileTree(
+                    FileTreeMessage::FetchFileTree(path),
+                )),
+            },
+            FilePanelMessage::RestoreSession(session) => Task::done(FilePanelMessage::FileTree(
+                FileTreeMessage::RestoreSession(session),
+            )),
+            FilePanelMessage::OpenWikiLink(note, heading) => Task::done(FilePanelMessage::FileTree(
+                FileTreeMessage::OpenWikiLink(note, heading),
+            )),
+            FilePanelMessage::CreateWikiNote(note) => Task::done(FilePanelMessage::FileTree(
+                FileTreeMessage::CreateWikiNote(note),
+            )),
+            FilePanelMessage::OpenDailyNote(date) => Task::done(FilePanelMessage::FileTree(
+                FileTreeMessage::OpenDailyNote(date),
+            )),
+            FilePanelMessage::OpenAdjacentDailyNote(offset) => Task::done(
+                FilePanelMessage::FileTree(FileTreeMessage::OpenAdjacentDailyNote(offset)),
+            ),
+            // 日记的日期同样从链接索引中读取，在预览的日历中标记
+            FilePanelMessage::RefreshJournalDates => Task::done(
+                FilePanelMessage::SendJournalDatesToPreview(Arc::new(
+                    self.file_tree.journal_dates(setting),
+                )),
+            ),
+            // 关系图同样从链接索引中生成，当前打开的笔记在图中高亮
+            FilePanelMessage::RefreshLinkGraph => Task::done(
+                FilePanelMessage::SendLinkGraphToPreview(
+                    Arc::new(self.file_tree.link_graph(setting.title_as_label)),
+                    self.file_tree.selected_md_path(),
+                ),
+            ),
+            FilePanelMessage::OpenLinkedPath(path, anchor) => Task::done(
+                FilePanelMessage::FileTree(FileTreeMessage::OpenLinkedPath(path, anchor)),
+            ),
+            FilePanelMessage::ResolveLinkRewrites(is_confirmed) => {
+                Task::done(FilePanelMessage::FileTree(FileTreeMessage::PathHistory(
+                    PathHistoryMessage::Resolve(is_confirmed),
+                )))
+            }
+            FilePanelMessage::UndoPathChange => Task::done(FilePanelMessage::FileTree(
+                FileTreeMessage::PathHistory(PathHistoryMessage::Undo),
+            )),
+            // 当前文档的标题由大纲模块维护
+            FilePanelMessage::FindAnchor(anchor) => Task::done(FilePanelMessage::Outline(
+                OutlineMessage::FindAnchor(anchor),
+            )),
             FilePanelMessage::GetImgIdFromPreview(id) => Task::done(FilePanelMessage::FileTree(
                 FileTreeMessage::CopyImgFileData(id),
             )),
@@ -174,10 +506,58 @@ impl FilePanel {
         }
     }
 
-    pub fn view(&self) -> Container<'_, FilePanelMessage> {
+    pub fn subscription(&self) -> Subscription<FilePanelMessage> {
+        self.file_tree.subscription().map(FilePanelMessage::FileTree)
+    }
+
+    pub fn is_quick_open_shown(&self) -> bool {
+        self.file_tree.is_quick_open_shown()
+    }
+
+    pub fn view_quick_open(&self) -> Element<'_, FilePanelMessage> {
+        self.file_tree.view_quick_open().map(FilePanelMessage::FileTree)
+    }
+
+    pub fn session_state(
+        &self,
+        gallery_node_ids: &[u32],
+        doc_views: &HashMap<u32, DocView>,
+    ) -> WorkspaceSession {
+        self.file_tree.session_state(gallery_node_ids, doc_views)
+    }
+
+    pub fn collect_unsaved_docs(&self, editor_file: Option<&FileData>) -> Vec<UnsavedDoc> {
+        self.file_tree.collect_unsaved_docs(editor_file)
+    }
+
+    pub fn view(&self, setting: &AppSetting) -> Container<'_, FilePanelMessage> {
         let panel = match self.mode {
-            Mode::FileTree => self.file_tree.view().map(FilePanelMessage::FileTree),
-            Mode::Content => text("aa").into(),
+            Mode::FileTree => self
+                .file_tree
+                .view(setting.title_as_label)
+                .map(FilePanelMessage::FileTree),
+            Mode::Content => self.outline.view().map(FilePanelMessage::Outline),
+            Mode::Backlinks => self.backlinks.view().map(FilePanelMessage::Backlinks),
+            Mode::Tags => self
+                .tags
+                .view(self.file_tree.tag_filter())
+                .map(FilePanelMessage::Tags),
+        };
+        let tab_rule = |mode: Mode| {
+            rule::horizontal(1).style(move |theme: &Theme| {
+                let ex_palette = theme.extended_palette();
+                let palette = theme.palette();
+                rule::Style {
+                    color: if self.mode == mode {
+                        palette.text
+                    } else {
+                        ex_palette.background.weaker.color
+                    },
+                    radius: Radius::default(),
+                    snap: true,
+                    fill_mode: rule::FillMode::Full,
+                }
+            })
         };
         container(column![
             row![
@@ -197,39 +577,31 @@ impl FilePanel {
                 )
                 .on_press(FilePanelMessage::ChangeMode(Mode::Content))
                 .interaction(mouse::Interaction::Pointer),
+                mouse_area(
+                    text("反链")
+                        .size(FONT_SIZE_BIGGER)
+                        .width(Length::FillPortion(1))
+                        .align_x(Alignment::Center)
+                )
+                .on_press(FilePanelMessage::ChangeMode(Mode::Backlinks))
+                .interaction(mouse::Interaction::Pointer),
+                mouse_area(
+                    text("标签")
+                        .size(FONT_SIZE_BIGGER)
+                        .width(Length::FillPortion(1))
+                        .align_x(Alignment::Center)
+                )
+                .on_press(FilePanelMessage::ChangeMode(Mode::Tags))
+                .interaction(mouse::Interaction::Pointer),
             ]
             .spacing(SPACING_SMALLER)
             .padding(Padding::from([PADDING_SMALLER, PADDING_BIGGER]))
             .height(Length::Shrink),
             row![
-                rule::horizontal(1).style(|theme: &Theme| {
-                    let ex_palette = theme.extended_palette();
-                    let palette = theme.palette();
-                    rule::Style {
-                        color: if self.mode == Mode::FileTree {
-                            palette.text
-                        } else {
-                            ex_palette.background.weaker.color
-                        },
-                        radius: Radius::default(),
-                        snap: true,
-                        fill_mode: rule::FillMode::Full,
-                    }
-                }),
-                rule::horizontal(1).style(|theme: &Theme| {
-                    let ex_palette = theme.extended_palette();
-                    let palette = theme.palette();
-                    rule::Style {
-                        color: if self.mode == Mode::Content {
-                            palette.text
-                        } else {
-                            ex_palette.background.weaker.color
-                        },
-                        radius: Radius::default(),
-                        snap: true,
-                        fill_mode: rule::FillMode::Full,
-                    }
-                })
+                tab_rule(Mode::FileTree),
+                tab_rule(Mode::Content),
+                tab_rule(Mode::Backlinks),
+                tab_rule(Mode::Tags)
             ],
             container(panel).padding(PADDING_BASE).height(Length::Fill),
             container(
@@ -0,0 +1,215 @@
+use std::{collections::BTreeSet, path::PathBuf, sync::Arc};
+
+use crate::{
+    common::wiki_link::NoteIndex,
+    file_panel::{
+        link_index::{self, IndexedFile, LinkIndex},
+        operation::{self, ScanOptions},
+    },
+};
+use iced::Task;
+use tracing::warn;
+
+// 工作区和临时工作区中所有笔记的名称索引和出链索引
+// 同一时间只进行一个索引任务，编号递增，只应用最新任务的结果
+#[derive(Debug, Default)]
+pub struct NoteIndexer {
+    // 用于解析[[链接]]
+    note_index: Arc<NoteIndex>,
+    // 各笔记的出链、标签和标题，用于反向链接、标签和关系图
+    link_index: Arc<LinkIndex>,
+    index_id: u64,
+    is_indexing: bool,
+    // 索引任务进行期间变动的路径，任务结束后一起更新
+    queued_paths: BTreeSet<PathBuf>,
+    // 最近一次由文件树提供的工作区，排队的路径按它读取
+    roots: IndexRoots,
+}
+
+// 需要索引的各工作区根路径和扫描设置，以及临时工作区中有路径的文档
+#[derive(Debug, Clone, Default)]
+pub struct IndexRoots {
+    pub workspaces: Vec<(PathBuf, ScanOptions)>,
+    pub temp_paths: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+pub enum NoteIndexerMessage {
+    // 扫描设置改变后全部重建，进行中的任务结果作废
+    Refresh(IndexRoots),
+    // 文件树增删、重命名和移动后只重新索引变动的路径
+    Update(IndexRoots, Vec<PathBuf>),
+    Loaded(u64, NoteIndex, LinkIndex),
+    Updated(u64, Vec<PathBuf>, Vec<(PathBuf, IndexedFile)>),
+    // 保存文件后只更新该文件的出链
+    IndexSavedFile(PathBuf, Arc<String>),
+    SavedFileIndexed(PathBuf, IndexedFile),
+    // 发送给其他模块的消息
+    SendNoteIndexChanged(Arc<NoteIndex>),
+    SendLinkIndexChanged,
+}
+
+impl NoteIndexer {
+    pub fn update(&mut self, message: NoteIndexerMessage) -> Task<NoteIndexerMessage> {
+        match message {
+            NoteIndexerMessage::Refresh(roots) => {
+                self.roots = roots;
+                self.queued_paths.clear();
+                self.start_full_index()
+            }
+            NoteIndexerMessage::Update(roots, paths) => {
+                self.roots = roots;
+                self.queued_paths.extend(paths);
+                match self.is_indexing {
+                    true => Task::none(),
+                    false => self.start_queued_index(),
+                }
+            }
+            NoteIndexerMessage::Loaded(index_id, note_index, link_index) => {
+                if index_id != self.index_id {
+                    return Task::none();
+                }
+                self.is_indexing = false;
+                self.note_index = Arc::new(note_index);
+                self.link_index = Arc::new(link_index);
+                Task::batch([
+                    Task::done(NoteIndexerMessage::SendNoteIndexChanged(Arc::clone(
+                        &self.note_index,
+                    ))),
+                    self.start_queued_index(),
+                ])
+            }
+            // 先删除变动路径下原有的笔记，再加入路径下现有的笔记
+            NoteIndexerMessage::Updated(index_id, changed_paths, files) => {
+                if index_id != self.index_id {
+                    return Task::none();
+                }
+                self.is_indexing = false;
+                let note_index = Arc::make_mut(&mut self.note_index);
+                let link_index = Arc::make_mut(&mut self.link_index);
+                for path in changed_paths.iter() {
+                    note_index.remove_under(path);
+                    link_index.remove_under(path);
+                }
+                for (path, file) in files {
+                    note_index.insert(path.clone());
+                    link_index.insert(path, file);
+                }
+                Task::batch([
+                    Task::done(NoteIndexerMessage::SendNoteIndexChanged(Arc::clone(
+                        &self.note_index,
+                    ))),
+                    self.start_queued_index(),
+                ])
+            }
+            // 只有已经在索引中的笔记才需要更新，另存为的新文件等文件树刷新时再加入
+            NoteIndexerMessage::IndexSavedFile(path, content) => {
+                if !self.link_index.contains(&path) {
+                    return Task::none();
+                }
+                Task::perform(
+                    link_index::index_saved_file(path, content),
+                    |(path, file)| NoteIndexerMessage::SavedFileIndexed(path, file),
+                )
+            }
+            NoteIndexerMessage::SavedFileIndexed(path, file) => {
+                Arc::make_mut(&mut self.link_index).insert(path, file);
+                Task::done(NoteIndexerMessage::SendLinkIndexChanged)
+            }
+            _ => Task::none(),
+        }
+    }
+
+    pub fn note_index(&self) -> &Arc<NoteIndex> {
+        &self.note_index
+    }
+
+    pub fn link_index(&self) -> &Arc<LinkIndex> {
+        &self.link_index
+    }
+
+    // 读取失败的工作区跳过，其余笔记照常参与解析
+    fn start_full_index(&mut self) -> Task<NoteIndexerMessage> {
+        let IndexRoots {
+            workspaces,
+            temp_paths,
+        } = self.roots.clone();
+        let previous_link_index = Arc::clone(&self.link_index);
+        self.index_id += 1;
+        self.is_indexing = true;
+        let index_id = self.index_id;
+        Task::perform(
+            async move {
+                let mut files = temp_paths;
+                for (root_path, options) in workspaces {
+                    match operation::collect_workspace_files(root_path, options).await {
+                        Ok(root_files) => files.extend(root_files),
+                        Err(error) => warn!("[NoteIndexer-Refresh]:{}", error),
+                    }
+                }
+                let link_index =
+                    link_index::update_link_index(files.clone(), previous_link_index).await;
+                (NoteIndex::new(files), link_index)
+            },
+            move |(note_index, link_index)| {
+                NoteIndexerMessage::Loaded(index_id, note_index, link_index)
+            },
+        )
+    }
+
+    // 重新读取排队的路径，已删除或不在工作区中的路径只从索引中删除
+    fn start_queued_index(&mut self) -> Task<NoteIndexerMessage> {
+        if self.queued_paths.is_empty() {
+            return Task::none();
+        }
+        let changed_paths = std::mem::take(&mut self.queued_paths)
+            .into_iter()
+            .collect::<Vec<_>>();
+        let IndexRoots {
+            workspaces,
+            temp_paths,
+        } = self.roots.clone();
+        self.index_id += 1;
+        self.is_indexing = true;
+        let index_id = self.index_id;
+        Task::perform(
+            async move {
+                let mut files = vec![];
+                for path in changed_paths.iter() {
+                    if temp_paths.contains(path) {
+                        files.push(path.clone());
+                        continue;
+                    }
+                    let Some((root_path, options)) = workspaces
+                        .iter()
+                        .find(|(root_path, _)| path.starts_with(root_path))
+                    else {
+                        continue;
+                    };
+                    let is_editable =
+                        operation::is_editable_path(path, &options.editable_extensions);
+                    match tokio::fs::metadata(path).await {
+                        Ok(metadata) if metadata.is_dir() => {
+                            match operation::collect_dir_files(
+                                root_path.clone(),
+                                path.clone(),
+                                options.clone(),
+                            )
+                            .await
+                            {
+                                Ok(dir_files) => files.extend(dir_files),
+                                Err(error) => warn!("[NoteIndexer-Update]:{}", error),
+                            }
+                        }
+                        Ok(_) if is_editable => files.push(path.clone()),
+                        _ => {}
+                    }
+                }
+                (changed_paths, link_index::index_files(files).await)
+            },
+            move |(changed_paths, files)| {
+                NoteIndexerMessage::Updated(index_id, changed_paths, files)
+            },
+        )
+    }
+}
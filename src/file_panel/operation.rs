@@ -1,17 +1,29 @@
-use crate::{common::*, file_panel::file_tree::FileTreeMessage};
+use crate::{
+    common::{
+        md_link,
+        template::{self, Template, TemplateContext},
+        *,
+    },
+    file_panel::{file_tree::FileTreeMessage, ignore::IgnoreRules, outline},
+};
 use iced::{
-    Background, Color, Length, Padding, Theme, mouse,
-    widget::{Column, Row, column, container, image, mouse_area, text},
+    Background, Border, Color, Element, Length, Padding, Theme,
+    alignment::Vertical,
+    mouse,
+    widget::{Row, container, image, mouse_area, text, text_input},
 };
 use std::{
-    collections::HashMap,
+    cmp,
+    collections::{BTreeMap, HashMap},
     path::{Path, PathBuf},
     sync::{
-        Arc,
-        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
     },
+    time::SystemTime,
 };
 use tokio::io::AsyncWriteExt;
+use tracing::warn;
 
 static FILE_NODE_COUNTER: AtomicU32 = AtomicU32::new(0);
 static IMG_NAME_COUNTER: AtomicU32 = AtomicU32::new(1);
@@ -39,6 +51,14 @@ pub struct FileNode {
     pub global_id: u32,
     pub name: String,
     pub node_content: NodeContent,
+    pub metadata: NodeMetadata,
+}
+
+// 排序用的文件信息，由工作区扫描时更新
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct NodeMetadata {
+    pub modified: Option<SystemTime>,
+    pub size: u64,
 }
 
 impl FileNode {
@@ -47,6 +67,7 @@ impl FileNode {
             global_id: get_next_id(),
             name,
             node_content,
+            metadata: NodeMetadata::default(),
         }
     }
 
@@ -114,6 +135,16 @@ impl FileNode {
         }
     }
 
+    pub fn try_get_asset_mut(&mut self) -> Result<&mut AssetFile, AppError> {
+        match self {
+            FileNode {
+                node_content: NodeContent::Asset(asset_file),
+                ..
+            } => Ok(asset_file),
+            _ => Err(AppError::FilePanelError("该节点不是附件!".to_string())),
+        }
+    }
+
     pub fn try_get_md_mut(&mut self) -> Result<&mut MdFile, AppError> {
         match self {
             FileNode {
@@ -134,6 +165,10 @@ impl FileNode {
                 node_content: NodeContent::DirectoryMd(Dir { path, .. }),
                 ..
             } => Ok(path),
+            FileNode {
+                node_content: NodeContent::Asset(AssetFile { path, .. }),
+                ..
+            } => Ok(path),
             FileNode {
                 node_content: NodeContent::Markdown(MdFile { path, .. }),
                 ..
@@ -145,6 +180,64 @@ impl FileNode {
         }
     }
 
+    // 重命名或移动后更新节点路径
+    pub fn set_path(&mut self, new_path: PathBuf) {
+        match self {
+            FileNode {
+                node_content: NodeContent::Image(ImageFile { path, .. }),
+                ..
+            } => *path = new_path,
+            FileNode {
+                node_content: NodeContent::DirectoryMd(Dir { path, .. }),
+                ..
+            } => *path = new_path,
+            FileNode {
+                node_content: NodeContent::Asset(AssetFile { path, .. }),
+                ..
+            } => *path = new_path,
+            FileNode {
+                node_content: NodeContent::Markdown(MdFile { path, .. }),
+                ..
+            } => *path = Some(new_path),
+            _ => {}
+        }
+    }
+
+    pub fn set_expanded_if_directory(&mut self, is_expanded: bool) {
+        match self {
+            FileNode {
+                node_content: NodeContent::DirectoryMd(Dir { expanded, .. }),
+                ..
+            } => *expanded = is_expanded,
+            FileNode {
+                node_content: NodeContent::DirectoryTemp(TempDir { expanded, .. }),
+                ..
+            } => *expanded = is_expanded,
+            _ => {}
+        }
+    }
+
+    // 临时文件夹的子节点始终在内存中，视为已读取
+    pub fn load_state(&self) -> LoadState {
+        match self {
+            FileNode {
+                node_content: NodeContent::DirectoryMd(Dir { load_state, .. }),
+                ..
+            } => *load_state,
+            _ => LoadState::Loaded,
+        }
+    }
+
+    pub fn set_load_state_if_directory(&mut self, new_state: LoadState) {
+        if let FileNode {
+            node_content: NodeContent::DirectoryMd(Dir { load_state, .. }),
+            ..
+        } = self
+        {
+            *load_state = new_state;
+        }
+    }
+
     pub fn is_expanded(&self) -> bool {
         match self {
             FileNode {
@@ -187,6 +280,16 @@ impl FileNode {
         }
     }
 
+    pub fn is_md_directory(&self) -> bool {
+        matches!(
+            self,
+            FileNode {
+                node_content: NodeContent::DirectoryMd { .. },
+                ..
+            }
+        )
+    }
+
     pub fn is_temp_file(&self) -> bool {
         match self {
             FileNode {
@@ -207,6 +310,16 @@ impl FileNode {
         }
     }
 
+    pub fn is_asset_file(&self) -> bool {
+        matches!(
+            self,
+            FileNode {
+                node_content: NodeContent::Asset { .. },
+                ..
+            }
+        )
+    }
+
     pub fn is_img_file(&self) -> bool {
         match self {
             FileNode {
@@ -223,6 +336,15 @@ pub struct Dir {
     pub path: PathBuf,
     pub children: Vec<u32>,
     pub expanded: bool,
+    pub load_state: LoadState,
+}
+
+// 文件夹在第一次展开时才读取子节点
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoadState {
+    Unloaded,
+    Loading,
+    Loaded,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -234,8 +356,16 @@ pub struct TempDir {
 pub struct MdFile {
     pub path: Option<PathBuf>,
     pub version: u64,
+    // 最近一次和磁盘内容一致时的版本号，与version不同时缓存中有没保存的改动
+    pub saved_version: u64,
     pub cache: Option<Arc<String>>,
 }
+
+impl MdFile {
+    pub fn is_dirty(&self) -> bool {
+        self.cache.is_some() && self.version != self.saved_version
+    }
+}
 #[derive(Debug, Clone, PartialEq)]
 pub struct ImageFile {
     pub path: PathBuf,
@@ -243,18 +373,63 @@ pub struct ImageFile {
     pub cache: image::Handle,
 }
 
+// 工作区中的图片和其他附件，打开时才读取内容
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetFile {
+    pub path: PathBuf,
+    pub kind: AssetKind,
+    // 第一次在图片库中打开时才分配编号
+    pub indep_id: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AssetKind {
+    Image,
+    Pdf,
+    Audio,
+    Video,
+}
+
+impl AssetKind {
+    pub fn from_path(path: &Path) -> Option<AssetKind> {
+        let extension = path.extension()?.to_string_lossy().to_ascii_lowercase();
+        let extension = extension.as_str();
+        if IMAGE_EXTENSIONS.contains(&extension) {
+            Some(AssetKind::Image)
+        } else if PDF_EXTENSIONS.contains(&extension) {
+            Some(AssetKind::Pdf)
+        } else if AUDIO_EXTENSIONS.contains(&extension) {
+            Some(AssetKind::Audio)
+        } else if VIDEO_EXTENSIONS.contains(&extension) {
+            Some(AssetKind::Video)
+        } else {
+            None
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AssetKind::Image => "图片",
+            AssetKind::Pdf => "PDF",
+            AssetKind::Audio => "音频",
+            AssetKind::Video => "视频",
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum NodeContent {
     DirectoryMd(Dir),
     DirectoryTemp(TempDir),
     Markdown(MdFile),
     Image(ImageFile),
+    Asset(AssetFile),
 }
 
 pub async fn open_md_file_dialog() -> Option<PathBuf> {
     let path = rfd::AsyncFileDialog::new()
         .set_title("打开文件")
-        .add_filter("markdown文件(*md)", &["md"])
+        .add_filter("文档(*md,*markdown,*mdx,*txt)", &EDITABLE_EXTENSIONS)
         .pick_file()
         .await?;
 
@@ -292,6 +467,7 @@ pub async fn open_img_folder_dialog() -> Option<PathBuf> {
 pub async fn save_file_dialog(file_name: String) -> Option<PathBuf> {
     rfd::AsyncFileDialog::new()
         .add_filter("markdown文件(*md)", &["md"])
+        .add_filter("文档(*md,*markdown,*mdx,*txt)", &EDITABLE_EXTENSIONS)
         .set_file_name(file_name)
         .set_title("保存文件")
         .save_file()
@@ -299,6 +475,61 @@ pub async fn save_file_dialog(file_name: String) -> Option<PathBuf> {
         .map(|file_handle| file_handle.path().to_path_buf())
 }
 
+pub async fn open_workspace_file_dialog() -> Option<PathBuf> {
+    let path = rfd::AsyncFileDialog::new()
+        .set_title("打开工作区文件")
+        .add_filter("工作区文件(*fugu-workspace)", &[WORKSPACE_FILE_EXTENSION])
+        .pick_file()
+        .await?;
+
+    Some(path.path().to_path_buf())
+}
+
+pub async fn save_workspace_file_dialog() -> Option<PathBuf> {
+    rfd::AsyncFileDialog::new()
+        .add_filter("工作区文件(*fugu-workspace)", &[WORKSPACE_FILE_EXTENSION])
+        .set_file_name(format!("workspace.{WORKSPACE_FILE_EXTENSION}"))
+        .set_title("工作区另存为")
+        .save_file()
+        .await
+        .map(|file_handle| file_handle.path().to_path_buf())
+}
+
+// 工作区文件中的相对路径相对工作区文件所在文件夹
+pub async fn read_workspace_file(path: PathBuf) -> Result<WorkspaceFile, AppError> {
+    let content = tokio::fs::read_to_string(&path).await?;
+    let mut workspace_file: WorkspaceFile = serde_json::from_str(&content).map_err(|error| {
+        AppError::FilePanelError(format!("[ReadWorkspaceFile]:工作区文件格式错误:{error}"))
+    })?;
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    for root in workspace_file.roots.iter_mut() {
+        if root.path.is_relative() {
+            root.path = base_dir.join(&root.path);
+        }
+    }
+    Ok(workspace_file)
+}
+
+pub async fn write_workspace_file(
+    path: PathBuf,
+    mut workspace_file: WorkspaceFile,
+) -> Result<(), AppError> {
+    if let Some(base_dir) = path.parent() {
+        for root in workspace_file.roots.iter_mut() {
+            if let Ok(relative_path) = root.path.strip_prefix(base_dir) {
+                root.path = match relative_path.as_os_str().is_empty() {
+                    true => PathBuf::from("."),
+                    false => relative_path.to_path_buf(),
+                };
+            }
+        }
+    }
+    let content = serde_json::to_string_pretty(&workspace_file).map_err(|error| {
+        AppError::FilePanelError(format!("[WriteWorkspaceFile]:工作区文件序列化失败:{error}"))
+    })?;
+    save_file(path, Arc::new(content)).await
+}
+
 pub async fn read_file(path: PathBuf) -> Result<(PathBuf, String), AppError> {
     let content = tokio::fs::read_to_string(&path).await?;
     Ok((path, content))
@@ -336,64 +567,1015 @@ pub async fn save_file(path: PathBuf, content: Arc<String>) -> Result<(), AppErr
     Ok(())
 }
 
-// 异步读取文件并生成节点树
+// 在目录下生成不重名的路径，重名时在文件名后追加序号
+pub async fn get_unique_path(
+    dir: &Path,
+    stem: &str,
+    extension: Option<&str>,
+) -> Result<PathBuf, AppError> {
+    let mut index = 0;
+    loop {
+        let name = match (index, extension) {
+            (0, Some(extension)) => format!("{}.{}", stem, extension),
+            (0, None) => stem.to_string(),
+            (index, Some(extension)) => format!("{}({}).{}", stem, index, extension),
+            (index, None) => format!("{}({})", stem, index),
+        };
+        let path = dir.join(name);
+        if !tokio::fs::try_exists(&path).await? {
+            return Ok(path);
+        }
+        index += 1;
+    }
+}
+
+// 按[[链接]]中的名称新建笔记，同名文件已存在时报错
+pub async fn create_named_md_file(path: PathBuf) -> Result<FileNode, AppError> {
+    tokio::fs::File::create_new(&path).await?;
+    Ok(FileNode::new(
+        get_file_name(&path),
+        NodeContent::Markdown(MdFile {
+            path: Some(path),
+            version: 0,
+            saved_version: 0,
+            cache: Some(Arc::new(String::default())),
+        }),
+    ))
+}
+
+// 列出各模板文件夹中的md文件，排在前面的文件夹中的同名模板优先，文件夹不存在时跳过
+pub async fn list_templates(template_dirs: Vec<(PathBuf, bool)>) -> Vec<Template> {
+    let mut templates: Vec<Template> = vec![];
+    for (dir, is_global) in template_dirs {
+        let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+            continue;
+        };
+        let mut dir_templates = vec![];
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            let is_md = path
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("md"));
+            if !is_md || !entry.file_type().await.is_ok_and(|file_type| file_type.is_file()) {
+                continue;
+            }
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            if templates.iter().any(|template| template.name == name) {
+                continue;
+            }
+            dir_templates.push(Template {
+                name,
+                path,
+                is_global,
+            });
+        }
+        dir_templates.sort_by(|a, b| a.name.cmp(&b.name));
+        templates.extend(dir_templates);
+    }
+    templates
+}
+
+// 按模板生成新文件的内容，没有选择模板时为空白文档
+pub async fn render_new_file(
+    template: Option<PathBuf>,
+    context: TemplateContext,
+) -> Result<String, AppError> {
+    match template {
+        Some(path) => {
+            let content = tokio::fs::read_to_string(&path).await?;
+            Ok(template::render(&content, &context))
+        }
+        None => Ok(String::new()),
+    }
+}
+
+// 在指定目录下以标题为文件名新建md文件，重名时追加序号
+pub async fn create_md_file_from_template(
+    dir: PathBuf,
+    template: Option<PathBuf>,
+    context: TemplateContext,
+) -> Result<FileNode, AppError> {
+    let stem = file_stem_from_title(&context.title);
+    let content = render_new_file(template, context).await?;
+    let path = get_unique_path(&dir, &stem, Some("md")).await?;
+    let mut file = tokio::fs::File::create_new(&path).await?;
+    file.write_all(content.as_bytes()).await?;
+    file.flush().await?;
+    Ok(FileNode::new(
+        get_file_name(&path),
+        NodeContent::Markdown(MdFile {
+            path: Some(path),
+            version: 0,
+            saved_version: 0,
+            cache: Some(Arc::new(content)),
+        }),
+    ))
+}
+
+// 标题中不能用于文件名的字符替换为-，标题为空时使用默认文件名
+pub fn file_stem_from_title(title: &str) -> String {
+    let stem = title
+        .trim()
+        .replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|'], "-");
+    match stem.trim_matches('.').is_empty() {
+        true => "新建文件".to_string(),
+        false => stem,
+    }
+}
+
+// 新建每日笔记，日记文件夹不存在时一并创建，已存在同名日记时返回false
+pub async fn create_daily_note(path: PathBuf, content: String) -> Result<bool, AppError> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    match tokio::fs::File::create_new(&path).await {
+        Ok(_) => {
+            tokio::fs::write(&path, content).await?;
+            Ok(true)
+        }
+        Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+        Err(error) => Err(error.into()),
+    }
+}
+
+// 查找[[笔记#标题]]中标题所在的行，找不到时停在第一行
+pub async fn find_heading_line(path: PathBuf, heading: String) -> usize {
+    let content = match tokio::fs::read_to_string(&path).await {
+        Ok(content) => content,
+        Err(error) => {
+            warn!("[FileTree-FindHeadingLine]:{} 读取失败:{}", path.display(), error);
+            return 0;
+        }
+    };
+    outline::parse_headings(&content)
+        .into_iter()
+        .find(|item| md_link::is_heading_match(&item.title, &heading))
+        .map(|item| item.line)
+        .unwrap_or_default()
+}
+
+// 在指定目录下新建文件夹
+pub async fn create_folder(dir: PathBuf) -> Result<FileNode, AppError> {
+    let path = get_unique_path(&dir, "新建文件夹", None).await?;
+    tokio::fs::create_dir(&path).await?;
+    Ok(FileNode::new(
+        get_file_name(&path),
+        NodeContent::DirectoryMd(Dir {
+            path,
+            children: vec![],
+            expanded: false,
+            load_state: LoadState::Loaded,
+        }),
+    ))
+}
+
+// 检查新名称是否合法，不允许为空或包含路径分隔符
+pub fn check_file_name(name: &str) -> Result<(), AppError> {
+    if name.trim().is_empty() {
+        return Err(AppError::FilePanelError("名称不能为空!".to_string()));
+    }
+    if name.contains(['/', '\\']) || name == "." || name == ".." {
+        return Err(AppError::FilePanelError(format!("名称 {} 不合法!", name)));
+    }
+    Ok(())
+}
+
+// 重命名文件或文件夹，目标已存在时报错而不是覆盖
+pub async fn rename_path(from: PathBuf, new_name: String) -> Result<PathBuf, AppError> {
+    check_file_name(&new_name)?;
+    let parent_path = from.parent().ok_or(AppError::FilePanelError(
+        "根目录无法重命名!".to_string(),
+    ))?;
+    let to = parent_path.join(new_name);
+    // 不区分大小写的文件系统上只改大小写时，目标路径指向的就是原文件
+    if tokio::fs::try_exists(&to).await?
+        && tokio::fs::canonicalize(&from).await? != tokio::fs::canonicalize(&to).await?
+    {
+        return Err(AppError::FilePanelError(format!(
+            "{} 已存在!",
+            to.to_string_lossy()
+        )));
+    }
+    tokio::fs::rename(&from, &to).await?;
+    Ok(to)
+}
+
+// 移动文件或文件夹到目标文件夹下
+pub async fn move_path(from: PathBuf, to_dir: PathBuf) -> Result<PathBuf, AppError> {
+    if to_dir.starts_with(&from) {
+        return Err(AppError::FilePanelError(
+            "不能移动到自身或子文件夹中!".to_string(),
+        ));
+    }
+    let to = to_dir.join(get_file_name(&from));
+    if tokio::fs::try_exists(&to).await? {
+        return Err(AppError::FilePanelError(format!(
+            "{} 已存在!",
+            to.to_string_lossy()
+        )));
+    }
+    tokio::fs::rename(&from, &to).await?;
+    Ok(to)
+}
+
+// 撤销重命名或移动时把路径改回去，原路径已被占用时放弃
+pub async fn restore_path(from: PathBuf, to: PathBuf) -> Result<(), AppError> {
+    if tokio::fs::try_exists(&to).await? {
+        return Err(AppError::FilePanelError(format!(
+            "{} 已存在，无法撤销!",
+            to.to_string_lossy()
+        )));
+    }
+    tokio::fs::rename(&from, &to).await?;
+    Ok(())
+}
+
+// 删除到系统回收站，方便用户恢复
+pub async fn move_to_trash(path: PathBuf) -> Result<(), AppError> {
+    tokio::task::spawn_blocking(move || trash::delete(path))
+        .await?
+        .map_err(|error| AppError::FilePanelError(error.to_string()))
+}
+
+// 在系统文件管理器中打开文件所在的文件夹
+pub async fn reveal_in_file_manager(path: PathBuf, opener: Vec<String>) -> Result<(), AppError> {
+    let dir_path = if tokio::fs::metadata(&path).await?.is_dir() {
+        path
+    } else {
+        path.parent()
+            .map(Path::to_path_buf)
+            .ok_or(AppError::FilePanelError("获取上级文件夹失败!".to_string()))?
+    };
+    open_with_system(opener, dir_path).await?;
+    Ok(())
+}
+
+// 用系统默认程序打开附件
+pub async fn open_with_default_app(path: PathBuf, opener: Vec<String>) -> Result<(), AppError> {
+    open_with_system(opener, path).await?;
+    Ok(())
+}
+
+// 复制文件或文件夹到同级目录并生成对应节点
+pub async fn duplicate_path(
+    path: PathBuf,
+    options: ScanOptions,
+) -> Result<(u32, HashMap<u32, FileNode>), AppError> {
+    let parent_path = path.parent().ok_or(AppError::FilePanelError(
+        "根目录无法复制!".to_string(),
+    ))?;
+    if tokio::fs::metadata(&path).await?.is_dir() {
+        let stem = format!("{} 副本", get_file_name(&path));
+        let new_path = get_unique_path(parent_path, &stem, None).await?;
+        // 中途失败时删除已复制的部分，不留下不完整的副本
+        if let Err(error) = copy_dir(path, new_path.clone()).await {
+            if let Err(remove_error) = tokio::fs::remove_dir_all(&new_path).await {
+                warn!("[FileTree-DuplicatePath]:清理不完整的副本失败:{remove_error}");
+            }
+            return Err(error);
+        }
+        let (root_key, mut nodes) =
+            fetch_file_tree(new_path, options, Arc::new(ScanProgress::default())).await?;
+        if let Some(root_node) = nodes.get_mut(&root_key) {
+            root_node.set_expanded_if_directory(false);
+        }
+        Ok((root_key, nodes))
+    } else {
+        let stem = format!(
+            "{} 副本",
+            path.file_stem().unwrap_or_default().to_string_lossy()
+        );
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().into_owned());
+        let new_path = get_unique_path(parent_path, &stem, extension.as_deref()).await?;
+        if let Err(error) = tokio::fs::copy(&path, &new_path).await {
+            let _ = tokio::fs::remove_file(&new_path).await;
+            return Err(error.into());
+        }
+        let file_node = new_workspace_node(
+            new_path,
+            ScanEntry {
+                is_dir: false,
+                metadata: NodeMetadata::default(),
+            },
+        );
+        let root_key = file_node.global_id;
+        Ok((root_key, HashMap::from([(root_key, file_node)])))
+    }
+}
+
+// 复制文件夹中的内容，跳过符号链接以及依赖和构建产物文件夹
+async fn copy_dir(from: PathBuf, to: PathBuf) -> Result<(), AppError> {
+    let mut path_stack = vec![(from, to)];
+    while let Some((from, to)) = path_stack.pop() {
+        tokio::fs::create_dir(&to).await?;
+        let mut dir = tokio::fs::read_dir(&from).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            let file_type = entry.file_type().await?;
+            let file_name = entry.file_name();
+            if file_type.is_symlink() {
+                warn!("[FileTree-DuplicatePath]:跳过符号链接 {}", entry.path().display());
+                continue;
+            }
+            let target = to.join(&file_name);
+            if file_type.is_dir() {
+                if !SCAN_SKIPPED_DIR_NAMES.contains(&file_name.to_string_lossy().as_ref()) {
+                    path_stack.push((entry.path(), target));
+                }
+            } else {
+                tokio::fs::copy(entry.path(), target).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// 关闭窗口前依次保存所有未保存文档，没有路径的文档逐个弹出另存为窗口
+// 中途取消时已保存的文档仍然返回，方便更新节点路径
+pub async fn save_unsaved_docs(
+    docs: Vec<UnsavedDoc>,
+) -> (Vec<(u32, PathBuf, Arc<String>)>, Result<(), AppError>) {
+    let mut saved_files = vec![];
+    for doc in docs {
+        let path = match doc.path {
+            Some(path) => path,
+            None => {
+                let file_name = if doc.name.ends_with(".md") {
+                    doc.name.clone()
+                } else {
+                    format!("{}.md", doc.name)
+                };
+                match save_file_dialog(file_name).await {
+                    Some(path) => path,
+                    None => {
+                        return (
+                            saved_files,
+                            Err(AppError::FilePanelError(format!(
+                                "已取消保存 {}!",
+                                doc.name
+                            ))),
+                        );
+                    }
+                }
+            }
+        };
+        if let Err(error) = save_file(path.clone(), Arc::clone(&doc.content)).await {
+            return (saved_files, Err(error));
+        }
+        if let DocSource::FileNode(id) = doc.source {
+            saved_files.push((id, path, doc.content));
+        }
+    }
+    (saved_files, Ok(()))
+}
+
+// 读取工作区根目录生成节点树，子文件夹等到展开时再读取
 pub async fn fetch_file_tree(
     root_path: PathBuf,
+    options: ScanOptions,
+    progress: Arc<ScanProgress>,
 ) -> Result<(u32, HashMap<u32, FileNode>), AppError> {
-    let mut all_nodes = HashMap::new();
-    let root_node_name = get_file_name(&root_path);
-    let root_node_content = NodeContent::DirectoryMd(Dir {
-        path: root_path.clone(),
-        children: vec![],
-        expanded: true,
-    });
-    let root_node = FileNode::new(root_node_name, root_node_content);
+    let entries = read_dir_children(root_path.clone(), root_path.clone(), options, progress).await?;
+    let mut root_node = new_workspace_node(
+        root_path,
+        ScanEntry {
+            is_dir: true,
+            metadata: NodeMetadata::default(),
+        },
+    );
+    root_node.set_expanded_if_directory(true);
+    root_node.set_load_state_if_directory(LoadState::Loaded);
     let root_node_key = root_node.global_id;
-    let mut node_index_stack = vec![(root_path, root_node.global_id)];
-    all_nodes.insert(root_node.global_id, root_node);
+    let mut all_nodes = HashMap::new();
+    for (path, entry) in entries {
+        let child_node = new_workspace_node(path, entry);
+        root_node.try_get_children_mut()?.push(child_node.global_id);
+        all_nodes.insert(child_node.global_id, child_node);
+    }
+    all_nodes.insert(root_node_key, root_node);
+    Ok((root_node_key, all_nodes))
+}
 
-    while let Some((path, key)) = node_index_stack.pop() {
-        let mut dir = tokio::fs::read_dir(path).await?;
+// 根据扫描到的路径生成工作区节点，新文件夹默认折叠
+pub fn new_workspace_node(path: PathBuf, entry: ScanEntry) -> FileNode {
+    let node_name = get_file_name(&path);
+    let node_content = match entry.is_dir {
+        true => NodeContent::DirectoryMd(Dir {
+            path,
+            children: vec![],
+            expanded: false,
+            load_state: LoadState::Unloaded,
+        }),
+        false => match AssetKind::from_path(&path) {
+            Some(kind) => NodeContent::Asset(AssetFile {
+                path,
+                kind,
+                indep_id: None,
+            }),
+            None => NodeContent::Markdown(MdFile {
+                path: Some(path),
+                version: 0,
+                saved_version: 0,
+                cache: None,
+            }),
+        },
+    };
+    let mut node = FileNode::new(node_name, node_content);
+    node.metadata = entry.metadata;
+    node
+}
 
-        while let Some(entry) = dir.next_entry().await? {
-            let path = entry.path();
-            if !path.is_dir() {
-                if let Some(extension) = path.extension()
-                    && extension.to_string_lossy().as_ref() == "md"
-                {
-                    let child_node_name = get_file_name(&path);
-                    let child_node_content = NodeContent::Markdown(MdFile {
-                        path: Some(path),
-                        version: 0,
-                        cache: None,
-                    });
+// 扫描到的单个路径
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScanEntry {
+    pub is_dir: bool,
+    pub metadata: NodeMetadata,
+}
 
-                    let child_node = FileNode::new(child_node_name, child_node_content);
-                    if let Some(file_node) = all_nodes.get_mut(&key) {
-                        file_node.try_get_children_mut()?.push(child_node.global_id);
-                    }
-                    all_nodes.insert(child_node.global_id, child_node);
-                } else {
+// 轮询时重新读取的文件夹，修改时间留给下一次扫描比较
+#[derive(Debug, Clone)]
+pub struct DirSnapshot {
+    pub modified: Option<SystemTime>,
+    pub entries: BTreeMap<PathBuf, ScanEntry>,
+}
+
+// 扫描工作区时的限制条件
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    pub max_depth: usize,
+    pub max_nodes: usize,
+    pub include_hidden: bool,
+    pub hide_dirs_without_markdown: bool,
+    pub editable_extensions: Arc<[String]>,
+    // 由文件树持有，定时扫描时复用上次的检查结果
+    pub markdown_dir_cache: Arc<MarkdownDirCache>,
+}
+
+impl From<&AppSetting> for ScanOptions {
+    fn from(setting: &AppSetting) -> Self {
+        ScanOptions {
+            max_depth: setting.scan_max_depth,
+            max_nodes: setting.scan_max_nodes,
+            include_hidden: setting.scan_include_hidden,
+            hide_dirs_without_markdown: false,
+            editable_extensions: setting.editable_extensions.clone().into(),
+            markdown_dir_cache: Arc::default(),
+        }
+    }
+}
+
+// 各文件夹是否直接包含文档，按文件夹的修改时间判断是否失效
+// 修改时间只随直接子项的增删和改名变化，所以子文件夹仍要逐个检查
+#[derive(Debug, Default)]
+pub struct MarkdownDirCache {
+    state: Mutex<MarkdownDirCacheState>,
+}
+
+#[derive(Debug, Default)]
+struct MarkdownDirCacheState {
+    // 生成缓存时的扫描设置，设置改变后缓存全部失效
+    include_hidden: bool,
+    editable_extensions: Arc<[String]>,
+    dirs: HashMap<PathBuf, MarkdownDirEntry>,
+}
+
+#[derive(Debug, Clone)]
+struct MarkdownDirEntry {
+    modified: SystemTime,
+    has_markdown: bool,
+    sub_dirs: Vec<PathBuf>,
+}
+
+impl MarkdownDirCache {
+    fn get(
+        &self,
+        options: &ScanOptions,
+        path: &Path,
+        modified: SystemTime,
+    ) -> Option<MarkdownDirEntry> {
+        let mut state = self.state.lock().ok()?;
+        if state.include_hidden != options.include_hidden
+            || state.editable_extensions != options.editable_extensions
+        {
+            state.include_hidden = options.include_hidden;
+            state.editable_extensions = Arc::clone(&options.editable_extensions);
+            state.dirs.clear();
+            return None;
+        }
+        state
+            .dirs
+            .get(path)
+            .filter(|entry| entry.modified == modified)
+            .cloned()
+    }
+
+    fn insert(&self, path: PathBuf, entry: MarkdownDirEntry) {
+        if let Ok(mut state) = self.state.lock() {
+            state.dirs.insert(path, entry);
+        }
+    }
+}
+
+// 扫描进度，界面定时读取已扫描数量，用户取消时由扫描任务自行退出
+#[derive(Debug, Default)]
+pub struct ScanProgress {
+    scanned_count: AtomicUsize,
+    is_canceled: AtomicBool,
+}
+
+impl ScanProgress {
+    // 展开文件夹时从工作区已有的节点数开始计数，保证整个工作区不超过节点上限
+    pub fn starting_at(scanned_count: usize) -> Self {
+        Self {
+            scanned_count: AtomicUsize::new(scanned_count),
+            is_canceled: AtomicBool::new(false),
+        }
+    }
+
+    pub fn scanned_count(&self) -> usize {
+        self.scanned_count.load(Ordering::Relaxed)
+    }
+
+    pub fn cancel(&self) {
+        self.is_canceled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_canceled(&self) -> bool {
+        self.is_canceled.load(Ordering::Relaxed)
+    }
+}
+
+// 展开文件夹时读取它的直接子节点
+pub async fn read_dir_children(
+    root_path: PathBuf,
+    dir_path: PathBuf,
+    options: ScanOptions,
+    progress: Arc<ScanProgress>,
+) -> Result<BTreeMap<PathBuf, ScanEntry>, AppError> {
+    let depth = dir_path
+        .strip_prefix(&root_path)
+        .map(|relative_path| relative_path.components().count())
+        .unwrap_or_default();
+    if depth >= options.max_depth {
+        warn!("[FileTree-ReadDir]:超过最大层级，不再读取 {}", dir_path.display());
+        return Ok(BTreeMap::new());
+    }
+    // 符号链接指回上级文件夹时视为循环，不再继续读取
+    let real_dir_path = tokio::fs::canonicalize(&dir_path).await?;
+    for ancestor in dir_path
+        .ancestors()
+        .skip(1)
+        .take_while(|ancestor| ancestor.starts_with(&root_path))
+    {
+        if tokio::fs::canonicalize(ancestor).await? == real_dir_path {
+            warn!("[FileTree-ReadDir]:检测到符号链接循环 {}", dir_path.display());
+            return Ok(BTreeMap::new());
+        }
+    }
+    let rules = load_ignore_rules(&root_path, &dir_path).await;
+    read_dir_entries(&dir_path, &rules, &options, &progress).await
+}
+
+// 文件夹路径和读取到的直接子节点
+pub type DirChildren = (PathBuf, BTreeMap<PathBuf, ScanEntry>);
+
+// 依次读取多个文件夹的直接子节点，上级文件夹排在前面
+pub async fn read_dirs_children(
+    root_path: PathBuf,
+    dir_paths: Vec<PathBuf>,
+    options: ScanOptions,
+    progress: Arc<ScanProgress>,
+) -> Result<Vec<DirChildren>, AppError> {
+    let mut dirs = vec![];
+    for dir_path in dir_paths {
+        let entries = read_dir_children(
+            root_path.clone(),
+            dir_path.clone(),
+            options.clone(),
+            Arc::clone(&progress),
+        )
+        .await?;
+        dirs.push((dir_path, entries));
+    }
+    Ok(dirs)
+}
+
+// 恢复会话时读取上次展开的文件夹，已经不存在的文件夹直接跳过
+pub async fn read_restored_dirs_children(
+    root_path: PathBuf,
+    dir_paths: Vec<PathBuf>,
+    options: ScanOptions,
+) -> Vec<DirChildren> {
+    let mut dirs = vec![];
+    for dir_path in dir_paths {
+        match read_dir_children(
+            root_path.clone(),
+            dir_path.clone(),
+            options.clone(),
+            Arc::new(ScanProgress::default()),
+        )
+        .await
+        {
+            Ok(entries) => dirs.push((dir_path, entries)),
+            Err(error) => warn!("[FileTree-RestoreSession]:读取 {} 失败:{}", dir_path.display(), error),
+        }
+    }
+    dirs
+}
+
+// 恢复会话时重新读取临时工作区中的文件，读取失败的文件不再恢复
+pub async fn read_restored_md_files(paths: Vec<PathBuf>) -> Vec<FileNode> {
+    let mut file_nodes = vec![];
+    for path in paths {
+        match read_file(path.clone()).await {
+            Ok((path, text)) => file_nodes.push(FileNode::new(
+                get_file_name(&path),
+                NodeContent::Markdown(MdFile {
+                    path: Some(path),
+                    version: 0,
+                    saved_version: 0,
+                    cache: Some(Arc::new(text)),
+                }),
+            )),
+            Err(error) => warn!("[FileTree-RestoreSession]:读取 {} 失败:{}", path.display(), error),
+        }
+    }
+    file_nodes
+}
+
+// 恢复会话时重新读取图片库中的图片
+pub async fn read_restored_img_files(paths: Vec<PathBuf>) -> HashMap<u32, FileNode> {
+    let mut img_nodes = HashMap::new();
+    for path in paths {
+        match read_img_file(path.clone()).await {
+            Ok((path, handle)) => {
+                let file_node = FileNode::new(
+                    get_file_name(&path),
+                    NodeContent::Image(ImageFile {
+                        path,
+                        indep_id: get_next_img_id(),
+                        cache: handle,
+                    }),
+                );
+                img_nodes.insert(file_node.global_id, file_node);
+            }
+            Err(error) => warn!("[FileTree-RestoreSession]:读取 {} 失败:{}", path.display(), error),
+        }
+    }
+    img_nodes
+}
+
+// 依次读取根目录到指定文件夹路径上的所有忽略规则文件
+pub async fn load_ignore_rules(root_path: &Path, dir_path: &Path) -> IgnoreRules {
+    let mut dirs = dir_path
+        .ancestors()
+        .take_while(|ancestor| ancestor.starts_with(root_path))
+        .collect::<Vec<_>>();
+    dirs.reverse();
+    let mut rules = IgnoreRules::default();
+    for dir in dirs {
+        if let Some(dir_rules) = rules.load_dir(dir).await {
+            rules = dir_rules;
+        }
+    }
+    rules
+}
+
+// 定时重新读取修改时间变化过的已展开文件夹，用于与文件树对比找出外部变动
+pub async fn scan_loaded_dirs(
+    root_path: PathBuf,
+    mut dir_paths: Vec<(PathBuf, Option<SystemTime>)>,
+    options: ScanOptions,
+) -> Result<BTreeMap<PathBuf, DirSnapshot>, AppError> {
+    // 父文件夹排在前面，子文件夹可以直接复用父文件夹的规则
+    dir_paths.sort();
+    let progress = ScanProgress::default();
+    let mut rules_cache: HashMap<PathBuf, Arc<IgnoreRules>> = HashMap::new();
+    let mut snapshots = BTreeMap::new();
+    for (dir_path, last_modified) in dir_paths {
+        // 增删和改名都会更新所在文件夹的修改时间，时间没变的文件夹不再读取
+        // 读取前记录时间，读取期间发生的变动留给下一次扫描
+        let modified = match tokio::fs::metadata(&dir_path).await {
+            Ok(metadata) => metadata.modified().ok(),
+            Err(_) if dir_path != root_path => continue,
+            Err(error) => return Err(error.into()),
+        };
+        if modified.is_some() && modified == last_modified {
+            continue;
+        }
+        let parent_rules = dir_path
+            .parent()
+            .and_then(|parent| rules_cache.get(parent))
+            .cloned();
+        let rules = match parent_rules {
+            Some(parent_rules) => match parent_rules.load_dir(&dir_path).await {
+                Some(dir_rules) => Arc::new(dir_rules),
+                None => parent_rules,
+            },
+            None => Arc::new(load_ignore_rules(&root_path, &dir_path).await),
+        };
+        match read_dir_entries(&dir_path, &rules, &options, &progress).await {
+            Ok(entries) => {
+                snapshots.insert(dir_path.clone(), DirSnapshot { modified, entries });
+            }
+            // 文件夹已被外部删除，交给上级文件夹的结果处理
+            Err(_) if dir_path != root_path => {}
+            Err(error) => return Err(error),
+        }
+        rules_cache.insert(dir_path, rules);
+    }
+    Ok(snapshots)
+}
+
+// 读取单个文件夹中的文件夹、文档和附件，跳过隐藏、依赖和被忽略的路径
+pub async fn read_dir_entries(
+    dir_path: &Path,
+    rules: &IgnoreRules,
+    options: &ScanOptions,
+    progress: &ScanProgress,
+) -> Result<BTreeMap<PathBuf, ScanEntry>, AppError> {
+    let mut paths = BTreeMap::new();
+    let mut dir = tokio::fs::read_dir(dir_path).await?;
+
+    while let Some(entry) = dir.next_entry().await? {
+        if progress.is_canceled() {
+            return Err(AppError::FilePanelError(
+                "[FileTree-ReadDir]:读取已取消!".to_string(),
+            ));
+        }
+        if progress.scanned_count() >= options.max_nodes {
+            warn!("[FileTree-ReadDir]:节点数量超过上限，剩余文件已跳过!");
+            break;
+        }
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        if !options.include_hidden && file_name.starts_with('.') {
+            continue;
+        }
+        // 符号链接失效时直接跳过
+        let Ok(metadata) = tokio::fs::metadata(&path).await else {
+            continue;
+        };
+        let is_dir = metadata.is_dir();
+        if rules.is_ignored(&path, is_dir) {
+            continue;
+        }
+        let entry = ScanEntry {
+            is_dir,
+            metadata: NodeMetadata {
+                modified: metadata.modified().ok(),
+                size: if is_dir { 0 } else { metadata.len() },
+            },
+        };
+        if is_dir {
+            if SCAN_SKIPPED_DIR_NAMES.contains(&file_name.as_str())
+                || (options.hide_dirs_without_markdown
+                    && !dir_contains_markdown(&path, rules, options).await)
+            {
+                continue;
+            }
+            paths.insert(path, entry);
+        } else if is_editable_path(&path, &options.editable_extensions)
+            || AssetKind::from_path(&path).is_some()
+        {
+            paths.insert(path, entry);
+        } else {
+            continue;
+        }
+        progress.scanned_count.fetch_add(1, Ordering::Relaxed);
+    }
+    Ok(paths)
+}
+
+// 读取工作区中所有可编辑的文档路径，用于全文搜索
+pub async fn collect_workspace_files(
+    root_path: PathBuf,
+    options: ScanOptions,
+) -> Result<Vec<PathBuf>, AppError> {
+    collect_dir_files(root_path.clone(), root_path, options).await
+}
+
+// 读取工作区中某个文件夹下的文档路径，忽略规则和层级都从工作区根目录算起
+pub async fn collect_dir_files(
+    root_path: PathBuf,
+    start_path: PathBuf,
+    options: ScanOptions,
+) -> Result<Vec<PathBuf>, AppError> {
+    let progress = ScanProgress::default();
+    let mut files = vec![];
+    let mut visited_dirs = vec![tokio::fs::canonicalize(&start_path).await?];
+    let start_depth = start_path
+        .strip_prefix(&root_path)
+        .map(|relative_path| relative_path.components().count())
+        .unwrap_or_default();
+    let mut dir_stack = vec![(
+        start_path.clone(),
+        Arc::new(load_ignore_rules(&root_path, &start_path).await),
+        start_depth,
+    )];
+    while let Some((dir_path, rules, depth)) = dir_stack.pop() {
+        let entries = match read_dir_entries(&dir_path, &rules, &options, &progress).await {
+            Ok(entries) => entries,
+            Err(_) if dir_path != start_path => continue,
+            Err(error) => return Err(error),
+        };
+        for (path, entry) in entries {
+            if !entry.is_dir {
+                if is_editable_path(&path, &options.editable_extensions) {
+                    files.push(path);
+                }
+                continue;
+            }
+            // 符号链接指向已读取过的文件夹时跳过，避免循环
+            let Ok(real_path) = tokio::fs::canonicalize(&path).await else {
+                continue;
+            };
+            if depth + 1 >= options.max_depth || visited_dirs.contains(&real_path) {
+                continue;
+            }
+            visited_dirs.push(real_path);
+            let dir_rules = match rules.load_dir(&path).await {
+                Some(dir_rules) => Arc::new(dir_rules),
+                None => Arc::clone(&rules),
+            };
+            dir_stack.push((path, dir_rules, depth + 1));
+        }
+        if progress.scanned_count() >= options.max_nodes {
+            warn!("[FileTree-CollectFiles]:节点数量超过上限，剩余文件已跳过!");
+            break;
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+// 替换前确认文件没有被外部修改，避免覆盖搜索之后的改动
+pub async fn apply_replacement(replacement: FileReplacement) -> Result<(), AppError> {
+    let content = tokio::fs::read_to_string(&replacement.path).await?;
+    if content != *replacement.original {
+        return Err(AppError::FilePanelError(format!(
+            "{} 在搜索后已被修改，已跳过替换!",
+            replacement.path.display()
+        )));
+    }
+    save_file(replacement.path, replacement.replaced).await
+}
+
+// 缓存中有未保存改动的文件只替换缓存，版本号增加后仍视为未保存
+pub fn apply_replacement_to_cache(
+    md_file: &mut MdFile,
+    replacement: FileReplacement,
+) -> Result<(), AppError> {
+    if md_file.cache.as_ref() != Some(&replacement.original) {
+        return Err(AppError::FilePanelError(format!(
+            "{} 在搜索后已被修改，已跳过替换!",
+            replacement.path.display()
+        )));
+    }
+    md_file.cache = Some(replacement.replaced);
+    md_file.version += 1;
+    Ok(())
+}
+
+// 找到第一个md文件就返回，最多检查max_nodes个路径
+// 修改时间没变的文件夹直接使用缓存，不再重新读取
+pub async fn dir_contains_markdown(dir_path: &Path, rules: &IgnoreRules, options: &ScanOptions) -> bool {
+    let mut checked_count = 0;
+    let mut path_stack = vec![(dir_path.to_path_buf(), 0)];
+    while let Some((path, depth)) = path_stack.pop() {
+        let Ok(modified) = tokio::fs::metadata(&path)
+            .await
+            .and_then(|metadata| metadata.modified())
+        else {
+            continue;
+        };
+        let entry = match options.markdown_dir_cache.get(options, &path, modified) {
+            Some(entry) => entry,
+            None => {
+                let Some(entry) = read_markdown_dir(&path, modified, rules, options).await else {
                     continue;
+                };
+                options.markdown_dir_cache.insert(path, entry.clone());
+                entry
+            }
+        };
+        checked_count += entry.sub_dirs.len() + 1;
+        if entry.has_markdown || checked_count > options.max_nodes {
+            return true;
+        }
+        if depth < options.max_depth {
+            path_stack.extend(entry.sub_dirs.into_iter().map(|sub_dir| (sub_dir, depth + 1)));
+        }
+    }
+    false
+}
+
+// 读取文件夹的直接子项，找到文档后不再记录子文件夹
+async fn read_markdown_dir(
+    dir_path: &Path,
+    modified: SystemTime,
+    rules: &IgnoreRules,
+    options: &ScanOptions,
+) -> Option<MarkdownDirEntry> {
+    let mut dir = tokio::fs::read_dir(dir_path).await.ok()?;
+    let mut sub_dirs = vec![];
+    while let Ok(Some(entry)) = dir.next_entry().await {
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        if !options.include_hidden && file_name.starts_with('.') {
+            continue;
+        }
+        // 不跟随符号链接，避免循环
+        let Ok(file_type) = entry.file_type().await else {
+            continue;
+        };
+        if rules.is_ignored(&path, file_type.is_dir()) {
+            continue;
+        }
+        if file_type.is_dir() {
+            if !SCAN_SKIPPED_DIR_NAMES.contains(&file_name.as_str()) {
+                sub_dirs.push(path);
+            }
+        } else if is_editable_path(&path, &options.editable_extensions) {
+            return Some(MarkdownDirEntry {
+                modified,
+                has_markdown: true,
+                sub_dirs: vec![],
+            });
+        }
+    }
+    Some(MarkdownDirEntry {
+        modified,
+        has_markdown: false,
+        sub_dirs,
+    })
+}
+
+pub fn is_editable_path(path: &Path, editable_extensions: &[String]) -> bool {
+    path.extension().is_some_and(|extension| {
+        let extension = extension.to_string_lossy();
+        editable_extensions
+            .iter()
+            .any(|editable_extension| extension.eq_ignore_ascii_case(editable_extension))
+    })
+}
+
+// 自然排序，数字部分按数值比较，chapter2排在chapter10前面
+pub fn natural_cmp(a: &str, b: &str) -> cmp::Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return cmp::Ordering::Less,
+            (Some(_), None) => return cmp::Ordering::Greater,
+            (Some(a_char), Some(b_char)) if a_char.is_ascii_digit() && b_char.is_ascii_digit() => {
+                let mut a_digits = String::new();
+                while let Some(c) = a_chars.next_if(char::is_ascii_digit) {
+                    a_digits.push(c);
                 }
-            } else {
-                let child_node_name = get_file_name(&path);
-                let child_node_content = NodeContent::DirectoryMd(Dir {
-                    path: path.clone(),
-                    children: vec![],
-                    expanded: false,
-                });
-
-                let child_node = FileNode::new(child_node_name, child_node_content);
-                node_index_stack.push((path, child_node.global_id));
-                if let Some(file_node) = all_nodes.get_mut(&key) {
-                    file_node.try_get_children_mut()?.push(child_node.global_id);
+                let mut b_digits = String::new();
+                while let Some(c) = b_chars.next_if(char::is_ascii_digit) {
+                    b_digits.push(c);
+                }
+                // 去掉前导0后先比较位数再逐位比较，避免数字过长溢出
+                let a_number = a_digits.trim_start_matches('0');
+                let b_number = b_digits.trim_start_matches('0');
+                let ordering = a_number
+                    .len()
+                    .cmp(&b_number.len())
+                    .then_with(|| a_number.cmp(b_number));
+                if ordering != cmp::Ordering::Equal {
+                    return ordering;
                 }
-                all_nodes.insert(child_node.global_id, child_node);
+            }
+            (Some(a_char), Some(b_char)) => {
+                let ordering = a_char.to_lowercase().cmp(b_char.to_lowercase());
+                if ordering != cmp::Ordering::Equal {
+                    return ordering;
+                }
+                a_chars.next();
+                b_chars.next();
             }
         }
     }
-    Ok((root_node_key, all_nodes))
+}
+
+// 文件夹排在文件前面，同类节点按排序方式比较，相同时按名称排序
+pub fn compare_nodes(a: &FileNode, b: &FileNode, sort_mode: SortMode) -> cmp::Ordering {
+    b.is_directory()
+        .cmp(&a.is_directory())
+        .then_with(|| match sort_mode {
+            SortMode::Name => cmp::Ordering::Equal,
+            // 最近修改和体积最大的排在前面
+            SortMode::Modified => b.metadata.modified.cmp(&a.metadata.modified),
+            SortMode::Size => b.metadata.size.cmp(&a.metadata.size),
+        })
+        .then_with(|| natural_cmp(&a.name, &b.name))
 }
 
 // 异步读取图片并生成节点列表
@@ -446,94 +1628,148 @@ pub async fn copy_img_to_folder(md_path: PathBuf, img_path: PathBuf) -> Result<S
     Ok(code)
 }
 
-// 递归渲染节点树
-pub fn view_node(
-    hovered_id: Option<u32>,
-    selected_id: Option<u32>,
-    all_file_nodes: &HashMap<u32, FileNode>,
-    key: u32,
+// 重命名输入框的id，用于自动聚焦
+pub const RENAME_INPUT_ID: &str = "file-tree-rename-input";
+// 文件树滚动区域的id，用于滚动到指定节点
+pub const FILE_TREE_SCROLLABLE_ID: &str = "file-tree-scrollable";
+
+// 渲染节点树时需要的各种交互状态
+#[derive(Debug, Clone, Copy)]
+pub struct NodeViewState<'a> {
+    pub hovered_id: Option<u32>,
+    pub selected_id: Option<u32>,
+    pub focused_id: Option<u32>,
+    pub dragging_id: Option<u32>,
+    pub renaming: Option<(u32, &'a str)>,
+}
+
+// 渲染文件树中的一行，文件树只渲染可见范围内的行，label为替代文件名显示的文字
+pub fn view_row<'a>(
+    state: NodeViewState<'a>,
+    node: &'a FileNode,
+    label: Option<&'a str>,
     depth: u16,
-) -> Column<'_, FileTreeMessage> {
-    let node = all_file_nodes.get(&key).expect("前置逻辑不会出错!");
+) -> Element<'a, FileTreeMessage> {
     let mut row = Row::new();
-    let children_node_view = if let Ok(children) = node.try_get_children()
-        && !children.is_empty()
-    {
-        let mut column = Column::new().height(match node.is_expanded() {
-            false => {
-                row = row.push(text(" ▶ ").size(FONT_SIZE_SMALLER));
-                0.into()
-            }
-            true => {
-                row = row.push(text(" ▼ ").size(FONT_SIZE_SMALLER));
-                Length::Shrink
-            }
-        });
-        if let Ok(children) = node.try_get_children() {
-            for child_key in children {
-                column = column.push(view_node(
-                    hovered_id,
-                    selected_id,
-                    all_file_nodes,
-                    *child_key,
-                    depth + 1,
-                ));
-            }
-        }
-        Some(column)
-    } else {
-        if node.is_directory() {
-            row = row.push(text(" ▷ ").size(FONT_SIZE_SMALLER));
-        }
-        if node.is_temp_file() {
-            row = row.push(
-                text("临时 ")
-                    .size(FONT_SIZE_SMALLER)
-                    .style(|theme: &Theme| {
-                        let palette = theme.palette();
-                        text::Style {
-                            color: Some(palette.warning),
-                        }
-                    }),
-            )
+    if node.is_directory() {
+        let marker = match (node.load_state(), node.is_expanded()) {
+            (LoadState::Unloaded, _) | (_, false) => " ▶ ",
+            (LoadState::Loaded, true) if node.is_children_empty() => " ▷ ",
+            _ => " ▼ ",
+        };
+        row = row.push(text(marker).size(FONT_SIZE_SMALLER));
+    }
+    if let NodeContent::Asset(AssetFile { kind, .. }) = &node.node_content {
+        row = row.push(
+            text!("{} ", kind.label())
+                .size(FONT_SIZE_SMALLER)
+                .style(|theme: &Theme| {
+                    let palette = theme.palette();
+                    text::Style {
+                        color: Some(palette.primary),
+                    }
+                }),
+        )
+    }
+    if node.is_temp_file() {
+        row = row.push(
+            text("临时 ")
+                .size(FONT_SIZE_SMALLER)
+                .style(|theme: &Theme| {
+                    let palette = theme.palette();
+                    text::Style {
+                        color: Some(palette.warning),
+                    }
+                }),
+        )
+    }
+
+    let name_view: Element<'a, FileTreeMessage> = match state.renaming {
+        Some((renaming_id, value)) if renaming_id == node.global_id => {
+            text_input("", value)
+                .id(RENAME_INPUT_ID)
+                .size(FONT_SIZE_SMALLER)
+                .padding(Padding::ZERO)
+                .on_input(FileTreeMessage::RenameInputChanged)
+                .on_submit(FileTreeMessage::ConfirmRenameNode)
+                .style(|theme: &Theme, _| {
+                    let ex_palette = theme.extended_palette();
+                    let palette = theme.palette();
+                    text_input::Style {
+                        background: Background::Color(ex_palette.background.weaker.color),
+                        border: Border::default(),
+                        icon: palette.text,
+                        placeholder: palette.text,
+                        value: palette.text,
+                        selection: palette.primary,
+                    }
+                })
+                .into()
         }
-        None
+        _ => text(label.unwrap_or(&node.name))
+            .size(FONT_SIZE_SMALLER)
+            .wrapping(text::Wrapping::None)
+            .into(),
     };
 
-    let node_view = mouse_area(
-        container(
-            row.push(
-                text(&node.name)
-                    .size(FONT_SIZE_SMALLER)
-                    .wrapping(text::Wrapping::None),
-            )
-            .padding(Padding::from([
-                PADDING_SMALLEST,
-                (depth * TEXT_INDENTATION) as f32,
-            ])),
-        )
+    row = row.push(name_view);
+    if node.load_state() == LoadState::Loading {
+        row = row.push(
+            text(" 读取中…")
+                .size(FONT_SIZE_SMALLER)
+                .style(|theme: &Theme| {
+                    let ex_palette = theme.extended_palette();
+                    text::Style {
+                        color: Some(ex_palette.background.strong.color),
+                    }
+                }),
+        );
+    }
+
+    mouse_area(
+        container(row.padding(Padding::from([
+            PADDING_SMALLEST,
+            (depth * TEXT_INDENTATION) as f32,
+        ])))
         .width(Length::Fill)
+        .height(FILE_TREE_ROW_HEIGHT)
+        .align_y(Vertical::Center)
         .style(move |theme: &Theme| {
             let ex_palette = theme.extended_palette();
-            let bg = if selected_id == Some(node.global_id) {
+            let palette = theme.palette();
+            let is_hovered = state.hovered_id == Some(node.global_id);
+            // 拖动到文件夹上方时高亮提示可以放下
+            let bg = if is_hovered
+                && node.is_md_directory()
+                && state.dragging_id.is_some_and(|id| id != node.global_id)
+            {
+                palette.primary.scale_alpha(0.25)
+            } else if state.selected_id == Some(node.global_id) {
                 ex_palette.background.strong.color.scale_alpha(0.75)
-            } else if hovered_id == Some(node.global_id) {
+            } else if is_hovered {
                 ex_palette.background.weaker.color
             } else {
                 Color::TRANSPARENT
             };
             container::Style {
                 background: Some(Background::Color(bg)),
+                border: Border {
+                    color: if state.focused_id == Some(node.global_id) {
+                        ex_palette.background.strong.color
+                    } else {
+                        Color::TRANSPARENT
+                    },
+                    ..Border::default()
+                }
+                .width(1.),
                 ..container::Style::default()
             }
         }),
     )
     .interaction(mouse::Interaction::Pointer)
-    .on_press(FileTreeMessage::ChangeSelectedNode(node.global_id))
-    .on_enter(FileTreeMessage::ChangeHoveredNode(node.global_id));
-
-    match children_node_view {
-        Some(children) => column![node_view, children],
-        None => column![node_view],
-    }
+    .on_press(FileTreeMessage::PressNode(node.global_id))
+    .on_release(FileTreeMessage::ReleaseNode(node.global_id))
+    .on_right_press(FileTreeMessage::RequestContextMenu(node.global_id))
+    .on_enter(FileTreeMessage::ChangeHoveredNode(node.global_id))
+    .into()
 }
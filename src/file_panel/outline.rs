@@ -1,21 +1,370 @@
+use std::{collections::HashSet, sync::Arc};
 
+use crate::common::{front_matter::FrontMatter, md_link, wiki_link, *};
+use iced::{
+    Background, Border, Color, Element, Length, Padding, Task, Theme,
+    alignment::Vertical,
+    mouse, padding,
+    widget::{Row, column, container, mouse_area, scrollable, text},
+};
+use tracing::info;
 
+// 大纲面板，根据编辑器中的内容实时生成标题树
 #[derive(Debug, Default)]
 pub struct Outline {
-    node: Vec<String>
+    raw: Arc<String>,
+    headings: Vec<Heading>,
+    // 折叠的标题，按级别和标题文字记录，编辑时行号变化也能保持折叠
+    collapsed: HashSet<(u8, String)>,
+    hovered_index: Option<usize>,
+    dragging_index: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heading {
+    pub level: u8,
+    pub title: String,
+    // 标题所在行和所在章节的结束行（不含），行号从0开始
+    pub line: usize,
+    pub end_line: usize,
 }
 
 #[derive(Debug, Clone)]
 pub enum OutlineMessage {
-    
+    SyncContent(Arc<String>),
+    ToggleCollapse(usize),
+    HoverHeading(usize),
+    PressHeading(usize),
+    ReleaseHeading(usize),
+    // 预览中点击#锚点链接时查找对应标题
+    FindAnchor(String),
+    // 发送给其他模块的消息
+    SendJumpToLine(usize),
+    SendPreviewScrollToLine(usize),
+    SendMovedContent(Arc<String>, Arc<String>, usize),
 }
 
 impl Outline {
-    pub fn update() {
-        
+    pub fn update(&mut self, message: OutlineMessage) -> Task<OutlineMessage> {
+        match message {
+            OutlineMessage::SyncContent(raw) => {
+                self.headings = parse_headings(&raw);
+                self.raw = raw;
+                if self
+                    .dragging_index
+                    .is_some_and(|index| index >= self.headings.len())
+                {
+                    self.dragging_index = None;
+                }
+                Task::none()
+            }
+            OutlineMessage::ToggleCollapse(index) => {
+                if let Some(heading) = self.headings.get(index) {
+                    let key = (heading.level, heading.title.clone());
+                    if !self.collapsed.remove(&key) {
+                        self.collapsed.insert(key);
+                    }
+                }
+                Task::none()
+            }
+            OutlineMessage::FindAnchor(anchor) => match self
+                .headings
+                .iter()
+                .find(|heading| md_link::is_heading_match(&heading.title, &anchor))
+            {
+                Some(heading) => Task::done(OutlineMessage::SendPreviewScrollToLine(heading.line)),
+                None => {
+                    info!("[Outline-FindAnchor]:找不到锚点对应的标题 {}", anchor);
+                    Task::none()
+                }
+            },
+            OutlineMessage::HoverHeading(index) => {
+                self.hovered_index = Some(index);
+                Task::none()
+            }
+            // 按下和松开在同一标题上视为点击，否则把按下的章节移动到松开的标题处
+            OutlineMessage::PressHeading(index) => {
+                self.dragging_index = Some(index);
+                Task::none()
+            }
+            OutlineMessage::ReleaseHeading(index) => match self.dragging_index.take() {
+                Some(source_index) if source_index != index => {
+                    match move_section(&self.raw, &self.headings, source_index, index) {
+                        Some((moved, line)) => {
+                            info!("[Outline-ReleaseHeading]:章节移动成功!");
+                            Task::done(OutlineMessage::SendMovedContent(
+                                Arc::clone(&self.raw),
+                                Arc::new(moved),
+                                line,
+                            ))
+                        }
+                        None => Task::none(),
+                    }
+                }
+                // 点击折叠按钮时按下事件已被按钮处理，不会记录拖动的标题
+                Some(_) => match self.headings.get(index) {
+                    Some(heading) => Task::done(OutlineMessage::SendJumpToLine(heading.line)),
+                    None => Task::none(),
+                },
+                None => Task::none(),
+            },
+            _ => Task::none(),
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, OutlineMessage> {
+        if self.headings.is_empty() {
+            return text("当前文档没有标题").size(FONT_SIZE_SMALLER).into();
+        }
+        let min_level = self
+            .headings
+            .iter()
+            .map(|heading| heading.level)
+            .min()
+            .unwrap_or(1);
+        // 折叠标题下的子标题不显示
+        let mut rows = vec![];
+        let mut collapsed_level: Option<u8> = None;
+        for (index, heading) in self.headings.iter().enumerate() {
+            match collapsed_level {
+                Some(level) if heading.level > level => continue,
+                _ => collapsed_level = None,
+            }
+            let is_collapsed = self
+                .collapsed
+                .contains(&(heading.level, heading.title.clone()));
+            if is_collapsed {
+                collapsed_level = Some(heading.level);
+            }
+            let has_children = self
+                .headings
+                .get(index + 1)
+                .is_some_and(|next| next.level > heading.level);
+            rows.push(self.view_heading(
+                index,
+                heading,
+                heading.level - min_level,
+                has_children,
+                is_collapsed,
+            ));
+        }
+        scrollable(column(rows)).height(Length::Fill).into()
+    }
+
+    fn view_heading<'a>(
+        &'a self,
+        index: usize,
+        heading: &'a Heading,
+        depth: u8,
+        has_children: bool,
+        is_collapsed: bool,
+    ) -> Element<'a, OutlineMessage> {
+        let mut row = Row::new().align_y(Vertical::Center);
+        row = match (has_children, is_collapsed) {
+            (true, true) => row.push(
+                mouse_area(text(" ▶ ").size(FONT_SIZE_SMALLER))
+                    .on_press(OutlineMessage::ToggleCollapse(index)),
+            ),
+            (true, false) => row.push(
+                mouse_area(text(" ▼ ").size(FONT_SIZE_SMALLER))
+                    .on_press(OutlineMessage::ToggleCollapse(index)),
+            ),
+            _ => row.push(text("   ").size(FONT_SIZE_SMALLER)),
+        };
+        row = row.push(
+            text(&heading.title)
+                .size(FONT_SIZE_SMALLER)
+                .wrapping(text::Wrapping::None),
+        );
+        mouse_area(
+            container(row.padding(padding::left((depth as u16 * TEXT_INDENTATION) as f32)))
+                .width(Length::Fill)
+                .height(FILE_TREE_ROW_HEIGHT)
+                .align_y(Vertical::Center)
+                .padding(Padding::from([PADDING_SMALLEST, 0.]))
+                .style(move |theme: &Theme| {
+                    let ex_palette = theme.extended_palette();
+                    let palette = theme.palette();
+                    let is_hovered = self.hovered_index == Some(index);
+                    // 拖动到其他标题上方时高亮提示可以放下
+                    let bg = if is_hovered
+                        && self.dragging_index.is_some_and(|dragging| dragging != index)
+                    {
+                        palette.primary.scale_alpha(0.25)
+                    } else if is_hovered {
+                        ex_palette.background.weaker.color
+                    } else {
+                        Color::TRANSPARENT
+                    };
+                    container::Style {
+                        background: Some(Background::Color(bg)),
+                        border: Border::default(),
+                        ..container::Style::default()
+                    }
+                }),
+        )
+        .interaction(mouse::Interaction::Pointer)
+        .on_press(OutlineMessage::PressHeading(index))
+        .on_release(OutlineMessage::ReleaseHeading(index))
+        .on_enter(OutlineMessage::HoverHeading(index))
+        .into()
     }
-    
-    pub fn view() {
-        
+}
+
+// 解析ATX(# 标题)和Setext(标题下一行为===或---)两种标题，跳过front matter和代码块中的内容
+pub fn parse_headings(raw: &str) -> Vec<Heading> {
+    let lines = raw.lines().collect::<Vec<_>>();
+    let mut headings: Vec<Heading> = vec![];
+    let mut fence: Option<(char, usize)> = None;
+    // front matter的结束分隔线---紧跟在字段后面，不能当作Setext标题
+    let body_start = FrontMatter::parse(raw)
+        .map(|front_matter| raw[..front_matter.range.end].lines().count())
+        .unwrap_or(0);
+    for (line_index, line) in lines.iter().enumerate().skip(body_start) {
+        // 与链接索引和标签解析使用同样的代码块判断
+        if wiki_link::update_fence(&mut fence, line) {
+            continue;
+        }
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        if indent >= 4 {
+            continue;
+        }
+        let trimmed = line.trim();
+        if let Some((level, title)) = parse_atx_heading(trimmed) {
+            headings.push(Heading {
+                level,
+                title,
+                line: line_index,
+                end_line: lines.len(),
+            });
+            continue;
+        }
+        // Setext标题的上一行必须是普通段落文字
+        let setext_level = match trimmed {
+            _ if !trimmed.is_empty() && trimmed.chars().all(|c| c == '=') => Some(1),
+            _ if trimmed.len() >= 2 && trimmed.chars().all(|c| c == '-') => Some(2),
+            _ => None,
+        };
+        if let Some(level) = setext_level
+            && line_index > 0
+        {
+            let previous = lines[line_index - 1].trim();
+            let is_previous_heading = headings
+                .last()
+                .is_some_and(|heading| heading.line == line_index - 1);
+            if !previous.is_empty()
+                && !is_previous_heading
+                && !previous.starts_with(['-', '*', '+', '>', '|'])
+            {
+                headings.push(Heading {
+                    level,
+                    title: previous.to_string(),
+                    line: line_index - 1,
+                    end_line: lines.len(),
+                });
+            }
+        }
+    }
+    // 章节到下一个同级或更高级的标题为止
+    for index in 0..headings.len() {
+        let level = headings[index].level;
+        if let Some(next) = headings[index + 1..]
+            .iter()
+            .find(|heading| heading.level <= level)
+        {
+            headings[index].end_line = next.line;
+        }
+    }
+    headings
+}
+
+fn parse_atx_heading(trimmed: &str) -> Option<(u8, String)> {
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
     }
-}
\ No newline at end of file
+    let rest = &trimmed[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    // 去掉结尾可选的#
+    let title = rest.trim();
+    let title = match title.trim_end_matches('#') {
+        stripped if stripped.is_empty() || stripped.ends_with([' ', '\t']) => stripped.trim(),
+        _ => title,
+    };
+    Some((level as u8, title.to_string()))
+}
+
+// 向上拖动时放到目标标题前面，向下拖动时放到目标章节后面
+// 返回移动后的内容和被移动标题的新行号
+pub fn move_section(
+    raw: &str,
+    headings: &[Heading],
+    source_index: usize,
+    target_index: usize,
+) -> Option<(String, usize)> {
+    let source = headings.get(source_index)?;
+    let target = headings.get(target_index)?;
+    // 不能移动到自己的子章节中
+    if target.line >= source.line && target.line < source.end_line {
+        return None;
+    }
+    let mut lines = raw.split_inclusive('\n').map(str::to_string).collect::<Vec<_>>();
+    let has_trailing_newline = raw.ends_with('\n');
+    if let Some(last) = lines.last_mut()
+        && !has_trailing_newline
+    {
+        last.push('\n');
+    }
+    let insert_line = match target.line < source.line {
+        true => target.line,
+        false => target.end_line,
+    };
+    let section = lines.drain(source.line..source.end_line).collect::<Vec<_>>();
+    let insert_line = match insert_line > source.line {
+        true => insert_line - section.len(),
+        false => insert_line,
+    };
+    lines.splice(insert_line..insert_line, section);
+    let mut moved = lines.concat();
+    if !has_trailing_newline {
+        moved.pop();
+    }
+    Some((moved, insert_line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outline() {
+        let raw = "# A\na\n```\n# 代码\n```\n## A1\nTitle\n---\n# B ##\nb";
+        let headings = parse_headings(raw);
+        let titles = headings
+            .iter()
+            .map(|heading| (heading.level, heading.title.as_str(), heading.line, heading.end_line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            titles,
+            vec![(1, "A", 0, 8), (2, "A1", 5, 6), (2, "Title", 6, 8), (1, "B", 8, 10)]
+        );
+        let (moved, line) = move_section(raw, &headings, 3, 0).unwrap();
+        assert_eq!(moved, "# B ##\nb\n# A\na\n```\n# 代码\n```\n## A1\nTitle\n---");
+        assert_eq!(line, 0);
+        let (moved, line) = move_section(raw, &headings, 1, 3).unwrap();
+        assert_eq!(moved, "# A\na\n```\n# 代码\n```\nTitle\n---\n# B ##\nb\n## A1");
+        assert_eq!(line, 9);
+        assert!(move_section(raw, &headings, 0, 1).is_none());
+        let raw = "---\ntitle: 笔记\ntags: [a]\n---\n# A\nTitle\n---";
+        let titles = parse_headings(raw)
+            .iter()
+            .map(|heading| (heading.level, heading.title.clone(), heading.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            titles,
+            vec![(1, "A".to_string(), 4), (2, "Title".to_string(), 5)]
+        );
+    }
+}
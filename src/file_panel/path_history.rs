@@ -0,0 +1,97 @@
+use std::{path::PathBuf, sync::Arc};
+
+use crate::{
+    common::{wiki_link::NoteIndex, *},
+    file_panel::{
+        link_index::LinkIndex,
+        link_rewrite::{self, PathChange},
+        operation,
+    },
+};
+use iced::Task;
+use tracing::info;
+
+// 重命名或移动后等待确认的链接更新，以及最近一次可以撤销的重命名或移动
+#[derive(Debug, Default)]
+pub struct PathHistory {
+    pending_change: Option<PathChange>,
+    last_change: Option<PathChange>,
+}
+
+#[derive(Debug, Clone)]
+pub enum PathHistoryMessage {
+    // 使用重命名之前的索引快照查找指向旧路径的链接
+    Plan(Arc<LinkIndex>, Arc<NoteIndex>, PathBuf, PathBuf),
+    Planned(PathChange),
+    Resolve(bool),
+    Undo,
+    Undone(PathChange, Result<(), AppError>),
+    // 发送给其他模块的消息
+    SendConfirmLinkRewrites(Vec<PathBuf>),
+    SendReplacements(Vec<FileReplacement>),
+    SendPathRestored(PathChange),
+    SendError(AppError),
+}
+
+impl PathHistory {
+    pub fn update(&mut self, message: PathHistoryMessage) -> Task<PathHistoryMessage> {
+        match message {
+            PathHistoryMessage::Plan(link_index, note_index, old_path, new_path) => Task::perform(
+                link_rewrite::plan_link_rewrites(link_index, note_index, old_path, new_path),
+                PathHistoryMessage::Planned,
+            ),
+            // 没有需要更新的链接时直接记录，否则等待用户确认
+            PathHistoryMessage::Planned(path_change) => {
+                if path_change.replacements.is_empty() {
+                    self.last_change = Some(path_change);
+                    return Task::none();
+                }
+                let paths = path_change
+                    .replacements
+                    .iter()
+                    .map(|replacement| replacement.path.clone())
+                    .collect();
+                self.pending_change = Some(path_change);
+                Task::done(PathHistoryMessage::SendConfirmLinkRewrites(paths))
+            }
+            // 不更新链接时也记录下来，撤销时只还原路径
+            PathHistoryMessage::Resolve(is_confirmed) => {
+                let Some(mut path_change) = self.pending_change.take() else {
+                    return Task::none();
+                };
+                let task = match is_confirmed {
+                    true => Task::done(PathHistoryMessage::SendReplacements(
+                        path_change.replacements.clone(),
+                    )),
+                    false => {
+                        path_change.replacements.clear();
+                        Task::none()
+                    }
+                };
+                self.last_change = Some(path_change);
+                task
+            }
+            PathHistoryMessage::Undo => {
+                let Some(path_change) = self.last_change.take() else {
+                    info!("[PathHistory-Undo]:没有可以撤销的重命名或移动!");
+                    return Task::none();
+                };
+                Task::perform(
+                    operation::restore_path(
+                        path_change.new_path.clone(),
+                        path_change.old_path.clone(),
+                    ),
+                    move |result| PathHistoryMessage::Undone(path_change.clone(), result),
+                )
+            }
+            PathHistoryMessage::Undone(path_change, result) => match result {
+                Ok(_) => {
+                    info!("[PathHistory-Undone]:已撤销重命名或移动!");
+                    Task::done(PathHistoryMessage::SendPathRestored(path_change))
+                }
+                Err(error) => Task::done(PathHistoryMessage::SendError(error)),
+            },
+            _ => Task::none(),
+        }
+    }
+}
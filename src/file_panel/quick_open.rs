@@ -0,0 +1,310 @@
+use std::path::PathBuf;
+
+use crate::common::*;
+use iced::{
+    Background, Border, Element, Length, Padding, Task, Theme, mouse,
+    widget::{column, container, mouse_area, rich_text, span, text, text_input},
+};
+
+// 输入框的id，打开时自动聚焦
+pub const QUICK_OPEN_INPUT_ID: &str = "quick-open-input";
+
+// Ctrl+P快速打开，按名称和相对路径模糊匹配文件树和链接索引中的所有文件
+#[derive(Debug, Default)]
+pub struct QuickOpen {
+    is_open: bool,
+    query: String,
+    // 打开时从文件树和链接索引收集的候选文件
+    candidates: Vec<QuickOpenCandidate>,
+    results: Vec<QuickOpenResult>,
+    selected_index: usize,
+}
+
+// 已读取的文件按节点打开，未展开的文件夹中的笔记按路径打开
+#[derive(Debug, Clone)]
+pub enum QuickOpenTarget {
+    Node(u32),
+    Path(PathBuf),
+}
+
+#[derive(Debug, Clone)]
+pub struct QuickOpenCandidate {
+    pub target: QuickOpenTarget,
+    pub name: String,
+    pub relative_path: String,
+    // 在最近打开列表中的位置，越小越近
+    pub recent_rank: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct QuickOpenResult {
+    target: QuickOpenTarget,
+    name: String,
+    relative_path: String,
+    // 匹配到的字符位置，用于高亮显示
+    name_indices: Vec<usize>,
+    path_indices: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub enum QuickOpenMessage {
+    Open(Vec<QuickOpenCandidate>),
+    Close,
+    QueryChanged(String),
+    SelectNext,
+    SelectPrevious,
+    HoverResult(usize),
+    Submit,
+    ResultClicked(usize),
+    SendOpenTarget(QuickOpenTarget),
+}
+
+impl QuickOpen {
+    pub fn update(&mut self, message: QuickOpenMessage) -> Task<QuickOpenMessage> {
+        match message {
+            QuickOpenMessage::Open(candidates) => {
+                self.is_open = true;
+                self.query.clear();
+                self.candidates = candidates;
+                self.refresh_results();
+                iced::widget::operation::focus(QUICK_OPEN_INPUT_ID)
+            }
+            QuickOpenMessage::Close => {
+                self.is_open = false;
+                self.candidates.clear();
+                self.results.clear();
+                Task::none()
+            }
+            QuickOpenMessage::QueryChanged(query) => {
+                self.query = query;
+                self.refresh_results();
+                Task::none()
+            }
+            QuickOpenMessage::SelectNext => {
+                if !self.results.is_empty() {
+                    self.selected_index = (self.selected_index + 1) % self.results.len();
+                }
+                Task::none()
+            }
+            QuickOpenMessage::SelectPrevious => {
+                if !self.results.is_empty() {
+                    self.selected_index =
+                        (self.selected_index + self.results.len() - 1) % self.results.len();
+                }
+                Task::none()
+            }
+            QuickOpenMessage::HoverResult(index) => {
+                self.selected_index = index;
+                Task::none()
+            }
+            QuickOpenMessage::Submit => {
+                Task::done(QuickOpenMessage::ResultClicked(self.selected_index))
+            }
+            QuickOpenMessage::ResultClicked(index) => {
+                let Some(result) = self.results.get(index) else {
+                    return Task::none();
+                };
+                let target = result.target.clone();
+                self.is_open = false;
+                self.candidates.clear();
+                self.results.clear();
+                Task::done(QuickOpenMessage::SendOpenTarget(target))
+            }
+            _ => Task::none(),
+        }
+    }
+
+    // 最近打开的文件排在最前，其余按匹配得分排序，名称匹配优先于路径匹配
+    fn refresh_results(&mut self) {
+        let mut results = self
+            .candidates
+            .iter()
+            .filter_map(|candidate| {
+                if self.query.is_empty() {
+                    return Some((0, candidate, vec![], vec![]));
+                }
+                match fuzzy_match(&self.query, &candidate.name) {
+                    Some((score, name_indices)) => {
+                        Some((score + NAME_MATCH_BONUS, candidate, name_indices, vec![]))
+                    }
+                    None => fuzzy_match(&self.query, &candidate.relative_path)
+                        .map(|(score, path_indices)| (score, candidate, vec![], path_indices)),
+                }
+            })
+            .collect::<Vec<_>>();
+        results.sort_by(|(a_score, a, ..), (b_score, b, ..)| {
+            a.recent_rank
+                .is_none()
+                .cmp(&b.recent_rank.is_none())
+                .then_with(|| a.recent_rank.cmp(&b.recent_rank))
+                .then_with(|| b_score.cmp(a_score))
+                .then_with(|| a.relative_path.cmp(&b.relative_path))
+        });
+        self.results = results
+            .into_iter()
+            .take(QUICK_OPEN_MAX_RESULTS)
+            .map(|(_, candidate, name_indices, path_indices)| QuickOpenResult {
+                target: candidate.target.clone(),
+                name: candidate.name.clone(),
+                relative_path: candidate.relative_path.clone(),
+                name_indices,
+                path_indices,
+            })
+            .collect();
+        self.selected_index = 0;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    pub fn view(&self) -> Element<'_, QuickOpenMessage> {
+        let input = text_input("输入文件名或路径", &self.query)
+            .id(QUICK_OPEN_INPUT_ID)
+            .size(FONT_SIZE_BASE)
+            .padding(Padding::from([PADDING_SMALLER, PADDING_BASE]))
+            .on_input(QuickOpenMessage::QueryChanged)
+            .on_submit(QuickOpenMessage::Submit);
+        let results: Element<'_, QuickOpenMessage> = if self.results.is_empty() {
+            container(text("没有匹配的文件").size(FONT_SIZE_SMALLER))
+                .padding(Padding::from([PADDING_SMALLER, PADDING_BASE]))
+                .into()
+        } else {
+            column(
+                self.results
+                    .iter()
+                    .enumerate()
+                    .map(|(index, result)| self.generate_result_item(index, result)),
+            )
+            .into()
+        };
+        container(column![input, results].spacing(SPACING_SMALLER))
+            .padding(PADDING_SMALLEST)
+            .width(QUICK_OPEN_WIDTH)
+            .style(|theme: &Theme| {
+                let ex_palette = theme.extended_palette();
+                container::Style {
+                    background: Some(Background::Color(ex_palette.background.weakest.color)),
+                    border: Border {
+                        color: ex_palette.background.weak.color,
+                        ..DEFAULT_BORDER
+                    },
+                    shadow: SHADOW_BASE,
+                    ..container::Style::default()
+                }
+            })
+            .into()
+    }
+
+    pub fn generate_result_item<'a>(
+        &'a self,
+        index: usize,
+        result: &'a QuickOpenResult,
+    ) -> Element<'a, QuickOpenMessage> {
+        mouse_area(
+            container(column![
+                highlighted_text(&result.name, &result.name_indices, FONT_SIZE_BASE),
+                highlighted_text(&result.relative_path, &result.path_indices, FONT_SIZE_SMALLEST),
+            ])
+            .width(Length::Fill)
+            .padding(Padding::from([PADDING_SMALLEST, PADDING_BASE]))
+            .style(move |theme: &Theme| {
+                let ex_palette = theme.extended_palette();
+                let background = if self.selected_index == index {
+                    ex_palette.background.weaker.color
+                } else {
+                    ex_palette.background.weakest.color
+                };
+                container::Style {
+                    background: Some(Background::Color(background)),
+                    ..container::Style::default()
+                }
+            }),
+        )
+        .interaction(mouse::Interaction::Pointer)
+        .on_enter(QuickOpenMessage::HoverResult(index))
+        .on_press(QuickOpenMessage::ResultClicked(index))
+        .into()
+    }
+}
+
+// 名称匹配的额外得分，保证名称匹配排在只有路径匹配的结果前面
+const NAME_MATCH_BONUS: i64 = 1000;
+
+// 把连续的高亮字符合并成一段，减少span数量
+pub fn highlighted_text<'a, Message: 'a>(
+    content: &'a str,
+    indices: &[usize],
+    size: u32,
+) -> Element<'a, Message> {
+    let mut spans = vec![];
+    let mut segment = String::new();
+    let mut is_segment_highlighted = false;
+    for (index, c) in content.chars().enumerate() {
+        let is_highlighted = indices.binary_search(&index).is_ok();
+        if is_highlighted != is_segment_highlighted && !segment.is_empty() {
+            spans.push(highlight_span(std::mem::take(&mut segment), is_segment_highlighted));
+        }
+        is_segment_highlighted = is_highlighted;
+        segment.push(c);
+    }
+    if !segment.is_empty() {
+        spans.push(highlight_span(segment, is_segment_highlighted));
+    }
+    rich_text(spans).size(size).into()
+}
+
+fn highlight_span<'a>(segment: String, is_highlighted: bool) -> text::Span<'a> {
+    match is_highlighted {
+        true => span(segment).color(MATCH_HIGHLIGHT_COLOR),
+        false => span(segment),
+    }
+}
+
+// 按顺序在目标中查找查询的每个字符，返回得分和匹配到的字符位置
+// 连续匹配和单词开头的匹配得分更高，中间跳过的字符越多得分越低
+pub fn fuzzy_match(query: &str, target: &str) -> Option<(i64, Vec<usize>)> {
+    let target_chars = target.chars().collect::<Vec<_>>();
+    let mut indices: Vec<usize> = vec![];
+    let mut score = 0;
+    let mut target_index = 0;
+    for query_char in query.chars().filter(|c| !c.is_whitespace()) {
+        let query_char = query_char.to_lowercase().collect::<String>();
+        let matched_index = (target_index..target_chars.len()).find(|index| {
+            target_chars[*index].to_lowercase().collect::<String>() == query_char
+        })?;
+        score += 10;
+        match indices.last() {
+            Some(last_index) if last_index + 1 == matched_index => score += 15,
+            Some(last_index) => score -= (matched_index - last_index - 1).min(10) as i64,
+            None => score -= matched_index.min(10) as i64,
+        }
+        let is_word_start = matched_index == 0
+            || matches!(
+                target_chars[matched_index - 1],
+                '/' | '\\' | '_' | '-' | '.' | ' '
+            );
+        if is_word_start {
+            score += 10;
+        }
+        indices.push(matched_index);
+        target_index = matched_index + 1;
+    }
+    // 同样的匹配结果，目标越短越靠前
+    score -= (target_chars.len() / 10) as i64;
+    Some((score, indices))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match() {
+        assert_eq!(fuzzy_match("rdm", "README.md").map(|(_, indices)| indices), Some(vec![0, 3, 4]));
+        assert!(fuzzy_match("xyz", "README.md").is_none());
+        let (prefix_score, _) = fuzzy_match("note", "notes.md").unwrap();
+        let (scattered_score, _) = fuzzy_match("note", "n_o_t_e.md").unwrap();
+        assert!(prefix_score > scattered_score);
+    }
+}
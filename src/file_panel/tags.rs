@@ -0,0 +1,86 @@
+use crate::common::*;
+use iced::{
+    Background, Color, Element, Length, Padding, Task, Theme, mouse,
+    widget::{Column, container, mouse_area, row, scrollable, space, text},
+};
+
+// 工作区中所有标签和对应的笔记数，点击标签按它过滤文件树
+#[derive(Debug, Default)]
+pub struct TagList {
+    tags: Vec<(String, usize)>,
+    hovered: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub enum TagListMessage {
+    TagsLoaded(Vec<(String, usize)>),
+    HoverTag(usize),
+    // 发送给其他模块的消息
+    SendFilterByTag(Option<String>),
+}
+
+impl TagList {
+    pub fn update(&mut self, message: TagListMessage) -> Task<TagListMessage> {
+        match message {
+            TagListMessage::TagsLoaded(tags) => {
+                self.tags = tags;
+                self.hovered = None;
+                Task::none()
+            }
+            TagListMessage::HoverTag(index) => {
+                self.hovered = Some(index);
+                Task::none()
+            }
+            _ => Task::none(),
+        }
+    }
+
+    pub fn tag_names(&self) -> Vec<String> {
+        self.tags.iter().map(|(tag, _)| tag.clone()).collect()
+    }
+
+    // 再次点击已选中的标签时取消过滤
+    pub fn view<'a>(&'a self, selected: Option<&'a str>) -> Element<'a, TagListMessage> {
+        if self.tags.is_empty() {
+            return text("工作区中还没有标签").size(FONT_SIZE_SMALLER).into();
+        }
+        let mut content = Column::new();
+        for (index, (tag, count)) in self.tags.iter().enumerate() {
+            let is_selected =
+                selected.is_some_and(|selected| selected.to_lowercase() == tag.to_lowercase());
+            let filter = match is_selected {
+                true => None,
+                false => Some(tag.clone()),
+            };
+            content = content.push(
+                mouse_area(
+                    container(row![
+                        text!("#{}", tag).size(FONT_SIZE_SMALLER),
+                        space::horizontal(),
+                        text(count.to_string()).size(FONT_SIZE_SMALLER),
+                    ])
+                    .width(Length::Fill)
+                    .padding(Padding::from([PADDING_SMALLEST, PADDING_SMALLER]))
+                    .style(move |theme: &Theme| {
+                        let ex_palette = theme.extended_palette();
+                        let bg = if is_selected {
+                            ex_palette.background.strong.color
+                        } else if self.hovered == Some(index) {
+                            ex_palette.background.weaker.color
+                        } else {
+                            Color::TRANSPARENT
+                        };
+                        container::Style {
+                            background: Some(Background::Color(bg)),
+                            ..container::Style::default()
+                        }
+                    }),
+                )
+                .interaction(mouse::Interaction::Pointer)
+                .on_enter(TagListMessage::HoverTag(index))
+                .on_press(TagListMessage::SendFilterByTag(filter)),
+            );
+        }
+        scrollable(content).height(Length::Fill).into()
+    }
+}
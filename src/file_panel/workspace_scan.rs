@@ -0,0 +1,211 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    path::PathBuf,
+    sync::Arc,
+    time::SystemTime,
+};
+
+use crate::{
+    common::*,
+    file_panel::operation::{self, DirSnapshot, ScanOptions, ScanProgress},
+};
+use iced::{
+    Element, Padding, Subscription, Task, mouse,
+    widget::{mouse_area, row, space, text},
+};
+use tracing::{info, warn};
+
+// 首次读取工作区和定时扫描已打开工作区的状态
+// 同一时间只读取一个工作区，读取期间又打开的工作区排队依次读取
+#[derive(Debug, Default)]
+pub struct WorkspaceScan {
+    // 首次读取工作区的进度，读取完成后清空
+    progress: Option<Arc<ScanProgress>>,
+    scanning_root_path: Option<PathBuf>,
+    pending_root_paths: VecDeque<PathBuf>,
+    // 正在轮询扫描的工作区
+    polling_root_keys: HashSet<u32>,
+    // 已展开文件夹上一次轮询读取时的修改时间，时间没变的文件夹跳过
+    loaded_dir_modified: HashMap<PathBuf, SystemTime>,
+    // 各工作区连续轮询失败的次数，达到上限后停止轮询
+    poll_errors: HashMap<u32, usize>,
+}
+
+// 一个工作区中需要轮询的已展开文件夹，由文件树收集
+#[derive(Debug, Clone)]
+pub struct PollTarget {
+    pub root_key: u32,
+    pub root_path: PathBuf,
+    pub dir_paths: Vec<PathBuf>,
+    pub options: ScanOptions,
+}
+
+#[derive(Debug, Clone)]
+pub enum WorkspaceScanMessage {
+    // 首次读取工作区相关消息
+    Start(PathBuf),
+    Finish,
+    Cancel,
+    ProgressTick,
+    // 定时扫描已打开的工作区
+    Poll(Vec<PollTarget>),
+    Polled(u32, Result<BTreeMap<PathBuf, DirSnapshot>, AppError>),
+    // 扫描设置改变后文件夹的修改时间不变，清空记录让所有已展开的文件夹重新读取
+    ClearDirModified,
+    // 发送给其他模块的消息
+    SendLoadRoot(PathBuf),
+    SendFetchRoot(PathBuf),
+    SendDirSnapshots(u32, BTreeMap<PathBuf, DirSnapshot>),
+    SendError(AppError),
+}
+
+impl WorkspaceScan {
+    pub fn update(&mut self, message: WorkspaceScanMessage) -> Task<WorkspaceScanMessage> {
+        match message {
+            WorkspaceScanMessage::Start(root_path) => {
+                if self.progress.is_some() {
+                    info!("[WorkspaceScan-Start]:上一个工作区仍在读取中，稍后读取!");
+                    self.pending_root_paths.push_back(root_path);
+                    return Task::none();
+                }
+                self.progress = Some(Arc::new(ScanProgress::default()));
+                self.scanning_root_path = Some(root_path.clone());
+                Task::done(WorkspaceScanMessage::SendLoadRoot(root_path))
+            }
+            // 读取完成或失败后读取排队中的下一个工作区
+            WorkspaceScanMessage::Finish => {
+                self.progress = None;
+                self.scanning_root_path = None;
+                match self.pending_root_paths.pop_front() {
+                    Some(root_path) => Task::done(WorkspaceScanMessage::SendFetchRoot(root_path)),
+                    None => Task::none(),
+                }
+            }
+            WorkspaceScanMessage::Cancel => {
+                if let Some(progress) = &self.progress {
+                    progress.cancel();
+                }
+                self.pending_root_paths.clear();
+                Task::none()
+            }
+            // 上一次扫描还没结束或连续失败过多的工作区跳过
+            WorkspaceScanMessage::Poll(targets) => {
+                let mut tasks = vec![];
+                let mut loaded_dir_paths = HashSet::new();
+                for target in targets {
+                    loaded_dir_paths.extend(target.dir_paths.iter().cloned());
+                    if !self.is_polling(target.root_key)
+                        || !self.polling_root_keys.insert(target.root_key)
+                    {
+                        continue;
+                    }
+                    let dir_paths = target
+                        .dir_paths
+                        .into_iter()
+                        .map(|path| {
+                            let modified = self.loaded_dir_modified.get(&path).copied();
+                            (path, modified)
+                        })
+                        .collect();
+                    let root_key = target.root_key;
+                    tasks.push(Task::perform(
+                        operation::scan_loaded_dirs(target.root_path, dir_paths, target.options),
+                        move |result| WorkspaceScanMessage::Polled(root_key, result),
+                    ));
+                }
+                // 已删除或关闭的文件夹不再保留修改时间
+                self.loaded_dir_modified
+                    .retain(|path, _| loaded_dir_paths.contains(path));
+                Task::batch(tasks)
+            }
+            WorkspaceScanMessage::Polled(root_key, result) => {
+                self.polling_root_keys.remove(&root_key);
+                let dir_snapshots = match result {
+                    Ok(dir_snapshots) => {
+                        self.poll_errors.remove(&root_key);
+                        dir_snapshots
+                    }
+                    // 偶尔失败时等下一次扫描，连续失败时提示一次并停止轮询
+                    Err(error) => {
+                        let error_count = self.poll_errors.entry(root_key).or_default();
+                        *error_count += 1;
+                        warn!("[WorkspaceScan-Polled]:第{error_count}次扫描失败:{error}");
+                        if *error_count < WORKSPACE_POLL_MAX_ERRORS {
+                            return Task::none();
+                        }
+                        return Task::done(WorkspaceScanMessage::SendError(error));
+                    }
+                };
+                // 没有文件夹变动则直接结束
+                if dir_snapshots.is_empty() {
+                    return Task::none();
+                }
+                for (dir_path, dir_snapshot) in dir_snapshots.iter() {
+                    if let Some(modified) = dir_snapshot.modified {
+                        self.loaded_dir_modified.insert(dir_path.clone(), modified);
+                    }
+                }
+                Task::done(WorkspaceScanMessage::SendDirSnapshots(
+                    root_key,
+                    dir_snapshots,
+                ))
+            }
+            WorkspaceScanMessage::ClearDirModified => {
+                self.loaded_dir_modified.clear();
+                Task::none()
+            }
+            _ => Task::none(),
+        }
+    }
+
+    pub fn progress(&self) -> Option<Arc<ScanProgress>> {
+        self.progress.clone()
+    }
+
+    // 连续扫描失败达到上限的工作区不再轮询，重新打开后恢复
+    pub fn is_polling(&self, root_key: u32) -> bool {
+        self.poll_errors
+            .get(&root_key)
+            .is_none_or(|error_count| *error_count < WORKSPACE_POLL_MAX_ERRORS)
+    }
+
+    pub fn remove_root(&mut self, root_key: u32) {
+        self.polling_root_keys.remove(&root_key);
+        self.poll_errors.remove(&root_key);
+    }
+
+    // 正在读取和排队中的工作区，保存会话时原样保留
+    pub fn queued_root_paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.scanning_root_path
+            .iter()
+            .chain(self.pending_root_paths.iter())
+    }
+
+    // 读取大型工作区时显示进度，允许用户中途取消
+    pub fn view(&self) -> Element<'_, WorkspaceScanMessage> {
+        let Some(progress) = &self.progress else {
+            return space().into();
+        };
+        row![
+            text!("正在读取工作区，已读取 {} 项", progress.scanned_count()).size(FONT_SIZE_SMALLER),
+            space::horizontal(),
+            mouse_area(text("取消").size(FONT_SIZE_SMALLER))
+                .interaction(mouse::Interaction::Pointer)
+                .on_press(WorkspaceScanMessage::Cancel),
+        ]
+        .spacing(SPACING)
+        .padding(Padding::from([0., PADDING_SMALLER]))
+        .into()
+    }
+
+    // 读取期间定时刷新进度
+    pub fn subscription(&self) -> Subscription<WorkspaceScanMessage> {
+        match self.progress {
+            Some(_) => {
+                iced::time::every(iced::time::Duration::from_millis(SCAN_PROGRESS_INTERVAL_MS))
+                    .map(|_| WorkspaceScanMessage::ProgressTick)
+            }
+            None => Subscription::none(),
+        }
+    }
+}
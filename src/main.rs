@@ -9,6 +9,7 @@ mod file_panel;
 mod setting;
 mod menu_bar;
 mod dialog;
+mod context_menu;
 mod app;
 
 fn main() -> iced::Result {
@@ -18,5 +19,7 @@ fn main() -> iced::Result {
         .window_size(DEFAULT_WINDOW_SIZE)
         .theme(DEFAULT_THEME.clone())
         .settings(DEFAULT_APP_SETTING.clone())
+        // 关闭窗口请求交给App处理，先检查未保存的文档
+        .exit_on_close_request(false)
         .run()
 }
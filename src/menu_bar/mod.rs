@@ -2,15 +2,23 @@ use iced::{
     Background, Border, Color, Element, Length, Padding, Renderer, Task, Theme,
     border::Radius,
     mouse,
-    widget::{Container, MouseArea, canvas::path::lyon_path::NO_ATTRIBUTES, container, mouse_area, text},
+    widget::{Container, MouseArea, canvas::path::lyon_path::NO_ATTRIBUTES, container, mouse_area, row, text},
 };
 use iced_aw::{Menu, menu::Item};
+use std::path::PathBuf;
+use tracing::{error, info};
 
-use crate::common::*;
+use crate::{
+    common::*,
+    menu_bar::recent::RecentList,
+    setting::{self, RECENT_LIST_FILE},
+};
+mod recent; // 最近打开的文件和文件夹
 
 #[derive(Debug)]
 pub struct MenuBar {
     hovered_id: Option<usize>,
+    recent_list: RecentList,
 }
 
 #[derive(Debug, Clone)]
@@ -24,12 +32,35 @@ pub enum MenuBarMessage {
     CommandSaveAs,
     CommandImportImg,
     CommandImportImgFolder,
-    SettingAutoSave(bool)
+    CommandOpenWorkspaceFile,
+    CommandSaveWorkspaceFile,
+    CommandCheckLinks,
+    CommandUndoPathChange,
+    CommandTodayNote,
+    CommandShowCalendar,
+    CommandShowLinkGraph,
+    // 最近打开列表相关消息
+    RecentListLoaded(RecentList),
+    RecordRecent(PathBuf, RecentKind),
+    RecentPathsChecked(Vec<PathBuf>),
+    OpenRecent(PathBuf),
+    TogglePinRecent(PathBuf),
+    ClearRecent,
+    SendOpenRecent(PathBuf, RecentKind),
+    SettingAutoSave(bool),
+    SettingScanHidden(bool),
+    SettingRestoreSession(bool),
+    SettingTitleAsLabel(bool),
+    SettingOpenPreference,
+    SettingEditableExtension(&'static str, bool)
 }
 
 impl MenuBar {
     pub fn new() -> Self {
-        Self { hovered_id: None }
+        Self {
+            hovered_id: None,
+            recent_list: RecentList::default(),
+        }
     }
 
     pub fn update(&mut self, menu_bar_message: MenuBarMessage) -> Task<MenuBarMessage> {
@@ -38,10 +69,57 @@ impl MenuBar {
                 self.hovered_id = Some(id);
                 Task::none()
             }
+            MenuBarMessage::RecentListLoaded(recent_list) => {
+                self.recent_list = recent_list;
+                self.check_recent_paths()
+            }
+            MenuBarMessage::RecordRecent(path, kind) => {
+                self.recent_list.record(path, kind);
+                Task::batch([self.save_recent_list(), self.check_recent_paths()])
+            }
+            MenuBarMessage::RecentPathsChecked(missing_paths) => {
+                self.recent_list.mark_missing(&missing_paths);
+                Task::none()
+            }
+            // 已经不存在的条目不再打开，重新检查一次以便恢复后可以继续使用
+            MenuBarMessage::OpenRecent(path) => match self.recent_list.find(&path) {
+                Some(entry) if entry.is_missing => {
+                    info!("[MenuBar-OpenRecent]:{} 已不存在!", path.display());
+                    self.check_recent_paths()
+                }
+                Some(entry) => Task::done(MenuBarMessage::SendOpenRecent(path, entry.kind)),
+                None => Task::none(),
+            },
+            MenuBarMessage::TogglePinRecent(path) => {
+                self.recent_list.toggle_pin(&path);
+                self.save_recent_list()
+            }
+            MenuBarMessage::ClearRecent => {
+                self.recent_list.clear();
+                self.save_recent_list()
+            }
             _ => Task::none(),
         }
     }
 
+    pub fn check_recent_paths(&self) -> Task<MenuBarMessage> {
+        Task::perform(
+            recent::find_missing_paths(self.recent_list.paths()),
+            MenuBarMessage::RecentPathsChecked,
+        )
+    }
+
+    pub fn save_recent_list(&self) -> Task<MenuBarMessage> {
+        Task::future(setting::save_config(RECENT_LIST_FILE, self.recent_list.clone())).then(
+            |result| {
+                if let Err(error) = result {
+                    error!("[MenuBar-SaveRecentList]:最近打开列表保存失败:{}", error);
+                }
+                Task::none()
+            },
+        )
+    }
+
     pub fn view(&self, setting: &AppSetting) -> Container<'_, MenuBarMessage> {
         container(self.create_menu_bar(setting))
             .padding(Padding::from([PADDING_SMALLER, PADDING_BIGGER]))
@@ -55,24 +133,29 @@ impl MenuBar {
     }
 
     pub fn create_menu_bar(&self, setting: &AppSetting) -> Element<'_, MenuBarMessage> {
-        let file_menu = self.generate_menu(
-            "文件(F)",
-            vec![
-                ("新建文件", MenuBarMessage::CommandCreateNewFile, None),
-                ("打开文件", MenuBarMessage::CommandOpenFile, None),
-                ("保存文件", MenuBarMessage::CommandSaveFile, None),
-                ("打开文件夹", MenuBarMessage::CommandOpenFolder, None),
-                ("文件另存为", MenuBarMessage::CommandSaveAs, None),
-                ("导入图片", MenuBarMessage::CommandImportImg, None),
-                ("导入图片文件夹", MenuBarMessage::CommandImportImgFolder, None),
-            ],
-        );
+        let file_menu_items = vec![
+            ("新建文件", MenuBarMessage::CommandCreateNewFile, None),
+            ("今日笔记", MenuBarMessage::CommandTodayNote, None),
+            ("打开文件", MenuBarMessage::CommandOpenFile, None),
+            ("保存文件", MenuBarMessage::CommandSaveFile, None),
+            ("打开文件夹", MenuBarMessage::CommandOpenFolder, None),
+            ("打开工作区文件", MenuBarMessage::CommandOpenWorkspaceFile, None),
+            ("工作区另存为", MenuBarMessage::CommandSaveWorkspaceFile, None),
+            ("文件另存为", MenuBarMessage::CommandSaveAs, None),
+            ("导入图片", MenuBarMessage::CommandImportImg, None),
+            ("导入图片文件夹", MenuBarMessage::CommandImportImgFolder, None),
+        ];
+        let recent_menu_id = file_menu_items.len();
+        let mut file_menu_items = self.generate_menu_items(file_menu_items);
+        file_menu_items.push(self.generate_recent_menu(recent_menu_id));
+        let file_menu = self.wrap_menu("文件(F)", file_menu_items);
 
         let edit_menu = self.generate_menu(
             "编辑(E)",
             vec![
                 ("撤销", MenuBarMessage::None, None),
                 ("重做", MenuBarMessage::None, None),
+                ("撤销重命名/移动", MenuBarMessage::CommandUndoPathChange, None),
                 ("剪切", MenuBarMessage::None, None),
                 ("复制", MenuBarMessage::None, None),
                 ("粘贴", MenuBarMessage::None, None),
@@ -85,6 +168,8 @@ impl MenuBar {
             "视图(V)",
             vec![
                 ("预览窗口", MenuBarMessage::None, None),
+                ("日历", MenuBarMessage::CommandShowCalendar, None),
+                ("关系图", MenuBarMessage::CommandShowLinkGraph, None),
                 ("快照窗口", MenuBarMessage::None, None),
                 ("日志窗口", MenuBarMessage::None, None),
                 ("剪切板窗口", MenuBarMessage::None, None),
@@ -97,6 +182,7 @@ impl MenuBar {
                 ("创建快照", MenuBarMessage::None, None),
                 ("恢复快照", MenuBarMessage::None, None),
                 ("删除快照", MenuBarMessage::None, None),
+                ("检查链接", MenuBarMessage::CommandCheckLinks, None),
             ],
         );
 
@@ -104,6 +190,13 @@ impl MenuBar {
             "设置(S)",
             vec![
                 ("自动保存", MenuBarMessage::SettingAutoSave(!setting.auto_save), Some(setting.auto_save)),
+                ("显示隐藏文件", MenuBarMessage::SettingScanHidden(!setting.scan_include_hidden), Some(setting.scan_include_hidden)),
+                ("启动时恢复会话", MenuBarMessage::SettingRestoreSession(!setting.restore_session), Some(setting.restore_session)),
+                ("用标题显示文件名", MenuBarMessage::SettingTitleAsLabel(!setting.title_as_label), Some(setting.title_as_label)),
+                self.generate_extension_item("编辑.markdown文件", "markdown", setting),
+                self.generate_extension_item("编辑.mdx文件", "mdx", setting),
+                self.generate_extension_item("编辑.txt文件", "txt", setting),
+                ("偏好设置...", MenuBarMessage::SettingOpenPreference, None),
                 ("快照窗口", MenuBarMessage::None, None),
                 ("日志窗口", MenuBarMessage::None, None),
                 ("剪切板窗口", MenuBarMessage::None, None),
@@ -183,27 +276,129 @@ impl MenuBar {
         .on_press(message)
     }
 
+    // 开关某种文档扩展名，md文件始终可以编辑
+    pub fn generate_extension_item(
+        &self,
+        menu_text: &'static str,
+        extension: &'static str,
+        setting: &AppSetting,
+    ) -> (&'static str, MenuBarMessage, Option<bool>) {
+        let is_enabled = setting
+            .editable_extensions
+            .iter()
+            .any(|editable_extension| editable_extension == extension);
+        (
+            menu_text,
+            MenuBarMessage::SettingEditableExtension(extension, !is_enabled),
+            Some(is_enabled),
+        )
+    }
+
     pub fn generate_menu(
         &self,
         label: &'static str,
         sub_item: Vec<(&'static str, MenuBarMessage, Option<bool>)>,
     ) -> Item<'_, MenuBarMessage, Theme, Renderer> {
+        self.wrap_menu(label, self.generate_menu_items(sub_item))
+    }
+
+    pub fn generate_menu_items(
+        &self,
+        sub_item: Vec<(&'static str, MenuBarMessage, Option<bool>)>,
+    ) -> Vec<Item<'_, MenuBarMessage, Theme, Renderer>> {
+        sub_item
+            .into_iter()
+            .enumerate()
+            .map(|(id, (menu_text, message, color_change_flag))| {
+                let item = self.generate_menu_item(menu_text, id, message, color_change_flag);
+                Item::new(item)
+            })
+            .collect()
+    }
+
+    pub fn wrap_menu<'a>(
+        &'a self,
+        label: &'static str,
+        items: Vec<Item<'a, MenuBarMessage, Theme, Renderer>>,
+    ) -> Item<'a, MenuBarMessage, Theme, Renderer> {
         Item::with_menu(
             mouse_area(text(label).size(FONT_SIZE_BIGGER)).interaction(mouse::Interaction::Pointer),
-            Menu::new(
-                sub_item
-                    .into_iter()
-                    .enumerate()
-                    .map(|(id, (menu_text, message, color_change_flag))| {
-                        let item = self.generate_menu_item(menu_text, id, message, color_change_flag);
-                        Item::new(item)
-                    })
-                    .collect(),
-            )
-            .offset(MENU_OFFSET)
-            .padding(PADDING_SMALLEST)
-            .width(MENU_WIDTH),
+            Menu::new(items)
+                .offset(MENU_OFFSET)
+                .padding(PADDING_SMALLEST)
+                .width(MENU_WIDTH),
         )
         .close_on_click(true)
     }
+
+    // 最近打开子菜单，点击条目打开，点击星标固定或取消固定
+    pub fn generate_recent_menu(&self, id: usize) -> Item<'_, MenuBarMessage, Theme, Renderer> {
+        let mut items = self
+            .recent_list
+            .sorted_entries()
+            .into_iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let item_id = RECENT_MENU_ID_OFFSET + index;
+                let pin_label = if entry.is_pinned { "★" } else { "☆" };
+                let pin_button = mouse_area(text(pin_label).size(FONT_SIZE_BASE))
+                    .interaction(mouse::Interaction::Pointer)
+                    .on_press(MenuBarMessage::TogglePinRecent(entry.path.clone()));
+                let is_missing = entry.is_missing;
+                let label = text(entry.label())
+                    .width(Length::Fill)
+                    .size(FONT_SIZE_BASE)
+                    .wrapping(text::Wrapping::None)
+                    .style(move |theme: &Theme| {
+                        let ex_palette = theme.extended_palette();
+                        text::Style {
+                            color: is_missing.then_some(ex_palette.background.strong.color),
+                        }
+                    });
+                let item = mouse_area(
+                    container(row![label, pin_button].spacing(SPACING_SMALLER))
+                        .padding(Padding::from([PADDING_SMALLER, PADDING_BASE]))
+                        .style(move |theme: &Theme| {
+                            let ex_palette = theme.extended_palette();
+                            let background = if self.hovered_id == Some(item_id) {
+                                ex_palette.background.weaker.color
+                            } else {
+                                ex_palette.background.weakest.color
+                            };
+                            container::Style {
+                                background: Some(Background::Color(background)),
+                                ..container::Style::default()
+                            }
+                        }),
+                )
+                .on_enter(MenuBarMessage::HoverEnter(item_id))
+                .interaction(mouse::Interaction::Pointer)
+                .on_press(MenuBarMessage::OpenRecent(entry.path.clone()));
+                Item::new(item)
+            })
+            .collect::<Vec<_>>();
+        let clear_id = RECENT_MENU_ID_OFFSET + items.len();
+        if self.recent_list.is_empty() {
+            items.push(Item::new(self.generate_menu_item(
+                "（无）",
+                clear_id,
+                MenuBarMessage::None,
+                None,
+            )));
+        } else {
+            items.push(Item::new(self.generate_menu_item(
+                "清空列表",
+                clear_id,
+                MenuBarMessage::ClearRecent,
+                None,
+            )));
+        }
+        Item::with_menu(
+            self.generate_menu_item("最近打开", id, MenuBarMessage::None, None),
+            Menu::new(items)
+                .offset(MENU_OFFSET)
+                .padding(PADDING_SMALLEST)
+                .width(RECENT_MENU_WIDTH),
+        )
+    }
 }
@@ -0,0 +1,149 @@
+use std::path::{Path, PathBuf};
+
+use crate::common::*;
+use serde::{Deserialize, Serialize};
+
+// 最近打开的文件和文件夹，固定的条目排在最前且不会被挤出列表
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecentList {
+    entries: Vec<RecentEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentEntry {
+    pub path: PathBuf,
+    pub kind: RecentKind,
+    #[serde(default)]
+    pub is_pinned: bool,
+    // 路径已经不存在，每次读取列表和记录新条目时重新检查
+    #[serde(skip)]
+    pub is_missing: bool,
+}
+
+impl RecentEntry {
+    // 菜单中显示名称和所在文件夹
+    pub fn label(&self) -> String {
+        let name = self
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.path.to_string_lossy().into_owned());
+        let parent = self
+            .path
+            .parent()
+            .map(|parent| parent.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let name = match self.kind {
+            RecentKind::File => name,
+            RecentKind::Folder => format!("{}/", name),
+        };
+        match self.is_missing {
+            true => format!("{}  {}（已不存在）", name, parent),
+            false => format!("{}  {}", name, parent),
+        }
+    }
+}
+
+impl RecentList {
+    // 重新打开的条目移到最前，保留原来的固定状态
+    pub fn record(&mut self, path: PathBuf, kind: RecentKind) {
+        let is_pinned = self
+            .entries
+            .iter()
+            .find(|entry| entry.path == path)
+            .is_some_and(|entry| entry.is_pinned);
+        self.entries.retain(|entry| entry.path != path);
+        self.entries.insert(
+            0,
+            RecentEntry {
+                path,
+                kind,
+                is_pinned,
+                is_missing: false,
+            },
+        );
+        let mut unpinned_count = 0;
+        self.entries.retain(|entry| {
+            if entry.is_pinned {
+                return true;
+            }
+            unpinned_count += 1;
+            unpinned_count <= RECENT_LIST_LIMIT
+        });
+    }
+
+    pub fn toggle_pin(&mut self, path: &Path) {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.path == path) {
+            entry.is_pinned = !entry.is_pinned;
+        }
+    }
+
+    // 清空时保留固定的条目
+    pub fn clear(&mut self) {
+        self.entries.retain(|entry| entry.is_pinned);
+    }
+
+    pub fn mark_missing(&mut self, missing_paths: &[PathBuf]) {
+        for entry in self.entries.iter_mut() {
+            entry.is_missing = missing_paths.contains(&entry.path);
+        }
+    }
+
+    pub fn find(&self, path: &Path) -> Option<&RecentEntry> {
+        self.entries.iter().find(|entry| entry.path == path)
+    }
+
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.entries.iter().map(|entry| entry.path.clone()).collect()
+    }
+
+    // 固定的条目在前，其余按打开时间排列
+    pub fn sorted_entries(&self) -> Vec<&RecentEntry> {
+        let (mut pinned, unpinned): (Vec<_>, Vec<_>) =
+            self.entries.iter().partition(|entry| entry.is_pinned);
+        pinned.extend(unpinned);
+        pinned
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+pub async fn find_missing_paths(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut missing_paths = vec![];
+    for path in paths {
+        if !tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            missing_paths.push(path);
+        }
+    }
+    missing_paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_list() {
+        let mut list = RecentList::default();
+        for index in 0..RECENT_LIST_LIMIT + 2 {
+            list.record(PathBuf::from(format!("/notes/{}.md", index)), RecentKind::File);
+        }
+        list.toggle_pin(Path::new("/notes/2.md"));
+        list.record(PathBuf::from("/notes"), RecentKind::Folder);
+        let paths = list
+            .sorted_entries()
+            .iter()
+            .map(|entry| entry.path.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(paths.len(), RECENT_LIST_LIMIT + 1);
+        assert_eq!(paths[0], PathBuf::from("/notes/2.md"));
+        assert_eq!(paths[1], PathBuf::from("/notes"));
+        assert!(list.find(Path::new("/notes/1.md")).is_none());
+        list.record(PathBuf::from("/notes/2.md"), RecentKind::File);
+        assert!(list.find(Path::new("/notes/2.md")).unwrap().is_pinned);
+        list.clear();
+        assert_eq!(list.paths(), vec![PathBuf::from("/notes/2.md")]);
+    }
+}
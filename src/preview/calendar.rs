@@ -0,0 +1,195 @@
+use std::{collections::HashSet, sync::Arc};
+
+use crate::common::*;
+use iced::{
+    Alignment, Background, Border, Color, Element, Length, Padding, Task, Theme,
+    border::Radius,
+    mouse,
+    widget::{Column, Row, column, container, mouse_area, row, rule, space, text},
+};
+use jiff::{Span, civil::Date};
+
+// 日历中显示的周数，每周从星期一开始
+const CALENDAR_WEEKS: usize = 6;
+const WEEKDAY_LABELS: [&str; 7] = ["一", "二", "三", "四", "五", "六", "日"];
+
+// 按月显示日历，标记有日记的日期，点击某天打开当天的日记，没有时按模板新建
+#[derive(Debug)]
+pub struct Calendar {
+    // 显示的月份，始终为该月第一天
+    month: Date,
+    note_dates: Arc<HashSet<Date>>,
+    hovered: Option<Date>,
+}
+
+#[derive(Debug, Clone)]
+pub enum CalendarMessage {
+    UpdateNoteDates(Arc<HashSet<Date>>),
+    ShowMonth(Date),
+    HoverDay(Date),
+    // 发送给其他模块的消息
+    SendOpenDailyNote(Date),
+}
+
+impl Calendar {
+    pub fn new() -> Self {
+        Self {
+            month: today().first_of_month(),
+            note_dates: Arc::default(),
+            hovered: None,
+        }
+    }
+
+    pub fn update(&mut self, message: CalendarMessage) -> Task<CalendarMessage> {
+        match message {
+            CalendarMessage::UpdateNoteDates(note_dates) => {
+                self.note_dates = note_dates;
+                Task::none()
+            }
+            CalendarMessage::ShowMonth(month) => {
+                self.month = month.first_of_month();
+                self.hovered = None;
+                Task::none()
+            }
+            CalendarMessage::HoverDay(date) => {
+                self.hovered = Some(date);
+                Task::none()
+            }
+            _ => Task::none(),
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, CalendarMessage> {
+        let today = today();
+        let note_count = self
+            .note_dates
+            .iter()
+            .filter(|date| date.first_of_month() == self.month)
+            .count();
+        let tool_row = row![
+            generate_action_button("‹", CalendarMessage::ShowMonth(add_months(self.month, -1))),
+            text!("{}年{}月", self.month.year(), self.month.month()).size(FONT_SIZE_BIGGER),
+            generate_action_button("›", CalendarMessage::ShowMonth(add_months(self.month, 1))),
+            text!("{} 篇日记", note_count).size(FONT_SIZE_SMALLER),
+            space::horizontal(),
+            generate_action_button("本月", CalendarMessage::ShowMonth(today)),
+            generate_action_button("今日笔记", CalendarMessage::SendOpenDailyNote(today)),
+        ]
+        .spacing(SPACING)
+        .align_y(Alignment::Center);
+        let weekday_row = Row::with_children(WEEKDAY_LABELS.map(|label| {
+            text(label)
+                .size(FONT_SIZE_SMALLER)
+                .width(Length::Fill)
+                .align_x(Alignment::Center)
+                .into()
+        }))
+        .spacing(SPACING_SMALLER);
+        let offset = self.month.weekday().to_monday_zero_offset();
+        let mut day = self
+            .month
+            .checked_sub(Span::new().days(offset))
+            .unwrap_or(self.month);
+        let mut weeks = Column::new().spacing(SPACING_SMALLER);
+        for _ in 0..CALENDAR_WEEKS {
+            let mut week = Row::new().spacing(SPACING_SMALLER);
+            for _ in 0..WEEKDAY_LABELS.len() {
+                week = week.push(self.view_day(day, today));
+                day = day.tomorrow().unwrap_or(day);
+            }
+            weeks = weeks.push(week);
+        }
+        container(column![
+            tool_row.padding(Padding::from([PADDING_SMALLER, PADDING_BIGGER])),
+            rule::horizontal(1).style(|theme: &Theme| {
+                let ex_palette = theme.extended_palette();
+                rule::Style {
+                    color: ex_palette.background.weaker.color,
+                    radius: Radius::default(),
+                    snap: true,
+                    fill_mode: rule::FillMode::Full,
+                }
+            }),
+            column![weekday_row, weeks]
+                .spacing(SPACING_SMALLER)
+                .padding(Padding::from([PADDING_SMALLER, PADDING_BIGGER])),
+        ])
+        .height(Length::Fill)
+        .into()
+    }
+
+    fn view_day(&self, date: Date, today: Date) -> Element<'_, CalendarMessage> {
+        let is_current_month = date.first_of_month() == self.month;
+        let has_note = self.note_dates.contains(&date);
+        let is_hovered = self.hovered == Some(date);
+        let mut cell = column![text(date.day().to_string()).size(FONT_SIZE_BASE).style(
+            move |theme: &Theme| {
+                let ex_palette = theme.extended_palette();
+                text::Style {
+                    color: (!is_current_month).then_some(ex_palette.background.strong.color),
+                }
+            }
+        )]
+        .align_x(Alignment::Center);
+        if has_note {
+            let marker = text("●")
+                .size(FONT_SIZE_SMALLEST)
+                .style(|theme: &Theme| text::Style {
+                    color: Some(theme.palette().primary),
+                });
+            cell = cell.push(marker);
+        }
+        mouse_area(
+            container(cell)
+                .width(Length::Fill)
+                .height(CALENDAR_CELL_HEIGHT)
+                .padding(PADDING_SMALLER)
+                .style(move |theme: &Theme| {
+                    let ex_palette = theme.extended_palette();
+                    let palette = theme.palette();
+                    let bg = if is_hovered {
+                        ex_palette.background.strong.color
+                    } else if has_note {
+                        ex_palette.background.weaker.color
+                    } else {
+                        Color::TRANSPARENT
+                    };
+                    container::Style {
+                        background: Some(Background::Color(bg)),
+                        border: Border {
+                            color: match date == today {
+                                true => palette.primary,
+                                false => Color::TRANSPARENT,
+                            },
+                            ..DEFAULT_BORDER
+                        },
+                        ..container::Style::default()
+                    }
+                }),
+        )
+        .interaction(mouse::Interaction::Pointer)
+        .on_enter(CalendarMessage::HoverDay(date))
+        .on_press(CalendarMessage::SendOpenDailyNote(date))
+        .into()
+    }
+}
+
+fn today() -> Date {
+    jiff::Zoned::now().date()
+}
+
+fn add_months(month: Date, months: i32) -> Date {
+    month
+        .checked_add(Span::new().months(months))
+        .unwrap_or(month)
+}
+
+fn generate_action_button<'a>(
+    label: &'static str,
+    message: CalendarMessage,
+) -> Element<'a, CalendarMessage> {
+    mouse_area(text(label).size(FONT_SIZE_BIGGER))
+        .interaction(mouse::Interaction::Pointer)
+        .on_press(message)
+        .into()
+}
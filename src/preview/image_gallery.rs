@@ -42,6 +42,11 @@ pub enum ImageGalleryMessage {
     ChangeSelectedImg(u32),
     SendImgIdToFilePanel(u32),
     ShowImageGallery,
+    RequestContextMenu(u32),
+    SendContextMenuItems(Vec<ContextMenuItem>),
+    RemoveImage(u32),
+    // 恢复会话时载入图片，不切换到图片页
+    RestoreImages(Vec<ImgData>),
 }
 
 impl ImageGallery {
@@ -81,6 +86,16 @@ impl ImageGallery {
                 }
                 Task::none()
             }
+            ImageGalleryMessage::RestoreImages(image_data) => {
+                for image in image_data {
+                    self.images.entry(image.indep_id).or_insert(image);
+                }
+                if self.mode.is_none() && !self.images.is_empty() {
+                    self.mode = Some(ImageGalleryMode::GridView);
+                    self.selected_option_item = self.images.keys().min().map(|id| OptionItem(*id));
+                }
+                Task::none()
+            }
             ImageGalleryMessage::ChangeSelectedImg(id) => {
                 self.selected_option_item = Some(OptionItem(id));
                 if self.mode == Some(ImageGalleryMode::GridView) {
@@ -92,10 +107,41 @@ impl ImageGallery {
                 self.mode = Some(mode);
                 Task::none()
             }
+            ImageGalleryMessage::RequestContextMenu(id) => match self.images.get(&id) {
+                Some(image) => Task::done(ImageGalleryMessage::SendContextMenuItems(vec![
+                    ContextMenuItem::new("插入到文档", ContextAction::InsertImage(image.global_id)),
+                    ContextMenuItem::new(
+                        "在文件管理器中显示",
+                        ContextAction::RevealNode(image.global_id),
+                    ),
+                    ContextMenuItem::new("从图片库移除", ContextAction::RemoveImage(id)),
+                ])),
+                None => Task::none(),
+            },
+            ImageGalleryMessage::RemoveImage(id) => {
+                if self.images.remove(&id).is_some() {
+                    info!("图片已从图片库移除!");
+                }
+                if self.selected_option_item == Some(OptionItem(id)) {
+                    self.selected_option_item =
+                        self.images.keys().min().map(|id| OptionItem(*id));
+                }
+                if self.images.is_empty() {
+                    self.mode = None;
+                }
+                Task::none()
+            }
             _ => Task::none(),
         }
     }
 
+    // 图片库中的图片对应的文件节点，按编号排序
+    pub fn node_ids(&self) -> Vec<u32> {
+        let mut images = self.images.values().collect::<Vec<_>>();
+        images.sort_by_key(|image| image.indep_id);
+        images.into_iter().map(|image| image.global_id).collect()
+    }
+
     pub fn view(&self) -> Element<'_, ImageGalleryMessage> {
         let hidden_scroller = scrollable::Scrollbar::new().scroller_width(0).width(0);
         let (head, body): (
@@ -157,6 +203,9 @@ impl ImageGallery {
                                             .on_press(ImageGalleryMessage::ChangeSelectedImg(
                                                 image.indep_id
                                             ))
+                                            .on_right_press(ImageGalleryMessage::RequestContextMenu(
+                                                image.indep_id
+                                            ))
                                     ]
                                     .spacing(SPACING_SMALLER),
                                 );
@@ -183,6 +232,9 @@ impl ImageGallery {
                                 ).interaction(mouse::Interaction::Pointer)
                                 .on_press(ImageGalleryMessage::SendImgIdToFilePanel(
                                     image.global_id
+                                ))
+                                .on_right_press(ImageGalleryMessage::RequestContextMenu(
+                                    image.indep_id
                                 )))
                                 .into()
                             } else {
@@ -0,0 +1,552 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::common::{link_graph::LinkGraph, *};
+use iced::{
+    Alignment, Color, Element, Length, Padding, Point, Rectangle, Renderer, Subscription, Task,
+    Theme, Vector,
+    advanced::text::Alignment as TextAlignment,
+    border::Radius,
+    mouse,
+    widget::{Action, canvas, center, column, container, mouse_area, row, rule, space, text},
+};
+
+// 在画布上绘制工作区笔记之间的链接，拖动空白处平移，滚轮缩放，拖动节点调整位置
+// 点击节点打开笔记，局部模式下只显示与当前笔记相距不超过hops条链接的笔记
+#[derive(Debug)]
+pub struct LinkGraphView {
+    graph: Arc<LinkGraph>,
+    current: Option<usize>,
+    // 各节点在图中的坐标和度数，与graph.nodes一一对应
+    positions: Vec<Point>,
+    degrees: Vec<usize>,
+    visible: Vec<bool>,
+    local_mode: bool,
+    hops: usize,
+    // 屏幕坐标 = (图中坐标 + offset) * scale + 画布中心
+    offset: Vector,
+    scale: f32,
+    dragging: Option<usize>,
+    hovered: Option<usize>,
+    // 剩余的布局迭代次数，为0时停止计算
+    layout_steps: usize,
+    cache: canvas::Cache,
+}
+
+#[derive(Debug, Clone)]
+pub enum LinkGraphMessage {
+    UpdateGraph(Arc<LinkGraph>, Option<PathBuf>),
+    UpdateCurrent(Option<PathBuf>),
+    LayoutTick,
+    ToggleLocalMode,
+    ChangeHops(usize),
+    ResetView,
+    // 画布中的操作，位置相对于画布中心
+    Pan(Vector),
+    Zoom(f32, Vector),
+    DragNode(usize, Point),
+    ReleaseNode,
+    HoverNode(Option<usize>),
+    // 发送给其他模块的消息
+    SendOpenFileAtLine(PathBuf, usize),
+}
+
+// 画布内部的鼠标状态
+#[derive(Debug, Default)]
+pub enum GraphInteraction {
+    #[default]
+    None,
+    Panning(Point),
+    Dragging {
+        node: usize,
+        moved: bool,
+    },
+}
+
+impl Default for LinkGraphView {
+    fn default() -> Self {
+        Self {
+            graph: Arc::default(),
+            current: None,
+            positions: vec![],
+            degrees: vec![],
+            visible: vec![],
+            local_mode: false,
+            hops: 1,
+            offset: Vector::ZERO,
+            scale: 1.,
+            dragging: None,
+            hovered: None,
+            layout_steps: 0,
+            cache: canvas::Cache::default(),
+        }
+    }
+}
+
+impl LinkGraphView {
+    pub fn update(&mut self, message: LinkGraphMessage) -> Task<LinkGraphMessage> {
+        match message {
+            // 链接索引变化后重新生成关系图，已有节点保留原来的位置
+            LinkGraphMessage::UpdateGraph(graph, current) => {
+                let is_changed = self.graph.edges != graph.edges
+                    || self.graph.nodes.len() != graph.nodes.len()
+                    || self
+                        .graph
+                        .nodes
+                        .iter()
+                        .zip(&graph.nodes)
+                        .any(|(old, new)| old.path != new.path);
+                if is_changed {
+                    let previous = self
+                        .graph
+                        .nodes
+                        .iter()
+                        .zip(&self.positions)
+                        .map(|(node, position)| (node.path.clone(), *position))
+                        .collect::<HashMap<_, _>>();
+                    self.positions = graph
+                        .nodes
+                        .iter()
+                        .enumerate()
+                        .map(|(index, node)| {
+                            previous
+                                .get(&node.path)
+                                .copied()
+                                .unwrap_or_else(|| spiral_position(index))
+                        })
+                        .collect();
+                    self.degrees = (0..graph.nodes.len())
+                        .map(|index| graph.degree(index))
+                        .collect();
+                    self.dragging = None;
+                    self.hovered = None;
+                    self.layout_steps = GRAPH_LAYOUT_STEPS;
+                }
+                self.current = current.and_then(|path| graph.index_of(&path));
+                self.graph = graph;
+                self.refresh_visible();
+            }
+            // 打开其他笔记时关系图不变，只更新高亮
+            LinkGraphMessage::UpdateCurrent(current) => {
+                self.current = current.and_then(|path| self.graph.index_of(&path));
+                self.refresh_visible();
+            }
+            LinkGraphMessage::LayoutTick => {
+                let started = Instant::now();
+                for _ in 0..GRAPH_STEPS_PER_TICK.min(self.layout_steps) {
+                    self.layout_step();
+                    if started.elapsed() >= Duration::from_millis(GRAPH_TICK_BUDGET_MS) {
+                        break;
+                    }
+                }
+            }
+            LinkGraphMessage::ToggleLocalMode => {
+                self.local_mode = !self.local_mode;
+                self.refresh_visible();
+            }
+            LinkGraphMessage::ChangeHops(hops) => {
+                self.hops = hops.clamp(1, GRAPH_MAX_HOPS);
+                self.refresh_visible();
+            }
+            LinkGraphMessage::ResetView => {
+                self.offset = Vector::ZERO;
+                self.scale = 1.;
+            }
+            LinkGraphMessage::Pan(delta) => {
+                self.offset += delta * (1. / self.scale);
+            }
+            // 缩放时保持鼠标所在位置不动
+            LinkGraphMessage::Zoom(factor, cursor) => {
+                let scale = (self.scale * factor).clamp(GRAPH_MIN_SCALE, GRAPH_MAX_SCALE);
+                self.offset += cursor * (1. / scale - 1. / self.scale);
+                self.scale = scale;
+            }
+            // 拖动时节点固定在鼠标位置，周围的节点继续按布局移动
+            LinkGraphMessage::DragNode(node, position) => {
+                if let Some(node_position) = self.positions.get_mut(node) {
+                    *node_position = position;
+                    self.dragging = Some(node);
+                    self.layout_steps = self.layout_steps.max(GRAPH_LAYOUT_STEPS / 5);
+                }
+            }
+            LinkGraphMessage::ReleaseNode => {
+                self.dragging = None;
+            }
+            LinkGraphMessage::HoverNode(node) => {
+                self.hovered = node;
+            }
+            _ => return Task::none(),
+        }
+        self.cache.clear();
+        Task::none()
+    }
+
+    pub fn view(&self) -> Element<'_, LinkGraphMessage> {
+        let visible_count = self
+            .visible
+            .iter()
+            .filter(|is_visible| **is_visible)
+            .count();
+        let edge_count = self
+            .graph
+            .edges
+            .iter()
+            .filter(|(from, to)| self.visible[*from] && self.visible[*to])
+            .count();
+        let mut tool_row = row![
+            text!("{} 篇笔记  {} 条链接", visible_count, edge_count).size(FONT_SIZE_SMALLER),
+            space::horizontal(),
+            generate_action_button(
+                match self.local_mode {
+                    true => "局部",
+                    false => "全部",
+                },
+                LinkGraphMessage::ToggleLocalMode,
+            ),
+        ]
+        .spacing(SPACING)
+        .align_y(Alignment::Center);
+        if self.local_mode {
+            tool_row = tool_row.extend([
+                generate_action_button(
+                    "-",
+                    LinkGraphMessage::ChangeHops(self.hops.saturating_sub(1)),
+                ),
+                text!("{} 层", self.hops).size(FONT_SIZE_SMALLER).into(),
+                generate_action_button("+", LinkGraphMessage::ChangeHops(self.hops + 1)),
+            ]);
+        }
+        tool_row = tool_row.push(generate_action_button(
+            "重置视图",
+            LinkGraphMessage::ResetView,
+        ));
+        let graph: Element<'_, LinkGraphMessage> = match self.graph.nodes.is_empty() {
+            true => center(text("工作区中还没有笔记").size(FONT_SIZE_SMALLER)).into(),
+            false => canvas(self).width(Length::Fill).height(Length::Fill).into(),
+        };
+        container(column![
+            tool_row.padding(Padding::from([PADDING_SMALLER, PADDING_BIGGER])),
+            rule::horizontal(1).style(|theme: &Theme| {
+                let ex_palette = theme.extended_palette();
+                rule::Style {
+                    color: ex_palette.background.weaker.color,
+                    radius: Radius::default(),
+                    snap: true,
+                    fill_mode: rule::FillMode::Full,
+                }
+            }),
+            graph,
+        ])
+        .height(Length::Fill)
+        .into()
+    }
+
+    pub fn subscription(&self) -> Subscription<LinkGraphMessage> {
+        match self.layout_steps > 0 {
+            true => {
+                iced::time::every(Duration::from_millis(16)).map(|_| LinkGraphMessage::LayoutTick)
+            }
+            false => Subscription::none(),
+        }
+    }
+
+    // 局部模式下当前没有打开笔记时显示全部笔记，显示的笔记变化后重新布局
+    fn refresh_visible(&mut self) {
+        let node_count = self.graph.nodes.len();
+        let visible = match (self.local_mode, self.current) {
+            (true, Some(current)) => {
+                let neighbors = self.graph.neighbors_within(current, self.hops);
+                (0..node_count)
+                    .map(|index| neighbors.contains(&index))
+                    .collect()
+            }
+            _ => vec![true; node_count],
+        };
+        if visible != self.visible {
+            self.visible = visible;
+            self.layout_steps = GRAPH_LAYOUT_STEPS;
+        }
+    }
+
+    // Fruchterman-Reingold布局的一次迭代，最大移动距离随迭代次数递减
+    // 斥力使用网格近似，每个节点只与相邻格子中的节点计算，远处节点的斥力忽略
+    fn layout_step(&mut self) {
+        let visible = (0..self.positions.len())
+            .filter(|index| self.visible[*index])
+            .collect::<Vec<_>>();
+        let cell_of = |position: Point| {
+            (
+                (position.x / GRAPH_REPULSION_RANGE).floor() as i32,
+                (position.y / GRAPH_REPULSION_RANGE).floor() as i32,
+            )
+        };
+        let mut grid: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+        for index in visible.iter() {
+            grid.entry(cell_of(self.positions[*index]))
+                .or_default()
+                .push(*index);
+        }
+        let mut forces = vec![Vector::ZERO; self.positions.len()];
+        for a in visible.iter() {
+            let (cell_x, cell_y) = cell_of(self.positions[*a]);
+            let neighbors = (-1..=1)
+                .flat_map(|dx| (-1..=1).map(move |dy| (cell_x + dx, cell_y + dy)))
+                .filter_map(|cell| grid.get(&cell))
+                .flatten();
+            for b in neighbors {
+                if a == b {
+                    continue;
+                }
+                let delta = self.positions[*a] - self.positions[*b];
+                let distance = vector_length(delta).max(1.);
+                if distance < GRAPH_REPULSION_RANGE {
+                    forces[*a] +=
+                        delta * (GRAPH_EDGE_LENGTH * GRAPH_EDGE_LENGTH / distance / distance);
+                }
+            }
+        }
+        for (a, b) in &self.graph.edges {
+            if !self.visible[*a] || !self.visible[*b] {
+                continue;
+            }
+            let delta = self.positions[*a] - self.positions[*b];
+            let force = delta * (vector_length(delta) / GRAPH_EDGE_LENGTH);
+            forces[*a] -= force;
+            forces[*b] += force;
+        }
+        let temperature =
+            GRAPH_EDGE_LENGTH * 0.5 * self.layout_steps as f32 / GRAPH_LAYOUT_STEPS as f32;
+        for index in visible {
+            if self.dragging == Some(index) {
+                continue;
+            }
+            let position = self.positions[index];
+            let force = forces[index] - Vector::new(position.x, position.y) * GRAPH_GRAVITY;
+            let length = vector_length(force);
+            if length > 0. {
+                self.positions[index] = position + force * (length.min(temperature) / length);
+            }
+        }
+        self.layout_steps = self.layout_steps.saturating_sub(1);
+    }
+
+    fn node_radius(&self, index: usize) -> f32 {
+        GRAPH_NODE_RADIUS + (self.degrees[index] as f32).sqrt() * 1.5
+    }
+
+    fn to_screen(&self, position: Point, center: Vector) -> Point {
+        let position = position + self.offset;
+        Point::new(position.x * self.scale, position.y * self.scale) + center
+    }
+
+    fn to_graph(&self, position: Point, center: Vector) -> Point {
+        Point::new(
+            (position.x - center.x) / self.scale,
+            (position.y - center.y) / self.scale,
+        ) - self.offset
+    }
+
+    // 鼠标位置下的节点，后绘制的节点在上层，优先选中
+    fn node_at(&self, position: Point, center: Vector) -> Option<usize> {
+        (0..self.positions.len()).rev().find(|index| {
+            self.visible[*index]
+                && self
+                    .to_screen(self.positions[*index], center)
+                    .distance(position)
+                    <= self.node_radius(*index) * self.scale + 3.
+        })
+    }
+}
+
+impl canvas::Program<LinkGraphMessage> for LinkGraphView {
+    type State = GraphInteraction;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: &canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Option<Action<LinkGraphMessage>> {
+        let center = Vector::new(bounds.width / 2., bounds.height / 2.);
+        let canvas::Event::Mouse(mouse_event) = event else {
+            return None;
+        };
+        match mouse_event {
+            mouse::Event::ButtonPressed(mouse::Button::Left) => {
+                let position = cursor.position_in(bounds)?;
+                *state = match self.node_at(position, center) {
+                    Some(node) => GraphInteraction::Dragging { node, moved: false },
+                    None => GraphInteraction::Panning(position),
+                };
+                Some(Action::capture())
+            }
+            mouse::Event::CursorMoved { .. } => {
+                let position = cursor.position_from(bounds.position())?;
+                match state {
+                    GraphInteraction::Dragging { node, moved } => {
+                        *moved = true;
+                        let position = self.to_graph(position, center);
+                        Some(Action::publish(LinkGraphMessage::DragNode(*node, position)))
+                    }
+                    GraphInteraction::Panning(last) => {
+                        let delta = position - *last;
+                        *last = position;
+                        Some(Action::publish(LinkGraphMessage::Pan(delta)))
+                    }
+                    GraphInteraction::None => {
+                        let hovered = cursor
+                            .position_in(bounds)
+                            .and_then(|position| self.node_at(position, center));
+                        (hovered != self.hovered)
+                            .then(|| Action::publish(LinkGraphMessage::HoverNode(hovered)))
+                    }
+                }
+            }
+            // 按下后没有移动视为点击，打开该笔记
+            mouse::Event::ButtonReleased(mouse::Button::Left) => match std::mem::take(state) {
+                GraphInteraction::Dragging { node, moved: false } => {
+                    self.graph.nodes.get(node).map(|node| {
+                        Action::publish(LinkGraphMessage::SendOpenFileAtLine(node.path.clone(), 0))
+                    })
+                }
+                GraphInteraction::Dragging { moved: true, .. } => {
+                    Some(Action::publish(LinkGraphMessage::ReleaseNode))
+                }
+                _ => None,
+            },
+            mouse::Event::WheelScrolled { delta } => {
+                let position = cursor.position_in(bounds)?;
+                let lines = match delta {
+                    mouse::ScrollDelta::Lines { y, .. } => *y,
+                    mouse::ScrollDelta::Pixels { y, .. } => *y / 50.,
+                };
+                let factor = 1.1_f32.powf(lines);
+                Some(
+                    Action::publish(LinkGraphMessage::Zoom(
+                        factor,
+                        position - Point::ORIGIN - center,
+                    ))
+                    .and_capture(),
+                )
+            }
+            _ => None,
+        }
+    }
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let geometry = self.cache.draw(renderer, bounds.size(), |frame| {
+            let center = Vector::new(bounds.width / 2., bounds.height / 2.);
+            let ex_palette = theme.extended_palette();
+            let palette = theme.palette();
+            let focused = self.hovered.or(self.current);
+            for (a, b) in &self.graph.edges {
+                if !self.visible[*a] || !self.visible[*b] {
+                    continue;
+                }
+                let is_focused = focused.is_some_and(|index| index == *a || index == *b);
+                let color = match is_focused {
+                    true => palette.primary,
+                    false => ex_palette.background.strong.color,
+                };
+                frame.stroke(
+                    &canvas::Path::line(
+                        self.to_screen(self.positions[*a], center),
+                        self.to_screen(self.positions[*b], center),
+                    ),
+                    canvas::Stroke::default().with_color(color).with_width(1.),
+                );
+            }
+            for (index, node) in self.graph.nodes.iter().enumerate() {
+                if !self.visible[index] {
+                    continue;
+                }
+                let position = self.to_screen(self.positions[index], center);
+                let radius = self.node_radius(index) * self.scale;
+                let is_current = self.current == Some(index);
+                let is_hovered = self.hovered == Some(index);
+                let color = if is_current {
+                    palette.primary
+                } else if is_hovered {
+                    ex_palette.primary.weak.color
+                } else {
+                    ex_palette.background.strongest.color
+                };
+                frame.fill(&canvas::Path::circle(position, radius), color);
+                if self.scale >= GRAPH_LABEL_SCALE || is_current || is_hovered {
+                    frame.fill_text(canvas::Text {
+                        content: node.label.clone(),
+                        position: position + Vector::new(0., radius + 2.),
+                        color: match is_current || is_hovered {
+                            true => palette.text,
+                            false => Color {
+                                a: 0.7,
+                                ..palette.text
+                            },
+                        },
+                        size: FONT_SIZE_SMALLEST.into(),
+                        font: DEFAULT_FONT,
+                        align_x: TextAlignment::Center,
+                        ..canvas::Text::default()
+                    });
+                }
+            }
+        });
+        vec![geometry]
+    }
+
+    fn mouse_interaction(
+        &self,
+        state: &Self::State,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> mouse::Interaction {
+        match state {
+            GraphInteraction::Dragging { .. } | GraphInteraction::Panning(_) => {
+                mouse::Interaction::Grabbing
+            }
+            GraphInteraction::None => match cursor.position_in(bounds) {
+                Some(position)
+                    if self
+                        .node_at(position, Vector::new(bounds.width / 2., bounds.height / 2.))
+                        .is_some() =>
+                {
+                    mouse::Interaction::Pointer
+                }
+                _ => mouse::Interaction::default(),
+            },
+        }
+    }
+}
+
+// 新节点按黄金角螺旋排列，避免初始位置重叠
+fn spiral_position(index: usize) -> Point {
+    let angle = index as f32 * 2.399_963;
+    let radius = GRAPH_EDGE_LENGTH * 0.5 * (index as f32 + 1.).sqrt();
+    Point::new(radius * angle.cos(), radius * angle.sin())
+}
+
+fn vector_length(vector: Vector) -> f32 {
+    (vector.x * vector.x + vector.y * vector.y).sqrt()
+}
+
+fn generate_action_button<'a>(
+    label: &'static str,
+    message: LinkGraphMessage,
+) -> Element<'a, LinkGraphMessage> {
+    mouse_area(text(label).size(FONT_SIZE_BIGGER))
+        .interaction(mouse::Interaction::Pointer)
+        .on_press(message)
+        .into()
+}
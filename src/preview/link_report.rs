@@ -0,0 +1,302 @@
+use std::{cmp, collections::HashSet, path::PathBuf, sync::Arc};
+
+use crate::common::*;
+use iced::{
+    Background, Element, Length, Padding, Task, Theme,
+    border::Radius,
+    mouse,
+    widget::{checkbox, column, container, mouse_area, row, rule, scrollable, space, text},
+};
+use tracing::info;
+
+// 工作区链接检查报告，按文件分组显示失效的链接、图片和标题锚点，可以批量应用修改建议
+#[derive(Debug, Default)]
+pub struct LinkReport {
+    results: Vec<FileLinkIssues>,
+    // 勾选的修改建议，按文件序号和问题序号记录
+    selected: HashSet<(usize, usize)>,
+    status: LinkReportStatus,
+}
+
+#[derive(Debug, Default, Clone)]
+pub enum LinkReportStatus {
+    #[default]
+    Idle,
+    Checking,
+    Finished,
+    Fixing,
+    Failed(String),
+    Fixed(usize, usize),
+}
+
+#[derive(Debug, Clone)]
+pub enum LinkReportMessage {
+    StartCheck,
+    ReportLoaded(Result<Vec<FileLinkIssues>, AppError>),
+    ToggleIssue(usize, usize),
+    SelectAllSuggestions,
+    IssueClicked(PathBuf, usize),
+    ApplyFixes,
+    FixFinished(usize, usize),
+    // 发送给其他模块的消息
+    SendCheckRequest,
+    SendFixRequest(Vec<FileReplacement>),
+    SendOpenFileAtLine(PathBuf, usize),
+}
+
+impl LinkReport {
+    pub fn update(&mut self, message: LinkReportMessage) -> Task<LinkReportMessage> {
+        match message {
+            LinkReportMessage::StartCheck => {
+                self.results.clear();
+                self.selected.clear();
+                self.status = LinkReportStatus::Checking;
+                Task::done(LinkReportMessage::SendCheckRequest)
+            }
+            LinkReportMessage::ReportLoaded(result) => {
+                match result {
+                    Ok(results) => {
+                        info!(
+                            "[LinkReport-ReportLoaded]:{} 个文件中有失效链接",
+                            results.len()
+                        );
+                        self.results = results;
+                        self.status = LinkReportStatus::Finished;
+                    }
+                    Err(error) => self.status = LinkReportStatus::Failed(error.to_string()),
+                }
+                Task::done(LinkReportMessage::SelectAllSuggestions)
+            }
+            LinkReportMessage::ToggleIssue(file_index, issue_index) => {
+                if !self.selected.remove(&(file_index, issue_index)) {
+                    self.selected.insert((file_index, issue_index));
+                }
+                Task::none()
+            }
+            LinkReportMessage::SelectAllSuggestions => {
+                self.selected = self
+                    .results
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(file_index, file_issues)| {
+                        file_issues
+                            .issues
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, issue)| issue.suggestion.is_some())
+                            .map(move |(issue_index, _)| (file_index, issue_index))
+                    })
+                    .collect();
+                Task::none()
+            }
+            LinkReportMessage::IssueClicked(path, line) => {
+                Task::done(LinkReportMessage::SendOpenFileAtLine(path, line))
+            }
+            LinkReportMessage::ApplyFixes => {
+                let replacements = build_fix_replacements(&self.results, &self.selected);
+                if replacements.is_empty() {
+                    return Task::none();
+                }
+                self.results.clear();
+                self.selected.clear();
+                self.status = LinkReportStatus::Fixing;
+                Task::done(LinkReportMessage::SendFixRequest(replacements))
+            }
+            // 全文替换也会返回结果，只处理由这里发起的修复
+            LinkReportMessage::FixFinished(succeeded_count, failed_count) => {
+                if matches!(self.status, LinkReportStatus::Fixing) {
+                    self.status = LinkReportStatus::Fixed(succeeded_count, failed_count);
+                }
+                Task::none()
+            }
+            _ => Task::none(),
+        }
+    }
+
+    fn status_text(&self) -> String {
+        let issue_count = self
+            .results
+            .iter()
+            .map(|file_issues| file_issues.issues.len())
+            .sum::<usize>();
+        match &self.status {
+            LinkReportStatus::Idle => "检查工作区中所有文档的链接、图片和标题锚点".to_string(),
+            LinkReportStatus::Checking => "检查中...".to_string(),
+            LinkReportStatus::Finished if issue_count == 0 => "没有发现失效的链接".to_string(),
+            LinkReportStatus::Finished => {
+                format!("{} 个文件中有 {} 处问题", self.results.len(), issue_count)
+            }
+            LinkReportStatus::Fixing => "修复中...".to_string(),
+            LinkReportStatus::Failed(error) => error.clone(),
+            LinkReportStatus::Fixed(succeeded_count, failed_count) => {
+                format!(
+                    "已修复 {} 个文件，失败 {} 个",
+                    succeeded_count, failed_count
+                )
+            }
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, LinkReportMessage> {
+        let mut tool_row = row![
+            generate_action_button("检查链接".to_string(), LinkReportMessage::StartCheck),
+            space::horizontal(),
+        ]
+        .spacing(SPACING_BIGGER);
+        if !self.results.is_empty() {
+            tool_row = tool_row
+                .push(generate_action_button(
+                    "选中全部建议".to_string(),
+                    LinkReportMessage::SelectAllSuggestions,
+                ))
+                .push(generate_action_button(
+                    format!("应用选中的修改 ({})", self.selected.len()),
+                    LinkReportMessage::ApplyFixes,
+                ));
+        }
+        container(column![
+            column![tool_row, text(self.status_text()).size(FONT_SIZE_SMALLER)]
+                .spacing(SPACING_SMALLER)
+                .padding(Padding::from([PADDING_SMALLER, PADDING_BIGGER])),
+            rule::horizontal(1).style(|theme: &Theme| {
+                let ex_palette = theme.extended_palette();
+                rule::Style {
+                    color: ex_palette.background.weaker.color,
+                    radius: Radius::default(),
+                    snap: true,
+                    fill_mode: rule::FillMode::Full,
+                }
+            }),
+            scrollable(
+                container(self.view_results())
+                    .padding(Padding::from([PADDING_SMALLER, PADDING_BIGGER]))
+            )
+            .height(Length::Fill),
+        ])
+        .height(Length::Fill)
+        .into()
+    }
+
+    fn view_results(&self) -> Element<'_, LinkReportMessage> {
+        column(
+            self.results
+                .iter()
+                .enumerate()
+                .map(|(file_index, file_issues)| {
+                    column![
+                        text!(
+                            "{}  ({})",
+                            file_issues.relative_path,
+                            file_issues.issues.len()
+                        )
+                        .size(FONT_SIZE_BASE)
+                        .style(|theme: &Theme| text::Style {
+                            color: Some(theme.palette().primary),
+                        }),
+                        column(file_issues.issues.iter().enumerate().map(
+                            |(issue_index, issue)| {
+                                self.view_issue(file_issues, file_index, issue_index, issue)
+                            }
+                        )),
+                    ]
+                    .spacing(SPACING_SMALLER)
+                    .into()
+                }),
+        )
+        .spacing(SPACING_BIGGER)
+        .into()
+    }
+
+    fn view_issue<'a>(
+        &'a self,
+        file_issues: &'a FileLinkIssues,
+        file_index: usize,
+        issue_index: usize,
+        issue: &'a LinkIssue,
+    ) -> Element<'a, LinkReportMessage> {
+        let mut detail = column![
+            mouse_area(
+                text!("{}  {}  {}", issue.line + 1, issue.kind.label(), issue.dest)
+                    .size(FONT_SIZE_SMALLER)
+            )
+            .interaction(mouse::Interaction::Pointer)
+            .on_press(LinkReportMessage::IssueClicked(
+                file_issues.path.clone(),
+                issue.line
+            ))
+        ];
+        detail = detail.push(match issue.suggestion {
+            Some(ref suggestion) => checkbox(self.selected.contains(&(file_index, issue_index)))
+                .label(format!("你是不是要找: {}", suggestion))
+                .text_size(FONT_SIZE_SMALLER)
+                .on_toggle(move |_| LinkReportMessage::ToggleIssue(file_index, issue_index))
+                .into(),
+            None => Element::from(
+                text("没有找到相近的文件或标题")
+                    .size(FONT_SIZE_SMALLEST)
+                    .style(|theme: &Theme| text::Style {
+                        color: Some(theme.extended_palette().background.strong.text),
+                    }),
+            ),
+        });
+        container(detail.spacing(SPACING_SMALLER))
+            .width(Length::Fill)
+            .padding(Padding::from([PADDING_SMALLER, PADDING_BASE]))
+            .style(|theme: &Theme| {
+                let ex_palette = theme.extended_palette();
+                container::Style {
+                    background: Some(Background::Color(ex_palette.background.weakest.color)),
+                    ..container::Style::default()
+                }
+            })
+            .into()
+    }
+}
+
+// 从后往前替换地址，前面的字节范围不受影响
+fn build_fix_replacements(
+    results: &[FileLinkIssues],
+    selected: &HashSet<(usize, usize)>,
+) -> Vec<FileReplacement> {
+    results
+        .iter()
+        .enumerate()
+        .filter_map(|(file_index, file_issues)| {
+            let mut fixes = file_issues
+                .issues
+                .iter()
+                .enumerate()
+                .filter(|(issue_index, _)| selected.contains(&(file_index, *issue_index)))
+                .filter_map(|(_, issue)| {
+                    issue
+                        .suggestion
+                        .as_ref()
+                        .map(|suggestion| (issue.dest_range.clone(), suggestion))
+                })
+                .collect::<Vec<_>>();
+            if fixes.is_empty() {
+                return None;
+            }
+            fixes.sort_by_key(|(range, _)| cmp::Reverse(range.start));
+            let mut replaced = file_issues.content.to_string();
+            for (range, suggestion) in fixes {
+                replaced.replace_range(range, suggestion);
+            }
+            Some(FileReplacement {
+                path: file_issues.path.clone(),
+                original: Arc::clone(&file_issues.content),
+                replaced: Arc::new(replaced),
+            })
+        })
+        .collect()
+}
+
+fn generate_action_button<'a>(
+    label: String,
+    message: LinkReportMessage,
+) -> Element<'a, LinkReportMessage> {
+    mouse_area(text(label).size(FONT_SIZE_SMALLER))
+        .interaction(mouse::Interaction::Pointer)
+        .on_press(message)
+        .into()
+}
@@ -1,12 +1,17 @@
 use crate::{
-    common::*,
+    common::{
+        front_matter::FrontMatter,
+        md_link,
+        wiki_link::{self, NoteIndex, WIKI_LINK_SCHEME, WIKI_NEW_LINK_SCHEME},
+        *,
+    },
     preview::{
         markdown,
         viewer::{CUSTOM_SETTINGS, CustomViewer},
     },
 };
 use iced::{
-    Element, Length, Padding, Task, Theme,
+    Background, Element, Length, Padding, Task, Theme,
     border::Radius,
     mouse,
     widget::{
@@ -25,6 +30,14 @@ pub struct Markdown {
     content: iced_markdown::Content,
     image: HashMap<String, image::Handle>,
     image_base_path: Option<PathBuf>,
+    // 当前滚动比例，以及恢复会话时等内容渲染后再应用的滚动比例
+    scroll_y: f32,
+    pending_scroll_y: Option<f32>,
+    // 工作区笔记索引，用于判断[[链接]]能否打开
+    note_index: Arc<NoteIndex>,
+    // 文档开头的front matter不参与渲染，显示为属性卡片或隐藏
+    front_matter: Option<FrontMatter>,
+    hide_front_matter: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -42,8 +55,22 @@ pub enum MarkdownMessage {
     InsertImageToDict(Vec<(String, image::Handle)>),
     SendImgUrlToFilePanel(Vec<PathBuf>),
     LinkClicked(iced_markdown::Uri),
+    UpdateNoteIndex(Arc<NoteIndex>),
+    SendOpenWikiLink(String, Option<String>),
+    SendCreateWikiNote(String),
+    SendOpenLinkedPath(PathBuf, Option<String>),
+    SendFindAnchor(String),
+    SendOpenExternalLink(String),
+    ScrollToLine(usize),
+    Scrolled(f32),
+    RestoreScroll(f32),
+    ToggleFrontMatter,
+    SendEditFrontMatter,
 }
 
+// 预览的滚动区域id，跳转到大纲标题时使用
+pub const MARKDOWN_SCROLLABLE_ID: &str = "markdown-preview-scrollable";
+
 impl Markdown {
     pub fn update(&mut self, markdown_message: MarkdownMessage) -> Task<MarkdownMessage> {
         match markdown_message {
@@ -53,11 +80,28 @@ impl Markdown {
             }
             MarkdownMessage::LoadRawText(raw) => {
                 self.raw = Some(raw);
-                Task::done(MarkdownMessage::RenderMarkdown)
+                match self.pending_scroll_y.take() {
+                    Some(y) => Task::done(MarkdownMessage::RenderMarkdown).chain(
+                        iced::widget::operation::snap_to(
+                            MARKDOWN_SCROLLABLE_ID,
+                            scrollable::RelativeOffset { x: 0., y },
+                        ),
+                    ),
+                    None => Task::done(MarkdownMessage::RenderMarkdown),
+                }
             }
             MarkdownMessage::RenderMarkdown => {
                 if let Some(ref content) = self.raw {
-                    self.content = iced_markdown::Content::parse(content);
+                    self.front_matter = FrontMatter::parse(content);
+                    let body_start = self
+                        .front_matter
+                        .as_ref()
+                        .map(|front_matter| front_matter.range.end)
+                        .unwrap_or_default();
+                    self.content = iced_markdown::Content::parse(&wiki_link::wiki_links_to_markdown(
+                        &content[body_start..],
+                        &self.note_index,
+                    ));
                     if let Some(ref path) = self.image_base_path {
                         let url_vec = self
                             .content
@@ -110,25 +154,131 @@ impl Markdown {
                 }
                 Task::none()
             }
+            // 渲染后的高度无法对应到源码行，按行号在全文中的比例滚动
+            MarkdownMessage::ScrollToLine(line) => {
+                let line_count = self
+                    .raw
+                    .as_ref()
+                    .map(|raw| raw.lines().count())
+                    .unwrap_or_default();
+                if line_count <= 1 {
+                    return Task::none();
+                }
+                let y = line.min(line_count - 1) as f32 / (line_count - 1) as f32;
+                iced::widget::operation::snap_to(
+                    MARKDOWN_SCROLLABLE_ID,
+                    scrollable::RelativeOffset { x: 0., y },
+                )
+            }
+            MarkdownMessage::Scrolled(y) => {
+                self.scroll_y = y;
+                Task::none()
+            }
+            MarkdownMessage::RestoreScroll(y) => {
+                self.pending_scroll_y = Some(y);
+                Task::none()
+            }
+            MarkdownMessage::ToggleFrontMatter => {
+                self.hide_front_matter = !self.hide_front_matter;
+                Task::none()
+            }
+            // 笔记增删改名后重新判断链接能否打开
+            MarkdownMessage::UpdateNoteIndex(note_index) => {
+                self.note_index = note_index;
+                Task::done(MarkdownMessage::RenderMarkdown)
+            }
+            MarkdownMessage::LinkClicked(uri) => {
+                if let Some(target) = uri.strip_prefix(WIKI_LINK_SCHEME) {
+                    let (note, heading) = wiki_link::split_target(target);
+                    return Task::done(MarkdownMessage::SendOpenWikiLink(note, heading));
+                }
+                if let Some(target) = uri.strip_prefix(WIKI_NEW_LINK_SCHEME) {
+                    let (note, _) = wiki_link::split_target(target);
+                    return Task::done(MarkdownMessage::SendCreateWikiNote(note));
+                }
+                let dest = uri.trim();
+                if let Some(anchor) = dest.strip_prefix('#') {
+                    return Task::done(MarkdownMessage::SendFindAnchor(anchor.to_string()));
+                }
+                if md_link::is_external(dest) {
+                    let scheme = dest.split_once(':').map(|(scheme, _)| scheme.to_lowercase());
+                    if scheme.is_some_and(|scheme| {
+                        md_link::EXTERNAL_LINK_SCHEMES.contains(&scheme.as_str())
+                    }) {
+                        return Task::done(MarkdownMessage::SendOpenExternalLink(dest.to_string()));
+                    }
+                    info!("[Preview-LinkClicked]:暂不支持打开该链接 {}", uri);
+                    return Task::none();
+                }
+                // 相对路径按当前文档所在的文件夹解析，和图片的规则一致
+                let Some(path) = self
+                    .image_base_path
+                    .as_ref()
+                    .and_then(|base_path| md_link::resolve_relative(base_path, dest))
+                else {
+                    info!("[Preview-LinkClicked]:当前文档还没有保存，无法解析链接 {}", uri);
+                    return Task::none();
+                };
+                let anchor = md_link::split_anchor(dest)
+                    .1
+                    .filter(|anchor| !anchor.is_empty())
+                    .map(str::to_string);
+                // 指向当前文档的链接只滚动预览
+                if self.image_base_path.as_ref() == Some(&path) {
+                    return match anchor {
+                        Some(anchor) => Task::done(MarkdownMessage::SendFindAnchor(anchor)),
+                        None => Task::none(),
+                    };
+                }
+                Task::done(MarkdownMessage::SendOpenLinkedPath(path, anchor))
+            }
             _ => Task::none(),
         }
     }
 
+    pub fn scroll_y(&self) -> f32 {
+        self.scroll_y
+    }
+
     pub fn view(&self) -> Element<'_, MarkdownMessage> {
         let hidden_scroller = scrollable::Scrollbar::new().scroller_width(0).width(0);
-        container(column![
-            row![
-                space::horizontal(),
+        let mut tool_row = row![space::horizontal()];
+        if self.front_matter.is_some() && self.hide_front_matter {
+            tool_row = tool_row.push(
+                mouse_area(text("显示属性").size(FONT_SIZE_BIGGER))
+                    .interaction(mouse::Interaction::Pointer)
+                    .on_press(MarkdownMessage::ToggleFrontMatter),
+            );
+        }
+        let mut body = column![];
+        if let Some(ref front_matter) = self.front_matter
+            && !self.hide_front_matter
+        {
+            body = body.push(view_front_matter(front_matter));
+        }
+        body = body.push(iced_markdown::view_with(
+            self.content.items(),
+            *CUSTOM_SETTINGS,
+            &CustomViewer { image: &self.image },
+        ));
+        tool_row = tool_row
+            .push(
                 mouse_area(text("恢复").size(FONT_SIZE_BIGGER))
                     .interaction(mouse::Interaction::Pointer),
+            )
+            .push(
                 mouse_area(text("删除").size(FONT_SIZE_BIGGER))
                     .interaction(mouse::Interaction::Pointer),
+            )
+            .push(
                 mouse_area(text("另存为").size(FONT_SIZE_BIGGER))
-                    .interaction(mouse::Interaction::Pointer)
-            ]
-            .spacing(SPACING_BIGGER)
-            .padding(Padding::from([PADDING_SMALLER, PADDING_BIGGER]))
-            .height(Length::Shrink),
+                    .interaction(mouse::Interaction::Pointer),
+            );
+        container(column![
+            tool_row
+                .spacing(SPACING_BIGGER)
+                .padding(Padding::from([PADDING_SMALLER, PADDING_BIGGER]))
+                .height(Length::Shrink),
             rule::horizontal(1).style(|theme: &Theme| {
                 let ex_palette = theme.extended_palette();
                 rule::Style {
@@ -139,12 +289,10 @@ impl Markdown {
                 }
             }),
             container(
-                scrollable(iced_markdown::view_with(
-                    self.content.items(),
-                    *CUSTOM_SETTINGS,
-                    &CustomViewer { image: &self.image },
-                ))
-                .direction(scrollable::Direction::Vertical(hidden_scroller))
+                scrollable(body.spacing(SPACING_BIGGER))
+                    .direction(scrollable::Direction::Vertical(hidden_scroller))
+                    .id(MARKDOWN_SCROLLABLE_ID)
+                    .on_scroll(|viewport| MarkdownMessage::Scrolled(viewport.relative_offset().y))
             )
             .height(Length::Fill)
             .width(Length::Fill)
@@ -153,3 +301,68 @@ impl Markdown {
         .into()
     }
 }
+
+// 属性卡片，标题、日期和标签在前，其余字段按原顺序列出
+fn view_front_matter(front_matter: &FrontMatter) -> Element<'_, MarkdownMessage> {
+    let fields = front_matter.common_fields();
+    let mut header = row![].spacing(SPACING_BIGGER);
+    if !fields.title.is_empty() {
+        header = header.push(text(fields.title).size(FONT_SIZE_BIGGER));
+    }
+    if !fields.date.is_empty() {
+        header = header.push(text(fields.date).size(FONT_SIZE_SMALLER).style(
+            |theme: &Theme| text::Style {
+                color: Some(theme.extended_palette().background.strong.text),
+            },
+        ));
+    }
+    header = header
+        .push(space::horizontal())
+        .push(
+            mouse_area(text("编辑").size(FONT_SIZE_SMALLER))
+                .interaction(mouse::Interaction::Pointer)
+                .on_press(MarkdownMessage::SendEditFrontMatter),
+        )
+        .push(
+            mouse_area(text("隐藏").size(FONT_SIZE_SMALLER))
+                .interaction(mouse::Interaction::Pointer)
+                .on_press(MarkdownMessage::ToggleFrontMatter),
+        );
+    let mut card = column![header].spacing(SPACING_SMALLER);
+    if !fields.tags.is_empty() {
+        card = card.push(
+            text(
+                fields
+                    .tags
+                    .iter()
+                    .map(|tag| format!("#{}", tag.trim_start_matches('#')))
+                    .collect::<Vec<_>>()
+                    .join("  "),
+            )
+            .size(FONT_SIZE_SMALLER)
+            .style(|theme: &Theme| text::Style {
+                color: Some(theme.palette().primary),
+            }),
+        );
+    }
+    for field in front_matter.fields.iter().filter(|field| {
+        !["title", "date", "tags"]
+            .iter()
+            .any(|key| field.key.eq_ignore_ascii_case(key))
+    }) {
+        card = card.push(
+            text!("{}: {}", field.key, field.value.display()).size(FONT_SIZE_SMALLEST),
+        );
+    }
+    container(card)
+        .width(Length::Fill)
+        .padding(Padding::from([PADDING_SMALLER, PADDING_BASE]))
+        .style(|theme: &Theme| {
+            let ex_palette = theme.extended_palette();
+            container::Style {
+                background: Some(Background::Color(ex_palette.background.weakest.color)),
+                ..container::Style::default()
+            }
+        })
+        .into()
+}
@@ -1,9 +1,13 @@
 use crate::{
-    common::*,
+    common::{journal, link_graph::LinkGraph, wiki_link::NoteIndex, *},
     preview::{
+        calendar::{Calendar, CalendarMessage},
         image_gallery::{ImageGallery, ImageGalleryMessage},
+        link_graph::{LinkGraphMessage, LinkGraphView},
+        link_report::{LinkReport, LinkReportMessage},
         log_viewer::{LogViewer, LogViewerMessage},
         markdown::{Markdown, MarkdownMessage},
+        search::{Search, SearchMessage},
         text_board::{TextBoard, TextBoardMessage},
     },
 };
@@ -18,12 +22,17 @@ use iced::{
         scrollable, space, text, text_editor,
     },
 };
-use jiff::civil::Weekday;
-use std::{path::PathBuf, sync::Arc};
-use tracing::info;
+use serde::{Deserialize, Serialize};
+use jiff::civil::Date;
+use std::{collections::HashSet, path::PathBuf, sync::Arc};
+use tracing::{error, info};
+mod calendar; // 按月显示的日记日历
 mod image_gallery;
+mod link_graph; // 笔记之间链接的关系图
+mod link_report; // 工作区链接检查报告
 mod log_viewer;
 mod markdown;
+mod search; // 工作区全文搜索和替换
 mod text_board;
 mod viewer;
 #[derive(Debug)]
@@ -38,6 +47,10 @@ pub struct Preview {
     image_gallery: ImageGallery,
     text_board: TextBoard,
     log_viewer: LogViewer,
+    search: Search,
+    link_report: LinkReport,
+    calendar: Calendar,
+    link_graph: LinkGraphView,
 }
 
 #[derive(Debug, Clone)]
@@ -52,16 +65,58 @@ pub enum PreviewMessage {
     Markdown(MarkdownMessage),
     ImageGallery(ImageGalleryMessage),
     SendImgIdToFilePanel(u32),
+    SendContextMenuItems(Vec<ContextMenuItem>),
+    HandleContextAction(ContextAction),
     TextBoard(TextBoardMessage),
     LogView(LogViewerMessage),
+    Search(SearchMessage),
+    LinkReport(LinkReportMessage),
+    // 全文搜索和替换需要文件面板和编辑器配合
+    SendSearchRequest,
+    StartWorkspaceSearch(Result<SearchTargets, AppError>),
+    SendReplaceRequest(Vec<FileReplacement>),
+    ReplaceFinished(usize, usize),
+    SendOpenFileAtLine(PathBuf, usize),
+    // 链接检查
+    StartLinkCheck,
+    SendLinkCheckRequest,
+    LinkReportLoaded(Result<Vec<FileLinkIssues>, AppError>),
+    ScrollToLine(usize),
+    // 笔记之间的[[链接]]
+    UpdateNoteIndex(Arc<NoteIndex>),
+    SendOpenWikiLink(String, Option<String>),
+    SendCreateWikiNote(String),
+    // 预览中点击的链接
+    SendOpenLinkedPath(PathBuf, Option<String>),
+    SendFindAnchor(String),
+    SendOpenExternalLink(String),
+    SendEditFrontMatter,
+    OpenExternalLink(String),
+    HandleError(AppError),
+    // 日记日历
+    Calendar(CalendarMessage),
+    UpdateJournalDates(Arc<HashSet<Date>>),
+    SendOpenDailyNote(Date),
+    // 链接关系图
+    LinkGraph(LinkGraphMessage),
+    UpdateLinkGraph(Arc<LinkGraph>, Option<PathBuf>),
+    UpdateLinkGraphCurrent(Option<PathBuf>),
+    // 恢复会话
+    RestoreSession(PreviewPage, f32),
+    RestoreGalleryImages(Vec<ImgData>),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
 pub enum PreviewPage {
+    #[default]
     MarkDown,
     TextBoard,
     ImageGallery,
     LogViewer,
+    Search,
+    LinkReport,
+    Calendar,
+    LinkGraph,
 }
 
 impl Preview {
@@ -75,6 +130,10 @@ impl Preview {
             image_gallery: ImageGallery::default(),
             text_board: TextBoard::default(),
             log_viewer: LogViewer::new(),
+            search: Search::default(),
+            link_report: LinkReport::default(),
+            calendar: Calendar::new(),
+            link_graph: LinkGraphView::default(),
         }
     }
 
@@ -103,6 +162,24 @@ impl Preview {
             }
             // 处理各种子模块预览界面消息
             PreviewMessage::Markdown(markdown_message) => match markdown_message {
+                MarkdownMessage::SendOpenWikiLink(note, heading) => {
+                    Task::done(PreviewMessage::SendOpenWikiLink(note, heading))
+                }
+                MarkdownMessage::SendCreateWikiNote(note) => {
+                    Task::done(PreviewMessage::SendCreateWikiNote(note))
+                }
+                MarkdownMessage::SendOpenLinkedPath(path, anchor) => {
+                    Task::done(PreviewMessage::SendOpenLinkedPath(path, anchor))
+                }
+                MarkdownMessage::SendFindAnchor(anchor) => {
+                    Task::done(PreviewMessage::SendFindAnchor(anchor))
+                }
+                MarkdownMessage::SendOpenExternalLink(url) => {
+                    Task::done(PreviewMessage::SendOpenExternalLink(url))
+                }
+                MarkdownMessage::SendEditFrontMatter => {
+                    Task::done(PreviewMessage::SendEditFrontMatter)
+                }
                 _ => self
                     .marddown
                     .update(markdown_message)
@@ -116,11 +193,17 @@ impl Preview {
                 ImageGalleryMessage::SendImgIdToFilePanel(id) => {
                     Task::done(PreviewMessage::SendImgIdToFilePanel(id))
                 }
+                ImageGalleryMessage::SendContextMenuItems(items) => {
+                    Task::done(PreviewMessage::SendContextMenuItems(items))
+                }
                 _ => self
                     .image_gallery
                     .update(image_gallery_message)
                     .map(PreviewMessage::ImageGallery),
             },
+            PreviewMessage::HandleContextAction(ContextAction::RemoveImage(id)) => Task::done(
+                PreviewMessage::ImageGallery(ImageGalleryMessage::RemoveImage(id)),
+            ),
             PreviewMessage::TextBoard(text_board_message) => match text_board_message {
                 _ => self
                     .text_board
@@ -133,6 +216,114 @@ impl Preview {
                     .update(log_view_message)
                     .map(PreviewMessage::LogView),
             },
+            PreviewMessage::Search(search_message) => match search_message {
+                SearchMessage::SendSearchRequest => Task::done(PreviewMessage::SendSearchRequest),
+                SearchMessage::SendReplaceRequest(replacements) => {
+                    Task::done(PreviewMessage::SendReplaceRequest(replacements))
+                }
+                SearchMessage::SendOpenFileAtLine(path, line) => {
+                    Task::done(PreviewMessage::SendOpenFileAtLine(path, line))
+                }
+                _ => self.search.update(search_message).map(PreviewMessage::Search),
+            },
+            PreviewMessage::LinkReport(link_report_message) => match link_report_message {
+                LinkReportMessage::SendCheckRequest => {
+                    Task::done(PreviewMessage::SendLinkCheckRequest)
+                }
+                LinkReportMessage::SendFixRequest(replacements) => {
+                    Task::done(PreviewMessage::SendReplaceRequest(replacements))
+                }
+                LinkReportMessage::SendOpenFileAtLine(path, line) => {
+                    Task::done(PreviewMessage::SendOpenFileAtLine(path, line))
+                }
+                _ => self
+                    .link_report
+                    .update(link_report_message)
+                    .map(PreviewMessage::LinkReport),
+            },
+            PreviewMessage::Calendar(calendar_message) => match calendar_message {
+                CalendarMessage::SendOpenDailyNote(date) => {
+                    Task::done(PreviewMessage::SendOpenDailyNote(date))
+                }
+                _ => self
+                    .calendar
+                    .update(calendar_message)
+                    .map(PreviewMessage::Calendar),
+            },
+            PreviewMessage::LinkGraph(link_graph_message) => match link_graph_message {
+                LinkGraphMessage::SendOpenFileAtLine(path, line) => {
+                    Task::done(PreviewMessage::SendOpenFileAtLine(path, line))
+                }
+                _ => self
+                    .link_graph
+                    .update(link_graph_message)
+                    .map(PreviewMessage::LinkGraph),
+            },
+            PreviewMessage::UpdateLinkGraph(graph, current) => Task::done(
+                PreviewMessage::LinkGraph(LinkGraphMessage::UpdateGraph(graph, current)),
+            ),
+            PreviewMessage::UpdateLinkGraphCurrent(current) => Task::done(
+                PreviewMessage::LinkGraph(LinkGraphMessage::UpdateCurrent(current)),
+            ),
+            PreviewMessage::UpdateJournalDates(note_dates) => Task::done(
+                PreviewMessage::Calendar(CalendarMessage::UpdateNoteDates(note_dates)),
+            ),
+            PreviewMessage::StartLinkCheck => {
+                self.current_page = PreviewPage::LinkReport;
+                Task::done(PreviewMessage::LinkReport(LinkReportMessage::StartCheck))
+            }
+            PreviewMessage::LinkReportLoaded(result) => Task::done(PreviewMessage::LinkReport(
+                LinkReportMessage::ReportLoaded(result),
+            )),
+            PreviewMessage::ScrollToLine(line) => {
+                self.current_page = PreviewPage::MarkDown;
+                Task::done(PreviewMessage::Markdown(MarkdownMessage::ScrollToLine(line)))
+            }
+            PreviewMessage::RestoreSession(page, scroll_y) => {
+                self.current_page = page;
+                Task::done(PreviewMessage::Markdown(MarkdownMessage::RestoreScroll(scroll_y)))
+            }
+            PreviewMessage::UpdateNoteIndex(note_index) => Task::done(PreviewMessage::Markdown(
+                MarkdownMessage::UpdateNoteIndex(note_index),
+            )),
+            // 用户确认后使用设置中的程序打开外部链接
+            PreviewMessage::OpenExternalLink(url) => {
+                let opener = setting.link_opener.clone();
+                Task::future(open_with_system(opener, url.clone())).then(move |result| {
+                    match result {
+                        Ok(_) => {
+                            info!("[Preview-OpenExternalLink]:已使用系统程序打开链接!");
+                            Task::none()
+                        }
+                        Err(error) => Task::done(PreviewMessage::HandleError(
+                            AppError::PreviewError(format!(
+                                "[Preview-OpenExternalLink]:打开链接{}失败:{}",
+                                url, error
+                            )),
+                        )),
+                    }
+                })
+            }
+            PreviewMessage::HandleError(error) => {
+                error!("{}", error);
+                Task::none()
+            }
+            PreviewMessage::RestoreGalleryImages(image_data) => Task::done(
+                PreviewMessage::ImageGallery(ImageGalleryMessage::RestoreImages(image_data)),
+            ),
+            PreviewMessage::StartWorkspaceSearch(result) => {
+                Task::done(PreviewMessage::Search(SearchMessage::StartSearch(result)))
+            }
+            PreviewMessage::ReplaceFinished(succeeded_count, failed_count) => Task::batch([
+                Task::done(PreviewMessage::Search(SearchMessage::ReplaceFinished(
+                    succeeded_count,
+                    failed_count,
+                ))),
+                Task::done(PreviewMessage::LinkReport(LinkReportMessage::FixFinished(
+                    succeeded_count,
+                    failed_count,
+                ))),
+            ]),
             _ => Task::none(),
         }
     }
@@ -145,6 +336,10 @@ impl Preview {
             }
             PreviewPage::TextBoard => self.text_board.veiw().map(PreviewMessage::TextBoard),
             PreviewPage::LogViewer => self.log_viewer.view().map(PreviewMessage::LogView),
+            PreviewPage::Search => self.search.view().map(PreviewMessage::Search),
+            PreviewPage::LinkReport => self.link_report.view().map(PreviewMessage::LinkReport),
+            PreviewPage::Calendar => self.calendar.view().map(PreviewMessage::Calendar),
+            PreviewPage::LinkGraph => self.link_graph.view().map(PreviewMessage::LinkGraph),
         };
 
         container(column![
@@ -154,6 +349,10 @@ impl Preview {
                     self.generate_page_change_button("图片", PreviewPage::ImageGallery),
                     self.generate_page_change_button("文本", PreviewPage::TextBoard),
                     self.generate_page_change_button("日志", PreviewPage::LogViewer),
+                    self.generate_page_change_button("搜索", PreviewPage::Search),
+                    self.generate_page_change_button("链接", PreviewPage::LinkReport),
+                    self.generate_page_change_button("日历", PreviewPage::Calendar),
+                    self.generate_page_change_button("图谱", PreviewPage::LinkGraph),
                 ]
                 .height(Length::Shrink)
             )
@@ -192,11 +391,34 @@ impl Preview {
         })
     }
 
+    pub fn current_page(&self) -> PreviewPage {
+        self.current_page
+    }
+
+    pub fn scroll_y(&self) -> f32 {
+        self.marddown.scroll_y()
+    }
+
+    pub fn gallery_node_ids(&self) -> Vec<u32> {
+        self.image_gallery.node_ids()
+    }
+
+    pub fn collect_unsaved_docs(&self) -> Vec<UnsavedDoc> {
+        self.text_board.collect_unsaved_docs()
+    }
+
     pub fn subscription(&self) -> Subscription<PreviewMessage> {
         Subscription::batch([
             iced::time::every(iced::time::Duration::from_secs(1))
                 .map(|_| PreviewMessage::UpdateTimeStr),
             self.log_viewer.subscription().map(PreviewMessage::LogView),
+            // 只在显示关系图时计算布局
+            match self.current_page {
+                PreviewPage::LinkGraph => {
+                    self.link_graph.subscription().map(PreviewMessage::LinkGraph)
+                }
+                _ => Subscription::none(),
+            },
         ])
     }
 
@@ -236,15 +458,6 @@ impl Preview {
     }
 
     pub fn get_week_str() -> String {
-        let weekday = jiff::Zoned::now().weekday();
-        match weekday {
-            Weekday::Monday => "星期一".to_string(),
-            Weekday::Tuesday => "星期二".to_string(),
-            Weekday::Wednesday => "星期三".to_string(),
-            Weekday::Thursday => "星期四".to_string(),
-            Weekday::Friday => "星期五".to_string(),
-            Weekday::Saturday => "星期六".to_string(),
-            Weekday::Sunday => "星期天".to_string(),
-        }
+        journal::weekday_label(jiff::Zoned::now().weekday()).to_string()
     }
 }
@@ -0,0 +1,626 @@
+use std::{ops::Range, path::PathBuf, sync::Arc};
+
+use crate::common::*;
+use fancy_regex::{NoExpand, Regex};
+use iced::{
+    Background, Element, Length, Padding, Task, Theme,
+    border::Radius,
+    mouse,
+    task::{self, sipper},
+    widget::{
+        column, container, mouse_area, rich_text, row, rule, scrollable, space, span, text,
+        text_input,
+    },
+};
+use tracing::{error, info};
+
+// 工作区全文搜索和替换，按文件分组显示每一处匹配所在的行
+#[derive(Debug, Default)]
+pub struct Search {
+    query: String,
+    replacement: String,
+    options: SearchOptions,
+    results: Vec<FileMatches>,
+    // 得到当前结果时使用的正则和选项，之后修改输入框也按搜索时的条件替换
+    matcher: Option<(Regex, SearchOptions)>,
+    status: SearchStatus,
+    // 每次搜索递增，丢弃旧搜索还在返回的结果
+    search_id: u64,
+    search_handle: Option<task::Handle>,
+    // 确认前展示的替换预览，为None时显示搜索结果
+    replace_preview: Option<ReplacePreview>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SearchOptions {
+    is_regex: bool,
+    is_case_sensitive: bool,
+    is_whole_word: bool,
+}
+
+#[derive(Debug, Default, Clone)]
+pub enum SearchStatus {
+    #[default]
+    Idle,
+    Searching,
+    Finished,
+    Replacing,
+    Failed(String),
+    Replaced(usize, usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct FileMatches {
+    path: PathBuf,
+    relative_path: String,
+    // 搜索时读取的内容，替换时用来确认文件没有被再次修改
+    content: Arc<String>,
+    hits: Vec<LineHit>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LineHit {
+    // 从0开始的行号
+    line: usize,
+    preview: String,
+    // 预览文本中匹配部分的字节范围
+    ranges: Vec<Range<usize>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplacePreview {
+    changes: Vec<ReplaceChange>,
+    replacements: Vec<FileReplacement>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplaceChange {
+    relative_path: String,
+    line: usize,
+    before: String,
+    after: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum SearchMessage {
+    QueryChanged(String),
+    ReplacementChanged(String),
+    ToggleRegex,
+    ToggleCaseSensitive,
+    ToggleWholeWord,
+    Submit,
+    StartSearch(Result<SearchTargets, AppError>),
+    FileSearched(u64, FileMatches),
+    SearchFinished(u64),
+    HitClicked(PathBuf, usize),
+    PreviewReplace,
+    CancelReplace,
+    ConfirmReplace,
+    ReplaceFinished(usize, usize),
+    // 发送给其他模块的消息
+    SendSearchRequest,
+    SendReplaceRequest(Vec<FileReplacement>),
+    SendOpenFileAtLine(PathBuf, usize),
+}
+
+impl Search {
+    pub fn update(&mut self, message: SearchMessage) -> Task<SearchMessage> {
+        match message {
+            SearchMessage::QueryChanged(query) => {
+                self.query = query;
+                Task::none()
+            }
+            SearchMessage::ReplacementChanged(replacement) => {
+                self.replacement = replacement;
+                Task::none()
+            }
+            SearchMessage::ToggleRegex => {
+                self.options.is_regex = !self.options.is_regex;
+                Task::done(SearchMessage::Submit)
+            }
+            SearchMessage::ToggleCaseSensitive => {
+                self.options.is_case_sensitive = !self.options.is_case_sensitive;
+                Task::done(SearchMessage::Submit)
+            }
+            SearchMessage::ToggleWholeWord => {
+                self.options.is_whole_word = !self.options.is_whole_word;
+                Task::done(SearchMessage::Submit)
+            }
+            SearchMessage::Submit => {
+                self.cancel_search();
+                self.results.clear();
+                self.matcher = None;
+                self.replace_preview = None;
+                if self.query.is_empty() {
+                    self.status = SearchStatus::Idle;
+                    return Task::none();
+                }
+                if let Err(error) = build_matcher(&self.query, self.options) {
+                    self.status = SearchStatus::Failed(error.to_string());
+                    return Task::none();
+                }
+                self.status = SearchStatus::Searching;
+                Task::done(SearchMessage::SendSearchRequest)
+            }
+            SearchMessage::StartSearch(result) => {
+                let targets = match result {
+                    Ok(targets) => targets,
+                    Err(error) => {
+                        self.status = SearchStatus::Failed(error.to_string());
+                        return Task::none();
+                    }
+                };
+                let regex = match build_matcher(&self.query, self.options) {
+                    Ok(regex) => regex,
+                    Err(error) => {
+                        self.status = SearchStatus::Failed(error.to_string());
+                        return Task::none();
+                    }
+                };
+                self.cancel_search();
+                self.matcher = Some((regex.clone(), self.options));
+                self.search_id += 1;
+                let search_id = self.search_id;
+                info!(
+                    "[Search-StartSearch]:开始在 {} 个文件中搜索 {}",
+                    targets.files.len(),
+                    self.query
+                );
+                let (task, handle) = Task::sip(
+                    search_files(targets, regex),
+                    move |file_matches| SearchMessage::FileSearched(search_id, file_matches),
+                    move |_| SearchMessage::SearchFinished(search_id),
+                )
+                .abortable();
+                self.search_handle = Some(handle);
+                task
+            }
+            SearchMessage::FileSearched(search_id, file_matches) => {
+                if search_id == self.search_id {
+                    self.results.push(file_matches);
+                }
+                Task::none()
+            }
+            SearchMessage::SearchFinished(search_id) => {
+                if search_id == self.search_id {
+                    self.search_handle = None;
+                    self.status = SearchStatus::Finished;
+                }
+                Task::none()
+            }
+            SearchMessage::HitClicked(path, line) => {
+                Task::done(SearchMessage::SendOpenFileAtLine(path, line))
+            }
+            SearchMessage::PreviewReplace => {
+                let Some((regex, options)) = self.matcher.as_ref() else {
+                    return Task::none();
+                };
+                match build_replace_preview(regex, *options, &self.replacement, &self.results) {
+                    Ok(preview) => self.replace_preview = Some(preview),
+                    Err(error) => self.status = SearchStatus::Failed(error.to_string()),
+                }
+                Task::none()
+            }
+            SearchMessage::CancelReplace => {
+                self.replace_preview = None;
+                Task::none()
+            }
+            SearchMessage::ConfirmReplace => match self.replace_preview.take() {
+                Some(preview) if !preview.replacements.is_empty() => {
+                    self.cancel_search();
+                    self.results.clear();
+                    self.matcher = None;
+                    self.status = SearchStatus::Replacing;
+                    Task::done(SearchMessage::SendReplaceRequest(preview.replacements))
+                }
+                _ => Task::none(),
+            },
+            // 链接检查的修复也会返回结果，只处理由这里发起的替换
+            SearchMessage::ReplaceFinished(succeeded_count, failed_count) => {
+                if matches!(self.status, SearchStatus::Replacing) {
+                    self.status = SearchStatus::Replaced(succeeded_count, failed_count);
+                }
+                Task::none()
+            }
+            _ => Task::none(),
+        }
+    }
+
+    fn cancel_search(&mut self) {
+        if let Some(handle) = self.search_handle.take() {
+            handle.abort();
+        }
+        self.search_id += 1;
+    }
+
+    fn status_text(&self) -> String {
+        let hit_count = self
+            .results
+            .iter()
+            .map(|file_matches| file_matches.hits.len())
+            .sum::<usize>();
+        match &self.status {
+            SearchStatus::Idle => "输入内容后按回车搜索".to_string(),
+            SearchStatus::Searching => {
+                format!("搜索中... {} 个文件中有 {} 处匹配", self.results.len(), hit_count)
+            }
+            SearchStatus::Finished => {
+                format!("{} 个文件中有 {} 处匹配", self.results.len(), hit_count)
+            }
+            SearchStatus::Replacing => "替换中...".to_string(),
+            SearchStatus::Failed(error) => error.clone(),
+            SearchStatus::Replaced(succeeded_count, failed_count) => {
+                format!("已替换 {} 个文件，失败 {} 个", succeeded_count, failed_count)
+            }
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, SearchMessage> {
+        let inputs = column![
+            text_input("搜索", &self.query)
+                .size(FONT_SIZE_BASE)
+                .padding(Padding::from([PADDING_SMALLER, PADDING_BASE]))
+                .on_input(SearchMessage::QueryChanged)
+                .on_submit(SearchMessage::Submit),
+            text_input("替换为", &self.replacement)
+                .size(FONT_SIZE_BASE)
+                .padding(Padding::from([PADDING_SMALLER, PADDING_BASE]))
+                .on_input(SearchMessage::ReplacementChanged)
+                .on_submit(SearchMessage::PreviewReplace),
+        ]
+        .spacing(SPACING_SMALLER);
+        let mut tool_row = row![
+            generate_option_button("正则", self.options.is_regex, SearchMessage::ToggleRegex),
+            generate_option_button(
+                "区分大小写",
+                self.options.is_case_sensitive,
+                SearchMessage::ToggleCaseSensitive
+            ),
+            generate_option_button(
+                "全词匹配",
+                self.options.is_whole_word,
+                SearchMessage::ToggleWholeWord
+            ),
+            space::horizontal(),
+        ]
+        .spacing(SPACING_BIGGER);
+        tool_row = match self.replace_preview {
+            Some(_) => tool_row
+                .push(generate_action_button("确认替换", SearchMessage::ConfirmReplace))
+                .push(generate_action_button("取消", SearchMessage::CancelReplace)),
+            None if !self.results.is_empty() => {
+                tool_row.push(generate_action_button("预览替换", SearchMessage::PreviewReplace))
+            }
+            None => tool_row,
+        };
+        let content = match self.replace_preview {
+            Some(ref preview) => self.view_replace_preview(preview),
+            None => self.view_results(),
+        };
+        container(column![
+            column![
+                inputs,
+                tool_row,
+                text(self.status_text()).size(FONT_SIZE_SMALLER)
+            ]
+            .spacing(SPACING_SMALLER)
+            .padding(Padding::from([PADDING_SMALLER, PADDING_BIGGER])),
+            rule::horizontal(1).style(|theme: &Theme| {
+                let ex_palette = theme.extended_palette();
+                rule::Style {
+                    color: ex_palette.background.weaker.color,
+                    radius: Radius::default(),
+                    snap: true,
+                    fill_mode: rule::FillMode::Full,
+                }
+            }),
+            scrollable(container(content).padding(Padding::from([PADDING_SMALLER, PADDING_BIGGER])))
+                .height(Length::Fill),
+        ])
+        .height(Length::Fill)
+        .into()
+    }
+
+    fn view_results(&self) -> Element<'_, SearchMessage> {
+        column(self.results.iter().map(|file_matches| {
+            column![
+                text!("{}  ({})", file_matches.relative_path, file_matches.hits.len())
+                    .size(FONT_SIZE_BASE)
+                    .style(|theme: &Theme| text::Style {
+                        color: Some(theme.palette().primary),
+                    }),
+                column(file_matches.hits.iter().map(|hit| {
+                    generate_hit_item(file_matches.path.clone(), hit)
+                })),
+            ]
+            .spacing(SPACING_SMALLER)
+            .into()
+        }))
+        .spacing(SPACING_BIGGER)
+        .into()
+    }
+
+    fn view_replace_preview<'a>(&'a self, preview: &'a ReplacePreview) -> Element<'a, SearchMessage> {
+        let summary = text!(
+            "将在 {} 个文件中修改 {} 行，确认后写入",
+            preview.replacements.len(),
+            preview.changes.len()
+        )
+        .size(FONT_SIZE_SMALLER);
+        column![summary]
+            .extend(preview.changes.iter().map(|change| {
+                column![
+                    text!("{}:{}", change.relative_path, change.line + 1).size(FONT_SIZE_SMALLEST),
+                    text!("- {}", change.before)
+                        .size(FONT_SIZE_SMALLER)
+                        .style(|theme: &Theme| text::Style {
+                            color: Some(theme.palette().danger),
+                        }),
+                    text!("+ {}", change.after)
+                        .size(FONT_SIZE_SMALLER)
+                        .style(|theme: &Theme| text::Style {
+                            color: Some(theme.palette().success),
+                        }),
+                ]
+                .into()
+            }))
+            .spacing(SPACING_SMALLER)
+            .into()
+    }
+}
+
+// 普通文本先转义，全词匹配时加上单词边界，不区分大小写时加上(?i)
+fn build_matcher(query: &str, options: SearchOptions) -> Result<Regex, AppError> {
+    let mut pattern = match options.is_regex {
+        true => query.to_string(),
+        false => fancy_regex::escape(query).into_owned(),
+    };
+    if options.is_whole_word {
+        pattern = format!(r"\b(?:{})\b", pattern);
+    }
+    if !options.is_case_sensitive {
+        pattern = format!("(?i){}", pattern);
+    }
+    Regex::new(&pattern)
+        .map_err(|error| AppError::PreviewError(format!("[Search]:正则表达式无效: {}", error)))
+}
+
+// 逐个文件搜索，有匹配的文件立即返回，编辑器中未保存的内容优先于磁盘文件
+fn search_files(targets: SearchTargets, regex: Regex) -> impl task::Sipper<(), FileMatches> {
+    sipper(async move |mut sender| {
+        let SearchTargets {
+            root_paths,
+            files,
+            buffers,
+        } = targets;
+        // 多个工作区时保留工作区名称以便区分
+        let base_paths = root_paths
+            .iter()
+            .map(|root_path| match root_paths.len() {
+                1 => root_path.clone(),
+                _ => root_path.parent().unwrap_or(root_path).to_path_buf(),
+            })
+            .collect::<Vec<_>>();
+        for path in files {
+            let content = match buffers.get(&path) {
+                Some(content) => Arc::clone(content),
+                None => match tokio::fs::read_to_string(&path).await {
+                    Ok(content) => Arc::new(content),
+                    Err(error) => {
+                        error!("[Search]:读取 {} 失败: {}", path.display(), error);
+                        continue;
+                    }
+                },
+            };
+            let hits = content
+                .lines()
+                .enumerate()
+                .filter_map(|(line, line_text)| {
+                    let ranges = find_ranges(&regex, line_text);
+                    (!ranges.is_empty()).then(|| build_line_hit(line, line_text, ranges))
+                })
+                .collect::<Vec<_>>();
+            if hits.is_empty() {
+                continue;
+            }
+            let relative_path = root_paths
+                .iter()
+                .zip(base_paths.iter())
+                .find(|(root_path, _)| path.starts_with(root_path))
+                .and_then(|(_, base_path)| path.strip_prefix(base_path).ok())
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .into_owned();
+            sender
+                .send(FileMatches {
+                    path,
+                    relative_path,
+                    content,
+                    hits,
+                })
+                .await;
+        }
+    })
+}
+
+// 空匹配没有可以显示和替换的内容，直接跳过
+fn find_ranges(regex: &Regex, line_text: &str) -> Vec<Range<usize>> {
+    regex
+        .find_iter(line_text)
+        .filter_map(Result::ok)
+        .filter(|matched| !matched.as_str().is_empty())
+        .map(|matched| matched.range())
+        .collect()
+}
+
+// 长行只保留第一处匹配附近的内容
+fn build_line_hit(line: usize, line_text: &str, ranges: Vec<Range<usize>>) -> LineHit {
+    let first_start = ranges.first().map(|range| range.start).unwrap_or_default();
+    let indent_len = line_text.len() - line_text.trim_start().len();
+    let start = line_text[..first_start]
+        .char_indices()
+        .rev()
+        .nth(SEARCH_PREVIEW_CONTEXT_CHARS)
+        .map(|(index, _)| index)
+        .unwrap_or(0)
+        .max(indent_len.min(first_start));
+    let end = line_text[start..]
+        .char_indices()
+        .nth(SEARCH_PREVIEW_MAX_CHARS)
+        .map(|(index, _)| start + index)
+        .unwrap_or(line_text.len());
+    let prefix = if start > indent_len { "…" } else { "" };
+    let ranges = ranges
+        .into_iter()
+        .filter(|range| range.start < end)
+        .map(|range| range.start - start + prefix.len()..range.end.min(end) - start + prefix.len())
+        .collect();
+    LineHit {
+        line,
+        preview: format!("{}{}", prefix, &line_text[start..end]),
+        ranges,
+    }
+}
+
+// 按行替换，保留原来的换行符，正则模式下替换内容支持$1这样的分组引用
+fn build_replace_preview(
+    regex: &Regex,
+    options: SearchOptions,
+    replacement: &str,
+    results: &[FileMatches],
+) -> Result<ReplacePreview, AppError> {
+    let mut changes = vec![];
+    let mut replacements = vec![];
+    for file_matches in results {
+        let mut replaced = String::with_capacity(file_matches.content.len());
+        let mut is_changed = false;
+        for (line, line_text) in file_matches.content.split_inclusive('\n').enumerate() {
+            let line_body = line_text.trim_end_matches(['\n', '\r']);
+            let line_ending = &line_text[line_body.len()..];
+            let new_body = match options.is_regex {
+                true => regex.try_replacen(line_body, 0, replacement),
+                false => regex.try_replacen(line_body, 0, NoExpand(replacement)),
+            }
+            .map_err(|error| AppError::PreviewError(format!("[Search]:替换失败: {}", error)))?;
+            if new_body != line_body {
+                is_changed = true;
+                changes.push(ReplaceChange {
+                    relative_path: file_matches.relative_path.clone(),
+                    line,
+                    before: line_body.trim().to_string(),
+                    after: new_body.trim().to_string(),
+                });
+            }
+            replaced.push_str(&new_body);
+            replaced.push_str(line_ending);
+        }
+        if is_changed {
+            replacements.push(FileReplacement {
+                path: file_matches.path.clone(),
+                original: Arc::clone(&file_matches.content),
+                replaced: Arc::new(replaced),
+            });
+        }
+    }
+    Ok(ReplacePreview {
+        changes,
+        replacements,
+    })
+}
+
+fn generate_hit_item<'a>(path: PathBuf, hit: &'a LineHit) -> Element<'a, SearchMessage> {
+    let mut spans: Vec<text::Span<'a>> = vec![];
+    let mut last_end = 0;
+    for range in hit.ranges.iter() {
+        if range.start > last_end {
+            spans.push(span(&hit.preview[last_end..range.start]));
+        }
+        spans.push(span(&hit.preview[range.clone()]).color(MATCH_HIGHLIGHT_COLOR));
+        last_end = range.end;
+    }
+    if last_end < hit.preview.len() {
+        spans.push(span(&hit.preview[last_end..]));
+    }
+    mouse_area(
+        container(
+            row![
+                text!("{}", hit.line + 1)
+                    .size(FONT_SIZE_SMALLEST)
+                    .width(SEARCH_LINE_NUMBER_WIDTH),
+                rich_text(spans).size(FONT_SIZE_SMALLER),
+            ]
+            .spacing(SPACING_SMALLER),
+        )
+        .width(Length::Fill)
+        .padding(Padding::from([0., PADDING_BASE]))
+        .style(|theme: &Theme| {
+            let ex_palette = theme.extended_palette();
+            container::Style {
+                background: Some(Background::Color(ex_palette.background.weakest.color)),
+                ..container::Style::default()
+            }
+        }),
+    )
+    .interaction(mouse::Interaction::Pointer)
+    .on_press(SearchMessage::HitClicked(path, hit.line))
+    .into()
+}
+
+fn generate_option_button<'a>(
+    label: &'a str,
+    is_enabled: bool,
+    message: SearchMessage,
+) -> Element<'a, SearchMessage> {
+    mouse_area(
+        text(label)
+            .size(FONT_SIZE_SMALLER)
+            .style(move |theme: &Theme| {
+                let palette = theme.palette();
+                text::Style {
+                    color: Some(if is_enabled { palette.primary } else { palette.text }),
+                }
+            }),
+    )
+    .interaction(mouse::Interaction::Pointer)
+    .on_press(message)
+    .into()
+}
+
+fn generate_action_button<'a>(label: &'a str, message: SearchMessage) -> Element<'a, SearchMessage> {
+    mouse_area(text(label).size(FONT_SIZE_SMALLER))
+        .interaction(mouse::Interaction::Pointer)
+        .on_press(message)
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_preview() {
+        let content = Arc::new("foo bar\r\nfood foo\nbar\n".to_string());
+        let results = vec![FileMatches {
+            path: PathBuf::from("/notes/a.md"),
+            relative_path: "a.md".to_string(),
+            content,
+            hits: vec![],
+        }];
+        let options = SearchOptions {
+            is_whole_word: true,
+            ..SearchOptions::default()
+        };
+        let regex = build_matcher("FOO", options).unwrap();
+        let preview = build_replace_preview(&regex, options, "baz", &results).unwrap();
+        assert_eq!(preview.changes.len(), 2);
+        assert_eq!(*preview.replacements[0].replaced, "baz bar\r\nfood baz\nbar\n");
+        let options = SearchOptions {
+            is_regex: true,
+            is_case_sensitive: true,
+            ..SearchOptions::default()
+        };
+        let regex = build_matcher(r"(\w+) bar", options).unwrap();
+        let preview = build_replace_preview(&regex, options, "$1-bar", &results).unwrap();
+        assert_eq!(*preview.replacements[0].replaced, "foo-bar\r\nfood foo\nbar\n");
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use crate::common::*;
 use iced::{
@@ -96,6 +96,25 @@ impl TextBoard {
         }
     }
 
+    // 文本素材只存在于内存中，非空文本都视为未保存
+    pub fn collect_unsaved_docs(&self) -> Vec<UnsavedDoc> {
+        let mut ids = self.all_text.keys().collect::<Vec<_>>();
+        ids.sort();
+        ids.into_iter()
+            .filter_map(|id| {
+                self.all_text
+                    .get(id)
+                    .filter(|text| !text.trim().is_empty())
+                    .map(|text| UnsavedDoc {
+                        name: id.clone(),
+                        path: None,
+                        content: Arc::new(text.clone()),
+                        source: DocSource::TextBoard(id.clone()),
+                    })
+            })
+            .collect()
+    }
+
     pub fn veiw(&self) -> Element<'_, TextBoardMessage> {
         let options: Element<'_, TextBoardMessage> = if !self.all_text.is_empty() {
             let mut options = self.all_text.keys().collect::<Vec<_>>();
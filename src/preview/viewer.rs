@@ -7,7 +7,10 @@ use iced::{
 };
 use std::{collections::HashMap, path::PathBuf, sync::LazyLock};
 
-use crate::{common::*, preview::markdown::MarkdownMessage};
+use crate::{
+    common::{wiki_link::WIKI_NEW_LINK_SCHEME, *},
+    preview::markdown::MarkdownMessage,
+};
 
 pub const CUSTOM_SETTINGS: LazyLock<iced_markdown::Settings> = LazyLock::new(|| {
     let palette = DEFAULT_THEME.palette();
@@ -49,6 +52,17 @@ impl<'a> iced_markdown::Viewer<'a, MarkdownMessage> for CustomViewer<'a> {
         }
     }
 
+    fn paragraph(
+        &self,
+        settings: iced_markdown::Settings,
+        text: &iced_markdown::Text,
+    ) -> Element<'a, MarkdownMessage, Theme, Renderer> {
+        rich_text(style_wiki_links(text, settings))
+            .size(settings.text_size)
+            .on_link_click(MarkdownMessage::LinkClicked)
+            .into()
+    }
+
     fn code_block(
         &self,
         settings: iced_markdown::Settings,
@@ -124,7 +138,7 @@ impl<'a> iced_markdown::Viewer<'a, MarkdownMessage> for CustomViewer<'a> {
             iced_markdown::HeadingLevel::H6 => (h6_size, H6_COLOR),
         };
         container(
-            rich_text(text.spans(settings.style))
+            rich_text(style_wiki_links(text, settings))
                 .color(color)
                 .on_link_click(MarkdownMessage::LinkClicked)
                 .size(size),
@@ -195,3 +209,24 @@ impl<'a> iced_markdown::Viewer<'a, MarkdownMessage> for CustomViewer<'a> {
             .into()
     }
 }
+
+// 找不到对应笔记的[[链接]]换成另一种颜色并加下划线，点击时提示新建
+fn style_wiki_links(
+    text: &iced_markdown::Text,
+    settings: iced_markdown::Settings,
+) -> Vec<iced::widget::text::Span<'static, iced_markdown::Uri>> {
+    text.spans(settings.style)
+        .iter()
+        .cloned()
+        .map(|span| {
+            let is_missing = span
+                .link
+                .as_ref()
+                .is_some_and(|link| link.starts_with(WIKI_NEW_LINK_SCHEME));
+            match is_missing {
+                true => span.color(WIKI_MISSING_LINK_COLOR).underline(true),
+                false => span,
+            }
+        })
+        .collect()
+}
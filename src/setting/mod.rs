@@ -0,0 +1,160 @@
+use std::path::PathBuf;
+
+use crate::{common::*, preview::PreviewPage};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use tracing::warn;
+
+// 各工作区的文件树排序和过滤设置
+pub const TREE_VIEW_SETTING_FILE: &str = "tree_view.json";
+// 最近打开的文件和文件夹
+pub const RECENT_LIST_FILE: &str = "recent.json";
+// 上次退出时的会话状态
+pub const SESSION_FILE: &str = "session.json";
+// 设置菜单和偏好设置窗口中修改的设置
+pub const USER_SETTING_FILE: &str = "setting.json";
+
+// 需要持久化的用户设置，启动时读取后覆盖AppSetting中的默认值
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserSetting {
+    pub scan_max_depth: usize,
+    pub scan_max_nodes: usize,
+    pub scan_include_hidden: bool,
+    pub title_as_label: bool,
+    pub journal_folder: String,
+    pub journal_template: String,
+    pub template_folder: String,
+    pub author: String,
+    pub editable_extensions: Vec<String>,
+}
+
+impl Default for UserSetting {
+    fn default() -> Self {
+        Self::from(&*DEFAULT_USER_SETTING)
+    }
+}
+
+impl From<&AppSetting> for UserSetting {
+    fn from(setting: &AppSetting) -> Self {
+        Self {
+            scan_max_depth: setting.scan_max_depth,
+            scan_max_nodes: setting.scan_max_nodes,
+            scan_include_hidden: setting.scan_include_hidden,
+            title_as_label: setting.title_as_label,
+            journal_folder: setting.journal_folder.clone(),
+            journal_template: setting.journal_template.clone(),
+            template_folder: setting.template_folder.clone(),
+            author: setting.author.clone(),
+            editable_extensions: setting.editable_extensions.clone(),
+        }
+    }
+}
+
+impl UserSetting {
+    pub fn apply_to(self, setting: &mut AppSetting) {
+        setting.scan_max_depth = self.scan_max_depth;
+        setting.scan_max_nodes = self.scan_max_nodes;
+        setting.scan_include_hidden = self.scan_include_hidden;
+        setting.title_as_label = self.title_as_label;
+        setting.journal_folder = self.journal_folder;
+        setting.journal_template = self.journal_template;
+        setting.template_folder = self.template_folder;
+        setting.author = self.author;
+        setting.editable_extensions = self.editable_extensions;
+    }
+}
+
+// 退出时和定时保存的会话状态，启动时据此恢复
+// 关闭恢复功能时只保存开关本身
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionState {
+    pub restore_enabled: bool,
+    pub workspace: WorkspaceSession,
+    pub preview_page: PreviewPage,
+    // 预览区滚动位置，0到1之间的比例
+    pub preview_scroll: f32,
+    pub window: Option<WindowState>,
+}
+
+impl Default for SessionState {
+    fn default() -> Self {
+        Self {
+            restore_enabled: true,
+            workspace: WorkspaceSession::default(),
+            preview_page: PreviewPage::default(),
+            preview_scroll: 0.,
+            window: None,
+        }
+    }
+}
+
+// 文件面板负责的部分，路径都是绝对路径
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkspaceSession {
+    pub roots: Vec<PathBuf>,
+    pub expanded_dirs: Vec<PathBuf>,
+    // 临时工作区中打开的文件
+    pub open_files: Vec<PathBuf>,
+    // 编辑器中的文件
+    pub active_file: Option<PathBuf>,
+    // 本次打开过的文档的光标和滚动位置，再次打开时恢复
+    pub documents: Vec<DocumentSession>,
+    pub gallery_images: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentSession {
+    pub path: PathBuf,
+    pub cursor_line: usize,
+    pub scroll_line: usize,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct WindowState {
+    pub width: f32,
+    pub height: f32,
+    // 部分平台无法获取窗口位置
+    pub position: Option<(f32, f32)>,
+}
+
+// 需要持久化的设置统一保存在用户配置目录下的fugu文件夹中
+pub fn config_dir() -> Option<PathBuf> {
+    let base_dir = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::home_dir().map(|home| home.join("Library").join("Application Support"))
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| std::env::home_dir().map(|home| home.join(".config")))
+    };
+    base_dir.map(|base_dir| base_dir.join(APP_NAME))
+}
+
+// 读取配置文件，文件不存在或内容损坏时使用默认值
+pub async fn load_config<T: DeserializeOwned + Default>(file_name: &'static str) -> T {
+    let Some(path) = config_dir().map(|dir| dir.join(file_name)) else {
+        return T::default();
+    };
+    match tokio::fs::read_to_string(&path).await {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|error| {
+            warn!("[Setting-LoadConfig]:配置文件{}解析失败:{}", path.display(), error);
+            T::default()
+        }),
+        Err(_) => T::default(),
+    }
+}
+
+pub async fn save_config<T: Serialize>(file_name: &'static str, value: T) -> Result<(), AppError> {
+    let dir = config_dir().ok_or(AppError::OtherError(
+        "[Setting-SaveConfig]:找不到用户配置目录!".to_string(),
+    ))?;
+    tokio::fs::create_dir_all(&dir).await?;
+    let content = serde_json::to_string_pretty(&value)
+        .map_err(|error| AppError::OtherError(error.to_string()))?;
+    tokio::fs::write(dir.join(file_name), content).await?;
+    Ok(())
+}